@@ -8,9 +8,11 @@ import { useNetwork } from "@/app/context/NetworkContext";
 import { useWallet } from "@/hooks/use-wallet";
 
 export interface QueuedWithdrawalData {
+  id: string;
   user: string;
   asset: string;
   shares: string;
+  sharePrice: string;
   timestamp: string;
 }
 
@@ -77,13 +79,15 @@ export function useWithdrawalQueuePosition(
         const nativeResult = scValToNative(simulated.result.retval);
         if (Array.isArray(nativeResult)) {
           const queue: QueuedWithdrawalData[] = nativeResult.map((item: any) => ({
+            id: item.id?.toString() || "0",
             user: item.user?.toString() || "",
             asset: item.asset?.toString() || "",
             shares: item.shares?.toString() || "0",
+            sharePrice: item.share_price?.toString() || "0",
             timestamp: item.timestamp?.toString() || "0",
           }));
 
-          // Find user's position in queue (0-based index)
+          // Find the position of the user's oldest ticket (0-based index)
           const userPosition = queue.findIndex(
             (withdrawal) => withdrawal.user === actualUserAddress
           );
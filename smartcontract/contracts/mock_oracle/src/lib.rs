@@ -1,5 +1,13 @@
 #![no_std]
-use soroban_sdk::{contract, contracterror, contractimpl, contracttype, Address, Env, Map};
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, panic_with_error, Address, Env, IntoVal,
+    Map, Symbol, Vec,
+};
+
+/// Prices are USD scaled to 9 decimals.
+pub const DECIMALS: u32 = 9;
+/// Records kept per asset; the oldest is dropped first.
+pub const MAX_HISTORY: u32 = 64;
 
 #[contracterror]
 #[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
@@ -7,13 +15,48 @@ use soroban_sdk::{contract, contracterror, contractimpl, contracttype, Address,
 pub enum Error {
     NotInitialized = 1,
     Unauthorized = 2,
+    AlreadyInitialized = 3,
+    /// No price has been recorded for the asset.
+    NoPrice = 4,
+    /// Raised by every query while `FailureMode::Revert` is set.
+    PriceUnavailable = 5,
 }
 
 #[contracttype]
 #[derive(Clone)]
 pub enum DataKey {
     Admin,
-    Prices,
+    /// `Vec<PriceData>` per asset, oldest first.
+    History(Address),
+    FailureMode,
+    /// Timestamp of the latest price recorded for any asset.
+    LastTimestamp,
+    /// Ledger timestamp the feed froze at while `FailureMode::Stale` is set.
+    FrozenAt,
+}
+
+/// SEP-40 price record.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PriceData {
+    pub price: i128,
+    pub timestamp: u64,
+}
+
+/// Misbehaviour applied to every response. The recorded history is kept
+/// intact, so switching back to `None` restores the real feed.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FailureMode {
+    None,
+    /// The feed stops at the moment the mode is set: later prices are
+    /// recorded but not served, and allocations are pushed with the frozen
+    /// timestamp.
+    Stale,
+    Zero,
+    /// Prices are served as negative values.
+    Negative,
+    Revert,
 }
 
 #[contract]
@@ -23,44 +66,198 @@ pub struct MockOracle;
 impl MockOracle {
     pub fn init(env: Env, admin: Address) -> Result<(), Error> {
         if env.storage().instance().has(&DataKey::Admin) {
-            return Err(Error::NotInitialized);
+            return Err(Error::AlreadyInitialized);
         }
         env.storage().instance().set(&DataKey::Admin, &admin);
+        Ok(())
+    }
+
+    /// Record `price` for `asset` at the current ledger timestamp. A second
+    /// price in the same ledger replaces the first.
+    pub fn set_price(env: Env, admin: Address, asset: Address, price: i128) -> Result<(), Error> {
+        Self::require_admin(&env, &admin)?;
+
+        let record = PriceData {
+            price,
+            timestamp: env.ledger().timestamp(),
+        };
+        let mut history = Self::history(&env, &asset);
+        if history
+            .last()
+            .is_some_and(|last| last.timestamp == record.timestamp)
+        {
+            history.pop_back();
+        }
+        history.push_back(record);
+        if history.len() > MAX_HISTORY {
+            history.pop_front();
+        }
+        env.storage()
+            .instance()
+            .set(&DataKey::History(asset), &history);
         env.storage()
             .instance()
-            .set(&DataKey::Prices, &Map::<Address, i128>::new(&env));
+            .set(&DataKey::LastTimestamp, &env.ledger().timestamp());
         Ok(())
     }
 
-    pub fn set_price(env: Env, admin: Address, asset: Address, price: i128) -> Result<(), Error> {
+    pub fn set_failure_mode(env: Env, admin: Address, mode: FailureMode) -> Result<(), Error> {
+        Self::require_admin(&env, &admin)?;
+        if mode == FailureMode::Stale {
+            env.storage()
+                .instance()
+                .set(&DataKey::FrozenAt, &env.ledger().timestamp());
+        }
+        env.storage().instance().set(&DataKey::FailureMode, &mode);
+        Ok(())
+    }
+
+    pub fn failure_mode(env: Env) -> FailureMode {
+        Self::mode(&env)
+    }
+
+    /// Push target allocations into `vault` through its `set_oracle_data`,
+    /// stamped with the feed's current timestamp. The vault only accepts
+    /// data from its configured oracle, so this contract must be it.
+    pub fn push_allocations(
+        env: Env,
+        admin: Address,
+        vault: Address,
+        allocations: Map<Address, i128>,
+    ) -> Result<(), Error> {
+        Self::require_admin(&env, &admin)?;
+        Self::check_reachable(&env);
+        let timestamp = Self::feed_time(&env);
+        env.invoke_contract::<()>(
+            &vault,
+            &Symbol::new(&env, "set_oracle_data"),
+            soroban_sdk::vec![&env, allocations.into_val(&env), timestamp.into_val(&env)],
+        );
+        Ok(())
+    }
+
+    /// Return USD price scaled to 9 decimals for the given asset.
+    ///
+    /// This is the call the vault makes; it panics with `NoPrice` for an
+    /// asset that was never priced.
+    pub fn price(env: Env, asset: Address) -> i128 {
+        match Self::lastprice(env.clone(), asset) {
+            Some(data) => data.price,
+            None => panic_with_error!(&env, Error::NoPrice),
+        }
+    }
+
+    // ── SEP-40 queries ───────────────────────────────────────────────
+    // `price(asset, timestamp)` is `price_at` here, since `price` keeps
+    // the single-argument form the vault calls.
+
+    pub fn decimals(_env: Env) -> u32 {
+        DECIMALS
+    }
+
+    /// Timestamp of the most recent price served for any asset.
+    pub fn last_timestamp(env: Env) -> u64 {
+        Self::check_reachable(&env);
+        let last: u64 = env
+            .storage()
+            .instance()
+            .get(&DataKey::LastTimestamp)
+            .unwrap_or(0);
+        last.min(Self::feed_time(&env))
+    }
+
+    /// Most recent price for `asset`.
+    pub fn lastprice(env: Env, asset: Address) -> Option<PriceData> {
+        Self::check_reachable(&env);
+        Self::served(&env, &asset).last()
+    }
+
+    /// Price that was current at `timestamp`.
+    pub fn price_at(env: Env, asset: Address, timestamp: u64) -> Option<PriceData> {
+        Self::check_reachable(&env);
+        Self::served(&env, &asset)
+            .iter()
+            .rev()
+            .find(|data| data.timestamp <= timestamp)
+    }
+
+    /// Up to `records` most recent prices for `asset`, newest first.
+    pub fn prices(env: Env, asset: Address, records: u32) -> Option<Vec<PriceData>> {
+        Self::check_reachable(&env);
+        let served = Self::served(&env, &asset);
+        if served.is_empty() {
+            return None;
+        }
+        let mut newest_first = Vec::new(&env);
+        for data in served.iter().rev().take(records as usize) {
+            newest_first.push_back(data);
+        }
+        Some(newest_first)
+    }
+}
+
+impl MockOracle {
+    fn require_admin(env: &Env, admin: &Address) -> Result<(), Error> {
         let stored_admin: Address = env
             .storage()
             .instance()
             .get(&DataKey::Admin)
             .ok_or(Error::NotInitialized)?;
         admin.require_auth();
-        if admin != stored_admin {
+        if *admin != stored_admin {
             return Err(Error::Unauthorized);
         }
+        Ok(())
+    }
 
-        let mut prices: Map<Address, i128> = env
-            .storage()
+    fn mode(env: &Env) -> FailureMode {
+        env.storage()
             .instance()
-            .get(&DataKey::Prices)
-            .unwrap_or(Map::new(&env));
-        prices.set(asset, price);
-        env.storage().instance().set(&DataKey::Prices, &prices);
-        Ok(())
+            .get(&DataKey::FailureMode)
+            .unwrap_or(FailureMode::None)
     }
 
-    /// Return USD price scaled to 9 decimals for the given asset.
-    pub fn price(env: Env, asset: Address) -> i128 {
-        let prices: Map<Address, i128> = env
-            .storage()
+    fn history(env: &Env, asset: &Address) -> Vec<PriceData> {
+        env.storage()
             .instance()
-            .get(&DataKey::Prices)
-            .unwrap_or(Map::new(&env));
-        prices.get(asset).unwrap_or(1_000_000_000)
+            .get(&DataKey::History(asset.clone()))
+            .unwrap_or(Vec::new(env))
+    }
+
+    fn check_reachable(env: &Env) {
+        if Self::mode(env) == FailureMode::Revert {
+            panic_with_error!(env, Error::PriceUnavailable);
+        }
+    }
+
+    /// The timestamp the feed reports as "now".
+    fn feed_time(env: &Env) -> u64 {
+        match Self::mode(env) {
+            FailureMode::Stale => env
+                .storage()
+                .instance()
+                .get(&DataKey::FrozenAt)
+                .unwrap_or(0),
+            _ => env.ledger().timestamp(),
+        }
     }
-}
 
+    /// Recorded history as the current failure mode presents it.
+    fn served(env: &Env, asset: &Address) -> Vec<PriceData> {
+        let mode = Self::mode(env);
+        let until = Self::feed_time(env);
+        let mut served = Vec::new(env);
+        for mut data in Self::history(env, asset).iter() {
+            if data.timestamp > until {
+                break;
+            }
+            data.price = match mode {
+                FailureMode::Zero => 0,
+                FailureMode::Negative => -data.price.abs(),
+                _ => data.price,
+            };
+            served.push_back(data);
+        }
+        served
+    }
+}
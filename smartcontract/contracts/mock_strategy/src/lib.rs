@@ -1,5 +1,8 @@
 #![no_std]
-use soroban_sdk::{contract, contractimpl, contracttype, token, Address, Env};
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, panic_with_error, token, vec, Address,
+    Env, IntoVal, Symbol, Val, Vec,
+};
 
 #[contracttype]
 pub enum DataKey {
@@ -8,6 +11,70 @@ pub enum DataKey {
     Vault,
     /// The underlying token used for real-token tests.
     Token,
+    /// Programmed misbehaviour, see `FailureModes`.
+    FailureModes,
+    /// Whether the vault accepted the last reentrant call.
+    ReentryAccepted,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum MockStrategyError {
+    DepositFailed = 1,
+    WithdrawFailed = 2,
+    BalanceFailed = 3,
+    CapacityExceeded = 4,
+}
+
+/// Vault entry point the strategy calls back into from `deposit` and
+/// `withdraw`.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Reentry {
+    None,
+    /// `deposit(strategy, token, amount, None)`
+    Deposit,
+    /// `withdraw(strategy, strategy, token, amount)`
+    Withdraw,
+    /// `harvest()`
+    Harvest,
+}
+
+/// Programmable misbehaviour. The default is a well-behaved strategy.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FailureModes {
+    pub fail_deposit: bool,
+    pub fail_withdraw: bool,
+    pub fail_balance: bool,
+    /// Bps of each withdrawal the strategy does not send back. The tokens
+    /// stay in the strategy and in its reported balance, so the vault can
+    /// still pull them.
+    pub withdraw_shortfall_bps: u32,
+    /// Bps of each withdrawal charged as a fee. The fee is burned, so the
+    /// vault never receives it.
+    pub withdraw_fee_bps: u32,
+    /// Bps of each deposit written off the reported balance.
+    pub deposit_loss_bps: u32,
+    /// Largest balance the strategy accepts.
+    pub capacity: Option<i128>,
+    pub reentry: Reentry,
+}
+
+impl Default for FailureModes {
+    fn default() -> Self {
+        FailureModes {
+            fail_deposit: false,
+            fail_withdraw: false,
+            fail_balance: false,
+            withdraw_shortfall_bps: 0,
+            withdraw_fee_bps: 0,
+            deposit_loss_bps: 0,
+            capacity: None,
+            reentry: Reentry::None,
+        }
+    }
 }
 
 #[contract]
@@ -22,15 +89,32 @@ impl MockStrategy {
         env.storage().instance().set(&DataKey::Token, &token);
     }
 
+    /// Replace the programmed failure modes.
+    pub fn set_failure_modes(env: Env, modes: FailureModes) {
+        env.storage().instance().set(&DataKey::FailureModes, &modes);
+    }
+
+    pub fn failure_modes(env: Env) -> FailureModes {
+        Self::modes(&env)
+    }
+
+    /// Whether the vault accepted the last reentrant call, if one was made.
+    pub fn reentry_accepted(env: Env) -> Option<bool> {
+        env.storage().instance().get(&DataKey::ReentryAccepted)
+    }
+
     /// Get the current balance of the strategy.
     pub fn balance(env: Env) -> i128 {
-        env.storage().instance().get(&DataKey::Balance).unwrap_or(0)
+        if Self::modes(&env).fail_balance {
+            panic_with_error!(&env, MockStrategyError::BalanceFailed);
+        }
+        Self::stored_balance(&env)
     }
 
     /// Collect currently accumulated yield and transfer it to the vault.
     /// Returns the amount transferred.
     pub fn collect_yield(env: Env) -> i128 {
-        let current: i128 = env.storage().instance().get(&DataKey::Balance).unwrap_or(0);
+        let current = Self::stored_balance(&env);
         if current <= 0 {
             return 0;
         }
@@ -48,26 +132,52 @@ impl MockStrategy {
 
     /// Deposit funds into the strategy.
     pub fn deposit(env: Env, amount: i128) {
-        let current: i128 = env.storage().instance().get(&DataKey::Balance).unwrap_or(0);
+        let modes = Self::modes(&env);
+        Self::reenter(&env, &modes, amount);
+        if modes.fail_deposit {
+            panic_with_error!(&env, MockStrategyError::DepositFailed);
+        }
+        let current = Self::stored_balance(&env);
+        if modes
+            .capacity
+            .is_some_and(|capacity| current + amount > capacity)
+        {
+            panic_with_error!(&env, MockStrategyError::CapacityExceeded);
+        }
+        let loss = amount * modes.deposit_loss_bps as i128 / 10_000;
         env.storage()
             .instance()
-            .set(&DataKey::Balance, &(current + amount));
+            .set(&DataKey::Balance, &(current + amount - loss));
     }
 
     /// Withdraw funds from the strategy.
     /// If a vault and token are configured, transfers tokens back to the vault.
     pub fn withdraw(env: Env, amount: i128) {
-        let current: i128 = env.storage().instance().get(&DataKey::Balance).unwrap_or(0);
+        let modes = Self::modes(&env);
+        Self::reenter(&env, &modes, amount);
+        if modes.fail_withdraw {
+            panic_with_error!(&env, MockStrategyError::WithdrawFailed);
+        }
+        let fee = amount * modes.withdraw_fee_bps as i128 / 10_000;
+        let kept = (amount - fee) * modes.withdraw_shortfall_bps as i128 / 10_000;
+        let current = Self::stored_balance(&env);
         env.storage()
             .instance()
-            .set(&DataKey::Balance, &(current - amount));
+            .set(&DataKey::Balance, &(current - (amount - kept)));
 
         // If real-token mode is configured, transfer tokens back to vault.
         let vault: Option<Address> = env.storage().instance().get(&DataKey::Vault);
         let token_addr: Option<Address> = env.storage().instance().get(&DataKey::Token);
         if let (Some(vault_addr), Some(tok)) = (vault, token_addr) {
             let token_client = token::Client::new(&env, &tok);
-            token_client.transfer(&env.current_contract_address(), &vault_addr, &amount);
+            if fee > 0 {
+                token_client.burn(&env.current_contract_address(), &fee);
+            }
+            token_client.transfer(
+                &env.current_contract_address(),
+                &vault_addr,
+                &(amount - fee - kept),
+            );
         }
     }
 
@@ -78,3 +188,62 @@ impl MockStrategy {
             .set(&DataKey::Balance, &new_balance);
     }
 }
+
+impl MockStrategy {
+    fn modes(env: &Env) -> FailureModes {
+        env.storage()
+            .instance()
+            .get(&DataKey::FailureModes)
+            .unwrap_or_default()
+    }
+
+    fn stored_balance(env: &Env) -> i128 {
+        env.storage().instance().get(&DataKey::Balance).unwrap_or(0)
+    }
+
+    /// Call back into the vault as programmed and record whether it let the
+    /// call through.
+    fn reenter(env: &Env, modes: &FailureModes, amount: i128) {
+        let vault: Option<Address> = env.storage().instance().get(&DataKey::Vault);
+        let token_addr: Option<Address> = env.storage().instance().get(&DataKey::Token);
+        let (Some(vault), Some(token)) = (vault, token_addr) else {
+            return;
+        };
+        let this = env.current_contract_address();
+        let (function, args): (&str, Vec<Val>) = match modes.reentry {
+            Reentry::None => return,
+            Reentry::Deposit => (
+                "deposit",
+                vec![
+                    env,
+                    this.into_val(env),
+                    token.into_val(env),
+                    amount.into_val(env),
+                    Option::<i128>::None.into_val(env),
+                ],
+            ),
+            Reentry::Withdraw => (
+                "withdraw",
+                vec![
+                    env,
+                    this.into_val(env),
+                    this.into_val(env),
+                    token.into_val(env),
+                    amount.into_val(env),
+                ],
+            ),
+            Reentry::Harvest => ("harvest", vec![env]),
+        };
+        let accepted = matches!(
+            env.try_invoke_contract::<Val, soroban_sdk::Error>(
+                &vault,
+                &Symbol::new(env, function),
+                args,
+            ),
+            Ok(Ok(_))
+        );
+        env.storage()
+            .instance()
+            .set(&DataKey::ReentryAccepted, &accepted);
+    }
+}
@@ -0,0 +1,69 @@
+//! Share/asset conversions at arbitrary vault totals.
+//!
+//! Every conversion must either succeed or fail with a vault `Error`, and a
+//! round trip through shares must never create value.
+#![no_main]
+
+use std::fmt::Debug;
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use soroban_sdk::testutils::{Address as _, EnvTestConfig};
+use soroban_sdk::{Address, Env, Error as HostError, InvokeError};
+use volatility_shield::{Error, VolatilityShield, VolatilityShieldClient};
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    total_assets: i128,
+    total_shares: i128,
+    amount: u128,
+}
+
+/// Unwrap a view call that reports failure by panicking with a vault error.
+fn view<T, C: Debug>(result: Result<Result<T, C>, Result<HostError, InvokeError>>) -> Option<T> {
+    match result {
+        Ok(value) => Some(value.expect("conversion")),
+        Err(Ok(error))
+            if error == HostError::from_contract_error(Error::ArithmeticOverflow as u32) =>
+        {
+            None
+        }
+        Err(error) => panic!("non-graceful failure: {error:?}"),
+    }
+}
+
+fuzz_target!(|input: Input| {
+    let env = Env::new_with_config(EnvTestConfig {
+        capture_snapshot_at_drop: false,
+    });
+    let vault = VolatilityShieldClient::new(&env, &env.register(VolatilityShield, ()));
+    let admin = Address::generate(&env);
+    vault.init(
+        &admin,
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &0u32,
+        &soroban_sdk::vec![&env, admin.clone()],
+        &1u32,
+    );
+    vault.set_total_assets(&input.total_assets);
+    vault.set_total_shares(&input.total_shares);
+    let amount = (input.amount >> 1) as i128;
+
+    let _ = view(vault.try_get_share_price());
+    let shares = view(vault.try_convert_to_shares(&amount));
+    let assets = view(vault.try_convert_to_assets(&amount));
+
+    if input.total_assets >= 0 && input.total_shares >= 0 {
+        if let Some(shares) = shares {
+            assert!(shares >= 0, "{amount} converts to {shares} shares");
+            if let Some(back) = view(vault.try_convert_to_assets(&shares)) {
+                assert!(back <= amount, "{amount} round-trips to {back}");
+            }
+        }
+        if let Some(assets) = assets {
+            assert!(assets >= 0, "{amount} shares are worth {assets}");
+        }
+    }
+});
@@ -0,0 +1,222 @@
+//! Arbitrary sequences of vault operations with arbitrary amounts and prices.
+//!
+//! Calls may fail with a vault `Error` but must never panic the host, and the
+//! share ledger must balance after every step.
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use mock_oracle::MockOracleClient;
+use soroban_sdk::testutils::{Address as _, EnvTestConfig};
+use soroban_sdk::token::{StellarAssetClient, TokenClient};
+use std::fmt::Debug;
+
+use soroban_sdk::{Address, Env, InvokeError};
+use volatility_shield::{VolatilityShield, VolatilityShieldClient};
+
+const USERS: usize = 3;
+
+#[derive(Arbitrary, Debug)]
+enum Op {
+    Deposit {
+        user: u8,
+        alt: bool,
+        amount: u128,
+    },
+    Withdraw {
+        user: u8,
+        alt: bool,
+        shares: u128,
+    },
+    Queue {
+        user: u8,
+        shares: u128,
+    },
+    Fulfil {
+        limit: u8,
+    },
+    /// Ticket ids are allocated from 0.
+    Claim {
+        ticket: u8,
+    },
+    Cancel {
+        ticket: u8,
+    },
+    /// Alt asset price `mantissa * 10^decimals`, 9-decimal fixed point.
+    SetAltPrice {
+        mantissa: u64,
+        decimals: u8,
+    },
+    /// Gains or losses booked outside deposits and withdrawals, including
+    /// donations to an empty vault.
+    SetTotalAssets {
+        total_assets: u128,
+    },
+}
+
+struct Harness<'a> {
+    env: &'a Env,
+    vault: VolatilityShieldClient<'a>,
+    oracle: MockOracleClient<'a>,
+    admin: Address,
+    assets: [(Address, StellarAssetClient<'a>); 2],
+    users: Vec<Address>,
+    alt_price: i128,
+}
+
+/// `Some(value)` on success, `None` on a contract error; panics on anything
+/// else.
+fn graceful<T, C: Debug, E>(
+    op: &Op,
+    result: Result<Result<T, C>, Result<E, InvokeError>>,
+) -> Option<T> {
+    match result {
+        Ok(value) => Some(value.expect("conversion")),
+        Err(Ok(_)) => None,
+        Err(Err(error)) => panic!("{op:?} failed without a vault error: {error:?}"),
+    }
+}
+
+fn positive(value: u128) -> i128 {
+    (value >> 1).max(1) as i128
+}
+
+impl<'a> Harness<'a> {
+    fn new(env: &'a Env) -> Self {
+        env.mock_all_auths_allowing_non_root_auth();
+        let token_admin = Address::generate(env);
+        let asset = || {
+            let id = env
+                .register_stellar_asset_contract_v2(token_admin.clone())
+                .address();
+            let admin = StellarAssetClient::new(env, &id);
+            (id, admin)
+        };
+        let assets = [asset(), asset()];
+
+        let admin = Address::generate(env);
+        let oracle = MockOracleClient::new(env, &env.register(mock_oracle::MockOracle, ()));
+        oracle.init(&admin);
+        let vault = VolatilityShieldClient::new(env, &env.register(VolatilityShield, ()));
+        vault.init(
+            &admin,
+            &assets[0].0,
+            &oracle.address,
+            &Address::generate(env),
+            &0u32,
+            &soroban_sdk::vec![env, admin.clone()],
+            &1u32,
+        );
+        vault.add_supported_asset(&assets[0].0);
+        vault.add_supported_asset(&assets[1].0);
+        oracle.set_price(&admin, &assets[1].0, &1_000_000_000);
+        Harness {
+            env,
+            vault,
+            oracle,
+            admin,
+            assets,
+            users: (0..USERS).map(|_| Address::generate(env)).collect(),
+            alt_price: 1_000_000_000,
+        }
+    }
+
+    fn apply(&mut self, op: &Op) {
+        match *op {
+            Op::Deposit { user, alt, amount } => {
+                let user = &self.users[user as usize % USERS];
+                let (asset, admin) = &self.assets[alt as usize];
+                let amount = positive(amount);
+                let held = TokenClient::new(self.env, asset).balance(user);
+                if held.checked_add(amount).is_none() {
+                    return;
+                }
+                admin.mint(user, &amount);
+                let before = self.vault.balance(user);
+                if graceful(op, self.vault.try_deposit(user, asset, &amount, &None)).is_none() {
+                    return;
+                }
+                // Shares never redeem for more than the deposit was worth.
+                let price = if alt { self.alt_price } else { 1_000_000_000 };
+                let minted = self.vault.balance(user) - before;
+                let worth = amount.checked_mul(price).map(|v| v / 1_000_000_000);
+                let redeemable = graceful(op, self.vault.try_convert_to_assets(&minted));
+                if let (Some(worth), Some(redeemable)) = (worth, redeemable) {
+                    assert!(
+                        redeemable <= worth,
+                        "{op:?}: {minted} shares redeem for {redeemable}, deposit worth {worth}"
+                    );
+                }
+            }
+            Op::Withdraw { user, alt, shares } => {
+                let user = &self.users[user as usize % USERS];
+                let shares = positive(shares).min(self.vault.balance(user).max(1));
+                let asset = &self.assets[alt as usize].0;
+                graceful(op, self.vault.try_withdraw(user, user, asset, &shares));
+            }
+            Op::Queue { user, shares } => {
+                let user = &self.users[user as usize % USERS];
+                let shares = positive(shares).min(self.vault.balance(user).max(1));
+                let asset = &self.assets[0].0;
+                graceful(
+                    op,
+                    self.vault.try_queue_withdraw(user, user, asset, &shares),
+                );
+            }
+            Op::Fulfil { limit } => {
+                graceful(
+                    op,
+                    self.vault
+                        .try_fulfil_withdrawals(&self.users[0], &(limit as u32 % 8)),
+                );
+            }
+            Op::Claim { ticket } => {
+                graceful(op, self.vault.try_claim_withdrawal(&(ticket as u64)));
+            }
+            Op::Cancel { ticket } => {
+                graceful(
+                    op,
+                    self.vault.try_cancel_queued_withdrawal(&(ticket as u64)),
+                );
+            }
+            Op::SetAltPrice { mantissa, decimals } => {
+                let price = (mantissa as i128)
+                    .checked_mul(10_i128.pow(decimals as u32 % 20))
+                    .unwrap_or(i128::MAX)
+                    .max(1);
+                self.alt_price = price;
+                self.oracle
+                    .set_price(&self.admin, &self.assets[1].0, &price);
+            }
+            Op::SetTotalAssets { total_assets } => {
+                self.vault.set_total_assets(&((total_assets >> 1) as i128));
+            }
+        }
+    }
+
+    /// Shares held plus shares waiting in the queue add up to the supply.
+    fn check(&self, op: &Op) {
+        let mut accounted: i128 = 0;
+        for user in &self.users {
+            let summary = self.vault.get_user_summary(user);
+            assert!(summary.balance >= 0, "{op:?}: negative balance");
+            accounted += summary.balance + summary.queued_shares;
+        }
+        assert_eq!(
+            accounted,
+            self.vault.total_shares(),
+            "{op:?}: share ledger out of balance"
+        );
+    }
+}
+
+fuzz_target!(|ops: Vec<Op>| {
+    let env = Env::new_with_config(EnvTestConfig {
+        capture_snapshot_at_drop: false,
+    });
+    let mut harness = Harness::new(&env);
+    for op in ops.iter().take(32) {
+        harness.apply(op);
+        harness.check(op);
+    }
+});
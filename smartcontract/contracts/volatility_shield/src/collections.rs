@@ -0,0 +1,352 @@
+//! Keyed persistent storage for the vault's unbounded collections.
+//!
+//! Instance storage is loaded on every invocation and is size-limited, so
+//! anything that grows with the number of users or proposals lives here
+//! instead: one persistent entry per item (or per page of items), with TTLs
+//! bumped on every read and write.
+
+use soroban_sdk::{contracttype, Address, Env, IntoVal, TryFromVal, Val, Vec};
+
+use crate::{
+    math, ClaimableWithdrawal, DataKey, Error, Proposal, VolatilityShield, WithdrawalTicket,
+};
+
+/// Number of items stored per page for paged lists and histories.
+pub const PAGE_SIZE: u32 = 64;
+
+/// Address sets stored as paged lists.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ListKind {
+    Blocklist,
+    Allowlist,
+}
+
+/// Capped histories stored as paged ring buffers.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HistoryKind {
+    SharePrice,
+    Pause,
+}
+
+#[contracttype]
+#[derive(Clone)]
+pub enum CollectionKey {
+    /// A governance proposal, by id.
+    Proposal(u64),
+    /// Lowest id that may still hold a live proposal.
+    ProposalHead,
+    /// `(head, tail)` ticket ids of the withdrawal queue.
+    WithdrawQueueBounds,
+    /// Number of addresses in a list.
+    ListLen(ListKind),
+    /// One page of a list.
+    ListPage(ListKind, u32),
+    /// Position of an address inside a list.
+    ListMember(ListKind, Address),
+    /// `(start, len)` of a history ring buffer, in absolute entry indices.
+    HistoryBounds(HistoryKind),
+    /// One page of a history.
+    HistoryPage(HistoryKind, u32),
+    /// A queued withdrawal ticket, by id.
+    WithdrawalTicket(u64),
+    /// Ids of a user's pending withdrawal tickets, oldest first.
+    UserTickets(Address),
+    /// A fulfilled ticket awaiting its claim, by ticket id.
+    ClaimableWithdrawal(u64),
+    /// Token units locked for all unclaimed fulfilled tickets.
+    ClaimableTotal,
+}
+
+fn get<T>(env: &Env, key: &CollectionKey) -> Option<T>
+where
+    T: TryFromVal<Env, Val>,
+    T::Error: core::fmt::Debug,
+{
+    VolatilityShield::get_persistent(env, key)
+}
+
+fn set<T>(env: &Env, key: &CollectionKey, value: &T)
+where
+    T: IntoVal<Env, Val>,
+{
+    VolatilityShield::set_persistent(env, key, value)
+}
+
+fn remove(env: &Env, key: &CollectionKey) {
+    env.storage().persistent().remove(key);
+}
+
+// ── Address lists ─────────────────────────────
+
+pub fn list_contains(env: &Env, kind: ListKind, user: &Address) -> bool {
+    env.storage()
+        .persistent()
+        .has(&CollectionKey::ListMember(kind, user.clone()))
+}
+
+/// Append `user` to the list. Returns `false` if it was already present.
+pub fn list_add(env: &Env, kind: ListKind, user: &Address) -> bool {
+    if list_contains(env, kind, user) {
+        return false;
+    }
+    let len: u32 = get(env, &CollectionKey::ListLen(kind)).unwrap_or(0);
+    let page_key = CollectionKey::ListPage(kind, len / PAGE_SIZE);
+    let mut page: Vec<Address> = get(env, &page_key).unwrap_or(Vec::new(env));
+    page.push_back(user.clone());
+    set(env, &page_key, &page);
+    set(env, &CollectionKey::ListMember(kind, user.clone()), &len);
+    set(env, &CollectionKey::ListLen(kind), &(len + 1));
+    true
+}
+
+/// Remove `user` from the list by moving the last entry into its slot.
+/// Returns `false` if it was not present.
+pub fn list_remove(env: &Env, kind: ListKind, user: &Address) -> bool {
+    let member_key = CollectionKey::ListMember(kind, user.clone());
+    let index: u32 = match get(env, &member_key) {
+        Some(index) => index,
+        None => return false,
+    };
+    let last = get::<u32>(env, &CollectionKey::ListLen(kind)).unwrap_or(1) - 1;
+
+    let last_page_key = CollectionKey::ListPage(kind, last / PAGE_SIZE);
+    let mut last_page: Vec<Address> = get(env, &last_page_key).unwrap_or(Vec::new(env));
+    let moved = last_page.pop_back().unwrap();
+    if last_page.is_empty() {
+        remove(env, &last_page_key);
+    } else {
+        set(env, &last_page_key, &last_page);
+    }
+
+    if index != last {
+        let page_key = CollectionKey::ListPage(kind, index / PAGE_SIZE);
+        let mut page: Vec<Address> = get(env, &page_key).unwrap();
+        page.set(index % PAGE_SIZE, moved.clone());
+        set(env, &page_key, &page);
+        set(env, &CollectionKey::ListMember(kind, moved), &index);
+    }
+
+    remove(env, &member_key);
+    set(env, &CollectionKey::ListLen(kind), &last);
+    true
+}
+
+pub fn list_all(env: &Env, kind: ListKind) -> Vec<Address> {
+    let len: u32 = get(env, &CollectionKey::ListLen(kind)).unwrap_or(0);
+    let mut all = Vec::new(env);
+    for page in 0..len.div_ceil(PAGE_SIZE) {
+        let items: Vec<Address> = get(env, &CollectionKey::ListPage(kind, page)).unwrap();
+        all.append(&items);
+    }
+    all
+}
+
+// ── Capped histories ──────────────────────────
+
+fn history_bounds(env: &Env, kind: HistoryKind) -> (u32, u32) {
+    get(env, &CollectionKey::HistoryBounds(kind)).unwrap_or((0, 0))
+}
+
+/// Append an entry, evicting the oldest one once `cap` entries are stored.
+pub fn history_push<T>(env: &Env, kind: HistoryKind, cap: u32, entry: T)
+where
+    T: IntoVal<Env, Val> + TryFromVal<Env, Val>,
+    T::Error: core::fmt::Debug,
+{
+    let (mut start, mut len) = history_bounds(env, kind);
+    let index = start + len;
+    let page_key = CollectionKey::HistoryPage(kind, index / PAGE_SIZE);
+    let mut page: Vec<T> = get(env, &page_key).unwrap_or(Vec::new(env));
+    page.push_back(entry);
+    set(env, &page_key, &page);
+
+    if len >= cap {
+        start += 1;
+        if start % PAGE_SIZE == 0 {
+            remove(env, &CollectionKey::HistoryPage(kind, start / PAGE_SIZE - 1));
+        }
+    } else {
+        len += 1;
+    }
+    set(env, &CollectionKey::HistoryBounds(kind), &(start, len));
+}
+
+/// All stored entries, oldest first.
+pub fn history_all<T>(env: &Env, kind: HistoryKind) -> Vec<T>
+where
+    T: IntoVal<Env, Val> + TryFromVal<Env, Val>,
+    T::Error: core::fmt::Debug,
+{
+    let (start, len) = history_bounds(env, kind);
+    let mut all = Vec::new(env);
+    let mut page_no = u32::MAX;
+    let mut page: Vec<T> = Vec::new(env);
+    for index in start..start + len {
+        if index / PAGE_SIZE != page_no {
+            page_no = index / PAGE_SIZE;
+            page = get(env, &CollectionKey::HistoryPage(kind, page_no)).unwrap();
+        }
+        all.push_back(page.get(index % PAGE_SIZE).unwrap());
+    }
+    all
+}
+
+// ── Withdrawal queue ──────────────────────────
+
+pub fn queue_bounds(env: &Env) -> (u64, u64) {
+    get(env, &CollectionKey::WithdrawQueueBounds).unwrap_or((0, 0))
+}
+
+/// Append a ticket to the back of the FIFO queue, assigning its id.
+pub fn queue_push(env: &Env, mut ticket: WithdrawalTicket) -> WithdrawalTicket {
+    let (head, tail) = queue_bounds(env);
+    ticket.id = tail;
+    set(env, &CollectionKey::WithdrawalTicket(tail), &ticket);
+
+    let mut ids = user_ticket_ids(env, &ticket.user);
+    ids.push_back(tail);
+    set(env, &CollectionKey::UserTickets(ticket.user.clone()), &ids);
+
+    set(env, &CollectionKey::WithdrawQueueBounds, &(head, tail + 1));
+    ticket
+}
+
+pub fn queue_get(env: &Env, id: u64) -> Option<WithdrawalTicket> {
+    get(env, &CollectionKey::WithdrawalTicket(id))
+}
+
+/// Ids of `user`'s pending tickets, oldest first.
+pub fn user_ticket_ids(env: &Env, user: &Address) -> Vec<u64> {
+    get(env, &CollectionKey::UserTickets(user.clone())).unwrap_or(Vec::new(env))
+}
+
+/// Remove ticket `id`, leaving a gap that `queue_pop_front` skips.
+pub fn queue_remove(env: &Env, id: u64) -> Option<WithdrawalTicket> {
+    let ticket = queue_get(env, id)?;
+    remove(env, &CollectionKey::WithdrawalTicket(id));
+
+    let user_key = CollectionKey::UserTickets(ticket.user.clone());
+    let mut ids = user_ticket_ids(env, &ticket.user);
+    if let Some(index) = ids.first_index_of(id) {
+        ids.remove(index);
+    }
+    if ids.is_empty() {
+        remove(env, &user_key);
+    } else {
+        set(env, &user_key, &ids);
+    }
+    Some(ticket)
+}
+
+/// The oldest pending ticket, without removing it. Gaps left by cancelled
+/// tickets are skipped and dropped from the queue.
+pub fn queue_front(env: &Env) -> Option<WithdrawalTicket> {
+    let (start, tail) = queue_bounds(env);
+    let mut head = start;
+    let mut front = None;
+    while head < tail && front.is_none() {
+        front = queue_get(env, head);
+        if front.is_none() {
+            head += 1;
+        }
+    }
+    if head != start {
+        set(env, &CollectionKey::WithdrawQueueBounds, &(head, tail));
+    }
+    front
+}
+
+/// Take the oldest pending ticket off the queue.
+pub fn queue_pop_front(env: &Env) -> Option<WithdrawalTicket> {
+    let (mut head, tail) = queue_bounds(env);
+    let mut popped = None;
+    while head < tail && popped.is_none() {
+        popped = queue_remove(env, head);
+        head += 1;
+    }
+    set(env, &CollectionKey::WithdrawQueueBounds, &(head, tail));
+    popped
+}
+
+/// All pending tickets in FIFO order.
+pub fn queue_all(env: &Env) -> Vec<WithdrawalTicket> {
+    let (head, tail) = queue_bounds(env);
+    let mut all = Vec::new(env);
+    for id in head..tail {
+        if let Some(ticket) = queue_get(env, id) {
+            all.push_back(ticket);
+        }
+    }
+    all
+}
+
+// ── Claimable withdrawals ─────────────────────
+
+pub fn claimable_total(env: &Env) -> i128 {
+    get(env, &CollectionKey::ClaimableTotal).unwrap_or(0)
+}
+
+pub fn claimable_get(env: &Env, ticket_id: u64) -> Option<ClaimableWithdrawal> {
+    get(env, &CollectionKey::ClaimableWithdrawal(ticket_id))
+}
+
+pub fn claimable_add(env: &Env, claim: &ClaimableWithdrawal) -> Result<(), Error> {
+    let total = math::add(claimable_total(env), claim.amount)?;
+    set(env, &CollectionKey::ClaimableWithdrawal(claim.ticket_id), claim);
+    set(env, &CollectionKey::ClaimableTotal, &total);
+    Ok(())
+}
+
+pub fn claimable_remove(env: &Env, ticket_id: u64) -> Result<Option<ClaimableWithdrawal>, Error> {
+    let Some(claim) = claimable_get(env, ticket_id) else {
+        return Ok(None);
+    };
+    let total = math::sub(claimable_total(env), claim.amount)?;
+    remove(env, &CollectionKey::ClaimableWithdrawal(ticket_id));
+    set(env, &CollectionKey::ClaimableTotal, &total);
+    Ok(Some(claim))
+}
+
+// ── Proposals ─────────────────────────────────
+
+pub fn read_proposal(env: &Env, id: u64) -> Option<Proposal> {
+    get(env, &CollectionKey::Proposal(id))
+}
+
+pub fn write_proposal(env: &Env, proposal: &Proposal) {
+    set(env, &CollectionKey::Proposal(proposal.id), proposal);
+}
+
+/// Remove proposal `id`, leaving a gap that the head skips once it gets there.
+pub fn remove_proposal(env: &Env, id: u64) {
+    remove(env, &CollectionKey::Proposal(id));
+
+    let (mut head, tail) = proposal_bounds(env);
+    if id != head {
+        return;
+    }
+    let persistent = env.storage().persistent();
+    while head < tail && !persistent.has(&CollectionKey::Proposal(head)) {
+        head += 1;
+    }
+    set_proposal_head(env, head);
+}
+
+/// `(head, tail)` ids of live proposals. Every live proposal has an id in
+/// `head..tail`; ids in that range without a proposal were pruned or
+/// cancelled. `tail` is the id the next proposal gets.
+pub fn proposal_bounds(env: &Env) -> (u64, u64) {
+    let head = get(env, &CollectionKey::ProposalHead).unwrap_or(1);
+    let tail = env
+        .storage()
+        .instance()
+        .get(&DataKey::NextProposalId)
+        .unwrap_or(1);
+    (head, tail)
+}
+
+pub fn set_proposal_head(env: &Env, head: u64) {
+    set(env, &CollectionKey::ProposalHead, &head);
+}
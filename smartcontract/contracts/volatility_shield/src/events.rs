@@ -0,0 +1,556 @@
+//! Typed, versioned event schema.
+//!
+//! Every event the vault emits is declared once in `vault_events!` below and
+//! published through [`publish`], so topic layout and payload shape cannot
+//! drift between emitting paths. Topics are always
+//! `[Symbol(NAME), EVENT_SCHEMA_VERSION, keys...]`; the data is the event
+//! struct itself, which encodes as a map keyed by field name.
+//!
+//! Bump `EVENT_SCHEMA_VERSION` whenever an existing event changes its topics
+//! or fields, and regenerate `docs/contract_events.schema.json` with
+//! `UPDATE_EVENT_SCHEMA=1 cargo test -p volatility_shield events`.
+
+use soroban_sdk::{contracttype, Address, BytesN, Env, IntoVal, Map, String, Symbol, Val, Vec};
+
+use crate::rebalance::{RebalanceMode, RebalanceReport};
+use crate::ttl::TtlKeyType;
+use crate::ActionKind;
+
+/// Version carried as the second topic of every event.
+pub const EVENT_SCHEMA_VERSION: u32 = 1;
+
+/// An event with a fixed name and topic layout.
+pub trait VaultEvent: IntoVal<Env, Val> {
+    /// Event name, published as the first topic.
+    const NAME: &'static str;
+    /// Fields repeated as topics after the schema version, so indexers can
+    /// filter on them without decoding the payload.
+    const KEYS: &'static [&'static str];
+
+    /// Full topic list: name, schema version, then the `KEYS` field values.
+    fn topics(&self, env: &Env) -> Vec<Val>;
+}
+
+/// Publish `event` with its versioned topics.
+pub fn publish<E: VaultEvent>(env: &Env, event: E) {
+    env.events().publish(event.topics(env), event);
+}
+
+macro_rules! vault_events {
+    ($(
+        $(#[doc = $doc:literal])*
+        $name:ident [$($key:ident),*] {
+            $($field:ident: $ty:ident $(<$($arg:tt),+>)?),* $(,)?
+        }
+    )*) => {
+        $(
+            $(#[doc = $doc])*
+            #[contracttype]
+            #[derive(Clone, Debug, Eq, PartialEq)]
+            pub struct $name {
+                $(pub $field: $ty $(<$($arg),+>)?,)*
+            }
+
+            impl VaultEvent for $name {
+                const NAME: &'static str = stringify!($name);
+                const KEYS: &'static [&'static str] = &[$(stringify!($key)),*];
+
+                fn topics(&self, env: &Env) -> Vec<Val> {
+                    #[allow(unused_mut)]
+                    let mut topics: Vec<Val> = Vec::from_array(
+                        env,
+                        [
+                            Symbol::new(env, Self::NAME).into_val(env),
+                            EVENT_SCHEMA_VERSION.into_val(env),
+                        ],
+                    );
+                    $(topics.push_back(self.$key.into_val(env));)*
+                    topics
+                }
+            }
+        )*
+
+        /// Name of every event the vault can emit, in declaration order.
+        pub const EVENT_NAMES: &[&str] = &[$(stringify!($name)),*];
+
+        /// `(name, keys, spec XDR)` of every event, used to generate the schema.
+        #[cfg(test)]
+        const EVENT_SPECS: &[(&str, &[&str], &[u8])] =
+            &[$(($name::NAME, $name::KEYS, &$name::spec_xdr())),*];
+
+        /// Publish one sample of every event.
+        #[cfg(test)]
+        fn publish_samples(env: &Env) {
+            $(publish(env, $name { $($field: tests::Sample::sample(env)),* });)*
+        }
+    };
+}
+
+vault_events! {
+    // ── Errors ───────────────────────────────────
+
+    /// A call failed with `error`; emitted just before the error is returned.
+    ErrorRaised [error] { error: Symbol, code: u32 }
+
+    // ── Setup and accounting ─────────────────────
+
+    VaultInitialized [admin] {
+        admin: Address,
+        asset: Address,
+        oracle: Address,
+        treasury: Address,
+        fee_percentage: u32,
+        guardians: Vec<Address>,
+        threshold: u32,
+    }
+    /// `set_total_assets` overwrote the accounted total assets.
+    TotalAssetsSet [] { total_assets: i128 }
+    /// `set_total_shares` overwrote the share supply.
+    TotalSharesSet [] { total_shares: i128 }
+    /// `set_balance` overwrote a user's share balance.
+    BalanceSet [user] { user: Address, shares: i128 }
+    /// `set_token` replaced the token withdrawals are paid in.
+    TokenSet [] { token: Address }
+
+    // ── Governance ───────────────────────────────
+
+    /// A guardian proposed a governance action.
+    ProposalCreated [proposal_id] { proposal_id: u64, proposer: Address, kind: ActionKind }
+    /// The timelock of a new proposal started running.
+    TimelockStarted [proposal_id] { proposal_id: u64, proposed_at: u64 }
+    /// A guardian approved a proposal.
+    ProposalApproved [proposal_id] { proposal_id: u64, guardian: Address }
+    /// A governance action was applied.
+    ProposalExecuted [proposal_id] { proposal_id: u64, kind: ActionKind, executor: Address }
+    /// The timelock of an applied action had elapsed.
+    TimelockExecuted [proposal_id] { proposal_id: u64, kind: ActionKind }
+    /// A pending proposal was cancelled.
+    ProposalCancelled [proposal_id] { proposal_id: u64, caller: Address }
+    /// An emergency action reached its approval threshold and was applied.
+    EmergencyActionExecuted [proposal_id] { proposal_id: u64, guardian: Address, approvals: u32 }
+    /// Share of guardians required to fast-track an emergency action.
+    EmergencyThresholdSet [] { bps: u32 }
+    /// Token used for vote weighting.
+    GovernanceTokenSet [] { token: Address }
+    /// A token holder voted on a proposal.
+    VoteCast [voter] { voter: Address, proposal_id: u64, support: bool, voting_power: i128 }
+    GuardianAdded [guardian] { guardian: Address }
+    GuardianRemoved [guardian] { guardian: Address }
+    /// Number of guardian approvals required to execute a proposal.
+    ThresholdChanged [] { threshold: u32 }
+    /// Governance replaced the oracle.
+    OracleRotated [] { previous: Address, oracle: Address }
+    /// A contract upgrade was proposed; depositors may exit before `executable_at`.
+    UpgradeProposed [proposal_id] { proposal_id: u64, wasm_hash: BytesN<32>, executable_at: u64 }
+    UpgradeCancelled [proposal_id] { proposal_id: u64, wasm_hash: BytesN<32> }
+    UpgradeExecuted [] { wasm_hash: BytesN<32> }
+    ProposalTtlLedgersSet [] { ledgers: u32 }
+    /// Expired proposals removed from storage.
+    ProposalsPruned [] { count: u32 }
+    TimelockDurationSet [] { duration: u64 }
+    ActionTimelockSet [kind] { kind: ActionKind, duration: u64 }
+    ActionTimelockCleared [kind] { kind: ActionKind }
+
+    // ── Deposits and withdrawals ─────────────────
+
+    Deposited [depositor] {
+        depositor: Address,
+        asset: Address,
+        amount: i128,
+        shares_minted: i128,
+        share_price: i128,
+        total_assets: i128,
+        total_shares: i128,
+    }
+    /// A deposit was rejected by the per-user or global cap.
+    DepositCapExceeded [depositor] { depositor: Address, amount: i128 }
+    /// One entry of a batch deposit was skipped; `reason` is a short code.
+    BatchDepositFailed [depositor] { depositor: Address, asset: Address, amount: i128, reason: Symbol }
+    Withdrawn [withdrawer] {
+        withdrawer: Address,
+        asset: Address,
+        shares_burned: i128,
+        amount_out: i128,
+        total_assets: i128,
+        total_shares: i128,
+    }
+    /// A withdrawal was rejected by the per-transaction cap.
+    WithdrawCapExceeded [withdrawer] { withdrawer: Address, amount: i128 }
+    /// One entry of a batch withdrawal was skipped; `reason` is a short code.
+    BatchWithdrawFailed [withdrawer] { withdrawer: Address, shares: i128, reason: Symbol }
+    WithdrawQueued [user, ticket_id] {
+        user: Address,
+        ticket_id: u64,
+        asset: Address,
+        shares: i128,
+        share_price: i128,
+        total_assets: i128,
+        total_shares: i128,
+    }
+    WithdrawQueueThresholdSet [] { threshold: i128 }
+    /// A queued withdrawal was paid out into a claimable balance.
+    WithdrawFulfilled [user, ticket_id] { user: Address, ticket_id: u64, shares: i128, amount: i128, share_price: i128 }
+    /// Totals of one `fulfil_withdrawals` call; `bounty` went to the keeper.
+    WithdrawalsProcessed [keeper] {
+        keeper: Address,
+        processed: u32,
+        paid_out: i128,
+        bounty: i128,
+        total_assets: i128,
+        total_shares: i128,
+    }
+    KeeperBountySet [] { bps: u32 }
+    /// Order in which strategies are drained to cover withdrawals.
+    UnwindOrderSet [] { order: Vec<Address> }
+    WithdrawClaimed [user, ticket_id] { user: Address, ticket_id: u64, amount: i128 }
+    WithdrawCancelled [user, ticket_id] { user: Address, ticket_id: u64, shares: i128 }
+    /// A user exited during emergency shutdown.
+    EmergencyWithdrawn [user] {
+        user: Address,
+        shares: i128,
+        amount: i128,
+        timestamp: u64,
+        total_assets: i128,
+        total_shares: i128,
+    }
+    DelegateSet [owner] { owner: Address, delegate: Address }
+    DelegateRemoved [owner] { owner: Address }
+    DepositCapSet [] { per_user: i128, global: i128 }
+    MinInitialDepositSet [] { amount: i128 }
+    WithdrawCapSet [] { per_tx: i128 }
+    IdleBufferSet [] { target_bps: u32, min_amount: i128, max_amount: i128 }
+
+    // ── Rebalancing ──────────────────────────────
+
+    RebalanceModeSet [] { mode: RebalanceMode }
+    RebalanceTriggerSet [] { drift_bps: u32, min_interval: u64, max_slippage_bps: u32, keeper_reward: i128 }
+    /// A keeper triggered a rebalance and was paid `reward` out of the vault.
+    RebalancePoked [keeper] { keeper: Address, reward: i128, total_assets: i128 }
+    /// An incremental rebalance started.
+    RebalanceStarted [caller] { caller: Address, strategy_count: u32, max_move_per_step: i128 }
+    /// One strategy moved during an incremental rebalance.
+    RebalanceStep [strategy] { strategy: Address, delta: i128, target_balance: i128 }
+    /// An incremental rebalance finished.
+    RebalanceCompleted [] { moved: i128, steps: u32 }
+    /// The admin abandoned an incremental rebalance.
+    RebalanceCancelled [] { cursor: u32, moved: i128 }
+    /// A strategy could not be rebalanced and was skipped.
+    RebalancePartialFailure [failed_strategy] { failed_strategy: Address, reason: String }
+    /// A strategy reported a withdrawal but the tokens did not arrive.
+    RebalanceWithdrawTransferFailed [strategy] { strategy: Address, amount: i128 }
+    /// A strategy ended a rebalance further from its target than allowed.
+    SlippageExceeded [strategy] { strategy: Address, expected_balance: i128, actual_balance: i128, slippage_bps: i128 }
+    Rebalanced [] { total_assets_before: i128, total_assets_after: i128 }
+    /// Vault totals and per-strategy balances after a rebalance.
+    VaultSnapshot [] { total_assets: i128, total_shares: i128, allocations: Map<Address, i128> }
+    /// Per-strategy outcomes of a completed rebalance.
+    RebalanceReported [] { report: RebalanceReport }
+    /// Rebalance refused because the oracle last updated at `last_update`.
+    OracleStale [] { last_update: u64 }
+
+    // ── Strategies and harvest ───────────────────
+
+    StrategyAdded [strategy] { strategy: Address }
+    /// A strategy was marked unhealthy.
+    StrategyFlagged [strategy] { strategy: Address, timestamp: u64 }
+    /// A health check recorded a new balance or failure count for a strategy.
+    StrategyHealthUpdated [strategy] {
+        strategy: Address,
+        balance: i128,
+        is_healthy: bool,
+        consecutive_failures: u32,
+    }
+    /// A strategy was removed after returning `final_balance`.
+    StrategyRemoved [strategy] { strategy: Address, final_balance: i128 }
+    MaxConsecutiveFailuresSet [] { threshold: u32 }
+    /// Ledger from which the next harvest may run.
+    HarvestScheduled [] { next_eligible: u32 }
+    Harvested [] { total_yield: i128, total_assets: i128, total_shares: i128 }
+
+    // ── Administration ───────────────────────────
+
+    AssetAdded [asset] { asset: Address }
+    /// The oracle published new target allocations (bps per strategy).
+    OracleDataUpdated [] { allocations: Map<Address, i128>, timestamp: u64 }
+    MaxStalenessSet [] { seconds: u64 }
+    OracleCircuitBreakerActivated [] { timestamp: u64 }
+    OracleCircuitBreakerReset [] { timestamp: u64 }
+    UserBlocked [user] { user: Address }
+    UserUnblocked [user] { user: Address }
+    UserAllowlisted [user] { user: Address }
+    UserRemovedFromAllowlist [user] { user: Address }
+    BlocklistModeSet [] { active: bool }
+    AllowlistModeSet [] { active: bool }
+    /// A blocked or non-allowlisted user was refused.
+    ComplianceRejected [user] { user: Address }
+    VaultPaused [caller] { caller: Address, timestamp: u64 }
+    VaultUnpaused [caller] { caller: Address, timestamp: u64 }
+    /// The admin switched the vault into emergency-withdraw-only mode.
+    EmergencyShutdownActivated [admin] { admin: Address, timestamp: u64 }
+    TtlPolicySet [key_type] { key_type: TtlKeyType, threshold: u32, extend_to: u32 }
+    /// A new vault metadata revision was published.
+    MetadataUpdated [] { name: String, risk_rating: u32, version: u32 }
+    MigrationStarted [] { from_version: u32, to_version: u32 }
+    MigrationStepCompleted [] { from_version: u32, to_version: u32 }
+    /// A migration reached its target version.
+    Migrated [] { version: u32 }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::format;
+    use std::string::{String as StdString, ToString};
+    use std::vec::Vec as StdVec;
+
+    use super::*;
+    use soroban_sdk::testutils::{Address as _, Events};
+    use soroban_sdk::xdr::{Limits, ReadXdr, ScSpecEntry, ScSpecTypeDef};
+    use soroban_sdk::TryFromVal;
+
+    use crate::VolatilityShield;
+
+    const SCHEMA_PATH: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../../docs/contract_events.schema.json"
+    );
+    const COMMITTED_SCHEMA: &str = include_str!("../../../../docs/contract_events.schema.json");
+
+    /// Placeholder values used to emit every event once.
+    pub(super) trait Sample {
+        fn sample(env: &Env) -> Self;
+    }
+
+    impl Sample for Address {
+        fn sample(env: &Env) -> Self {
+            Address::generate(env)
+        }
+    }
+
+    impl Sample for i128 {
+        fn sample(_env: &Env) -> Self {
+            1
+        }
+    }
+
+    impl Sample for u32 {
+        fn sample(_env: &Env) -> Self {
+            1
+        }
+    }
+
+    impl Sample for u64 {
+        fn sample(_env: &Env) -> Self {
+            1
+        }
+    }
+
+    impl Sample for bool {
+        fn sample(_env: &Env) -> Self {
+            true
+        }
+    }
+
+    impl Sample for Symbol {
+        fn sample(env: &Env) -> Self {
+            Symbol::new(env, "sample")
+        }
+    }
+
+    impl Sample for String {
+        fn sample(env: &Env) -> Self {
+            String::from_str(env, "sample")
+        }
+    }
+
+    impl Sample for BytesN<32> {
+        fn sample(env: &Env) -> Self {
+            BytesN::from_array(env, &[1; 32])
+        }
+    }
+
+    impl Sample for Vec<Address> {
+        fn sample(env: &Env) -> Self {
+            Vec::new(env)
+        }
+    }
+
+    impl Sample for Map<Address, i128> {
+        fn sample(env: &Env) -> Self {
+            Map::new(env)
+        }
+    }
+
+    impl Sample for ActionKind {
+        fn sample(_env: &Env) -> Self {
+            ActionKind::Rebalance
+        }
+    }
+
+    impl Sample for TtlKeyType {
+        fn sample(_env: &Env) -> Self {
+            TtlKeyType::Instance
+        }
+    }
+
+    impl Sample for RebalanceMode {
+        fn sample(_env: &Env) -> Self {
+            RebalanceMode::Atomic
+        }
+    }
+
+    impl Sample for RebalanceReport {
+        fn sample(env: &Env) -> Self {
+            RebalanceReport {
+                total_assets_before: 1,
+                total_assets_after: 1,
+                withdrawn: 0,
+                deposited: 0,
+                successful_strategies: 0,
+                outcomes: Vec::new(env),
+            }
+        }
+    }
+
+    fn type_name(ty: &ScSpecTypeDef) -> StdString {
+        match ty {
+            ScSpecTypeDef::Bool => "bool".to_string(),
+            ScSpecTypeDef::U32 => "u32".to_string(),
+            ScSpecTypeDef::U64 => "u64".to_string(),
+            ScSpecTypeDef::I128 => "i128".to_string(),
+            ScSpecTypeDef::Symbol => "symbol".to_string(),
+            ScSpecTypeDef::String => "string".to_string(),
+            ScSpecTypeDef::Address => "address".to_string(),
+            ScSpecTypeDef::BytesN(bytes) => format!("bytes{}", bytes.n),
+            ScSpecTypeDef::Vec(vec) => format!("vec<{}>", type_name(&vec.element_type)),
+            ScSpecTypeDef::Map(map) => format!(
+                "map<{}, {}>",
+                type_name(&map.key_type),
+                type_name(&map.value_type)
+            ),
+            ScSpecTypeDef::Udt(udt) => udt.name.to_utf8_string_lossy(),
+            other => panic!("event field type {other:?} has no schema name"),
+        }
+    }
+
+    /// `(name, topic keys, [(field, type)])` of one event.
+    type EventFields = (
+        &'static str,
+        &'static [&'static str],
+        StdVec<(StdString, StdString)>,
+    );
+
+    /// Field names and types of every event, decoded from the contract spec.
+    fn event_fields() -> StdVec<EventFields> {
+        EVENT_SPECS
+            .iter()
+            .map(|&(name, keys, xdr)| {
+                let entry = ScSpecEntry::from_xdr(xdr, Limits::none()).unwrap();
+                let ScSpecEntry::UdtStructV0(spec) = entry else {
+                    panic!("{name} is not a struct");
+                };
+                assert_eq!(spec.name.to_utf8_string_lossy(), name);
+                let fields = spec
+                    .fields
+                    .iter()
+                    .map(|field| (field.name.to_utf8_string_lossy(), type_name(&field.type_)))
+                    .collect();
+                (name, keys, fields)
+            })
+            .collect()
+    }
+
+    fn generate_schema() -> StdString {
+        let mut out = format!("{{\n  \"version\": {EVENT_SCHEMA_VERSION},\n  \"events\": [\n");
+        let events = event_fields();
+        for (i, (name, keys, fields)) in events.iter().enumerate() {
+            let topics: StdVec<StdString> = ["\"name\"".to_string(), "\"version\"".to_string()]
+                .into_iter()
+                .chain(keys.iter().map(|key| format!("\"{key}\"")))
+                .collect();
+            let fields: StdVec<StdString> = fields
+                .iter()
+                .map(|(field, ty)| format!("{{ \"name\": \"{field}\", \"type\": \"{ty}\" }}"))
+                .collect();
+            out.push_str(&format!(
+                "    {{\n      \"name\": \"{name}\",\n      \"topics\": [{}],\n      \"fields\": [\n        {}\n      ]\n    }}{}\n",
+                topics.join(", "),
+                fields.join(",\n        "),
+                if i + 1 == events.len() { "" } else { "," },
+            ));
+        }
+        out.push_str("  ]\n}\n");
+        out
+    }
+
+    #[test]
+    fn committed_schema_matches_event_definitions() {
+        let schema = generate_schema();
+        if std::env::var("UPDATE_EVENT_SCHEMA").is_ok() {
+            std::fs::write(SCHEMA_PATH, &schema).unwrap();
+            return;
+        }
+        assert_eq!(
+            COMMITTED_SCHEMA, schema,
+            "event definitions changed; bump EVENT_SCHEMA_VERSION if needed and rerun with UPDATE_EVENT_SCHEMA=1"
+        );
+    }
+
+    #[test]
+    fn every_event_is_published_with_versioned_topics_and_declared_fields() {
+        let env = Env::default();
+        let contract_id = env.register(VolatilityShield, ());
+        env.as_contract(&contract_id, || publish_samples(&env));
+
+        let emitted = env.events().all();
+        let expected = event_fields();
+        assert_eq!(emitted.len() as usize, expected.len());
+        assert_eq!(expected.len(), EVENT_NAMES.len());
+
+        for ((_, topics, data), (name, keys, fields)) in emitted.iter().zip(expected.iter()) {
+            let topic_name = Symbol::try_from_val(&env, &topics.get(0).unwrap()).unwrap();
+            assert_eq!(topic_name, Symbol::new(&env, name));
+            let version = u32::try_from_val(&env, &topics.get(1).unwrap()).unwrap();
+            assert_eq!(version, EVENT_SCHEMA_VERSION, "{name}");
+            assert_eq!(topics.len() as usize, 2 + keys.len(), "{name}");
+
+            let payload = Map::<Symbol, Val>::try_from_val(&env, &data).unwrap();
+            assert_eq!(payload.len() as usize, fields.len(), "{name}");
+            for (field, _) in fields.iter() {
+                assert!(
+                    payload.contains_key(Symbol::new(&env, field)),
+                    "{name} payload is missing {field}"
+                );
+            }
+            for key in keys.iter() {
+                assert!(
+                    fields.iter().any(|(field, _)| field == key),
+                    "{name} topic key {key} is not a field"
+                );
+            }
+        }
+
+        let mut names: StdVec<&str> = EVENT_NAMES.to_vec();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), EVENT_NAMES.len(), "event names must be unique");
+    }
+
+    #[test]
+    fn events_are_only_published_through_the_schema() {
+        let src = concat!(env!("CARGO_MANIFEST_DIR"), "/src");
+        for entry in std::fs::read_dir(src).unwrap() {
+            let path = entry.unwrap().path();
+            let file = path.file_name().unwrap().to_string_lossy().to_string();
+            if file == "events.rs" || file == "test.rs" {
+                continue;
+            }
+            let source = std::fs::read_to_string(&path).unwrap();
+            assert!(
+                !source.contains(".events()"),
+                "{file} publishes an event outside events::publish"
+            );
+        }
+    }
+}
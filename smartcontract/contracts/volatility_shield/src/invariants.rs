@@ -62,8 +62,9 @@ proptest! {
         let (_env, client, _admin, _asset) = setup_test_env();
 
         // Initial state
-        assert_eq!(client.convert_to_shares(&amount), amount);
-        assert_eq!(client.convert_to_assets(&amount), amount);
+        let initial_shares = amount * math::VIRTUAL_SHARES;
+        assert_eq!(client.convert_to_shares(&amount), initial_shares);
+        assert_eq!(client.convert_to_assets(&initial_shares), amount);
 
         // State with some growth
         client.set_total_assets(&2000);
@@ -101,6 +102,11 @@ proptest! {
 
         let user = Address::generate(&env);
         stellar_asset_client.mint(&user, &amount);
+        if amount < client.get_min_initial_deposit() {
+            let res = client.try_deposit(&user, &token_id, &amount, &None::<i128>);
+            assert_eq!(res, Err(Ok(Error::InvalidAmount)));
+            return Ok(());
+        }
         client.deposit(&user, &token_id, &amount, &None::<i128>);
         let shares = client.balance(&user);
 
@@ -260,13 +266,13 @@ proptest! {
         stellar_asset_client.mint(&user, &deposit_amount);
 
         let res = client.try_deposit(&user, &token_id, &deposit_amount, &None::<i128>);
-        if deposit_amount > cap {
+        if deposit_amount > cap || deposit_amount < client.get_min_initial_deposit() {
             assert!(res.is_err());
         } else {
             assert!(res.is_ok());
         }
 
-        assert!(client.balance(&user) <= cap);
+        assert!(client.convert_to_assets(&client.balance(&user)) <= cap);
     }
 }
 
@@ -1,7 +1,7 @@
 #![no_std]
 use soroban_sdk::{
-    contract, contracterror, contractimpl, contracttype, symbol_short, token, Address, Env,
-    IntoVal, Map, Symbol, TryFromVal, Val, Vec,
+    contract, contracterror, contractimpl, contracttype, panic_with_error, symbol_short, token,
+    Address, BytesN, Env, IntoVal, Map, Symbol, TryFromVal, Val, Vec,
 };
 
 const DEFAULT_PROPOSAL_TTL_LEDGERS: u32 = 518_400;
@@ -10,8 +10,14 @@ const BALANCE_TTL_THRESHOLD: u32 = DEFAULT_PROPOSAL_TTL_LEDGERS;
 const BALANCE_TTL_BUMP: u32 = BALANCE_TTL_THRESHOLD + DAY_IN_LEDGERS;
 const DEFAULT_PROPOSAL_TTL_SECONDS: u64 = 2_592_000; // 30 days = 518,400 * 5s
 const SHARE_PRICE_HISTORY_CAP: u32 = 365;
+const PAUSE_HISTORY_CAP: u32 = 500;
+const YIELD_HISTORY_CAP: u32 = 730; // 365 harvests × (before, after)
+const PROPOSAL_PRUNE_BATCH: u32 = 16;
 const TVL_HISTORY_CAP: u32 = 500;
 const TVL_HISTORY_KEY: Symbol = symbol_short!("TvlHist");
+const DEFAULT_EMERGENCY_THRESHOLD_BPS: u32 = 6_667; // two thirds of guardians
+const MIN_UPGRADE_TIMELOCK_SECONDS: u64 = 172_800; // 2 days exit window before a WASM swap
+const DEFAULT_MIN_INITIAL_DEPOSIT: i128 = 1_000; // value (9 decimals) seeding an empty vault
 
 // ─────────────────────────────────────────────
 // Error types
@@ -72,6 +78,55 @@ pub enum Error {
     CircuitBreakerActive = 25,
     /// Operation is blocked because emergency shutdown mode is active.
     EmergencyShutdownActive = 26,
+    /// Supplied approval threshold is outside the allowed range.
+    InvalidThreshold = 27,
+    /// Only defensive actions may bypass the timelock through the emergency path.
+    ActionNotDefensive = 28,
+    /// Another contract upgrade is already pending execution.
+    UpgradeAlreadyPending = 29,
+    /// Requested storage version is not reachable through the registered migrations.
+    UnsupportedVersion = 30,
+    /// A storage migration is in progress; state-changing calls are rejected until it completes.
+    MigrationInProgress = 31,
+    /// TTL policy must satisfy `0 < threshold <= extend_to <= max_ttl`.
+    InvalidTtlPolicy = 32,
+    /// Vault metadata name exceeds 64 characters.
+    MetadataNameTooLong = 33,
+    /// Vault metadata description or strategy description is too long.
+    MetadataDescriptionTooLong = 34,
+    /// Risk rating must be between 1 and 5.
+    InvalidRiskRating = 35,
+    /// A configuration value is out of range (URLs, fees, audit links).
+    InvalidConfig = 36,
+    /// User does not hold enough shares for the requested withdrawal.
+    InsufficientShares = 37,
+    /// Asset is not on the vault's accepted asset list.
+    UnsupportedAsset = 38,
+    /// Stored contract version is not the one this build writes.
+    VersionMismatch = 39,
+    /// Caller is neither the position owner nor its registered delegate.
+    NotDelegate = 40,
+    /// Amount must be strictly positive, mint at least one share and, for the
+    /// first deposit into an empty vault, meet the minimum initial deposit.
+    InvalidAmount = 41,
+    /// Emergency withdrawals are only available during emergency shutdown.
+    EmergencyShutdownInactive = 42,
+    /// Withdrawal is at or below the queue threshold and idle liquidity covers
+    /// it, so it must be withdrawn directly.
+    BelowQueueThreshold = 43,
+    /// Atomic rebalance was rejected by its plan or a planned move failed;
+    /// no funds were moved.
+    RebalanceAborted = 44,
+    /// An incremental rebalance is already in flight.
+    RebalanceInProgress = 45,
+    /// No incremental rebalance is in flight.
+    NoRebalanceInProgress = 46,
+    /// Neither the drift nor the interval trigger allows a keeper rebalance.
+    RebalanceNotNeeded = 47,
+    /// The oracle reported a zero or negative price.
+    InvalidPrice = 48,
+    /// A price or share calculation does not fit in an `i128`.
+    ArithmeticOverflow = 49,
 }
 
 impl Error {
@@ -104,6 +159,31 @@ impl Error {
             Error::UserBlocked => Symbol::new(env, "user_blocked"),
             Error::CircuitBreakerActive => Symbol::new(env, "circuit_breaker_active"),
             Error::EmergencyShutdownActive => Symbol::new(env, "emergency_shutdown_active"),
+            Error::InvalidThreshold => Symbol::new(env, "invalid_threshold"),
+            Error::ActionNotDefensive => Symbol::new(env, "action_not_defensive"),
+            Error::UpgradeAlreadyPending => Symbol::new(env, "upgrade_already_pending"),
+            Error::UnsupportedVersion => Symbol::new(env, "unsupported_version"),
+            Error::MigrationInProgress => Symbol::new(env, "migration_in_progress"),
+            Error::InvalidTtlPolicy => Symbol::new(env, "invalid_ttl_policy"),
+            Error::MetadataNameTooLong => Symbol::new(env, "metadata_name_too_long"),
+            Error::MetadataDescriptionTooLong => {
+                Symbol::new(env, "metadata_description_too_long")
+            }
+            Error::InvalidRiskRating => Symbol::new(env, "invalid_risk_rating"),
+            Error::InvalidConfig => Symbol::new(env, "invalid_config"),
+            Error::InsufficientShares => Symbol::new(env, "insufficient_shares"),
+            Error::UnsupportedAsset => Symbol::new(env, "unsupported_asset"),
+            Error::VersionMismatch => Symbol::new(env, "version_mismatch"),
+            Error::NotDelegate => Symbol::new(env, "not_delegate"),
+            Error::InvalidAmount => Symbol::new(env, "invalid_amount"),
+            Error::EmergencyShutdownInactive => Symbol::new(env, "emergency_shutdown_inactive"),
+            Error::BelowQueueThreshold => Symbol::new(env, "below_queue_threshold"),
+            Error::RebalanceAborted => Symbol::new(env, "rebalance_aborted"),
+            Error::RebalanceInProgress => Symbol::new(env, "rebalance_in_progress"),
+            Error::NoRebalanceInProgress => Symbol::new(env, "no_rebalance_in_progress"),
+            Error::RebalanceNotNeeded => Symbol::new(env, "rebalance_not_needed"),
+            Error::InvalidPrice => Symbol::new(env, "invalid_price"),
+            Error::ArithmeticOverflow => Symbol::new(env, "arithmetic_overflow"),
         }
     }
 }
@@ -111,6 +191,11 @@ impl Error {
 // ─────────────────────────────────────────────
 // Storage keys
 // ─────────────────────────────────────────────
+/// Core vault storage keys.
+///
+/// `DataKey` is at the 50-variant limit of `#[contracttype]` enums, so newer
+/// settings live in their own key enums (`GovernanceKey`, `DepositKey`,
+/// `MetadataKey`, ...).
 #[contracttype]
 #[derive(Clone)]
 pub enum DataKey {
@@ -135,11 +220,14 @@ pub enum DataKey {
     TargetAllocations,
     Guardians,
     Threshold,
+    /// v2 layout only; proposals now live under `CollectionKey::Proposal`.
     Proposals,
+    /// v2 layout only; see `CollectionKey::ProposalHead`.
     ProposalIds,
     NextProposalId,
     ProposalTtlLedgers,
     WithdrawQueueThreshold,
+    /// v2 layout only; see `CollectionKey::WithdrawalTicket`.
     PendingWithdrawals,
     StrategyHealth(Address),
     /// Admin-configurable consecutive-failure threshold (default: 3).
@@ -151,68 +239,103 @@ pub enum DataKey {
     HarvestInterval,
     LastHarvestLedger,
     ReentrancyGuard,
+    /// Persistent since v3.
     StrategyYieldSnapshot(Address),
     LastSafeAllocation,
     OracleCircuitBreakerActive,
     BlocklistMode,
     AllowlistMode,
+    /// v2 layout only; see `CollectionKey::ListPage`.
     Blocklist,
+    /// v2 layout only; see `CollectionKey::ListPage`.
     Allowlist,
+    /// v2 layout only; see `CollectionKey::HistoryPage`.
     SharePriceHistory,
+    /// v2 layout only; see `CollectionKey::HistoryPage`.
     PauseHistory,
     SupportedAssets,
     Delegate(Address),
+    /// Persistent since v3 (older votes remain in instance storage).
     VoteRecord(u64, Address),
+    /// Persistent since v3.
     VoteTally(u64),
 }
 
+/// Governance configuration keys.
 #[contracttype]
-#[derive(Clone, Debug, Eq, PartialEq)]
-pub struct Deposited {
-    pub depositor: Address,
-    pub amount: i128,
-    pub shares_minted: i128,
+#[derive(Clone)]
+pub enum GovernanceKey {
+    /// Per-kind timelock override in seconds.
+    ActionTimelock(ActionKind),
+    /// Guardian supermajority (bps) required to fast-track defensive actions.
+    EmergencyThresholdBps,
+    /// The announced contract upgrade awaiting its timelock, if any.
+    PendingUpgrade,
 }
 
+/// Deposit configuration keys.
 #[contracttype]
-#[derive(Clone, Debug, Eq, PartialEq)]
-pub struct Withdrawn {
-    pub withdrawer: Address,
-    pub shares_burned: i128,
-    pub amount_out: i128,
+#[derive(Clone)]
+pub enum DepositKey {
+    /// Smallest value (9 decimals) accepted as the first deposit into a vault
+    /// with no shares outstanding.
+    MinInitialDeposit,
 }
 
+/// A contract upgrade announced through governance, returned by `get_pending_upgrade`.
+///
+/// Depositors can compare `wasm_hash` against a reproducible build and exit
+/// before `executable_at` if they do not agree with the new code.
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
-pub struct Rebalanced {
-    pub total_assets_before: i128,
-    pub total_assets_after: i128,
+pub struct PendingUpgrade {
+    pub proposal_id: u64,
+    pub wasm_hash: BytesN<32>,
+    pub proposed_at: u64,
+    pub executable_at: u64,
 }
 
+// ─────────────────────────────────────────────
+// Queued withdrawal structs
+// ─────────────────────────────────────────────
+/// Queue entry of the v2 layout (one per user); superseded by
+/// `WithdrawalTicket` and only read by storage migrations.
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
-pub struct RebalancePartialFailure {
-    pub failed_strategy: Address,
-    pub reason: soroban_sdk::String,
+pub struct QueuedWithdrawal {
+    pub user: Address,
+    pub asset: Address,
+    pub shares: i128,
+    pub timestamp: u64,
 }
 
+/// A queued withdrawal request. Users may hold several at once.
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
-pub struct RebalanceWithdrawTransferFailed {
-    pub strategy: Address,
-    pub amount: i128,
+pub struct WithdrawalTicket {
+    /// Ticket id; ids are assigned in FIFO order and never reused.
+    pub id: u64,
+    pub user: Address,
+    pub asset: Address,
+    pub shares: i128,
+    /// Share price (9 decimals) when the withdrawal was requested.
+    pub share_price: i128,
+    pub timestamp: u64,
 }
 
-// ─────────────────────────────────────────────
-// Queued withdrawal struct
-// ─────────────────────────────────────────────
+/// A fulfilled withdrawal ticket whose tokens are locked until the user
+/// claims them. Locked tokens are excluded from `total_assets`.
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
-pub struct QueuedWithdrawal {
+pub struct ClaimableWithdrawal {
+    pub ticket_id: u64,
     pub user: Address,
-    pub asset: Address,
     pub shares: i128,
-    pub timestamp: u64,
+    /// Token units locked for the user.
+    pub amount: i128,
+    /// Share price (9 decimals) the ticket was settled at.
+    pub share_price: i128,
+    pub fulfilled_at: u64,
 }
 
 #[contracttype]
@@ -223,6 +346,50 @@ pub enum ActionType {
     Rebalance(u32),
     SetThreshold(u32),
     AddSupportedAsset(Address),
+    SetOracle(Address),
+    Upgrade(BytesN<32>),
+    /// Override the timelock of one kind of action; `None` clears the override.
+    /// The duration must be shorter than the proposal TTL, or proposals of
+    /// that kind would expire before they could execute.
+    SetActionTimelock(ActionKind, Option<u64>),
+}
+
+/// Payload-free category of an `ActionType`, used to key per-action timelock delays.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ActionKind {
+    Pause,
+    Unpause,
+    AddStrategy,
+    Rebalance,
+    SetThreshold,
+    AddSupportedAsset,
+    SetOracle,
+    Upgrade,
+    SetActionTimelock,
+}
+
+impl ActionType {
+    /// Returns the timelock category of this action.
+    pub fn kind(&self) -> ActionKind {
+        match self {
+            ActionType::SetPaused(true) => ActionKind::Pause,
+            ActionType::SetPaused(false) => ActionKind::Unpause,
+            ActionType::AddStrategy(_) => ActionKind::AddStrategy,
+            ActionType::Rebalance(_) => ActionKind::Rebalance,
+            ActionType::SetThreshold(_) => ActionKind::SetThreshold,
+            ActionType::AddSupportedAsset(_) => ActionKind::AddSupportedAsset,
+            ActionType::SetOracle(_) => ActionKind::SetOracle,
+            ActionType::Upgrade(_) => ActionKind::Upgrade,
+            ActionType::SetActionTimelock(..) => ActionKind::SetActionTimelock,
+        }
+    }
+
+    /// Defensive actions only reduce what the vault can do and may be
+    /// fast-tracked by a guardian supermajority.
+    pub fn is_defensive(&self) -> bool {
+        matches!(self, ActionType::SetPaused(true))
+    }
 }
 
 #[contracttype]
@@ -408,8 +575,56 @@ pub struct VolatilityShield;
 
 impl VolatilityShield {
     fn emit_error(env: &Env, error: Error) {
-        env.events()
-            .publish((Symbol::new(env, "Error"), error.to_symbol(env)), ());
+        events::publish(
+            env,
+            events::ErrorRaised {
+                error: error.to_symbol(env),
+                code: error as u32,
+            },
+        );
+    }
+
+    /// Value (9 decimals) of one share. Shares carry
+    /// `SHARE_DECIMALS_OFFSET` extra decimals, so an empty vault starts at
+    /// 0.001 while a vault migrated with 1:1 totals stays near 1.0.
+    fn share_price(env: &Env) -> Result<i128, Error> {
+        let (assets, shares) = Self::virtual_totals(env)?;
+        math::mul_div(assets, math::PRICE_SCALE, shares)
+    }
+
+    /// Shares worth `value` at the current share price.
+    fn shares_for_value(env: &Env, value: i128) -> Result<i128, Error> {
+        if value < 0 {
+            return Err(Error::InvalidAmount);
+        }
+        let (assets, shares) = Self::virtual_totals(env)?;
+        math::mul_div(value, shares, assets)
+    }
+
+    /// Value of `shares` at the current share price.
+    fn value_for_shares(env: &Env, shares: i128) -> Result<i128, Error> {
+        if shares < 0 {
+            return Err(Error::InvalidAmount);
+        }
+        let (assets, total_shares) = Self::virtual_totals(env)?;
+        math::mul_div(shares, assets, total_shares)
+    }
+
+    /// Whether a deposit worth `value` that mints `shares` may proceed: it
+    /// must mint at least one share, and the first deposit into a vault
+    /// without shares must meet the configured minimum.
+    fn deposit_allowed(env: &Env, value: i128, shares: i128) -> bool {
+        if shares <= 0 {
+            return false;
+        }
+        Self::total_shares(env) > 0 || value >= Self::get_min_initial_deposit(env.clone())
+    }
+
+    /// Total assets and shares including the virtual position.
+    fn virtual_totals(env: &Env) -> Result<(i128, i128), Error> {
+        let assets = math::add(Self::total_assets(env).max(0), math::VIRTUAL_ASSETS)?;
+        let shares = math::add(Self::total_shares(env), math::VIRTUAL_SHARES)?;
+        Ok((assets, shares))
     }
 
     fn emit_and_err<T>(env: &Env, error: Error) -> Result<T, Error> {
@@ -417,6 +632,23 @@ impl VolatilityShield {
         Err(error)
     }
 
+    /// Stored guardian set, or `NotInitialized` before `init`.
+    fn read_guardians(env: &Env) -> Result<Vec<Address>, Error> {
+        match env.storage().instance().get(&DataKey::Guardians) {
+            Some(guardians) => Ok(guardians),
+            None => Self::emit_and_err(env, Error::NotInitialized),
+        }
+    }
+
+    /// Proposal `proposal_id`, or `ProposalNotFound` if it was never created,
+    /// was cancelled or has been pruned.
+    fn read_live_proposal(env: &Env, proposal_id: u64) -> Result<Proposal, Error> {
+        match collections::read_proposal(env, proposal_id) {
+            Some(proposal) => Ok(proposal),
+            None => Self::emit_and_err(env, Error::ProposalNotFound),
+        }
+    }
+
     fn balance_deviation_amount(actual_balance: i128, expected_balance: i128) -> i128 {
         // Avoid `abs(i128::MIN)`, which can panic in debug builds.
         actual_balance
@@ -425,20 +657,20 @@ impl VolatilityShield {
     }
 
     fn bump_instance_ttl(env: &Env) {
-        env.storage()
-            .instance()
-            .extend_ttl(BALANCE_TTL_THRESHOLD, BALANCE_TTL_BUMP);
+        ttl::extend_instance(env);
     }
 
-    fn bump_persistent_ttl(env: &Env, key: &DataKey) {
+    fn bump_persistent_ttl<K>(env: &Env, key: &K)
+    where
+        K: PersistentKey,
+    {
         Self::bump_instance_ttl(env);
-        env.storage()
-            .persistent()
-            .extend_ttl(key, BALANCE_TTL_THRESHOLD, BALANCE_TTL_BUMP);
+        ttl::extend_persistent(env, key);
     }
 
-    fn get_persistent<T>(env: &Env, key: &DataKey) -> Option<T>
+    fn get_persistent<K, T>(env: &Env, key: &K) -> Option<T>
     where
+        K: PersistentKey,
         T: TryFromVal<Env, Val>,
         T::Error: core::fmt::Debug,
     {
@@ -449,8 +681,9 @@ impl VolatilityShield {
         value
     }
 
-    fn set_persistent<T>(env: &Env, key: &DataKey, value: &T)
+    fn set_persistent<K, T>(env: &Env, key: &K, value: &T)
     where
+        K: PersistentKey,
         T: IntoVal<Env, Val>,
     {
         env.storage().persistent().set(key, value);
@@ -518,10 +751,15 @@ impl VolatilityShield {
         }
         proposer.require_auth();
 
-        let guardians: Vec<Address> = env.storage().instance().get(&DataKey::Guardians).unwrap();
+        let guardians = Self::read_guardians(&env)?;
         if !guardians.contains(proposer.clone()) {
             return Self::emit_and_err(&env, Error::Unauthorized);
         }
+        if let ActionType::SetActionTimelock(_, Some(duration)) = &action {
+            if *duration >= DEFAULT_PROPOSAL_TTL_SECONDS {
+                return Self::emit_and_err(&env, Error::InvalidConfig);
+            }
+        }
 
         Self::prune_old_proposals_internal(&env);
 
@@ -535,6 +773,9 @@ impl VolatilityShield {
             .set(&DataKey::NextProposalId, &(id + 1));
 
         let proposed_at = env.ledger().timestamp();
+        if let ActionType::Upgrade(wasm_hash) = &action {
+            Self::announce_upgrade(&env, id, wasm_hash, proposed_at)?;
+        }
         let mut proposal = Proposal {
             id,
             proposer: proposer.clone(),
@@ -546,11 +787,20 @@ impl VolatilityShield {
         };
 
         // Emit Governance events
-        env.events()
-            .publish((soroban_sdk::Symbol::new(&env, "ProposalCreated"),), id);
-        env.events().publish(
-            (soroban_sdk::Symbol::new(&env, "TimelockStarted"),),
-            (id, proposed_at),
+        events::publish(
+            &env,
+            events::ProposalCreated {
+                proposal_id: id,
+                proposer: proposer.clone(),
+                kind: action.kind(),
+            },
+        );
+        events::publish(
+            &env,
+            events::TimelockStarted {
+                proposal_id: id,
+                proposed_at,
+            },
         );
 
         let threshold: u32 = env
@@ -558,65 +808,34 @@ impl VolatilityShield {
             .instance()
             .get(&DataKey::Threshold)
             .unwrap_or(1);
-        if threshold <= 1 {
-            // Try to execute, but if timelock hasn't elapsed, the proposal will remain unexecuted
-            let res = Self::execute_action(&env, &proposer, &action, proposed_at);
-            if let Err(e) = res {
-                if e != Error::TimelockNotElapsed {
-                    return Err(e);
-                }
-            } else {
-                proposal.executed = true;
-                proposal.executed_ledger = env.ledger().sequence();
-            }
+        // While the timelock runs the proposal stays queued for `execute_proposal`.
+        if threshold <= 1 && Self::timelock_elapsed(&env, &action, proposed_at) {
+            Self::execute_action(&env, &proposer, id, &action, proposed_at)?;
+            proposal.executed = true;
+            proposal.executed_ledger = env.ledger().sequence();
         }
 
-        let mut proposals: Map<u64, Proposal> = env
-            .storage()
-            .instance()
-            .get(&DataKey::Proposals)
-            .unwrap_or(Map::new(&env));
-        proposals.set(id, proposal);
-        env.storage()
-            .instance()
-            .set(&DataKey::Proposals, &proposals);
-        let mut proposal_ids: Vec<u64> = env
-            .storage()
-            .instance()
-            .get(&DataKey::ProposalIds)
-            .unwrap_or(Vec::new(&env));
-        proposal_ids.push_back(id);
-        env.storage()
-            .instance()
-            .set(&DataKey::ProposalIds, &proposal_ids);
+        collections::write_proposal(&env, &proposal);
 
         Ok(id)
     }
 
     /// Approve a pending governance proposal.
     ///
-    /// If the approval threshold is reached, the action is executed.
+    /// If the approval threshold is reached, the action is executed, or stays
+    /// queued for `execute_proposal` while its timelock is running.
     /// Guardians cannot approve the same proposal twice.
     pub fn approve_action(env: Env, guardian: Address, proposal_id: u64) -> Result<(), Error> {
         guardian.require_auth();
 
-        let guardians: Vec<Address> = env
-            .storage()
-            .instance()
-            .get(&DataKey::Guardians)
-            .ok_or(Error::NotInitialized)?;
+        let guardians = Self::read_guardians(&env)?;
         if !guardians.contains(guardian.clone()) {
             return Self::emit_and_err(&env, Error::Unauthorized);
         }
 
         Self::prune_old_proposals_internal(&env);
 
-        let mut proposals: Map<u64, Proposal> = env
-            .storage()
-            .instance()
-            .get(&DataKey::Proposals)
-            .ok_or(Error::NotInitialized)?;
-        let mut proposal = proposals.get(proposal_id).ok_or(Error::ProposalNotFound)?;
+        let mut proposal = Self::read_live_proposal(&env, proposal_id)?;
 
         if proposal.executed {
             return Self::emit_and_err(&env, Error::ProposalExecuted);
@@ -629,12 +848,12 @@ impl VolatilityShield {
         proposal.approvals.push_back(guardian.clone());
 
         // Emit ProposalApproved event
-        env.events().publish(
-            (
-                soroban_sdk::Symbol::new(&env, "ProposalApproved"),
+        events::publish(
+            &env,
+            events::ProposalApproved {
                 proposal_id,
-            ),
-            guardian.clone(),
+                guardian: guardian.clone(),
+            },
         );
 
         let threshold: u32 = env
@@ -642,26 +861,196 @@ impl VolatilityShield {
             .instance()
             .get(&DataKey::Threshold)
             .unwrap_or(1);
-        if proposal.approvals.len() >= threshold {
-            Self::execute_action(&env, &guardian, &proposal.action, proposal.proposed_at)?;
+        // The approval is kept even if the timelock blocks execution.
+        if proposal.approvals.len() >= threshold
+            && Self::timelock_elapsed(&env, &proposal.action, proposal.proposed_at)
+        {
+            Self::execute_action(
+                &env,
+                &guardian,
+                proposal_id,
+                &proposal.action,
+                proposal.proposed_at,
+            )?;
             proposal.executed = true;
             proposal.executed_ledger = env.ledger().sequence();
         }
 
-        proposals.set(proposal_id, proposal);
-        env.storage()
+        collections::write_proposal(&env, &proposal);
+
+        Ok(())
+    }
+
+    /// Execute a proposal whose approvals already meet the threshold once its
+    /// timelock has elapsed.
+    ///
+    /// Proposals that reached the threshold while their delay was still running
+    /// (e.g. a single-guardian proposal with a long per-action timelock) are
+    /// finalised through this entry point. Only guardians can call it.
+    pub fn execute_proposal(env: Env, executor: Address, proposal_id: u64) -> Result<(), Error> {
+        executor.require_auth();
+
+        let guardians = Self::read_guardians(&env)?;
+        if !guardians.contains(executor.clone()) {
+            return Self::emit_and_err(&env, Error::Unauthorized);
+        }
+
+        let mut proposal = Self::read_live_proposal(&env, proposal_id)?;
+
+        if proposal.executed {
+            return Self::emit_and_err(&env, Error::ProposalExecuted);
+        }
+
+        let threshold: u32 = env
+            .storage()
             .instance()
-            .set(&DataKey::Proposals, &proposals);
+            .get(&DataKey::Threshold)
+            .unwrap_or(1);
+        if proposal.approvals.len() < threshold {
+            return Self::emit_and_err(&env, Error::InsufficientApprovals);
+        }
+
+        Self::execute_action(
+            &env,
+            &executor,
+            proposal_id,
+            &proposal.action,
+            proposal.proposed_at,
+        )?;
+        proposal.executed = true;
+        proposal.executed_ledger = env.ledger().sequence();
+
+        collections::write_proposal(&env, &proposal);
+
+        Ok(())
+    }
 
+    /// Approve a defensive proposal through the emergency fast-track.
+    ///
+    /// Approvals are recorded without triggering the regular timelocked execution.
+    /// Once a supermajority of guardians (see `set_emergency_threshold_bps`) has
+    /// approved, the action executes immediately, bypassing its timelock.
+    /// Only defensive actions (currently `SetPaused(true)`) are eligible.
+    /// @return `true` if the action was executed by this call.
+    pub fn fast_track_action(env: Env, guardian: Address, proposal_id: u64) -> Result<bool, Error> {
+        guardian.require_auth();
+
+        let guardians = Self::read_guardians(&env)?;
+        if !guardians.contains(guardian.clone()) {
+            return Self::emit_and_err(&env, Error::Unauthorized);
+        }
+
+        let mut proposal = Self::read_live_proposal(&env, proposal_id)?;
+
+        if proposal.executed {
+            return Self::emit_and_err(&env, Error::ProposalExecuted);
+        }
+        if !proposal.action.is_defensive() {
+            return Self::emit_and_err(&env, Error::ActionNotDefensive);
+        }
+
+        if !proposal.approvals.contains(guardian.clone()) {
+            proposal.approvals.push_back(guardian.clone());
+            events::publish(
+                &env,
+                events::ProposalApproved {
+                    proposal_id,
+                    guardian: guardian.clone(),
+                },
+            );
+        }
+
+        let required = Self::emergency_approvals_required(&env);
+        let executed = proposal.approvals.len() >= required;
+        if executed {
+            Self::apply_action(&env, &guardian, proposal_id, &proposal.action)?;
+            proposal.executed = true;
+            proposal.executed_ledger = env.ledger().sequence();
+            events::publish(
+                &env,
+                events::EmergencyActionExecuted {
+                    proposal_id,
+                    guardian,
+                    approvals: proposal.approvals.len(),
+                },
+            );
+        }
+
+        collections::write_proposal(&env, &proposal);
+
+        Ok(executed)
+    }
+
+    /// Cancel a proposal before it executes.
+    ///
+    /// The admin or the original proposer may cancel. Cancelling an upgrade
+    /// proposal also clears the pending upgrade announcement.
+    pub fn cancel_proposal(env: Env, caller: Address, proposal_id: u64) -> Result<(), Error> {
+        caller.require_auth();
+
+        let proposal = Self::read_live_proposal(&env, proposal_id)?;
+
+        if caller != Self::read_admin(&env) && caller != proposal.proposer {
+            return Self::emit_and_err(&env, Error::Unauthorized);
+        }
+        if proposal.executed {
+            return Self::emit_and_err(&env, Error::ProposalExecuted);
+        }
+
+        collections::remove_proposal(&env, proposal_id);
+
+        if let ActionType::Upgrade(wasm_hash) = &proposal.action {
+            Self::clear_pending_upgrade_for(&env, proposal_id);
+            events::publish(
+                &env,
+                events::UpgradeCancelled {
+                    proposal_id,
+                    wasm_hash: wasm_hash.clone(),
+                },
+            );
+        }
+
+        events::publish(
+            &env,
+            events::ProposalCancelled {
+                proposal_id,
+                caller,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Set the share of guardians, in basis points, that must approve a
+    /// defensive action before it can bypass its timelock.
+    ///
+    /// Must be a strict majority (5_001..=10_000). Only the admin can call this.
+    pub fn set_emergency_threshold_bps(env: Env, bps: u32) -> Result<(), Error> {
+        Self::require_admin(&env);
+        if bps <= 5_000 || bps > 10_000 {
+            return Self::emit_and_err(&env, Error::InvalidThreshold);
+        }
+        env.storage()
+            .instance()
+            .set(&GovernanceKey::EmergencyThresholdBps, &bps);
+        events::publish(&env, events::EmergencyThresholdSet { bps });
         Ok(())
     }
 
+    /// Get the emergency fast-track supermajority in basis points (default: 6_667).
+    pub fn get_emergency_threshold_bps(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&GovernanceKey::EmergencyThresholdBps)
+            .unwrap_or(DEFAULT_EMERGENCY_THRESHOLD_BPS)
+    }
+
     pub fn set_governance_token(env: Env, token: Address) {
         Self::require_admin(&env);
         env.storage()
             .instance()
             .set(&DataKey::GovernanceToken, &token);
-        env.events().publish((symbol_short!("GovToken"),), token);
+        events::publish(&env, events::GovernanceTokenSet { token });
     }
 
     pub fn get_voting_power(env: Env, user: Address) -> i128 {
@@ -683,31 +1072,24 @@ impl VolatilityShield {
         voter.require_auth();
 
         // Proposal must exist and not be executed
-        let proposals: Map<u64, Proposal> = env
-            .storage()
-            .instance()
-            .get(&DataKey::Proposals)
-            .ok_or(Error::NotInitialized)?;
-        let proposal = proposals.get(proposal_id).ok_or(Error::ProposalNotFound)?;
+        let proposal = Self::read_live_proposal(&env, proposal_id)?;
         if proposal.executed {
-            return Err(Error::ProposalExecuted);
+            return Self::emit_and_err(&env, Error::ProposalExecuted);
         }
 
-        // Each address may only vote once per proposal
+        // Each address may only vote once per proposal. Votes cast before the
+        // v3 layout are still recorded in instance storage.
         let vote_key = DataKey::VoteRecord(proposal_id, voter.clone());
-        if env.storage().instance().has(&vote_key) {
-            return Err(Error::AlreadyApproved);
+        if env.storage().persistent().has(&vote_key) || env.storage().instance().has(&vote_key) {
+            return Self::emit_and_err(&env, Error::AlreadyApproved);
         }
-        env.storage().instance().set(&vote_key, &true);
+        Self::set_persistent(&env, &vote_key, &true);
 
         // Tally the vote, weighted by voting power
         let voting_power = Self::get_voting_power(env.clone(), voter.clone());
         let tally_key = DataKey::VoteTally(proposal_id);
-        let mut tally: VoteTally = env
-            .storage()
-            .instance()
-            .get(&tally_key)
-            .unwrap_or(VoteTally {
+        let mut tally: VoteTally =
+            Self::get_persistent(&env, &tally_key).unwrap_or(VoteTally {
                 yes_votes: 0,
                 no_votes: 0,
             });
@@ -723,11 +1105,16 @@ impl VolatilityShield {
                 .checked_add(voting_power)
                 .unwrap_or(i128::MAX);
         }
-        env.storage().instance().set(&tally_key, &tally);
+        Self::set_persistent(&env, &tally_key, &tally);
 
-        env.events().publish(
-            (soroban_sdk::Symbol::new(&env, "VoteCast"), voter),
-            (proposal_id, support, voting_power),
+        events::publish(
+            &env,
+            events::VoteCast {
+                voter,
+                proposal_id,
+                support,
+                voting_power,
+            },
         );
 
         Ok(())
@@ -735,10 +1122,7 @@ impl VolatilityShield {
 
     /// Get the current vote tally for a proposal.
     pub fn get_vote_tally(env: Env, proposal_id: u64) -> VoteTally {
-        env.storage()
-            .instance()
-            .get(&DataKey::VoteTally(proposal_id))
-            .unwrap_or(VoteTally {
+        Self::get_persistent(&env, &DataKey::VoteTally(proposal_id)).unwrap_or(VoteTally {
                 yes_votes: 0,
                 no_votes: 0,
             })
@@ -760,8 +1144,7 @@ impl VolatilityShield {
         env.storage()
             .instance()
             .set(&DataKey::Guardians, &guardians);
-        env.events()
-            .publish((symbol_short!("GuardAdd"), guardian), ());
+        events::publish(&env, events::GuardianAdded { guardian });
         Ok(())
     }
 
@@ -774,15 +1157,14 @@ impl VolatilityShield {
             .instance()
             .get(&DataKey::Guardians)
             .unwrap_or(Vec::new(&env));
-        let index = guardians
-            .first_index_of(guardian.clone())
-            .ok_or(Error::Unauthorized)?;
+        let Some(index) = guardians.first_index_of(guardian.clone()) else {
+            return Self::emit_and_err(&env, Error::Unauthorized);
+        };
         guardians.remove(index);
         env.storage()
             .instance()
             .set(&DataKey::Guardians, &guardians);
-        env.events()
-            .publish((symbol_short!("GuardRm"), guardian), ());
+        events::publish(&env, events::GuardianRemoved { guardian });
         Ok(())
     }
 
@@ -801,19 +1183,36 @@ impl VolatilityShield {
         env.storage()
             .instance()
             .set(&DataKey::Threshold, &threshold);
-        env.events()
-            .publish((symbol_short!("Threshold"),), threshold);
+        events::publish(&env, events::ThresholdChanged { threshold });
         Ok(())
     }
 
     fn execute_action(
         env: &Env,
         _caller: &Address,
+        proposal_id: u64,
         action: &ActionType,
         proposed_at: u64,
     ) -> Result<(), Error> {
         // Check if timelock has elapsed
-        Self::assert_timelock_elapsed(env, proposed_at)?;
+        Self::assert_timelock_elapsed(env, action, proposed_at)?;
+        Self::apply_action(env, _caller, proposal_id, action)?;
+        events::publish(
+            env,
+            events::TimelockExecuted {
+                proposal_id,
+                kind: action.kind(),
+            },
+        );
+        Ok(())
+    }
+
+    fn apply_action(
+        env: &Env,
+        _caller: &Address,
+        proposal_id: u64,
+        action: &ActionType,
+    ) -> Result<(), Error> {
         match action {
             ActionType::SetPaused(state) => {
                 Self::record_pause_change(env, env.current_contract_address(), *state);
@@ -830,46 +1229,172 @@ impl VolatilityShield {
             ActionType::AddSupportedAsset(asset) => {
                 Self::add_supported_asset(env.clone(), asset.clone());
             }
+            ActionType::SetOracle(oracle) => {
+                let previous = Self::get_oracle(env);
+                env.storage().instance().set(&DataKey::Oracle, oracle);
+                events::publish(
+                    env,
+                    events::OracleRotated {
+                        previous,
+                        oracle: oracle.clone(),
+                    },
+                );
+            }
+            ActionType::Upgrade(wasm_hash) => {
+                env.storage()
+                    .instance()
+                    .remove(&GovernanceKey::PendingUpgrade);
+                events::publish(
+                    env,
+                    events::UpgradeExecuted {
+                        wasm_hash: wasm_hash.clone(),
+                    },
+                );
+                env.deployer()
+                    .update_current_contract_wasm(wasm_hash.clone());
+            }
+            ActionType::SetActionTimelock(kind, duration) => {
+                let key = GovernanceKey::ActionTimelock(*kind);
+                match duration {
+                    Some(duration) => {
+                        env.storage().instance().set(&key, duration);
+                        events::publish(
+                            env,
+                            events::ActionTimelockSet {
+                                kind: *kind,
+                                duration: *duration,
+                            },
+                        );
+                    }
+                    None => {
+                        env.storage().instance().remove(&key);
+                        events::publish(env, events::ActionTimelockCleared { kind: *kind });
+                    }
+                }
+            }
         }
 
-        // Emit TimelockExecuted event
-        env.events()
-            .publish((soroban_sdk::Symbol::new(&env, "ProposalExecuted"),), ());
-        env.events()
-            .publish((soroban_sdk::Symbol::new(&env, "TimelockExecuted"),), ());
+        events::publish(
+            env,
+            events::ProposalExecuted {
+                proposal_id,
+                kind: action.kind(),
+                executor: _caller.clone(),
+            },
+        );
 
         Ok(())
     }
 
-    fn assert_timelock_elapsed(env: &Env, proposed_at: u64) -> Result<(), Error> {
-        let timelock_duration: u64 = env
-            .storage()
-            .instance()
-            .get(&DataKey::TimelockDuration)
-            .unwrap_or(0);
-
-        // If timelock duration is 0, no timelock is enforced
-        if timelock_duration == 0 {
-            return Ok(());
-        }
-
-        let now = env.ledger().timestamp();
-        let elapsed = now.checked_sub(proposed_at).unwrap_or(0);
+    /// Effective timelock for `action`: the per-kind override when configured,
+    /// otherwise the global `TimelockDuration`.
+    fn timelock_for(env: &Env, action: &ActionType) -> u64 {
+        Self::get_action_timelock(env.clone(), action.kind())
+    }
 
-        if elapsed < timelock_duration {
-            return Self::emit_and_err(env, Error::TimelockNotElapsed);
+    /// Drop the pending upgrade announcement if it belongs to `proposal_id`.
+    fn clear_pending_upgrade_for(env: &Env, proposal_id: u64) {
+        let pending: Option<PendingUpgrade> =
+            env.storage().instance().get(&GovernanceKey::PendingUpgrade);
+        if let Some(pending) = pending {
+            if pending.proposal_id == proposal_id {
+                env.storage()
+                    .instance()
+                    .remove(&GovernanceKey::PendingUpgrade);
+            }
         }
-
-        Ok(())
     }
 
-    // ── Initialization ────────────────────────
-    /// Initialize the contract state.
-    ///
-    /// This function can only be called once.
-    /// @param admin The address with administrative privileges.
-    /// @param asset The address of the asset being managed (e.g., USDC).
-    /// @param oracle The address of the oracle provider.
+    /// Record `wasm_hash` as the pending upgrade and publish it so depositors
+    /// can verify the new code during the exit window.
+    fn announce_upgrade(
+        env: &Env,
+        proposal_id: u64,
+        wasm_hash: &BytesN<32>,
+        proposed_at: u64,
+    ) -> Result<(), Error> {
+        if env
+            .storage()
+            .instance()
+            .has(&GovernanceKey::PendingUpgrade)
+        {
+            return Self::emit_and_err(env, Error::UpgradeAlreadyPending);
+        }
+        let executable_at = proposed_at.saturating_add(Self::get_action_timelock(
+            env.clone(),
+            ActionKind::Upgrade,
+        ));
+        let pending = PendingUpgrade {
+            proposal_id,
+            wasm_hash: wasm_hash.clone(),
+            proposed_at,
+            executable_at,
+        };
+        env.storage()
+            .instance()
+            .set(&GovernanceKey::PendingUpgrade, &pending);
+        events::publish(
+            env,
+            events::UpgradeProposed {
+                proposal_id,
+                wasm_hash: wasm_hash.clone(),
+                executable_at,
+            },
+        );
+        Ok(())
+    }
+
+    /// Whether the timelock of `action`, proposed at `proposed_at`, has run out.
+    fn timelock_elapsed(env: &Env, action: &ActionType, proposed_at: u64) -> bool {
+        let timelock_duration = Self::timelock_for(env, action);
+
+        // If timelock duration is 0, no timelock is enforced
+        if timelock_duration == 0 {
+            return true;
+        }
+
+        let now = env.ledger().timestamp();
+        let elapsed = now.checked_sub(proposed_at).unwrap_or(0);
+        elapsed >= timelock_duration
+    }
+
+    fn assert_timelock_elapsed(
+        env: &Env,
+        action: &ActionType,
+        proposed_at: u64,
+    ) -> Result<(), Error> {
+        if !Self::timelock_elapsed(env, action, proposed_at) {
+            return Self::emit_and_err(env, Error::TimelockNotElapsed);
+        }
+        Ok(())
+    }
+
+    /// Number of guardian approvals required to fast-track a defensive action.
+    ///
+    /// Never lower than the regular approval threshold.
+    fn emergency_approvals_required(env: &Env) -> u32 {
+        let guardians: Vec<Address> = env
+            .storage()
+            .instance()
+            .get(&DataKey::Guardians)
+            .unwrap_or(Vec::new(env));
+        let bps = Self::get_emergency_threshold_bps(env.clone());
+        let supermajority = (guardians.len() as u64 * bps as u64).div_ceil(10_000) as u32;
+        let threshold: u32 = env
+            .storage()
+            .instance()
+            .get(&DataKey::Threshold)
+            .unwrap_or(1);
+        supermajority.max(threshold).max(1)
+    }
+
+    // ── Initialization ────────────────────────
+    /// Initialize the contract state.
+    ///
+    /// This function can only be called once.
+    /// @param admin The address with administrative privileges.
+    /// @param asset The address of the asset being managed (e.g., USDC).
+    /// @param oracle The address of the oracle provider.
     /// @param treasury The address where fees are collected.
     /// @param fee_percentage The management fee in basis points (1/10000).
     /// @param guardians A list of addresses for the multisig governance.
@@ -903,13 +1428,7 @@ impl VolatilityShield {
             .instance()
             .set(&DataKey::EmergencyShutdown, &false);
 
-        // Initialize maps and durations
-        env.storage()
-            .instance()
-            .set(&DataKey::Proposals, &Map::<u64, Proposal>::new(&env));
-        env.storage()
-            .instance()
-            .set(&DataKey::ProposalIds, &Vec::<u64>::new(&env));
+        // Initialize durations
         env.storage()
             .instance()
             .set(&DataKey::TimelockDuration, &0_u64);
@@ -931,11 +1450,6 @@ impl VolatilityShield {
             .instance()
             .set(&DataKey::MaxStaleness, &3600u64);
 
-        // Initialize contract version
-        env.storage()
-            .instance()
-            .set(&DataKey::ContractVersion, &1u32);
-
         // Multisig initialization
         env.storage()
             .instance()
@@ -944,22 +1458,32 @@ impl VolatilityShield {
             .instance()
             .set(&DataKey::Threshold, &threshold);
 
-        // Initialize contract version
+        // Fresh deployments start at the latest storage layout
         env.storage()
             .instance()
-            .set(&DataKey::ContractVersion, &1u32);
+            .set(&DataKey::ContractVersion, &migration::CONTRACT_VERSION);
 
         Self::bump_instance_ttl(&env);
 
+        events::publish(
+            &env,
+            events::VaultInitialized {
+                admin,
+                asset,
+                oracle,
+                treasury,
+                fee_percentage,
+                guardians,
+                threshold,
+            },
+        );
+
         Ok(())
     }
 
     /// Returns the share price history snapshots for the vault.
     pub fn get_share_price_history(env: Env) -> Vec<(u64, i128)> {
-        env.storage()
-            .instance()
-            .get(&DataKey::SharePriceHistory)
-            .unwrap_or(Vec::new(&env))
+        collections::history_all(&env, HistoryKind::SharePrice)
     }
 
     /// Returns the TVL history snapshots filtered by ledger range.
@@ -967,11 +1491,7 @@ impl VolatilityShield {
     /// Entries are returned in ascending ledger order. Only entries with
     /// `ledger >= from_ledger` are included, capped to at most `limit` entries.
     pub fn get_tvl_history(env: Env, from_ledger: u64, limit: u32) -> Vec<(u64, i128)> {
-        let history: Vec<(u64, i128)> = env
-            .storage()
-            .instance()
-            .get(&TVL_HISTORY_KEY)
-            .unwrap_or(Vec::new(&env));
+        let history = Self::read_tvl_history(&env);
         let mut result = Vec::new(&env);
         if limit == 0 {
             return result;
@@ -992,10 +1512,7 @@ impl VolatilityShield {
 
     /// Returns the pause/unpause history for the vault.
     pub fn get_pause_history(env: Env) -> Vec<(u64, Address, bool)> {
-        env.storage()
-            .instance()
-            .get(&DataKey::PauseHistory)
-            .unwrap_or(Vec::new(&env))
+        collections::history_all(&env, HistoryKind::Pause)
     }
 
     // ── Deposit ───────────────────────────────
@@ -1019,34 +1536,31 @@ impl VolatilityShield {
         _min_shares_out: Option<i128>,
     ) -> Result<(), Error> {
         let _guard = Guard::new(&env);
-        Self::check_version(&env, 1);
-        Self::assert_not_emergency_shutdown(&env);
-        Self::assert_not_paused(&env);
+        Self::check_version(&env)?;
+        Self::assert_not_emergency_shutdown(&env)?;
+        Self::assert_not_paused(&env)?;
         if amount <= 0 {
-            panic!("deposit amount must be positive");
+            return Self::emit_and_err(&env, Error::InvalidAmount);
         }
         from.require_auth();
 
         // Compliance checks
-        if let Err(e) = Self::check_compliance(&env, &from) {
-            panic!("Compliance check failed: {:?}", e);
-        }
+        Self::check_compliance(&env, &from)?;
 
         // Verify asset is accepted
         if !Self::is_supported_asset(env.clone(), asset.clone()) {
-            panic!("unsupported asset");
+            return Self::emit_and_err(&env, Error::UnsupportedAsset);
         }
 
-        let price = Self::get_asset_price(env.clone(), asset.clone());
-        let value_deposited = amount
-            .checked_mul(price)
-            .unwrap()
-            .checked_div(1_000_000_000)
-            .unwrap();
+        let price = Self::get_asset_price(env.clone(), asset.clone())?;
+        let value_deposited = math::mul_div(amount, price, math::PRICE_SCALE)?;
 
         // ── Checks ───────────────────────────────────────────────────────────
         // Compute shares using pre-deposit totals so the ratio is not skewed.
-        let shares_to_mint = Self::convert_to_shares(env.clone(), value_deposited);
+        let shares_to_mint = Self::shares_for_value(&env, value_deposited)?;
+        if !Self::deposit_allowed(&env, value_deposited, shares_to_mint) {
+            return Self::emit_and_err(&env, Error::InvalidAmount);
+        }
 
         // Slippage check
         if let Some(min_shares) = _min_shares_out {
@@ -1057,11 +1571,11 @@ impl VolatilityShield {
 
         // Track per-asset user balance
         let current_asset_balance = Self::read_asset_balance(&env, &asset, &from);
-        let new_asset_balance = current_asset_balance.checked_add(shares_to_mint).unwrap();
+        let new_asset_balance = math::add(current_asset_balance, shares_to_mint)?;
 
         // Also track total user balance (for backward compatibility)
         let current_balance = Self::read_user_balance(&env, &from);
-        let new_user_balance = current_balance.checked_add(shares_to_mint).unwrap();
+        let new_user_balance = math::add(current_balance, shares_to_mint)?;
 
         // --- Deposit Caps Validation ---
         let max_deposit_per_user: i128 = env
@@ -1069,16 +1583,23 @@ impl VolatilityShield {
             .instance()
             .get(&DataKey::MaxDepositPerUser)
             .unwrap_or(i128::MAX);
-        if new_user_balance > max_deposit_per_user {
-            env.events().publish(
-                (soroban_sdk::Symbol::new(&env, "DepositCapExceeded"),),
-                amount,
+        let position_value = math::add(
+            Self::value_for_shares(&env, current_balance)?,
+            value_deposited,
+        )?;
+        if position_value > max_deposit_per_user {
+            events::publish(
+                &env,
+                events::DepositCapExceeded {
+                    depositor: from.clone(),
+                    amount,
+                },
             );
-            panic!("DepositCapExceeded: per-user deposit cap exceeded");
+            return Self::emit_and_err(&env, Error::DepositCapExceeded);
         }
 
         let total_assets_value = Self::total_assets(&env);
-        let new_total_assets_value = total_assets_value.checked_add(value_deposited).unwrap();
+        let new_total_assets_value = math::add(total_assets_value, value_deposited)?;
 
         let max_total_assets: i128 = env
             .storage()
@@ -1086,11 +1607,14 @@ impl VolatilityShield {
             .get(&DataKey::MaxTotalAssets)
             .unwrap_or(i128::MAX);
         if new_total_assets_value > max_total_assets {
-            env.events().publish(
-                (soroban_sdk::Symbol::new(&env, "DepositCapExceeded"),),
-                amount,
+            events::publish(
+                &env,
+                events::DepositCapExceeded {
+                    depositor: from.clone(),
+                    amount,
+                },
             );
-            panic!("DepositCapExceeded: global deposit cap exceeded");
+            return Self::emit_and_err(&env, Error::DepositCapExceeded);
         }
         // -------------------------------
 
@@ -1109,15 +1633,15 @@ impl VolatilityShield {
             .instance()
             .get(&DataKey::AssetTotalAssets(asset.clone()))
             .unwrap_or(0);
-        let new_asset_total = asset_total.checked_add(amount).unwrap();
+        let new_asset_total = math::add(asset_total, amount)?;
         env.storage()
             .instance()
             .set(&DataKey::AssetTotalAssets(asset.clone()), &new_asset_total);
 
         let total_shares = Self::total_shares(&env);
-        let new_total_shares = total_shares.checked_add(shares_to_mint).unwrap();
+        let new_total_shares = math::add(total_shares, shares_to_mint)?;
 
-        Self::set_total_shares(env.clone(), new_total_shares);
+        Self::write_total_shares(&env, new_total_shares);
         env.storage()
             .instance()
             .set(&DataKey::TotalAssets, &new_total_assets_value);
@@ -1126,20 +1650,19 @@ impl VolatilityShield {
         // Token transfer occurs last, after all state is committed (CEI pattern).
         token::Client::new(&env, &asset).transfer(&from, &env.current_contract_address(), &amount);
 
-        let share_price = Self::get_share_price(&env);
+        let share_price = Self::share_price(&env)?;
 
-        env.events().publish(
-            (soroban_sdk::Symbol::new(&env, "Deposited"), from.clone()),
-            (
-                Deposited {
-                    depositor: from.clone(),
-                    amount,
-                    shares_minted: shares_to_mint,
-                },
+        events::publish(
+            &env,
+            events::Deposited {
+                depositor: from.clone(),
+                asset: asset.clone(),
+                amount,
+                shares_minted: shares_to_mint,
                 share_price,
-                new_total_assets_value,
-                new_total_shares,
-            ),
+                total_assets: new_total_assets_value,
+                total_shares: new_total_shares,
+            },
         );
 
         Self::record_tvl_snapshot(&env);
@@ -1151,10 +1674,13 @@ impl VolatilityShield {
     /// Process multiple deposit operations in a single transaction.
     ///
     /// Validates each operation independently. Failed operations are skipped and do not revert the batch.
-    pub fn batch_deposit(env: Env, operations: Vec<(Address, Address, i128)>) -> Vec<bool> {
-        Self::check_version(&env, 1);
-        Self::assert_not_emergency_shutdown(&env);
-        Self::assert_not_paused(&env);
+    pub fn batch_deposit(
+        env: Env,
+        operations: Vec<(Address, Address, i128)>,
+    ) -> Result<Vec<bool>, Error> {
+        Self::check_version(&env)?;
+        Self::assert_not_emergency_shutdown(&env)?;
+        Self::assert_not_paused(&env)?;
         Self::require_admin(&env);
 
         let mut results = Vec::new(&env);
@@ -1163,61 +1689,79 @@ impl VolatilityShield {
             let (from, asset, amount) = op;
 
             if amount <= 0 {
-                env.events().publish(
-                    (symbol_short!("BatchDep"), symbol_short!("Fail")),
-                    (
-                        from.clone(),
-                        asset.clone(),
+                events::publish(
+                    &env,
+                    events::BatchDepositFailed {
+                        depositor: from.clone(),
+                        asset: asset.clone(),
                         amount,
-                        symbol_short!("AmtZero"),
-                    ),
+                        reason: symbol_short!("AmtZero"),
+                    },
                 );
                 results.push_back(false);
                 continue;
             }
 
             if !Self::is_supported_asset(env.clone(), asset.clone()) {
-                env.events().publish(
-                    (symbol_short!("BatchDep"), symbol_short!("Fail")),
-                    (
-                        from.clone(),
-                        asset.clone(),
+                events::publish(
+                    &env,
+                    events::BatchDepositFailed {
+                        depositor: from.clone(),
+                        asset: asset.clone(),
                         amount,
-                        symbol_short!("BadAsset"),
-                    ),
+                        reason: symbol_short!("BadAsset"),
+                    },
                 );
                 results.push_back(false);
                 continue;
             }
 
-            let price = Self::get_asset_price(env.clone(), asset.clone());
-            let value_deposited = amount
-                .checked_mul(price)
-                .unwrap()
-                .checked_div(1_000_000_000)
-                .unwrap();
-            let shares_to_mint = Self::convert_to_shares(env.clone(), value_deposited);
+            let price = Self::get_asset_price(env.clone(), asset.clone())?;
+            let value_deposited = math::mul_div(amount, price, math::PRICE_SCALE)?;
+            let shares_to_mint = Self::shares_for_value(&env, value_deposited)?;
+            if !Self::deposit_allowed(&env, value_deposited, shares_to_mint) {
+                events::publish(
+                    &env,
+                    events::BatchDepositFailed {
+                        depositor: from.clone(),
+                        asset: asset.clone(),
+                        amount,
+                        reason: symbol_short!("TooSmall"),
+                    },
+                );
+                results.push_back(false);
+                continue;
+            }
 
             let current_asset_balance = Self::read_asset_balance(&env, &asset, &from);
             let current_balance = Self::read_user_balance(&env, &from);
-            let new_user_balance = current_balance.checked_add(shares_to_mint).unwrap();
+            let new_user_balance = math::add(current_balance, shares_to_mint)?;
 
             let max_deposit_per_user: i128 = env
                 .storage()
                 .instance()
                 .get(&DataKey::MaxDepositPerUser)
                 .unwrap_or(i128::MAX);
-            if new_user_balance > max_deposit_per_user {
-                env.events().publish(
-                    (symbol_short!("BatchDep"), symbol_short!("Fail")),
-                    (from.clone(), asset.clone(), amount, symbol_short!("UsrCap")),
+            let position_value = math::add(
+                Self::value_for_shares(&env, current_balance)?,
+                value_deposited,
+            )?;
+            if position_value > max_deposit_per_user {
+                events::publish(
+                    &env,
+                    events::BatchDepositFailed {
+                        depositor: from.clone(),
+                        asset: asset.clone(),
+                        amount,
+                        reason: symbol_short!("UsrCap"),
+                    },
                 );
                 results.push_back(false);
                 continue;
             }
 
             let total_assets_value = Self::total_assets(&env);
-            let new_total_assets_value = total_assets_value.checked_add(value_deposited).unwrap();
+            let new_total_assets_value = math::add(total_assets_value, value_deposited)?;
 
             let max_total_assets: i128 = env
                 .storage()
@@ -1225,16 +1769,26 @@ impl VolatilityShield {
                 .get(&DataKey::MaxTotalAssets)
                 .unwrap_or(i128::MAX);
             if new_total_assets_value > max_total_assets {
-                env.events().publish(
-                    (symbol_short!("BatchDep"), symbol_short!("Fail")),
-                    (from.clone(), asset.clone(), amount, symbol_short!("GlbCap")),
+                events::publish(
+                    &env,
+                    events::BatchDepositFailed {
+                        depositor: from.clone(),
+                        asset: asset.clone(),
+                        amount,
+                        reason: symbol_short!("GlbCap"),
+                    },
                 );
                 results.push_back(false);
                 continue;
             }
 
             // ── Effects: commit all state before external call (CEI) ──────────
-            Self::write_asset_balance(&env, &asset, &from, current_asset_balance + shares_to_mint);
+            Self::write_asset_balance(
+                &env,
+                &asset,
+                &from,
+                math::add(current_asset_balance, shares_to_mint)?,
+            );
             Self::write_user_balance(&env, &from, new_user_balance);
 
             let asset_total: i128 = env
@@ -1242,15 +1796,15 @@ impl VolatilityShield {
                 .instance()
                 .get(&DataKey::AssetTotalAssets(asset.clone()))
                 .unwrap_or(0);
-            let new_asset_total = asset_total.checked_add(amount).unwrap();
+            let new_asset_total = math::add(asset_total, amount)?;
             env.storage()
                 .instance()
                 .set(&DataKey::AssetTotalAssets(asset.clone()), &new_asset_total);
 
             let total_shares = Self::total_shares(&env);
-            let new_total_shares = total_shares.checked_add(shares_to_mint).unwrap();
+            let new_total_shares = math::add(total_shares, shares_to_mint)?;
 
-            Self::set_total_shares(env.clone(), new_total_shares);
+            Self::write_total_shares(&env, new_total_shares);
             env.storage()
                 .instance()
                 .set(&DataKey::TotalAssets, &new_total_assets_value);
@@ -1262,25 +1816,24 @@ impl VolatilityShield {
                 &amount,
             );
 
-            let share_price = Self::get_share_price(&env);
+            let share_price = Self::share_price(&env)?;
 
-            env.events().publish(
-                (soroban_sdk::Symbol::new(&env, "Deposited"), from.clone()),
-                (
-                    Deposited {
-                        depositor: from.clone(),
-                        amount,
-                        shares_minted: shares_to_mint,
-                    },
+            events::publish(
+                &env,
+                events::Deposited {
+                    depositor: from.clone(),
+                    asset: asset.clone(),
+                    amount,
+                    shares_minted: shares_to_mint,
                     share_price,
-                    new_total_assets_value,
-                    new_total_shares,
-                ),
+                    total_assets: new_total_assets_value,
+                    total_shares: new_total_shares,
+                },
             );
 
             results.push_back(true);
         }
-        results
+        Ok(results)
     }
 
     // ── Withdraw ──────────────────────────────
@@ -1300,30 +1853,27 @@ impl VolatilityShield {
         shares: i128,
     ) -> Result<(), Error> {
         let _guard = Guard::new(&env);
-        Self::check_version(&env, 1);
-        Self::assert_not_paused(&env);
+        Self::check_version(&env)?;
+        Self::assert_not_paused(&env)?;
         if shares <= 0 {
-            panic!("shares to withdraw must be positive");
+            return Self::emit_and_err(&env, Error::InvalidAmount);
         }
-        Self::require_owner_or_delegate(&env, &from, &caller);
+        Self::require_owner_or_delegate(&env, &from, &caller)?;
 
         let current_balance = Self::read_user_balance(&env, &from);
 
         if current_balance < shares {
-            panic!("insufficient shares for withdrawal");
+            return Self::emit_and_err(&env, Error::InsufficientShares);
         }
 
         if !Self::is_supported_asset(env.clone(), asset.clone()) {
-            panic!("unsupported asset");
+            return Self::emit_and_err(&env, Error::UnsupportedAsset);
         }
 
-        let assets_to_withdraw_value = Self::convert_to_assets(env.clone(), shares);
-        let asset_price = Self::get_asset_price(env.clone(), asset.clone());
-        let token_units_to_withdraw = assets_to_withdraw_value
-            .checked_mul(1_000_000_000)
-            .unwrap()
-            .checked_div(asset_price)
-            .unwrap();
+        let assets_to_withdraw_value = Self::value_for_shares(&env, shares)?;
+        let asset_price = Self::get_asset_price(env.clone(), asset.clone())?;
+        let token_units_to_withdraw =
+            math::mul_div(assets_to_withdraw_value, math::PRICE_SCALE, asset_price)?;
 
         // --- Withdraw Caps Validation ---
         let max_withdraw_per_tx: i128 = env
@@ -1332,36 +1882,39 @@ impl VolatilityShield {
             .get(&DataKey::MaxWithdrawPerTx)
             .unwrap_or(i128::MAX);
         if assets_to_withdraw_value > max_withdraw_per_tx {
-            env.events().publish(
-                (soroban_sdk::Symbol::new(&env, "WithdrawCapExceeded"),),
-                assets_to_withdraw_value,
+            events::publish(
+                &env,
+                events::WithdrawCapExceeded {
+                    withdrawer: from.clone(),
+                    amount: assets_to_withdraw_value,
+                },
             );
-            panic!("WithdrawalCapExceeded: per-tx withdrawal cap exceeded");
+            return Self::emit_and_err(&env, Error::WithdrawalCapExceeded);
         }
         // --------------------------------
 
-        // Check if withdrawal exceeds queue threshold
+        // Queue the withdrawal instead of processing immediately if it exceeds
+        // the queue threshold or the vault's idle tokens cannot cover it.
         let queue_threshold: i128 = env
             .storage()
             .instance()
             .get(&DataKey::WithdrawQueueThreshold)
             .unwrap_or(i128::MAX);
-        if assets_to_withdraw_value > queue_threshold {
-            // Queue the withdrawal instead of processing immediately
-            Self::internal_queue_withdraw(env.clone(), from, asset, shares);
+        if assets_to_withdraw_value > queue_threshold
+            || Self::instant_liquidity(&env, &asset) < token_units_to_withdraw
+        {
+            Self::internal_queue_withdraw(env.clone(), from, asset, shares)?;
             return Ok(());
         }
 
         let total_shares = Self::total_shares(&env);
         let total_assets_value = Self::total_assets(&env);
 
-        let new_total_shares = total_shares.checked_sub(shares).unwrap();
-        let new_total_assets_value = total_assets_value
-            .checked_sub(assets_to_withdraw_value)
-            .unwrap();
-        let new_user_balance = current_balance.checked_sub(shares).unwrap();
+        let new_total_shares = math::sub(total_shares, shares)?;
+        let new_total_assets_value = math::sub(total_assets_value, assets_to_withdraw_value)?;
+        let new_user_balance = math::sub(current_balance, shares)?;
 
-        Self::set_total_shares(env.clone(), new_total_shares);
+        Self::write_total_shares(&env, new_total_shares);
 
         // Update per-asset accounting
         let asset_total: i128 = env
@@ -1388,7 +1941,7 @@ impl VolatilityShield {
             current_asset_balance.saturating_sub(shares),
         );
 
-        let _share_price = Self::get_share_price(&env);
+        let _share_price = Self::share_price(&env)?;
 
         token::Client::new(&env, &asset).transfer(
             &env.current_contract_address(),
@@ -1396,12 +1949,15 @@ impl VolatilityShield {
             &token_units_to_withdraw,
         );
 
-        env.events().publish(
-            (soroban_sdk::Symbol::new(&env, "Withdrawn"), from.clone()),
-            Withdrawn {
+        events::publish(
+            &env,
+            events::Withdrawn {
                 withdrawer: from,
+                asset,
                 shares_burned: shares,
                 amount_out: assets_to_withdraw_value,
+                total_assets: Self::total_assets(&env),
+                total_shares: Self::total_shares(&env),
             },
         );
 
@@ -1410,13 +1966,28 @@ impl VolatilityShield {
         Ok(())
     }
 
+    /// Tokens of `asset` the vault can pay out immediately. Base tokens locked
+    /// for withdrawal claims are excluded.
+    fn instant_liquidity(env: &Env, asset: &Address) -> i128 {
+        let asset_client = token::Client::new(env, asset);
+        let base_token: Option<Address> = env.storage().instance().get(&DataKey::Token);
+        if base_token.as_ref() == Some(asset) {
+            liquidity::idle_liquidity(env, &asset_client)
+        } else {
+            asset_client.balance(&env.current_contract_address())
+        }
+    }
+
     // ── Batch Withdraw ─────────────────────────
     /// Process multiple withdraw operations in a single transaction.
     ///
     /// Validates each operation independently. Failed operations are skipped and do not revert the batch.
-    pub fn batch_withdraw(env: Env, operations: Vec<(Address, Address, i128)>) -> Vec<bool> {
-        Self::check_version(&env, 1);
-        Self::assert_not_paused(&env);
+    pub fn batch_withdraw(
+        env: Env,
+        operations: Vec<(Address, Address, i128)>,
+    ) -> Result<Vec<bool>, Error> {
+        Self::check_version(&env)?;
+        Self::assert_not_paused(&env)?;
         Self::require_admin(&env);
 
         let mut results = Vec::new(&env);
@@ -1425,18 +1996,26 @@ impl VolatilityShield {
             let (from, asset, shares) = op;
 
             if shares <= 0 {
-                env.events().publish(
-                    (symbol_short!("BatchWd"), symbol_short!("Fail")),
-                    (from.clone(), shares, symbol_short!("Zero")),
+                events::publish(
+                    &env,
+                    events::BatchWithdrawFailed {
+                        withdrawer: from.clone(),
+                        shares,
+                        reason: symbol_short!("Zero"),
+                    },
                 );
                 results.push_back(false);
                 continue;
             }
 
             if !Self::is_supported_asset(env.clone(), asset.clone()) {
-                env.events().publish(
-                    (symbol_short!("BatchWd"), symbol_short!("Fail")),
-                    (from.clone(), shares, symbol_short!("BadAsset")),
+                events::publish(
+                    &env,
+                    events::BatchWithdrawFailed {
+                        withdrawer: from.clone(),
+                        shares,
+                        reason: symbol_short!("BadAsset"),
+                    },
                 );
                 results.push_back(false);
                 continue;
@@ -1445,21 +2024,22 @@ impl VolatilityShield {
             let current_balance = Self::read_user_balance(&env, &from);
 
             if current_balance < shares {
-                env.events().publish(
-                    (symbol_short!("BatchWd"), symbol_short!("Fail")),
-                    (from.clone(), shares, symbol_short!("Insuf")),
+                events::publish(
+                    &env,
+                    events::BatchWithdrawFailed {
+                        withdrawer: from.clone(),
+                        shares,
+                        reason: symbol_short!("Insuf"),
+                    },
                 );
                 results.push_back(false);
                 continue;
             }
 
-            let assets_to_withdraw_value = Self::convert_to_assets(env.clone(), shares);
-            let asset_price = Self::get_asset_price(env.clone(), asset.clone());
-            let token_units_to_withdraw = assets_to_withdraw_value
-                .checked_mul(1_000_000_000)
-                .unwrap()
-                .checked_div(asset_price)
-                .unwrap();
+            let assets_to_withdraw_value = Self::value_for_shares(&env, shares)?;
+            let asset_price = Self::get_asset_price(env.clone(), asset.clone())?;
+            let token_units_to_withdraw =
+                math::mul_div(assets_to_withdraw_value, math::PRICE_SCALE, asset_price)?;
 
             let max_withdraw_per_tx: i128 = env
                 .storage()
@@ -1467,9 +2047,13 @@ impl VolatilityShield {
                 .get(&DataKey::MaxWithdrawPerTx)
                 .unwrap_or(i128::MAX);
             if assets_to_withdraw_value > max_withdraw_per_tx {
-                env.events().publish(
-                    (symbol_short!("BatchWd"), symbol_short!("Fail")),
-                    (from.clone(), shares, symbol_short!("CapExcd")),
+                events::publish(
+                    &env,
+                    events::BatchWithdrawFailed {
+                        withdrawer: from.clone(),
+                        shares,
+                        reason: symbol_short!("CapExcd"),
+                    },
                 );
                 results.push_back(false);
                 continue;
@@ -1480,30 +2064,10 @@ impl VolatilityShield {
                 .instance()
                 .get(&DataKey::WithdrawQueueThreshold)
                 .unwrap_or(i128::MAX);
-            if assets_to_withdraw_value > queue_threshold {
-                let existing: Vec<QueuedWithdrawal> = env
-                    .storage()
-                    .instance()
-                    .get(&DataKey::PendingWithdrawals)
-                    .unwrap_or(Vec::new(&env));
-                let already_queued = existing.iter().any(|w| w.user == from);
-                if already_queued {
-                    env.events().publish(
-                        (symbol_short!("BatchWd"), symbol_short!("Fail")),
-                        (from.clone(), shares, symbol_short!("Queued")),
-                    );
-                    results.push_back(false);
-                    continue;
-                }
-
-                let queued_withdrawal = QueuedWithdrawal {
-                    user: from.clone(),
-                    asset: asset.clone(),
-                    shares,
-                    timestamp: env.ledger().timestamp(),
-                };
-
-                let new_user_balance = current_balance.checked_sub(shares).unwrap();
+            if assets_to_withdraw_value > queue_threshold
+                || Self::instant_liquidity(&env, &asset) < token_units_to_withdraw
+            {
+                let new_user_balance = math::sub(current_balance, shares)?;
                 Self::write_user_balance(&env, &from, new_user_balance);
 
                 // Update per-asset user balance
@@ -1515,33 +2079,7 @@ impl VolatilityShield {
                     current_asset_balance.saturating_sub(shares),
                 );
 
-                let mut pending_withdrawals: Vec<QueuedWithdrawal> = env
-                    .storage()
-                    .instance()
-                    .get(&DataKey::PendingWithdrawals)
-                    .unwrap_or(Vec::new(&env));
-                pending_withdrawals.push_back(queued_withdrawal);
-                env.storage()
-                    .instance()
-                    .set(&DataKey::PendingWithdrawals, &pending_withdrawals);
-
-                let total_assets = Self::total_assets(&env);
-                let total_shares = Self::total_shares(&env);
-                let share_price = Self::get_share_price(&env);
-
-                env.events().publish(
-                    (
-                        soroban_sdk::Symbol::new(&env, "WithdrawQueued"),
-                        from.clone(),
-                    ),
-                    (
-                        asset.clone(),
-                        shares,
-                        share_price,
-                        total_assets,
-                        total_shares,
-                    ),
-                );
+                Self::push_withdrawal_ticket(&env, from.clone(), asset.clone(), shares)?;
 
                 results.push_back(true);
                 continue;
@@ -1550,13 +2088,11 @@ impl VolatilityShield {
             let total_shares = Self::total_shares(&env);
             let total_assets_value = Self::total_assets(&env);
 
-            let new_total_shares = total_shares.checked_sub(shares).unwrap();
-            let new_total_assets_value = total_assets_value
-                .checked_sub(assets_to_withdraw_value)
-                .unwrap();
-            let new_user_balance = current_balance.checked_sub(shares).unwrap();
+            let new_total_shares = math::sub(total_shares, shares)?;
+            let new_total_assets_value = math::sub(total_assets_value, assets_to_withdraw_value)?;
+            let new_user_balance = math::sub(current_balance, shares)?;
 
-            Self::set_total_shares(env.clone(), new_total_shares);
+            Self::write_total_shares(&env, new_total_shares);
 
             let asset_total: i128 = env
                 .storage()
@@ -1582,7 +2118,7 @@ impl VolatilityShield {
                 current_asset_balance.saturating_sub(shares),
             );
 
-            let _share_price = Self::get_share_price(&env);
+            let _share_price = Self::share_price(&env)?;
 
             token::Client::new(&env, &asset).transfer(
                 &env.current_contract_address(),
@@ -1590,18 +2126,21 @@ impl VolatilityShield {
                 &token_units_to_withdraw,
             );
 
-            env.events().publish(
-                (soroban_sdk::Symbol::new(&env, "Withdrawn"), from.clone()),
-                Withdrawn {
+            events::publish(
+                &env,
+                events::Withdrawn {
                     withdrawer: from.clone(),
+                    asset: asset.clone(),
                     shares_burned: shares,
                     amount_out: assets_to_withdraw_value,
+                    total_assets: Self::total_assets(&env),
+                    total_shares: Self::total_shares(&env),
                 },
             );
 
             results.push_back(true);
         }
-        results
+        Ok(results)
     }
 
     // ── Withdrawal Queue ───────────────────────
@@ -1612,35 +2151,37 @@ impl VolatilityShield {
     /// @param from The address of the user withdrawing.
     /// @param asset The address of the asset being withdrawn.
     /// @param shares The amount of shares to burn.
-    pub fn queue_withdraw(env: Env, caller: Address, from: Address, asset: Address, shares: i128) {
+    /// @return The id of the new withdrawal ticket.
+    pub fn queue_withdraw(
+        env: Env,
+        caller: Address,
+        from: Address,
+        asset: Address,
+        shares: i128,
+    ) -> Result<u64, Error> {
         let _guard = Guard::new(&env);
-        Self::assert_not_paused(&env);
+        Self::check_version(&env)?;
+        Self::assert_not_paused(&env)?;
         if shares <= 0 {
-            panic!("shares to queue must be positive");
+            return Self::emit_and_err(&env, Error::InvalidAmount);
         }
-        Self::require_owner_or_delegate(&env, &from, &caller);
-        Self::internal_queue_withdraw(env.clone(), from, asset, shares);
+        Self::require_owner_or_delegate(&env, &from, &caller)?;
+        Self::internal_queue_withdraw(env.clone(), from, asset, shares)
     }
 
-    fn internal_queue_withdraw(env: Env, from: Address, asset: Address, shares: i128) {
+    fn internal_queue_withdraw(
+        env: Env,
+        from: Address,
+        asset: Address,
+        shares: i128,
+    ) -> Result<u64, Error> {
         let current_balance = Self::read_user_balance(&env, &from);
 
         if current_balance < shares {
-            panic!("insufficient shares for withdrawal");
-        }
-
-        // Reject if the user already has a pending queued withdrawal.
-        let existing: Vec<QueuedWithdrawal> = env
-            .storage()
-            .instance()
-            .get(&DataKey::PendingWithdrawals)
-            .unwrap_or(Vec::new(&env));
-        let already_queued = existing.iter().any(|w| w.user == from);
-        if already_queued {
-            panic!("user already has a pending withdrawal");
+            return Self::emit_and_err(&env, Error::InsufficientShares);
         }
 
-        let assets_to_withdraw = Self::convert_to_assets(env.clone(), shares);
+        let assets_to_withdraw = Self::value_for_shares(&env, shares)?;
 
         // Check if withdrawal exceeds queue threshold
         let queue_threshold: i128 = env
@@ -1649,88 +2190,92 @@ impl VolatilityShield {
             .get(&DataKey::WithdrawQueueThreshold)
             .unwrap_or(i128::MAX);
 
-        if assets_to_withdraw <= queue_threshold {
-            panic!("withdrawal amount does not exceed queue threshold");
-        }
+        let price = Self::get_asset_price(env.clone(), asset.clone())?;
+        let token_units = math::mul_div(assets_to_withdraw, math::PRICE_SCALE, price)?;
 
-        // Create queued withdrawal entry
-        let queued_withdrawal = QueuedWithdrawal {
-            user: from.clone(),
-            asset: asset.clone(),
-            shares,
-            timestamp: env.ledger().timestamp(),
-        };
+        if assets_to_withdraw <= queue_threshold
+            && Self::instant_liquidity(&env, &asset) >= token_units
+        {
+            return Self::emit_and_err(&env, Error::BelowQueueThreshold);
+        }
 
         // Subtract shares from user balance immediately to prevent double-spending/inflation
-        let new_user_balance = current_balance.checked_sub(shares).unwrap();
+        let new_user_balance = math::sub(current_balance, shares)?;
         Self::write_user_balance(&env, &from, new_user_balance);
 
-        // Add to pending withdrawals queue
-        let mut pending_withdrawals: Vec<QueuedWithdrawal> = env
-            .storage()
-            .instance()
-            .get(&DataKey::PendingWithdrawals)
-            .unwrap_or(Vec::new(&env));
+        Ok(Self::push_withdrawal_ticket(&env, from, asset, shares)?.id)
+    }
 
-        pending_withdrawals.push_back(queued_withdrawal);
-        env.storage()
-            .instance()
-            .set(&DataKey::PendingWithdrawals, &pending_withdrawals);
+    /// Append a withdrawal ticket to the queue and emit `WithdrawQueued`.
+    /// The caller has already debited the shares from the user's balance.
+    fn push_withdrawal_ticket(
+        env: &Env,
+        from: Address,
+        asset: Address,
+        shares: i128,
+    ) -> Result<WithdrawalTicket, Error> {
+        let share_price = Self::share_price(env)?;
+        let ticket = collections::queue_push(
+            env,
+            WithdrawalTicket {
+                id: 0,
+                user: from.clone(),
+                asset: asset.clone(),
+                shares,
+                share_price,
+                timestamp: env.ledger().timestamp(),
+            },
+        );
 
-        let total_assets = Self::total_assets(&env);
-        let total_shares = Self::total_shares(&env);
-        let share_price = Self::get_share_price(&env);
+        let total_assets = Self::total_assets(env);
+        let total_shares = Self::total_shares(env);
 
-        env.events().publish(
-            (
-                soroban_sdk::Symbol::new(&env, "WithdrawQueued"),
-                from.clone(),
-            ),
-            (
-                asset.clone(),
+        events::publish(
+            env,
+            events::WithdrawQueued {
+                user: from,
+                ticket_id: ticket.id,
+                asset,
                 shares,
                 share_price,
                 total_assets,
                 total_shares,
-            ),
+            },
         );
+        Ok(ticket)
     }
 
     /// Set the threshold for queuing withdrawals.
     ///
     /// Withdrawals larger than this amount will be queued for admin processing.
     /// Only the admin can call this.
-    pub fn set_withdraw_queue_threshold(env: Env, threshold: i128) {
+    pub fn set_withdraw_queue_threshold(env: Env, threshold: i128) -> Result<(), Error> {
         Self::require_admin(&env);
         if threshold < 0 {
-            panic!("threshold must be non-negative");
+            return Self::emit_and_err(&env, Error::NegativeAmount);
         }
         env.storage()
             .instance()
             .set(&DataKey::WithdrawQueueThreshold, &threshold);
-        env.events()
-            .publish((symbol_short!("QueueThr"),), threshold);
+        events::publish(&env, events::WithdrawQueueThresholdSet { threshold });
+        Ok(())
     }
 
-    /// Process a batch of queued withdrawals.
+    /// Fulfil queued withdrawal tickets in FIFO order. Callable by any keeper.
     ///
-    /// The admin processes pending withdrawals in FIFO order up to the specified limit.
-    /// @param limit The maximum number of withdrawals to process.
-    /// @return The number of withdrawals actually processed.
-    pub fn process_queued_withdrawals(env: Env, limit: u32) -> u32 {
-        Self::require_admin(&env);
-
-        let pending_withdrawals: Vec<QueuedWithdrawal> = env
-            .storage()
-            .instance()
-            .get(&DataKey::PendingWithdrawals)
-            .unwrap_or(Vec::new(&env));
-
-        let mut processed = 0;
-        let mut remaining_withdrawals = Vec::new(&env);
-
-        let mut total_shares = Self::total_shares(&env);
-        let mut total_assets = Self::total_assets(&env);
+    /// Each ticket's shares are burned at the current share price and the
+    /// resulting tokens, minus the keeper bounty, are locked for the user to
+    /// pull with `claim_withdrawal`. If idle tokens do not cover a ticket,
+    /// strategy positions are unwound (see `get_unwind_order`); processing
+    /// stops at the first ticket that still cannot be covered, so the queue
+    /// stays FIFO and earlier tickets are still settled.
+    /// @param keeper The address receiving the bounty.
+    /// @param limit The maximum number of tickets to fulfil.
+    /// @return The number of tickets fulfilled.
+    pub fn fulfil_withdrawals(env: Env, keeper: Address, limit: u32) -> Result<u32, Error> {
+        let _guard = Guard::new(&env);
+        Self::check_version(&env)?;
+        keeper.require_auth();
 
         let token: Address = env
             .storage()
@@ -1738,88 +2283,248 @@ impl VolatilityShield {
             .get(&DataKey::Token)
             .expect("Token not initialized");
         let token_client = token::Client::new(&env, &token);
+        let vault = env.current_contract_address();
 
-        for queued_withdrawal in pending_withdrawals.iter() {
-            if processed >= limit {
-                remaining_withdrawals.push_back(queued_withdrawal.clone());
-                continue;
-            }
+        let mut processed = 0;
+        let mut paid_out = 0_i128;
+        let mut bounty_total = 0_i128;
+        while processed < limit {
+            let ticket = match collections::queue_front(&env) {
+                Some(ticket) => ticket,
+                None => break,
+            };
 
-            // Process the withdrawal
-            let assets_to_withdraw = Self::convert_to_assets(env.clone(), queued_withdrawal.shares);
+            let share_price = Self::share_price(&env)?;
+            let amount = Self::value_for_shares(&env, ticket.shares)?;
+
+            // Bounties accrued in this call are still held by the vault.
+            let reserved = math::add(collections::claimable_total(&env), bounty_total)?;
+            let available = math::sub(token_client.balance(&vault), reserved)?;
+            if available < amount {
+                let shortfall = math::sub(amount, available)?;
+                liquidity::pull_from_strategies(&env, &token_client, shortfall)?;
+                if math::sub(token_client.balance(&vault), reserved)? < amount {
+                    break;
+                }
+            }
 
-            total_shares = total_shares.checked_sub(queued_withdrawal.shares).unwrap();
-            total_assets = total_assets.checked_sub(assets_to_withdraw).unwrap();
+            let bounty = liquidity::keeper_bounty(&env, amount)?;
+            let payout = math::sub(amount, bounty)?;
+            paid_out = math::add(paid_out, payout)?;
+            bounty_total = math::add(bounty_total, bounty)?;
 
-            token_client.transfer(
-                &env.current_contract_address(),
-                &queued_withdrawal.user,
-                &assets_to_withdraw,
-            );
+            collections::queue_pop_front(&env);
+            Self::write_total_shares(&env, math::sub(Self::total_shares(&env), ticket.shares)?);
+            Self::write_total_assets(&env, math::sub(Self::total_assets(&env), amount)?);
+            collections::claimable_add(
+                &env,
+                &ClaimableWithdrawal {
+                    ticket_id: ticket.id,
+                    user: ticket.user.clone(),
+                    shares: ticket.shares,
+                    amount: payout,
+                    share_price,
+                    fulfilled_at: env.ledger().timestamp(),
+                },
+            )?;
 
-            env.events().publish(
-                (symbol_short!("WithdrawP"), queued_withdrawal.user.clone()),
-                queued_withdrawal.shares,
+            events::publish(
+                &env,
+                events::WithdrawFulfilled {
+                    user: ticket.user,
+                    ticket_id: ticket.id,
+                    shares: ticket.shares,
+                    amount: payout,
+                    share_price,
+                },
             );
 
             processed += 1;
         }
 
-        // Update totals
-        Self::set_total_shares(env.clone(), total_shares);
-        Self::set_total_assets(env.clone(), total_assets);
+        if bounty_total > 0 {
+            token_client.transfer(&vault, &keeper, &bounty_total);
+        }
+        if processed > 0 {
+            events::publish(
+                &env,
+                events::WithdrawalsProcessed {
+                    keeper,
+                    processed,
+                    paid_out,
+                    bounty: bounty_total,
+                    total_assets: Self::total_assets(&env),
+                    total_shares: Self::total_shares(&env),
+                },
+            );
+        }
 
-        // Update remaining withdrawals
-        env.storage()
-            .instance()
-            .set(&DataKey::PendingWithdrawals, &remaining_withdrawals);
+        Ok(processed)
+    }
 
-        processed
+    /// Set the keeper bounty, in bps of each fulfilled ticket (at most 100).
+    /// Only the admin can call this.
+    pub fn set_keeper_bounty_bps(env: Env, bps: u32) -> Result<(), Error> {
+        Self::require_admin(&env);
+        if bps > liquidity::MAX_KEEPER_BOUNTY_BPS {
+            return Self::emit_and_err(&env, Error::InvalidConfig);
+        }
+        liquidity::set_keeper_bounty_bps(&env, bps);
+        events::publish(&env, events::KeeperBountySet { bps });
+        Ok(())
     }
 
-    /// Cancel a queued withdrawal and return shares to the user.
-    ///
-    /// @param from The address of the user whose withdrawal is being cancelled.
-    pub fn cancel_queued_withdrawal(env: Env, from: Address) -> Result<(), Error> {
-        from.require_auth();
+    pub fn get_keeper_bounty_bps(env: Env) -> u32 {
+        liquidity::keeper_bounty_bps(&env)
+    }
 
-        let mut pending_withdrawals: Vec<QueuedWithdrawal> = env
-            .storage()
-            .instance()
-            .get(&DataKey::PendingWithdrawals)
-            .unwrap_or(Vec::new(&env));
+    /// Configure the idle buffer rebalance keeps in the vault for instant
+    /// withdrawals. Only the admin can call this.
+    pub fn set_idle_buffer(env: Env, config: IdleBufferConfig) -> Result<(), Error> {
+        Self::require_admin(&env);
+        if !liquidity::set_idle_buffer(&env, &config) {
+            return Self::emit_and_err(&env, Error::InvalidConfig);
+        }
+        events::publish(
+            &env,
+            events::IdleBufferSet {
+                target_bps: config.target_bps,
+                min_amount: config.min_amount,
+                max_amount: config.max_amount,
+            },
+        );
+        Ok(())
+    }
 
-        let mut found_index: Option<u32> = None;
-        let mut found_withdrawal: Option<QueuedWithdrawal> = None;
+    pub fn get_idle_buffer(env: Env) -> IdleBufferConfig {
+        liquidity::idle_buffer(&env)
+    }
 
-        for i in 0..pending_withdrawals.len() {
-            let w = pending_withdrawals.get(i).unwrap();
-            if w.user == from {
-                found_index = Some(i);
-                found_withdrawal = Some(w);
-                break;
-            }
-        }
+    /// Base tokens held by the vault and available for instant withdrawals
+    /// (excludes tokens locked for fulfilled withdrawal claims).
+    pub fn get_idle_liquidity(env: Env) -> i128 {
+        let token: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Token)
+            .expect("Token not initialized");
+        liquidity::idle_liquidity(&env, &token::Client::new(&env, &token))
+    }
 
-        let index = found_index.ok_or(Error::WithdrawalNotFound)?;
-        let w = found_withdrawal.unwrap();
+    /// Set the order in which strategies are unwound to fund withdrawals.
+    /// An empty list restores the default lowest-APY-first order.
+    /// Only the admin can call this.
+    pub fn set_unwind_order(env: Env, order: Vec<Address>) -> Result<(), Error> {
+        Self::require_admin(&env);
+        let strategies = Self::get_strategies(&env);
+        if order.iter().any(|strategy| !strategies.contains(&strategy)) {
+            return Self::emit_and_err(&env, Error::ZeroAddressStrategy);
+        }
+        liquidity::set_unwind_order(&env, &order);
+        events::publish(&env, events::UnwindOrderSet { order });
+        Ok(())
+    }
 
-        pending_withdrawals.remove(index);
+    /// Get the effective strategy unwind order.
+    pub fn get_unwind_order(env: Env) -> Vec<Address> {
+        liquidity::unwind_order(&env)
+    }
 
-        // Return shares to user balance
-        let current_balance = Self::read_user_balance(&env, &from);
-        Self::write_user_balance(&env, &from, current_balance + w.shares);
+    /// Claim the tokens locked for a fulfilled withdrawal ticket.
+    ///
+    /// @param ticket_id The fulfilled ticket. Requires the ticket owner's auth.
+    /// @return The number of tokens transferred.
+    pub fn claim_withdrawal(env: Env, ticket_id: u64) -> Result<i128, Error> {
+        let _guard = Guard::new(&env);
+        Self::check_version(&env)?;
+        let claim = match collections::claimable_get(&env, ticket_id) {
+            Some(claim) => claim,
+            None => return Self::emit_and_err(&env, Error::WithdrawalNotFound),
+        };
+        claim.user.require_auth();
+        collections::claimable_remove(&env, ticket_id)?;
 
-        env.storage()
+        let token: Address = env
+            .storage()
             .instance()
-            .set(&DataKey::PendingWithdrawals, &pending_withdrawals);
+            .get(&DataKey::Token)
+            .expect("Token not initialized");
+        token::Client::new(&env, &token).transfer(
+            &env.current_contract_address(),
+            &claim.user,
+            &claim.amount,
+        );
 
-        env.events()
-            .publish((symbol_short!("WdrwCncl"),), (from, w.shares));
+        events::publish(
+            &env,
+            events::WithdrawClaimed {
+                user: claim.user,
+                ticket_id,
+                amount: claim.amount,
+            },
+        );
+
+        Ok(claim.amount)
+    }
+
+    /// Get the claim for a fulfilled withdrawal ticket, if it has not been claimed yet.
+    pub fn get_claimable_withdrawal(env: Env, ticket_id: u64) -> Option<ClaimableWithdrawal> {
+        collections::claimable_get(&env, ticket_id)
+    }
+
+    /// Get the tokens locked for all unclaimed fulfilled withdrawals.
+    pub fn get_total_claimable(env: Env) -> i128 {
+        collections::claimable_total(&env)
+    }
+
+    /// Cancel a queued withdrawal ticket and return its shares to the user.
+    ///
+    /// @param ticket_id The id returned by `queue_withdraw`. Requires the ticket owner's auth.
+    pub fn cancel_queued_withdrawal(env: Env, ticket_id: u64) -> Result<(), Error> {
+        Self::check_version(&env)?;
+        let ticket = match collections::queue_get(&env, ticket_id) {
+            Some(ticket) => ticket,
+            None => return Self::emit_and_err(&env, Error::WithdrawalNotFound),
+        };
+        ticket.user.require_auth();
+        collections::queue_remove(&env, ticket_id);
+
+        // Return shares to user balance
+        let current_balance = Self::read_user_balance(&env, &ticket.user);
+        Self::write_user_balance(
+            &env,
+            &ticket.user,
+            math::add(current_balance, ticket.shares)?,
+        );
+
+        events::publish(
+            &env,
+            events::WithdrawCancelled {
+                user: ticket.user,
+                ticket_id,
+                shares: ticket.shares,
+            },
+        );
 
         Ok(())
     }
 
+    /// Get a pending withdrawal ticket by id.
+    pub fn get_withdrawal_ticket(env: Env, ticket_id: u64) -> Option<WithdrawalTicket> {
+        collections::queue_get(&env, ticket_id)
+    }
+
+    /// Get a user's pending withdrawal tickets, oldest first.
+    pub fn get_user_withdrawal_tickets(env: Env, user: Address) -> Vec<WithdrawalTicket> {
+        let mut tickets = Vec::new(&env);
+        for id in collections::user_ticket_ids(&env, &user).iter() {
+            if let Some(ticket) = collections::queue_get(&env, id) {
+                tickets.push_back(ticket);
+            }
+        }
+        tickets
+    }
+
     /// Get the current withdrawal queue threshold
     pub fn get_withdraw_queue_threshold(env: Env) -> i128 {
         env.storage()
@@ -1828,22 +2533,16 @@ impl VolatilityShield {
             .unwrap_or(i128::MAX)
     }
 
-    /// Get all pending queued withdrawals
-    pub fn get_pending_withdrawals(env: Env) -> Vec<QueuedWithdrawal> {
-        env.storage()
-            .instance()
-            .get(&DataKey::PendingWithdrawals)
-            .unwrap_or(Vec::new(&env))
+    /// Get all pending withdrawal tickets in FIFO order
+    pub fn get_pending_withdrawals(env: Env) -> Vec<WithdrawalTicket> {
+        collections::queue_all(&env)
     }
 
     /// Approve a delegate that may execute withdrawals on behalf of `owner`.
     pub fn set_delegate(env: Env, owner: Address, delegate: Address) {
         owner.require_auth();
         Self::write_delegate(&env, &owner, &delegate);
-        env.events().publish(
-            (soroban_sdk::Symbol::new(&env, "DelegateSet"),),
-            (owner, delegate),
-        );
+        events::publish(&env, events::DelegateSet { owner, delegate });
     }
 
     /// Remove the approved delegate for `owner`.
@@ -1852,10 +2551,7 @@ impl VolatilityShield {
         env.storage()
             .persistent()
             .remove(&DataKey::Delegate(owner.clone()));
-        env.events().publish(
-            (soroban_sdk::Symbol::new(&env, "DelegateRemoved"),),
-            (owner,),
-        );
+        events::publish(&env, events::DelegateRemoved { owner });
     }
 
     /// Return the approved delegate for `owner`, if one is set.
@@ -1864,29 +2560,405 @@ impl VolatilityShield {
     }
 
     // ── Rebalance ─────────────────────────────
+    /// Select how rebalance handles failing strategies. Only the admin can
+    /// call this.
+    pub fn set_rebalance_mode(env: Env, mode: RebalanceMode) {
+        Self::require_admin(&env);
+        rebalance::set_mode(&env, mode);
+        events::publish(&env, events::RebalanceModeSet { mode });
+    }
+
+    pub fn get_rebalance_mode(env: Env) -> RebalanceMode {
+        rebalance::mode(&env)
+    }
+
+    /// Dry-run a rebalance against the current target allocations.
+    ///
+    /// Returns the planned per-strategy moves and whether an atomic rebalance
+    /// with `max_slippage_bps` would commit them. Does not move funds.
+    pub fn simulate_rebalance(env: Env, max_slippage_bps: u32) -> Result<RebalancePlan, Error> {
+        let allocations = Self::rebalance_allocations(&env)?;
+        rebalance::plan(&env, &allocations, max_slippage_bps)
+    }
+
+    /// Configure when keepers may trigger a rebalance with `poke_rebalance`.
+    /// Only the admin can call this.
+    pub fn set_rebalance_trigger(env: Env, trigger: RebalanceTrigger) -> Result<(), Error> {
+        Self::require_admin(&env);
+        if trigger.keeper_reward < 0 {
+            return Self::emit_and_err(&env, Error::NegativeAmount);
+        }
+        rebalance::set_trigger(&env, &trigger);
+        events::publish(
+            &env,
+            events::RebalanceTriggerSet {
+                drift_bps: trigger.drift_bps,
+                min_interval: trigger.min_interval,
+                max_slippage_bps: trigger.max_slippage_bps,
+                keeper_reward: trigger.keeper_reward,
+            },
+        );
+        Ok(())
+    }
+
+    pub fn get_rebalance_trigger(env: Env) -> Option<RebalanceTrigger> {
+        rebalance::trigger(&env)
+    }
+
+    /// Whether `poke_rebalance` would currently run a rebalance.
+    pub fn rebalance_needed(env: Env) -> Result<bool, Error> {
+        if rebalance::in_flight(&env).is_some() {
+            return Ok(false);
+        }
+        let trigger = match rebalance::trigger(&env) {
+            Some(trigger) => trigger,
+            None => return Ok(false),
+        };
+        let allocations: Map<Address, i128> = env
+            .storage()
+            .instance()
+            .get(&DataKey::TargetAllocations)
+            .unwrap_or(Map::new(&env));
+        rebalance::needed(&env, &trigger, &allocations)
+    }
+
+    /// Rebalance towards the target allocations if the configured drift or
+    /// interval trigger allows it. Callable by any keeper, who is paid the
+    /// configured reward out of idle liquidity if the rebalance moved funds
+    /// or reduced the drift.
+    pub fn poke_rebalance(env: Env, keeper: Address) -> Result<RebalanceReport, Error> {
+        let _guard = Guard::new(&env);
+        Self::check_version(&env)?;
+        Self::assert_not_emergency_shutdown(&env)?;
+        keeper.require_auth();
+        if !Self::rebalance_needed(env.clone())? {
+            return Self::emit_and_err(&env, Error::RebalanceNotNeeded);
+        }
+        let trigger = rebalance::trigger(&env).unwrap();
+        let allocations = Self::rebalance_allocations(&env)?;
+        let drift_before = rebalance::max_drift_bps(&env, &allocations)?;
+        let report = Self::execute_rebalance(&env, trigger.max_slippage_bps)?;
+        let progressed = report.withdrawn > 0
+            || report.deposited > 0
+            || rebalance::max_drift_bps(&env, &allocations)? < drift_before;
+
+        let token_client = token::Client::new(&env, &Self::get_asset(&env));
+        let reward = if progressed {
+            trigger
+                .keeper_reward
+                .min(liquidity::idle_liquidity(&env, &token_client))
+        } else {
+            0
+        };
+        if reward > 0 {
+            token_client.transfer(&env.current_contract_address(), &keeper, &reward);
+            Self::write_total_assets(&env, math::sub(Self::total_assets(&env), reward)?);
+        }
+        events::publish(
+            &env,
+            events::RebalancePoked {
+                keeper,
+                reward,
+                total_assets: Self::total_assets(&env),
+            },
+        );
+        Ok(report)
+    }
+
+    /// Start an incremental rebalance towards the current target allocations.
+    ///
+    /// Snapshots the allocations and a cursor; keepers then move the funds
+    /// with `continue_rebalance`. Only the admin or oracle can call this.
+    /// @param max_move_per_step Largest amount moved per strategy step.
+    pub fn start_rebalance(env: Env, caller: Address, max_move_per_step: i128) -> Result<(), Error> {
+        Self::check_version(&env)?;
+        Self::assert_not_emergency_shutdown(&env)?;
+        caller.require_auth();
+        let admin = Self::read_admin(&env);
+        let oracle = Self::get_oracle(&env);
+        Self::assert_admin_or_oracle(&env, &caller, &admin, &oracle)?;
+        if max_move_per_step <= 0 {
+            return Self::emit_and_err(&env, Error::InvalidAmount);
+        }
+        if rebalance::in_flight(&env).is_some() {
+            return Self::emit_and_err(&env, Error::RebalanceInProgress);
+        }
+
+        let allocations = Self::rebalance_allocations(&env)?;
+        // Reuse the plan's ordering so withdrawals come before deposits.
+        let mut strategies = Vec::new(&env);
+        for planned in rebalance::plan(&env, &allocations, 0)?.moves.iter() {
+            strategies.push_back(planned.strategy);
+        }
+        let progress = RebalanceProgress {
+            strategies,
+            allocations,
+            cursor: 0,
+            max_move_per_step,
+            moved: 0,
+            steps: 0,
+            started_at: env.ledger().timestamp(),
+        };
+        rebalance::set_in_flight(&env, &progress);
+
+        events::publish(
+            &env,
+            events::RebalanceStarted {
+                caller,
+                strategy_count: progress.strategies.len(),
+                max_move_per_step,
+            },
+        );
+        Ok(())
+    }
+
+    /// Advance the in-flight incremental rebalance by up to `max_strategies`
+    /// steps. Callable by any keeper.
+    ///
+    /// Each step moves one strategy towards its target by at most
+    /// `max_move_per_step`; the cursor moves on once the strategy is done.
+    /// Strategies whose balance cannot be read are skipped.
+    /// @return `true` once the rebalance has completed.
+    pub fn continue_rebalance(env: Env, keeper: Address, max_strategies: u32) -> Result<bool, Error> {
+        let _guard = Guard::new(&env);
+        Self::check_version(&env)?;
+        Self::assert_not_emergency_shutdown(&env)?;
+        keeper.require_auth();
+        let mut progress = match rebalance::in_flight(&env) {
+            Some(progress) => progress,
+            None => return Self::emit_and_err(&env, Error::NoRebalanceInProgress),
+        };
+
+        let token_client = token::Client::new(&env, &Self::get_asset(&env));
+        let mut steps = 0;
+        while steps < max_strategies && progress.cursor < progress.strategies.len() {
+            steps += 1;
+            progress.steps += 1;
+
+            let planned = match rebalance::next_step(&env, &progress)? {
+                Some(planned) => planned,
+                None => {
+                    let strategy = progress.strategies.get(progress.cursor).unwrap();
+                    events::publish(
+                        &env,
+                        events::RebalancePartialFailure {
+                            failed_strategy: strategy,
+                            reason: soroban_sdk::String::from_str(&env, "balance failed"),
+                        },
+                    );
+                    progress.cursor += 1;
+                    continue;
+                }
+            };
+
+            if planned.delta != 0 {
+                Self::execute_planned_move(&env, &token_client, &planned)?;
+                progress.moved += planned.delta.abs();
+                events::publish(
+                    &env,
+                    events::RebalanceStep {
+                        strategy: planned.strategy.clone(),
+                        delta: planned.delta,
+                        target_balance: planned.target_balance,
+                    },
+                );
+            }
+
+            // Stay on this strategy only while the step cap is what held it back.
+            let remaining = planned.target_balance - planned.current_balance;
+            let capped = remaining.abs() > progress.max_move_per_step
+                && planned.delta.abs() == progress.max_move_per_step;
+            if !capped {
+                progress.cursor += 1;
+            }
+        }
+
+        if progress.cursor < progress.strategies.len() {
+            rebalance::set_in_flight(&env, &progress);
+            return Ok(false);
+        }
+
+        rebalance::clear_in_flight(&env);
+        rebalance::record_rebalance(&env);
+        events::publish(
+            &env,
+            events::RebalanceCompleted {
+                moved: progress.moved,
+                steps: progress.steps,
+            },
+        );
+        Self::publish_vault_snapshot(&env);
+        Self::record_share_price_snapshot(&env);
+        Ok(true)
+    }
+
+    /// Abandon the in-flight incremental rebalance. Funds already moved stay
+    /// where they are. Only the admin can call this.
+    pub fn cancel_rebalance(env: Env) -> Result<(), Error> {
+        Self::require_admin(&env);
+        let progress = match rebalance::in_flight(&env) {
+            Some(progress) => progress,
+            None => return Self::emit_and_err(&env, Error::NoRebalanceInProgress),
+        };
+        rebalance::clear_in_flight(&env);
+        events::publish(
+            &env,
+            events::RebalanceCancelled {
+                cursor: progress.cursor,
+                moved: progress.moved,
+            },
+        );
+        Ok(())
+    }
+
+    /// Per-strategy outcome of the last completed rebalance, if any.
+    pub fn get_last_rebalance_report(env: Env) -> Option<RebalanceReport> {
+        rebalance::last_report(&env)
+    }
+
+    /// Progress of the in-flight incremental rebalance, if any.
+    pub fn get_rebalance_progress(env: Env) -> Option<RebalanceProgress> {
+        rebalance::in_flight(&env)
+    }
+
     /// Move funds between strategies according to `allocations`.
     ///
     /// `allocations` maps each strategy address to its *target* balance.
     /// If target > current  → vault sends tokens to the strategy and calls deposit().
     /// If target < current  → strategy withdraws and sends tokens back to vault.
+    /// All withdrawals run before any deposit, so deposits are funded from
+    /// withdrawn tokens first. The per-strategy outcomes are returned, emitted
+    /// and stored as a `RebalanceReport`.
     ///
     /// When circuit breaker is active, uses LastSafeAllocation instead of current oracle data.
+    /// In `RebalanceMode::Atomic` the moves are planned first and either all
+    /// of them are committed or the call fails with `RebalanceAborted`.
     /// **Access control**: must be called via the multi-sig governance system.
     fn internal_rebalance(
         env: &Env,
         caller: &Address,
         max_slippage_bps: u32,
-    ) -> Result<u32, Error> {
-        Self::check_version(env, 1);
-        if Self::emergency_shutdown_active(env) {
-            return Self::emit_and_err(env, Error::EmergencyShutdownActive);
-        }
+    ) -> Result<RebalanceReport, Error> {
+        Self::check_version(env)?;
+        Self::assert_not_emergency_shutdown(env)?;
         let admin = Self::read_admin(env);
         let oracle = Self::get_oracle(env);
 
-        // OR-auth: require that either Admin or Oracle authorised this invocation.
-        Self::require_admin_or_oracle(env, caller, &admin, &oracle);
+        // Either Admin or Oracle must have initiated this invocation.
+        Self::assert_admin_or_oracle(env, caller, &admin, &oracle)?;
+        Self::execute_rebalance(env, max_slippage_bps)
+    }
+
+    /// Run a full rebalance. Callers have already checked who may trigger it.
+    fn execute_rebalance(env: &Env, max_slippage_bps: u32) -> Result<RebalanceReport, Error> {
+        if rebalance::in_flight(env).is_some() {
+            return Self::emit_and_err(env, Error::RebalanceInProgress);
+        }
+
+        let allocations = Self::rebalance_allocations(env)?;
+        let total_assets_before = Self::total_assets(env);
+        let plan = rebalance::plan(env, &allocations, max_slippage_bps)?;
+        let atomic = rebalance::mode(env) == RebalanceMode::Atomic;
+        if atomic && !plan.feasible {
+            return Self::emit_and_err(env, Error::RebalanceAborted);
+        }
+
+        // The plan lists withdrawals before deposits.
+        let token_client = token::Client::new(env, &Self::get_asset(env));
+        let mut outcomes = Vec::new(env);
+        let mut withdrawn = 0_i128;
+        let mut deposited = 0_i128;
+        let mut successful_strategies: u32 = 0;
+        for planned in plan.moves.iter() {
+            let outcome = if atomic {
+                Self::execute_planned_move(env, &token_client, &planned)?;
+                StrategyOutcome {
+                    strategy: planned.strategy.clone(),
+                    balance_before: planned.current_balance,
+                    target_balance: planned.target_balance,
+                    moved: planned.delta,
+                    result: MoveResult::from_delta(planned.delta),
+                }
+            } else {
+                Self::execute_move_best_effort(env, &token_client, &planned)
+            };
+            match outcome.result {
+                MoveResult::Withdrawn => withdrawn -= outcome.moved,
+                MoveResult::Deposited => deposited += outcome.moved,
+                _ => {}
+            }
+            if outcome.result.succeeded() {
+                successful_strategies += 1;
+            }
+            outcomes.push_back(outcome);
+        }
+
+        // Verify slippage after all operations
+        for outcome in outcomes.iter() {
+            if outcome.result == MoveResult::Unreachable {
+                continue;
+            }
+            let strategy = StrategyClient::new(env, outcome.strategy.clone());
+            let final_balance = match strategy.try_balance() {
+                Ok(bal) => bal,
+                Err(_) => continue,
+            };
+            let expected_balance = outcome.target_balance;
+
+            // Calculate slippage in basis points
+            if expected_balance > 0 {
+                let slippage_abs = Self::balance_deviation_amount(final_balance, expected_balance);
+                let slippage_bps = math::mul_div(slippage_abs, 10_000, expected_balance)?;
+
+                if slippage_bps > max_slippage_bps as i128 {
+                    // Emit SlippageExceeded event
+                    events::publish(
+                        env,
+                        events::SlippageExceeded {
+                            strategy: outcome.strategy.clone(),
+                            expected_balance,
+                            actual_balance: final_balance,
+                            slippage_bps,
+                        },
+                    );
+                    return Self::emit_and_err(env, Error::SlippageExceeded);
+                }
+            }
+        }
 
+        let final_total_assets = Self::total_assets(env);
+
+        events::publish(
+            env,
+            events::Rebalanced {
+                total_assets_before,
+                total_assets_after: final_total_assets,
+            },
+        );
+        Self::publish_vault_snapshot(env);
+        Self::record_share_price_snapshot(env);
+
+        let report = RebalanceReport {
+            total_assets_before,
+            total_assets_after: final_total_assets,
+            withdrawn,
+            deposited,
+            successful_strategies,
+            outcomes,
+        };
+        rebalance::set_last_report(env, &report);
+        rebalance::record_rebalance(env);
+        events::publish(
+            env,
+            events::RebalanceReported {
+                report: report.clone(),
+            },
+        );
+        Ok(report)
+    }
+
+    /// Target allocations (bps per strategy) to rebalance towards.
+    fn rebalance_allocations(env: &Env) -> Result<Map<Address, i128>, Error> {
         // Check if circuit breaker is active
         let circuit_breaker_active: bool = env
             .storage()
@@ -1900,10 +2972,10 @@ impl VolatilityShield {
 
         let allocations: Map<Address, i128> = if circuit_breaker_active {
             // Use last safe allocation when circuit breaker is active
-            env.storage()
-                .instance()
-                .get(&DataKey::LastSafeAllocation)
-                .ok_or(Error::NotInitialized)?
+            match env.storage().instance().get(&DataKey::LastSafeAllocation) {
+                Some(allocations) => allocations,
+                None => return Self::emit_and_err(env, Error::NotInitialized),
+            }
         } else {
             // Normal path: check oracle staleness
             let now = env.ledger().timestamp();
@@ -1915,191 +2987,146 @@ impl VolatilityShield {
             let max_staleness = Self::max_staleness(env);
 
             if now > last_update.saturating_add(max_staleness) {
-                env.events()
-                    .publish((soroban_sdk::Symbol::new(env, "OracleStale"),), last_update);
+                events::publish(env, events::OracleStale { last_update });
                 return Self::emit_and_err(env, Error::StaleOracleData);
             }
 
-            env.storage()
-                .instance()
-                .get(&DataKey::TargetAllocations)
-                .ok_or(Error::NotInitialized)?
+            match env.storage().instance().get(&DataKey::TargetAllocations) {
+                Some(allocations) => allocations,
+                None => return Self::emit_and_err(env, Error::NotInitialized),
+            }
         };
+        Ok(allocations)
+    }
 
-        let asset_addr = Self::get_asset(&env);
-        let token_client = token::Client::new(&env, &asset_addr);
+    /// Execute a planned move, skipping it if it fails. Failing strategies
+    /// are flagged and reported through `RebalancePartialFailure`.
+    fn execute_move_best_effort(
+        env: &Env,
+        token_client: &token::Client,
+        planned: &PlannedMove,
+    ) -> StrategyOutcome {
+        let strategy_addr = planned.strategy.clone();
+        let mut outcome = StrategyOutcome {
+            strategy: strategy_addr.clone(),
+            balance_before: planned.current_balance,
+            target_balance: planned.target_balance,
+            moved: 0,
+            result: MoveResult::Unchanged,
+        };
+        let strategy = StrategyClient::new(env, strategy_addr.clone());
         let vault = env.current_contract_address();
 
-        let mut initial_balances: Map<Address, i128> = Map::new(&env);
-        let total_assets = Self::total_assets(env);
-        let mut successful_strategies: u32 = 0;
-
-        // Execute rebalance operations
-        for (strategy_addr, bps_allocation) in allocations.iter() {
-            let strategy = StrategyClient::new(&env, strategy_addr.clone());
-
-            let current_balance = match strategy.try_balance() {
-                Ok(bal) => bal,
-                Err(reason) => {
-                    let _ = Self::flag_strategy(env.clone(), strategy_addr.clone());
-                    env.events().publish(
-                        (
-                            soroban_sdk::Symbol::new(env, "RebalancePartialFailure"),
-                            strategy_addr.clone(),
-                        ),
-                        RebalancePartialFailure {
-                            failed_strategy: strategy_addr.clone(),
-                            reason,
-                        },
-                    );
-                    continue;
+        let failure = if !planned.reachable {
+            outcome.result = MoveResult::Unreachable;
+            Some(soroban_sdk::String::from_str(env, "balance failed"))
+        } else if planned.delta > 0 {
+            // Earlier withdrawals may have returned less than planned.
+            let amount = planned
+                .delta
+                .min(liquidity::idle_liquidity(env, token_client));
+            if amount <= 0 {
+                return outcome;
+            }
+            // CEI: confirm strategy accepts before transferring tokens
+            match strategy.try_deposit(amount) {
+                Ok(_) => {
+                    token_client.transfer(&vault, &strategy_addr, &amount);
+                    liquidity::book_transfer(env, &strategy_addr, amount);
+                    outcome.moved = amount;
+                    outcome.result = MoveResult::Deposited;
+                    None
                 }
-            };
-
-            initial_balances.set(strategy_addr.clone(), current_balance);
-
-            // Convert BPS to absolute target allocation
-            let target_allocation = total_assets
-                .checked_mul(bps_allocation)
-                .unwrap()
-                .checked_div(10_000)
-                .unwrap_or(0);
-
-            let mut op_success = true;
-
-            if target_allocation > current_balance {
-                // Vault → Strategy (CEI: confirm strategy accepts before transferring tokens)
-                let diff = target_allocation - current_balance;
-                match strategy.try_deposit(diff) {
-                    Ok(_) => {
-                        // Strategy accepted the deposit; now transfer tokens to back it.
-                        token_client.transfer(&vault, &strategy_addr, &diff);
-                    }
-                    Err(reason) => {
-                        let _ = Self::flag_strategy(env.clone(), strategy_addr.clone());
-                        env.events().publish(
-                            (
-                                soroban_sdk::Symbol::new(env, "RebalancePartialFailure"),
-                                strategy_addr.clone(),
-                            ),
-                            RebalancePartialFailure {
-                                failed_strategy: strategy_addr.clone(),
-                                reason,
+                Err(reason) => Some(reason),
+            }
+        } else if planned.delta < 0 {
+            let amount = -planned.delta;
+            let before = token_client.balance(&vault);
+            match strategy.try_withdraw(amount) {
+                Err(reason) => Some(reason),
+                Ok(_) => {
+                    // Strategies may push the tokens themselves; pull only what is missing.
+                    let missing = amount - (token_client.balance(&vault) - before);
+                    if missing > 0
+                        && token_client
+                            .try_transfer(&strategy_addr, &vault, &missing)
+                            .is_err()
+                    {
+                        // Tokens didn't arrive — re-deposit to restore strategy balance,
+                        // then emit an alert event for off-chain monitoring.
+                        let _ = strategy.try_deposit(amount);
+                        events::publish(
+                            env,
+                            events::RebalanceWithdrawTransferFailed {
+                                strategy: strategy_addr.clone(),
+                                amount,
                             },
                         );
-                        op_success = false;
-                    }
-                }
-            } else if target_allocation < current_balance {
-                // Strategy → Vault
-                let diff = current_balance - target_allocation;
-                if let Err(reason) = strategy.try_withdraw(diff) {
-                    let _ = Self::flag_strategy(env.clone(), strategy_addr.clone());
-                    env.events().publish(
-                        (
-                            soroban_sdk::Symbol::new(env, "RebalancePartialFailure"),
-                            strategy_addr.clone(),
-                        ),
-                        RebalancePartialFailure {
-                            failed_strategy: strategy_addr.clone(),
-                            reason,
-                        },
-                    );
-                    op_success = false;
-                } else {
-                    // Strategy withdrew successfully; now pull the tokens into the vault.
-                    // Use try_transfer so a transfer failure is caught and handled rather
-                    // than leaving vault accounting inconsistent with actual balances.
-                    match token_client.try_transfer(&strategy_addr, &vault, &diff) {
-                        Ok(_) => {}
-                        Err(_) => {
-                            // Tokens didn't arrive — re-deposit to restore strategy balance,
-                            // then emit an alert event for off-chain monitoring.
-                            let _ = strategy.try_deposit(diff);
-                            env.events().publish(
-                                (
-                                    soroban_sdk::Symbol::new(
-                                        env,
-                                        "RebalanceWithdrawTransferFailed",
-                                    ),
-                                    strategy_addr.clone(),
-                                ),
-                                RebalanceWithdrawTransferFailed {
-                                    strategy: strategy_addr.clone(),
-                                    amount: diff,
-                                },
-                            );
-                            op_success = false;
-                        }
+                        outcome.result = MoveResult::Failed;
+                        return outcome;
                     }
+                    liquidity::book_transfer(env, &strategy_addr, planned.delta);
+                    outcome.moved = planned.delta;
+                    outcome.result = MoveResult::Withdrawn;
+                    None
                 }
             }
+        } else {
+            None
+        };
 
-            if op_success {
-                successful_strategies += 1;
+        if let Some(reason) = failure {
+            let _ = Self::flag_strategy(env.clone(), strategy_addr.clone());
+            events::publish(
+                env,
+                events::RebalancePartialFailure {
+                    failed_strategy: strategy_addr,
+                    reason,
+                },
+            );
+            if outcome.result != MoveResult::Unreachable {
+                outcome.result = MoveResult::Failed;
             }
         }
+        outcome
+    }
 
-        // Verify slippage after all operations
-        for (strategy_addr, target_allocation) in allocations.iter() {
-            if !initial_balances.contains_key(strategy_addr.clone()) {
-                continue;
+    /// Execute a planned move. Any failure returns `RebalanceAborted`, and the
+    /// host rolls back everything the calling invocation already moved.
+    fn execute_planned_move(
+        env: &Env,
+        token_client: &token::Client,
+        planned: &PlannedMove,
+    ) -> Result<(), Error> {
+        let strategy = StrategyClient::new(env, planned.strategy.clone());
+        let vault = env.current_contract_address();
+        if planned.delta > 0 {
+            if strategy.try_deposit(planned.delta).is_err()
+                || token_client
+                    .try_transfer(&vault, &planned.strategy, &planned.delta)
+                    .is_err()
+            {
+                return Self::emit_and_err(env, Error::RebalanceAborted);
             }
-            let strategy = StrategyClient::new(&env, strategy_addr.clone());
-            let final_balance = match strategy.try_balance() {
-                Ok(bal) => bal,
-                Err(_) => continue,
-            };
-            let _initial_balance = initial_balances.get(strategy_addr.clone()).unwrap_or(0);
-
-            // Calculate expected balance based on target allocation (BPS -> Absolute)
-            let expected_balance = total_assets
-                .checked_mul(target_allocation)
-                .unwrap()
-                .checked_div(10_000)
-                .unwrap_or(0);
-
-            // Calculate slippage in basis points
-            if expected_balance > 0 {
-                let slippage_abs = if final_balance > expected_balance {
-                    final_balance - expected_balance
-                } else {
-                    expected_balance - final_balance
-                };
-
-                let slippage_bps = (slippage_abs.checked_mul(10000).unwrap())
-                    .checked_div(expected_balance)
-                    .unwrap_or(0);
-
-                if slippage_bps > max_slippage_bps as i128 {
-                    // Emit SlippageExceeded event
-                    env.events().publish(
-                        (soroban_sdk::Symbol::new(&env, "SlippageExceeded"),),
-                        (
-                            strategy_addr.clone(),
-                            expected_balance,
-                            final_balance,
-                            slippage_bps,
-                        ),
-                    );
-                    return Self::emit_and_err(&env, Error::SlippageExceeded);
-                }
+            liquidity::book_transfer(env, &planned.strategy, planned.delta);
+        } else if planned.delta < 0 {
+            let amount = -planned.delta;
+            let before = token_client.balance(&vault);
+            if strategy.try_withdraw(amount).is_err() {
+                return Self::emit_and_err(env, Error::RebalanceAborted);
             }
+            // Strategies may push the tokens themselves; pull only what is missing.
+            let missing = amount - (token_client.balance(&vault) - before);
+            if missing > 0
+                && token_client
+                    .try_transfer(&planned.strategy, &vault, &missing)
+                    .is_err()
+            {
+                return Self::emit_and_err(env, Error::RebalanceAborted);
+            }
+            liquidity::book_transfer(env, &planned.strategy, planned.delta);
         }
-
-        let total_assets_before = total_assets;
-        let final_total_assets = Self::total_assets(env);
-
-        env.events().publish(
-            (soroban_sdk::Symbol::new(&env, "Rebalanced"),),
-            Rebalanced {
-                total_assets_before,
-                total_assets_after: final_total_assets,
-            },
-        );
-        Self::record_share_price_snapshot(env);
-
-        Ok(successful_strategies)
+        Ok(())
     }
 
     /// Stores new target allocations from the Oracle. Validates timestamp freshness.
@@ -2148,6 +3175,14 @@ impl VolatilityShield {
                 .set(&DataKey::LastSafeAllocation, &allocations);
         }
 
+        events::publish(
+            &env,
+            events::OracleDataUpdated {
+                allocations,
+                timestamp,
+            },
+        );
+
         Ok(())
     }
 
@@ -2196,10 +3231,8 @@ impl VolatilityShield {
     }
 
     /// Calculate the difference between current and target balances.
-    pub fn calc_rebalance_delta(current: i128, target: i128) -> i128 {
-        target
-            .checked_sub(current)
-            .expect("arithmetic overflow in rebalance delta")
+    pub fn calc_rebalance_delta(current: i128, target: i128) -> Result<i128, Error> {
+        math::sub(target, current)
     }
 
     // ── Strategy Management ───────────────────
@@ -2210,7 +3243,7 @@ impl VolatilityShield {
     /// Direct admin calls are intentionally not possible — the two-step governance
     /// approval is the sole entry point, satisfying the whitelist requirement.
     fn internal_add_strategy(env: &Env, strategy: Address) -> Result<(), Error> {
-        Self::check_version(env, 1);
+        Self::check_version(env)?;
         // No require_admin here — access is enforced by the proposal/approval flow above.
 
         let mut strategies: Vec<Address> = env
@@ -2225,6 +3258,7 @@ impl VolatilityShield {
         env.storage()
             .instance()
             .set(&DataKey::Strategies, &strategies);
+        liquidity::set_booked(env, &strategy, 0);
 
         // Initialize health state
         let health_key = DataKey::StrategyHealth(strategy.clone());
@@ -2236,8 +3270,7 @@ impl VolatilityShield {
         };
         env.storage().instance().set(&health_key, &default_health);
 
-        env.events()
-            .publish((soroban_sdk::Symbol::new(&env, "StrategyAdded"),), strategy);
+        events::publish(env, events::StrategyAdded { strategy });
 
         Ok(())
     }
@@ -2267,10 +3300,7 @@ impl VolatilityShield {
             .get::<_, u32>(&DataKey::LastHarvestLedger)
             .unwrap_or(current)
             .saturating_add(ledgers);
-        env.events().publish(
-            (soroban_sdk::Symbol::new(&env, "HarvestScheduled"),),
-            next_eligible,
-        );
+        events::publish(&env, events::HarvestScheduled { next_eligible });
     }
 
     pub fn can_harvest(env: Env) -> bool {
@@ -2291,12 +3321,14 @@ impl VolatilityShield {
         seq >= last.saturating_add(interval)
     }
 
-    /// Harvest yields from all strategies and move them to the treasury.
+    /// Book what each strategy gained or lost since the vault last booked its
+    /// balance into total assets.
     ///
     /// Records yield snapshots before and after collection for APY calculation.
-    /// @return The total amount of yield harvested.
+    /// Strategies whose balance cannot be read are marked unhealthy and skipped.
+    /// @return The net amount booked; negative when strategies lost value.
     pub fn harvest(env: Env) -> Result<i128, Error> {
-        Self::check_version(&env, 1);
+        Self::check_version(&env)?;
 
         let interval: u32 = env
             .storage()
@@ -2312,10 +3344,7 @@ impl VolatilityShield {
                 .instance()
                 .set(&DataKey::LastHarvestLedger, &current);
             let next_eligible = current.saturating_add(interval);
-            env.events().publish(
-                (soroban_sdk::Symbol::new(&env, "HarvestScheduled"),),
-                next_eligible,
-            );
+            events::publish(&env, events::HarvestScheduled { next_eligible });
         } else {
             Self::require_admin(&env);
         }
@@ -2327,72 +3356,63 @@ impl VolatilityShield {
 
         let current_ledger = env.ledger().sequence();
 
-        // Record before-harvest snapshots
+        // Read each balance once. Unreadable strategies are marked unhealthy
+        // and left out of this harvest.
+        let mut balances: Vec<(Address, i128)> = Vec::new(&env);
         for strategy_addr in strategies.iter() {
-            let addr = strategy_addr.clone();
-            let strategy = StrategyClient::new(&env, addr.clone());
-            let before_balance = strategy.balance();
+            let strategy = StrategyClient::new(&env, strategy_addr.clone());
+            match strategy.try_balance() {
+                Ok(balance) => balances.push_back((strategy_addr, balance)),
+                Err(_) => Self::mark_unhealthy(&env, &strategy_addr),
+            }
+        }
+
+        // Record before-harvest snapshots
+        for (addr, before_balance) in balances.iter() {
             let snapshot = YieldSnapshot {
                 balance: before_balance,
                 ledger: current_ledger,
             };
 
             let history_key = DataKey::StrategyYieldSnapshot(addr.clone());
-            let mut history: YieldHistory =
-                env.storage()
-                    .instance()
-                    .get(&history_key)
-                    .unwrap_or(YieldHistory {
-                        snapshots: Vec::new(&env),
-                    });
-            history.snapshots.push_back(snapshot);
-            env.storage().instance().set(&history_key, &history);
+            Self::record_yield_snapshot(&env, &history_key, snapshot);
         }
 
         let mut total_yield: i128 = 0;
-        for strategy_addr in strategies.iter() {
-            let strategy = StrategyClient::new(&env, strategy_addr);
-            let yield_amount = strategy.balance();
-            total_yield = total_yield.checked_add(yield_amount).unwrap();
+        for (addr, balance) in balances.iter() {
+            // Strategies registered before booking start from their current balance.
+            let booked = liquidity::booked(&env, &addr).unwrap_or(balance);
+            total_yield = math::add(total_yield, math::sub(balance, booked)?)?;
+            liquidity::set_booked(&env, &addr, balance);
         }
 
-        if total_yield > 0 {
+        if total_yield != 0 {
             let current_assets = Self::total_assets(&env);
-            Self::set_total_assets(
-                env.clone(),
-                current_assets.checked_add(total_yield).unwrap(),
-            );
+            Self::write_total_assets(&env, math::add(current_assets, total_yield)?);
         }
 
-        // Record after-harvest snapshots (balance should be 0 after harvest)
-        for strategy_addr in strategies.iter() {
-            let addr = strategy_addr.clone();
-            let strategy = StrategyClient::new(&env, addr.clone());
-            let after_balance = strategy.balance();
+        // Record after-harvest snapshots
+        for (addr, after_balance) in balances.iter() {
             let snapshot = YieldSnapshot {
                 balance: after_balance,
                 ledger: current_ledger,
             };
 
             let history_key = DataKey::StrategyYieldSnapshot(addr.clone());
-            let mut history: YieldHistory =
-                env.storage()
-                    .instance()
-                    .get(&history_key)
-                    .unwrap_or(YieldHistory {
-                        snapshots: Vec::new(&env),
-                    });
-            history.snapshots.push_back(snapshot);
-            env.storage().instance().set(&history_key, &history);
+            Self::record_yield_snapshot(&env, &history_key, snapshot);
         }
 
         let total_assets_after = Self::total_assets(&env);
         let total_shares_after = Self::total_shares(&env);
         Self::record_share_price_snapshot(&env);
         Self::record_tvl_snapshot(&env);
-        env.events().publish(
-            (soroban_sdk::Symbol::new(&env, "Harvest"),),
-            (total_yield, total_assets_after, total_shares_after),
+        events::publish(
+            &env,
+            events::Harvested {
+                total_yield,
+                total_assets: total_assets_after,
+                total_shares: total_shares_after,
+            },
         );
         Ok(total_yield)
     }
@@ -2400,7 +3420,8 @@ impl VolatilityShield {
     // ── Strategy Health Monitoring ───────────────────
     /// Check the health of all registered strategies.
     ///
-    /// Strategies are considered unhealthy if their actual balance deviates significantly from the expected balance.
+    /// Strategies are considered unhealthy if their actual balance deviates significantly from the expected balance,
+    /// or if it cannot be read.
     /// @return A list of addresses for strategies detected as unhealthy.
     pub fn check_strategy_health(env: Env) -> Result<Vec<Address>, Error> {
         Self::require_admin(&env);
@@ -2426,15 +3447,21 @@ impl VolatilityShield {
             .get(&DataKey::TargetAllocations)
             .unwrap_or(Map::new(&env));
 
+        // Rebalance keeps the idle buffer in the vault and allocates the rest.
         let total_assets = Self::total_assets(&env);
+        let deployable = total_assets - liquidity::idle_buffer_target(&env, total_assets)?;
 
         for strategy_addr in strategies.iter() {
             let strategy = StrategyClient::new(&env, strategy_addr.clone());
-            let actual_balance = strategy.balance();
+            let Ok(actual_balance) = strategy.try_balance() else {
+                Self::mark_unhealthy(&env, &strategy_addr);
+                unhealthy_strategies.push_back(strategy_addr.clone());
+                continue;
+            };
 
             // Get expected balance from allocations
             let bps_allocation = expected_allocations.get(strategy_addr.clone()).unwrap_or(0);
-            let expected_balance = total_assets
+            let expected_balance = deployable
                 .checked_mul(bps_allocation)
                 .unwrap_or(0)
                 .checked_div(10_000)
@@ -2480,9 +3507,12 @@ impl VolatilityShield {
                 if consecutive_failures >= max_failures {
                     is_healthy = false;
                     // Auto-flag event
-                    env.events().publish(
-                        (symbol_short!("StrategyF"), strategy_addr.clone()),
-                        current_time,
+                    events::publish(
+                        &env,
+                        events::StrategyFlagged {
+                            strategy: strategy_addr.clone(),
+                            timestamp: current_time,
+                        },
                     );
                 }
             } else {
@@ -2506,6 +3536,15 @@ impl VolatilityShield {
                     consecutive_failures,
                 };
                 env.storage().instance().set(&health_key, &current_health);
+                events::publish(
+                    &env,
+                    events::StrategyHealthUpdated {
+                        strategy: strategy_addr.clone(),
+                        balance: actual_balance,
+                        is_healthy,
+                        consecutive_failures,
+                    },
+                );
             }
 
             if !is_healthy {
@@ -2529,6 +3568,12 @@ impl VolatilityShield {
             return Self::emit_and_err(&env, Error::NotInitialized);
         }
 
+        Self::mark_unhealthy(&env, &strategy);
+        Ok(())
+    }
+
+    /// Mark a strategy unhealthy and emit `StrategyFlagged`.
+    fn mark_unhealthy(env: &Env, strategy: &Address) {
         let health_key = DataKey::StrategyHealth(strategy.clone());
         let current_time = env.ledger().timestamp();
 
@@ -2553,10 +3598,13 @@ impl VolatilityShield {
         env.storage().instance().set(&health_key, &updated_health);
 
         // Emit StrategyFlagged event
-        env.events()
-            .publish((symbol_short!("StrategyF"), strategy.clone()), current_time);
-
-        Ok(())
+        events::publish(
+            env,
+            events::StrategyFlagged {
+                strategy: strategy.clone(),
+                timestamp: current_time,
+            },
+        );
     }
 
     /// Remove a strategy from the vault and withdraw all funds from it.
@@ -2585,14 +3633,16 @@ impl VolatilityShield {
 
             // Withdraw from strategy
             strategy_client.withdraw(strategy_balance);
+        }
 
-            // Update total assets to reflect returned funds
+        // The booked part is already in total assets; book only the difference.
+        let booked = liquidity::booked(&env, &strategy).unwrap_or(strategy_balance);
+        let gain = math::sub(strategy_balance, booked)?;
+        if gain != 0 {
             let current_assets = Self::total_assets(&env);
-            Self::set_total_assets(
-                env.clone(),
-                current_assets.checked_add(strategy_balance).unwrap(),
-            );
+            Self::write_total_assets(&env, math::add(current_assets, gain)?);
         }
+        liquidity::clear_booked(&env, &strategy);
 
         // Remove from strategies list
         strategies.remove(strategy_index.unwrap() as u32);
@@ -2605,9 +3655,12 @@ impl VolatilityShield {
         env.storage().instance().remove(&health_key);
 
         // Emit StrategyRemoved event
-        env.events().publish(
-            (symbol_short!("StrategyR"), strategy.clone()),
-            strategy_balance,
+        events::publish(
+            &env,
+            events::StrategyRemoved {
+                strategy: strategy.clone(),
+                final_balance: strategy_balance,
+            },
         );
 
         Ok(())
@@ -2626,12 +3679,12 @@ impl VolatilityShield {
     pub fn set_max_consecutive_failures(env: Env, threshold: u32) -> Result<(), Error> {
         Self::require_admin(&env);
         if threshold == 0 {
-            return Err(Error::NegativeAmount);
+            return Self::emit_and_err(&env, Error::NegativeAmount);
         }
         env.storage()
             .instance()
             .set(&DataKey::MaxConsecutiveFailures, &threshold);
-        env.events().publish((symbol_short!("MaxFail"),), threshold);
+        events::publish(&env, events::MaxConsecutiveFailuresSet { threshold });
         Ok(())
     }
 
@@ -2654,7 +3707,7 @@ impl VolatilityShield {
     /// @return APY in basis points.
     pub fn get_strategy_apy(env: Env, strategy: Address, periods: u32) -> i128 {
         let history_key = DataKey::StrategyYieldSnapshot(strategy.clone());
-        let history: Option<YieldHistory> = env.storage().instance().get(&history_key);
+        let history: Option<YieldHistory> = Self::get_persistent(&env, &history_key);
 
         match history {
             Some(h) if h.snapshots.len() >= 2 => {
@@ -2693,11 +3746,7 @@ impl VolatilityShield {
                 }
 
                 // Calculate growth rate
-                let growth = end_balance
-                    .checked_mul(10_000)
-                    .unwrap()
-                    .checked_div(start_balance)
-                    .unwrap();
+                let growth = end_balance.saturating_mul(10_000) / start_balance;
                 let growth_bps = growth.saturating_sub(10_000);
 
                 // Annualize: assume ~10 ledgers per second on Stellar testnet
@@ -2711,8 +3760,8 @@ impl VolatilityShield {
 
                 // Compound annual growth: (1 + rate)^periods - 1
                 // Using simple multiplication for basis points approximation
-                let apy = growth_bps.checked_mul(periods_per_year).unwrap();
-                apy
+                // Saturates: an APY this large only ranks strategies.
+                growth_bps.saturating_mul(periods_per_year)
             }
             _ => 0,
         }
@@ -2771,13 +3820,10 @@ impl VolatilityShield {
                 .instance()
                 .get(&DataKey::AssetTotalAssets(asset.clone()))
                 .unwrap_or(0);
-            let price = Self::get_asset_price(env.clone(), asset.clone());
-            let value = asset_quantity
-                .checked_mul(price)
-                .unwrap_or(0)
-                .checked_div(1_000_000_000)
-                .unwrap_or(0);
-            total_value = total_value.checked_add(value).unwrap_or(total_value);
+            total_value = Self::get_asset_price(env.clone(), asset.clone())
+                .and_then(|price| math::mul_div(asset_quantity, price, math::PRICE_SCALE))
+                .and_then(|value| math::add(total_value, value))
+                .unwrap_or_else(|error| panic_with_error!(env, error));
         }
         total_value
     }
@@ -2811,16 +3857,22 @@ impl VolatilityShield {
         asset == Self::get_asset(&env)
     }
 
-    pub fn get_asset_price(env: Env, asset: Address) -> i128 {
+    /// Price of `asset` in base-asset units, scaled by `PRICE_SCALE`.
+    /// Fails with `InvalidPrice` when the oracle reports zero or less.
+    pub fn get_asset_price(env: Env, asset: Address) -> Result<i128, Error> {
         if asset == Self::get_asset(&env) {
-            return 1_000_000_000;
+            return Ok(1_000_000_000);
         }
         let oracle = Self::get_oracle(&env);
-        env.invoke_contract::<i128>(
+        let price = env.invoke_contract::<i128>(
             &oracle,
             &soroban_sdk::Symbol::new(&env, "price"),
             soroban_sdk::vec![&env, asset.into_val(&env)],
-        )
+        );
+        if price <= 0 {
+            return Self::emit_and_err(&env, Error::InvalidPrice);
+        }
+        Ok(price)
     }
 
     /// Add an asset to the supported/whitelisted list for deposits.
@@ -2836,7 +3888,7 @@ impl VolatilityShield {
             env.storage()
                 .instance()
                 .set(&DataKey::SupportedAssets, &supported);
-            env.events().publish((symbol_short!("AssetAdd"),), asset);
+            events::publish(&env, events::AssetAdded { asset });
         }
     }
 
@@ -2866,12 +3918,11 @@ impl VolatilityShield {
         env.storage()
             .instance()
             .set(&DataKey::OracleCircuitBreakerActive, &true);
-        env.events().publish(
-            (soroban_sdk::Symbol::new(
-                &env,
-                "OracleCircuitBreakerActivated",
-            ),),
-            env.ledger().timestamp(),
+        events::publish(
+            &env,
+            events::OracleCircuitBreakerActivated {
+                timestamp: env.ledger().timestamp(),
+            },
         );
     }
 
@@ -2884,9 +3935,11 @@ impl VolatilityShield {
         env.storage()
             .instance()
             .set(&DataKey::OracleCircuitBreakerActive, &false);
-        env.events().publish(
-            (soroban_sdk::Symbol::new(&env, "OracleCircuitBreakerReset"),),
-            env.ledger().timestamp(),
+        events::publish(
+            &env,
+            events::OracleCircuitBreakerReset {
+                timestamp: env.ledger().timestamp(),
+            },
         );
     }
 
@@ -2917,26 +3970,13 @@ impl VolatilityShield {
             return Ok(());
         }
 
-        let blocklist: Vec<Address> = env
-            .storage()
-            .instance()
-            .get(&DataKey::Blocklist)
-            .unwrap_or(Vec::new(env));
-        let allowlist: Vec<Address> = env
-            .storage()
-            .instance()
-            .get(&DataKey::Allowlist)
-            .unwrap_or(Vec::new(env));
-
-        if blocklist_mode && blocklist.contains(user.clone()) {
-            env.events()
-                .publish((soroban_sdk::Symbol::new(env, "UserBlocked"),), user);
+        if blocklist_mode && collections::list_contains(env, ListKind::Blocklist, user) {
+            events::publish(env, events::ComplianceRejected { user: user.clone() });
             return Self::emit_and_err(env, Error::UserBlocked);
         }
 
-        if allowlist_mode && !allowlist.contains(user.clone()) {
-            env.events()
-                .publish((soroban_sdk::Symbol::new(env, "UserBlocked"),), user);
+        if allowlist_mode && !collections::list_contains(env, ListKind::Allowlist, user) {
+            events::publish(env, events::ComplianceRejected { user: user.clone() });
             return Self::emit_and_err(env, Error::UserBlocked);
         }
 
@@ -2947,18 +3987,8 @@ impl VolatilityShield {
     /// Only the admin can call this.
     pub fn add_to_blocklist(env: Env, user: Address) {
         Self::require_admin(&env);
-        let mut blocklist: Vec<Address> = env
-            .storage()
-            .instance()
-            .get(&DataKey::Blocklist)
-            .unwrap_or(Vec::new(&env));
-        if !blocklist.contains(user.clone()) {
-            blocklist.push_back(user.clone());
-            env.storage()
-                .instance()
-                .set(&DataKey::Blocklist, &blocklist);
-            env.events()
-                .publish((soroban_sdk::Symbol::new(&env, "UserBlocked"),), user);
+        if collections::list_add(&env, ListKind::Blocklist, &user) {
+            events::publish(&env, events::UserBlocked { user });
         }
     }
 
@@ -2966,16 +3996,8 @@ impl VolatilityShield {
     /// Only the admin can call this.
     pub fn remove_from_blocklist(env: Env, user: Address) {
         Self::require_admin(&env);
-        let mut blocklist: Vec<Address> = env
-            .storage()
-            .instance()
-            .get(&DataKey::Blocklist)
-            .unwrap_or(Vec::new(&env));
-        if let Some(index) = blocklist.iter().position(|x| x == user) {
-            blocklist.remove(index as u32);
-            env.storage()
-                .instance()
-                .set(&DataKey::Blocklist, &blocklist);
+        if collections::list_remove(&env, ListKind::Blocklist, &user) {
+            events::publish(&env, events::UserUnblocked { user });
         }
     }
 
@@ -2983,18 +4005,8 @@ impl VolatilityShield {
     /// Only the admin can call this.
     pub fn add_to_allowlist(env: Env, user: Address) {
         Self::require_admin(&env);
-        let mut allowlist: Vec<Address> = env
-            .storage()
-            .instance()
-            .get(&DataKey::Allowlist)
-            .unwrap_or(Vec::new(&env));
-        if !allowlist.contains(user.clone()) {
-            allowlist.push_back(user.clone());
-            env.storage()
-                .instance()
-                .set(&DataKey::Allowlist, &allowlist);
-            env.events()
-                .publish((soroban_sdk::Symbol::new(&env, "UserAllowlisted"),), user);
+        if collections::list_add(&env, ListKind::Allowlist, &user) {
+            events::publish(&env, events::UserAllowlisted { user });
         }
     }
 
@@ -3002,16 +4014,8 @@ impl VolatilityShield {
     /// Only the admin can call this.
     pub fn remove_from_allowlist(env: Env, user: Address) {
         Self::require_admin(&env);
-        let mut allowlist: Vec<Address> = env
-            .storage()
-            .instance()
-            .get(&DataKey::Allowlist)
-            .unwrap_or(Vec::new(&env));
-        if let Some(index) = allowlist.iter().position(|x| x == user) {
-            allowlist.remove(index as u32);
-            env.storage()
-                .instance()
-                .set(&DataKey::Allowlist, &allowlist);
+        if collections::list_remove(&env, ListKind::Allowlist, &user) {
+            events::publish(&env, events::UserRemovedFromAllowlist { user });
         }
     }
 
@@ -3022,6 +4026,7 @@ impl VolatilityShield {
         env.storage()
             .instance()
             .set(&DataKey::BlocklistMode, &active);
+        events::publish(&env, events::BlocklistModeSet { active });
     }
 
     /// Enable or disable allowlist mode.
@@ -3031,22 +4036,17 @@ impl VolatilityShield {
         env.storage()
             .instance()
             .set(&DataKey::AllowlistMode, &active);
+        events::publish(&env, events::AllowlistModeSet { active });
     }
 
     /// Get the current blocklist.
     pub fn get_blocklist(env: Env) -> Vec<Address> {
-        env.storage()
-            .instance()
-            .get(&DataKey::Blocklist)
-            .unwrap_or(Vec::new(&env))
+        collections::list_all(&env, ListKind::Blocklist)
     }
 
     /// Get the current allowlist.
     pub fn get_allowlist(env: Env) -> Vec<Address> {
-        env.storage()
-            .instance()
-            .get(&DataKey::Allowlist)
-            .unwrap_or(Vec::new(&env))
+        collections::list_all(&env, ListKind::Allowlist)
     }
 
     /// Check if blocklist mode is active.
@@ -3107,10 +4107,7 @@ impl VolatilityShield {
         env.storage()
             .instance()
             .set(&DataKey::ProposalTtlLedgers, &ledgers);
-        env.events().publish(
-            (soroban_sdk::Symbol::new(&env, "ProposalTtlLedgers"),),
-            ledgers,
-        );
+        events::publish(&env, events::ProposalTtlLedgersSet { ledgers });
     }
 
     pub fn get_proposal_ttl_ledgers(env: Env) -> u32 {
@@ -3130,16 +4127,7 @@ impl VolatilityShield {
             return Vec::new(&env);
         }
 
-        let proposal_ids: Vec<u64> = env
-            .storage()
-            .instance()
-            .get(&DataKey::ProposalIds)
-            .unwrap_or(Vec::new(&env));
-        let proposals: Map<u64, Proposal> = env
-            .storage()
-            .instance()
-            .get(&DataKey::Proposals)
-            .unwrap_or(Map::new(&env));
+        let (head, tail) = collections::proposal_bounds(&env);
 
         let mut listed = Vec::new(&env);
         let mut skipped = 0;
@@ -3147,9 +4135,8 @@ impl VolatilityShield {
 
         let now = env.ledger().timestamp();
 
-        for proposal_id in proposal_ids.iter() {
-            let id = proposal_id.clone();
-            if let Some(proposal) = proposals.get(id) {
+        for id in head..tail {
+            if let Some(proposal) = collections::read_proposal(&env, id) {
                 if !include_expired {
                     // Filter out ALL executed proposals by default
                     if proposal.executed {
@@ -3180,12 +4167,7 @@ impl VolatilityShield {
     }
 
     pub fn get_proposal(env: Env, proposal_id: u64) -> Option<Proposal> {
-        let proposals: Map<u64, Proposal> = env
-            .storage()
-            .instance()
-            .get(&DataKey::Proposals)
-            .unwrap_or(Map::new(&env));
-        proposals.get(proposal_id)
+        collections::read_proposal(&env, proposal_id)
     }
 
     // ── Internal Helpers ──────────────────────
@@ -3194,77 +4176,69 @@ impl VolatilityShield {
         if fee_pct == 0 {
             return amount;
         }
-        let fee = amount
-            .checked_mul(fee_pct as i128)
-            .unwrap()
-            .checked_div(10000)
-            .unwrap();
+        let fee = math::mul_div(amount, fee_pct as i128, 10_000)
+            .unwrap_or_else(|error| panic_with_error!(env, error));
         amount - fee
     }
 
     pub fn get_share_price(env: &Env) -> i128 {
-        let total_assets = Self::total_assets(env);
-        let total_shares = Self::total_shares(env);
-        if total_shares == 0 {
-            return 1_000_000_000; // 1.0 with 9 decimals
-        }
-        total_assets
-            .checked_mul(1_000_000_000)
-            .unwrap()
-            .checked_div(total_shares)
-            .unwrap()
+        Self::share_price(env).unwrap_or_else(|error| panic_with_error!(env, error))
     }
 
     pub fn convert_to_shares(env: Env, amount: i128) -> i128 {
-        if amount < 0 {
-            panic!("negative amount");
-        }
-        let total_shares = Self::total_shares(&env);
-        let total_assets = Self::total_assets(&env);
-        if total_shares == 0 || total_assets == 0 {
-            return amount;
-        }
-        amount
-            .checked_mul(total_shares)
-            .unwrap()
-            .checked_div(total_assets)
-            .unwrap()
+        Self::shares_for_value(&env, amount).unwrap_or_else(|error| panic_with_error!(&env, error))
     }
 
     pub fn convert_to_assets(env: Env, shares: i128) -> i128 {
-        if shares < 0 {
-            panic!("negative amount");
-        }
-        let total_shares = Self::total_shares(&env);
-        let total_assets = Self::total_assets(&env);
-        if total_shares == 0 {
-            return shares;
-        }
-        shares
-            .checked_mul(total_assets)
-            .unwrap()
-            .checked_div(total_shares)
-            .unwrap()
+        Self::value_for_shares(&env, shares).unwrap_or_else(|error| panic_with_error!(&env, error))
     }
 
     pub fn set_total_assets(env: Env, amount: i128) {
-        env.storage().instance().set(&DataKey::TotalAssets, &amount);
-        let asset = Self::get_asset(&env);
-        env.storage()
-            .instance()
-            .set(&DataKey::AssetTotalAssets(asset), &amount);
+        Self::write_total_assets(&env, amount);
+        events::publish(
+            &env,
+            events::TotalAssetsSet {
+                total_assets: amount,
+            },
+        );
     }
 
     pub fn set_total_shares(env: Env, amount: i128) {
-        env.storage().instance().set(&DataKey::TotalShares, &amount);
+        Self::write_total_shares(&env, amount);
+        events::publish(
+            &env,
+            events::TotalSharesSet {
+                total_shares: amount,
+            },
+        );
     }
 
     pub fn set_balance(env: Env, user: Address, amount: i128) {
         Self::write_user_balance(&env, &user, amount);
+        events::publish(
+            &env,
+            events::BalanceSet {
+                user,
+                shares: amount,
+            },
+        );
     }
 
     pub fn set_token(env: Env, token: Address) {
         env.storage().instance().set(&DataKey::Token, &token);
+        events::publish(&env, events::TokenSet { token });
+    }
+
+    fn write_total_assets(env: &Env, amount: i128) {
+        env.storage().instance().set(&DataKey::TotalAssets, &amount);
+        let asset = Self::get_asset(env);
+        env.storage()
+            .instance()
+            .set(&DataKey::AssetTotalAssets(asset), &amount);
+    }
+
+    fn write_total_shares(env: &Env, amount: i128) {
+        env.storage().instance().set(&DataKey::TotalShares, &amount);
     }
 
     fn require_admin(env: &Env) -> Address {
@@ -3280,24 +4254,16 @@ impl VolatilityShield {
             .unwrap_or(DEFAULT_PROPOSAL_TTL_LEDGERS)
     }
 
+    /// Prune expired proposals among the `PROPOSAL_PRUNE_BATCH` oldest ids,
+    /// so the cost of a call does not grow with the number of live proposals.
     fn prune_old_proposals_internal(env: &Env) -> u32 {
-        let mut proposals: Map<u64, Proposal> = env
-            .storage()
-            .instance()
-            .get(&DataKey::Proposals)
-            .unwrap_or(Map::new(env));
-        let proposal_ids: Vec<u64> = env
-            .storage()
-            .instance()
-            .get(&DataKey::ProposalIds)
-            .unwrap_or(Vec::new(env));
+        let (head, tail) = collections::proposal_bounds(env);
         let ttl = Self::proposal_ttl_ledgers(env);
         let current_ledger = env.ledger().sequence();
-        let mut retained_ids = Vec::new(env);
         let mut pruned = 0_u32;
 
-        for proposal_id in proposal_ids.iter() {
-            if let Some(proposal) = proposals.get(proposal_id) {
+        for proposal_id in head..tail.min(head.saturating_add(PROPOSAL_PRUNE_BATCH as u64)) {
+            if let Some(proposal) = collections::read_proposal(env, proposal_id) {
                 let now = env.ledger().timestamp();
                 let is_executed_expired = proposal.executed
                     && proposal.executed_ledger > 0
@@ -3306,24 +4272,17 @@ impl VolatilityShield {
                     && now.saturating_sub(proposal.proposed_at) >= DEFAULT_PROPOSAL_TTL_SECONDS;
 
                 if is_executed_expired || is_unexecuted_expired {
-                    proposals.remove(proposal_id);
+                    if let ActionType::Upgrade(_) = proposal.action {
+                        Self::clear_pending_upgrade_for(env, proposal_id);
+                    }
+                    collections::remove_proposal(env, proposal_id);
                     pruned = pruned.saturating_add(1);
-                } else {
-                    retained_ids.push_back(proposal_id);
                 }
             }
         }
 
-        env.storage()
-            .instance()
-            .set(&DataKey::Proposals, &proposals);
-        env.storage()
-            .instance()
-            .set(&DataKey::ProposalIds, &retained_ids);
-
         if pruned > 0 {
-            env.events()
-                .publish((soroban_sdk::Symbol::new(env, "ProposalsPruned"),), pruned);
+            events::publish(env, events::ProposalsPruned { count: pruned });
         }
 
         pruned
@@ -3332,55 +4291,83 @@ impl VolatilityShield {
     fn record_pause_change(env: &Env, caller: Address, state: bool) {
         env.storage().instance().set(&DataKey::Paused, &state);
         let timestamp = env.ledger().timestamp();
-        let mut history: Vec<(u64, Address, bool)> = env
-            .storage()
-            .instance()
-            .get(&DataKey::PauseHistory)
-            .unwrap_or(Vec::new(env));
-        history.push_back((timestamp, caller.clone(), state));
-        env.storage()
-            .instance()
-            .set(&DataKey::PauseHistory, &history);
+        collections::history_push(
+            env,
+            HistoryKind::Pause,
+            PAUSE_HISTORY_CAP,
+            (timestamp, caller.clone(), state),
+        );
 
-        let event_name = if state {
-            "VaultPaused"
+        if state {
+            events::publish(env, events::VaultPaused { caller, timestamp });
         } else {
-            "VaultUnpaused"
-        };
-        env.events().publish(
-            (soroban_sdk::Symbol::new(env, event_name),),
-            (caller, timestamp),
+            events::publish(env, events::VaultUnpaused { caller, timestamp });
+        }
+    }
+
+    /// Publish vault totals and the live balance of every readable strategy.
+    fn publish_vault_snapshot(env: &Env) {
+        let mut allocations = Map::new(env);
+        for strategy in Self::get_strategies(env).iter() {
+            if let Ok(balance) = StrategyClient::new(env, strategy.clone()).try_balance() {
+                allocations.set(strategy, balance);
+            }
+        }
+        events::publish(
+            env,
+            events::VaultSnapshot {
+                total_assets: Self::total_assets(env),
+                total_shares: Self::total_shares(env),
+                allocations,
+            },
         );
     }
 
     fn record_share_price_snapshot(env: &Env) {
-        let mut history: Vec<(u64, i128)> = env
-            .storage()
-            .instance()
-            .get(&DataKey::SharePriceHistory)
-            .unwrap_or(Vec::new(env));
-        if history.len() >= SHARE_PRICE_HISTORY_CAP {
-            history.remove(0);
+        collections::history_push(
+            env,
+            HistoryKind::SharePrice,
+            SHARE_PRICE_HISTORY_CAP,
+            (env.ledger().timestamp(), Self::get_share_price(env)),
+        );
+    }
+
+    /// Append a strategy yield snapshot, keeping at most `YIELD_HISTORY_CAP`
+    /// (an even number, so before/after harvest pairs stay aligned).
+    fn record_yield_snapshot(env: &Env, history_key: &DataKey, snapshot: YieldSnapshot) {
+        let mut history: YieldHistory =
+            Self::get_persistent(env, history_key).unwrap_or(YieldHistory {
+                snapshots: Vec::new(env),
+            });
+        if history.snapshots.len() >= YIELD_HISTORY_CAP {
+            history.snapshots = history.snapshots.slice(2..);
         }
-        history.push_back((env.ledger().timestamp(), Self::get_share_price(env)));
-        env.storage()
-            .instance()
-            .set(&DataKey::SharePriceHistory, &history);
+        history.snapshots.push_back(snapshot);
+        Self::set_persistent(env, history_key, &history);
     }
 
     fn record_tvl_snapshot(env: &Env) {
-        let mut history: Vec<(u64, i128)> = env
-            .storage()
-            .instance()
-            .get(&TVL_HISTORY_KEY)
-            .unwrap_or(Vec::new(env));
+        let mut history = Self::read_tvl_history(env);
         if history.len() >= TVL_HISTORY_CAP {
             history.remove(0);
         }
         history.push_back((env.ledger().sequence() as u64, Self::total_assets(env)));
-        env.storage()
-            .instance()
-            .set(&TVL_HISTORY_KEY, &history);
+        if Self::version(env) < 2 {
+            env.storage().instance().set(&TVL_HISTORY_KEY, &history);
+        } else {
+            Self::set_persistent(env, &TVL_HISTORY_KEY, &history);
+        }
+    }
+
+    /// TVL history lives in instance storage up to v1 and in its own
+    /// persistent entry from v2 onwards.
+    fn read_tvl_history(env: &Env) -> Vec<(u64, i128)> {
+        let history = if Self::version(env) < 2 {
+            env.storage().instance().get(&TVL_HISTORY_KEY)
+        } else {
+            Self::get_persistent(env, &TVL_HISTORY_KEY)
+        };
+        history.unwrap_or(Vec::new(env))
     }
 
     // ── Emergency Pause ──────────────────────────
@@ -3404,61 +4391,60 @@ impl VolatilityShield {
         env.storage()
             .instance()
             .set(&DataKey::EmergencyShutdown, &true);
+        events::publish(
+            &env,
+            events::EmergencyShutdownActivated {
+                admin: admin.clone(),
+                timestamp: env.ledger().timestamp(),
+            },
+        );
 
         Self::record_pause_change(&env, admin, true);
     }
 
-    pub fn emergency_withdraw(env: Env, from: Address) {
+    pub fn emergency_withdraw(env: Env, from: Address) -> Result<(), Error> {
         let _guard = Guard::new(&env);
-        Self::check_version(&env, 1);
+        Self::check_version(&env)?;
 
         if !Self::emergency_shutdown_active(&env) {
-            panic!("EmergencyShutdownNotActive");
+            return Self::emit_and_err(&env, Error::EmergencyShutdownInactive);
         }
 
         from.require_auth();
 
         let current_balance = Self::read_user_balance(&env, &from);
 
-        let mut pending_withdrawals: Vec<QueuedWithdrawal> = env
-            .storage()
-            .instance()
-            .get(&DataKey::PendingWithdrawals)
-            .unwrap_or(Vec::new(&env));
-
+        // Queued tickets are cancelled and their shares redeemed with the rest.
         let mut queued_shares = 0_i128;
-        let mut queued_index: Option<u32> = None;
-        for i in 0..pending_withdrawals.len() {
-            let w = pending_withdrawals.get(i).unwrap();
-            if w.user == from {
-                queued_shares = w.shares;
-                queued_index = Some(i);
-                break;
+        for id in collections::user_ticket_ids(&env, &from).iter() {
+            if let Some(ticket) = collections::queue_remove(&env, id) {
+                queued_shares = math::add(queued_shares, ticket.shares)?;
+                events::publish(
+                    &env,
+                    events::WithdrawCancelled {
+                        user: ticket.user,
+                        ticket_id: id,
+                        shares: ticket.shares,
+                    },
+                );
             }
         }
 
-        if let Some(index) = queued_index {
-            pending_withdrawals.remove(index);
-            env.storage()
-                .instance()
-                .set(&DataKey::PendingWithdrawals, &pending_withdrawals);
-        }
-
-        let shares_to_withdraw = current_balance.checked_add(queued_shares).unwrap();
+        let shares_to_withdraw = math::add(current_balance, queued_shares)?;
         if shares_to_withdraw <= 0 {
-            panic!("insufficient shares for withdrawal");
+            return Self::emit_and_err(&env, Error::InsufficientShares);
         }
 
-        let assets_to_withdraw = Self::convert_to_assets(env.clone(), shares_to_withdraw);
+        let assets_to_withdraw = Self::value_for_shares(&env, shares_to_withdraw)?;
 
         let total_shares = Self::total_shares(&env);
         let total_assets = Self::total_assets(&env);
 
-        let new_total_shares = total_shares.checked_sub(shares_to_withdraw).unwrap();
-        let new_total_assets = total_assets.checked_sub(assets_to_withdraw).unwrap();
+        let new_total_shares = math::sub(total_shares, shares_to_withdraw)?;
+        let new_total_assets = math::sub(total_assets, assets_to_withdraw)?;
 
-        Self::set_total_shares(env.clone(), new_total_shares);
-        Self::set_total_assets(env.clone(), new_total_assets);
+        Self::write_total_shares(&env, new_total_shares);
+        Self::write_total_assets(&env, new_total_assets);
         Self::write_user_balance(&env, &from, 0_i128);
 
         let token: Address = env
@@ -3472,19 +4458,26 @@ impl VolatilityShield {
             &assets_to_withdraw,
         );
 
-        env.events().publish(
-            (soroban_sdk::Symbol::new(&env, "EmergencyWithdraw"), from),
-            (
-                shares_to_withdraw,
-                assets_to_withdraw,
-                env.ledger().timestamp(),
-            ),
+        events::publish(
+            &env,
+            events::EmergencyWithdrawn {
+                user: from,
+                shares: shares_to_withdraw,
+                amount: assets_to_withdraw,
+                timestamp: env.ledger().timestamp(),
+                total_assets: new_total_assets,
+                total_shares: new_total_shares,
+            },
         );
+        Ok(())
     }
 
     // ── Deposit / Withdrawal Caps ──────────────────────────
-    pub fn set_deposit_cap(env: Env, per_user: i128, global: i128) {
-        Self::check_version(&env, 1);
+    /// Set the deposit caps, both in value (9 decimals): `per_user` bounds
+    /// the value of one depositor's position, `global` the vault's total
+    /// assets.
+    pub fn set_deposit_cap(env: Env, per_user: i128, global: i128) -> Result<(), Error> {
+        Self::check_version(&env)?;
         Self::require_admin(&env);
         env.storage()
             .instance()
@@ -3492,13 +4485,32 @@ impl VolatilityShield {
         env.storage()
             .instance()
             .set(&DataKey::MaxTotalAssets, &global);
-        env.events().publish(
-            (
-                soroban_sdk::Symbol::new(&env, "CapsSet"),
-                soroban_sdk::Symbol::new(&env, "Deposit"),
-            ),
-            (per_user, global),
-        );
+        events::publish(&env, events::DepositCapSet { per_user, global });
+        Ok(())
+    }
+
+    /// Set the smallest value (9 decimals) the first deposit into an empty
+    /// vault must bring (default 1_000). Seeding the vault with a meaningful
+    /// amount makes inflating its share price by donation prohibitively
+    /// expensive.
+    pub fn set_min_initial_deposit(env: Env, amount: i128) -> Result<(), Error> {
+        Self::check_version(&env)?;
+        Self::require_admin(&env);
+        if amount < 0 {
+            return Self::emit_and_err(&env, Error::InvalidConfig);
+        }
+        env.storage()
+            .instance()
+            .set(&DepositKey::MinInitialDeposit, &amount);
+        events::publish(&env, events::MinInitialDepositSet { amount });
+        Ok(())
+    }
+
+    pub fn get_min_initial_deposit(env: Env) -> i128 {
+        env.storage()
+            .instance()
+            .get(&DepositKey::MinInitialDeposit)
+            .unwrap_or(DEFAULT_MIN_INITIAL_DEPOSIT)
     }
 
     pub fn set_withdraw_cap(env: Env, per_tx: i128) {
@@ -3506,13 +4518,7 @@ impl VolatilityShield {
         env.storage()
             .instance()
             .set(&DataKey::MaxWithdrawPerTx, &per_tx);
-        env.events().publish(
-            (
-                soroban_sdk::Symbol::new(&env, "CapsSet"),
-                soroban_sdk::Symbol::new(&env, "Withdraw"),
-            ),
-            per_tx,
-        );
+        events::publish(&env, events::WithdrawCapSet { per_tx });
     }
 
     pub fn set_max_staleness(env: Env, seconds: u64) {
@@ -3520,7 +4526,7 @@ impl VolatilityShield {
         env.storage()
             .instance()
             .set(&DataKey::MaxStaleness, &seconds);
-        env.events().publish((symbol_short!("Staleness"),), seconds);
+        events::publish(&env, events::MaxStalenessSet { seconds });
     }
 
     pub fn set_timelock_duration(env: Env, duration: u64) {
@@ -3528,8 +4534,33 @@ impl VolatilityShield {
         env.storage()
             .instance()
             .set(&DataKey::TimelockDuration, &duration);
-        env.events()
-            .publish((symbol_short!("TimelockD"),), duration);
+        events::publish(&env, events::TimelockDurationSet { duration });
+    }
+
+    /// Return the timelock, in seconds, that applies to actions of `kind`.
+    ///
+    /// Overrides are proposed with `ActionType::SetActionTimelock` and wait for
+    /// the timelock of that kind, so no single signer can shorten a delay.
+    /// Actions without an override fall back to the global `TimelockDuration`;
+    /// an override of 0 lets that kind execute as soon as it is approved.
+    ///
+    /// Upgrades never wait less than `MIN_UPGRADE_TIMELOCK_SECONDS` so users
+    /// always have a window to exit before new code goes live.
+    pub fn get_action_timelock(env: Env, kind: ActionKind) -> u64 {
+        let configured = env
+            .storage()
+            .instance()
+            .get(&GovernanceKey::ActionTimelock(kind))
+            .unwrap_or_else(|| {
+                env.storage()
+                    .instance()
+                    .get(&DataKey::TimelockDuration)
+                    .unwrap_or(0)
+            });
+        if kind == ActionKind::Upgrade {
+            return configured.max(MIN_UPGRADE_TIMELOCK_SECONDS);
+        }
+        configured
     }
 
     pub fn max_staleness(env: &Env) -> u64 {
@@ -3540,30 +4571,113 @@ impl VolatilityShield {
     }
 
     // ── Contract Upgrade & Migration ──────────────────
-    pub fn upgrade(env: Env, new_wasm_hash: soroban_sdk::BytesN<32>) {
-        Self::require_admin(&env);
-        env.deployer().update_current_contract_wasm(new_wasm_hash);
-        env.events()
-            .publish((symbol_short!("upgrade"), symbol_short!("wasm")), ());
+    /// Return the upgrade announced through governance that is waiting for
+    /// its timelock, if any.
+    ///
+    /// Upgrades are proposed with `ActionType::Upgrade(wasm_hash)`; there is no
+    /// direct admin path to swap the contract WASM.
+    pub fn get_pending_upgrade(env: Env) -> Option<PendingUpgrade> {
+        env.storage()
+            .instance()
+            .get(&GovernanceKey::PendingUpgrade)
     }
 
-    pub fn migrate(env: Env, new_version: u32) {
+    /// Migrate storage to `new_version` by running the registered migration
+    /// steps (v1→v2, v2→v3, ...) in order.
+    ///
+    /// Work is done in batches of at most `DEFAULT_MIGRATION_BATCH` items; if
+    /// the migration does not finish, state-changing entry points are rejected
+    /// until `continue_migration` completes it. Calling `migrate` again with the
+    /// same target resumes the pending migration.
+    /// @return `true` once storage is at `new_version`.
+    pub fn migrate(env: Env, new_version: u32) -> Result<bool, Error> {
         Self::require_admin(&env);
-        let current_version = Self::version(&env);
-        if new_version <= current_version {
-            panic!("new version must be greater than current version");
+        if let Err(e) = migration::begin(&env, new_version) {
+            return Self::emit_and_err(&env, e);
         }
+        migration::run(&env, migration::DEFAULT_MIGRATION_BATCH)
+    }
+
+    /// Resume a pending migration, migrating at most `max_items` items.
+    /// @return `true` once the migration has completed.
+    pub fn continue_migration(env: Env, max_items: u32) -> Result<bool, Error> {
+        Self::require_admin(&env);
+        migration::run(&env, max_items)
+    }
 
-        // Execute any necessary state migrations here if migrating from specific versions
-        // e.g. if current_version == 1 && new_version == 2 { ... migrate v1 state to v2 layout ... }
+    /// Return the pending migration, if one has been started but not finished.
+    pub fn get_migration_status(env: Env) -> Option<MigrationState> {
+        migration::pending(&env)
+    }
 
-        env.storage()
-            .instance()
-            .set(&DataKey::ContractVersion, &new_version);
-        env.events().publish(
-            (symbol_short!("upgrade"), symbol_short!("migrate")),
-            new_version,
+    // ── Storage TTL ───────────────────────────
+    /// Extend the TTL of the contract instance and of the given persistent
+    /// entries according to their TTL policies.
+    ///
+    /// Permissionless, so keepers can stop inactive positions from being
+    /// archived. Keys that do not exist in persistent storage are skipped.
+    /// @return The number of persistent entries that were found.
+    pub fn extend_ttl(env: Env, keys: Vec<StorageKey>) -> u32 {
+        Self::bump_instance_ttl(&env);
+        let mut extended = 0;
+        for key in keys.iter() {
+            if ttl::extend_existing(&env, &key) {
+                extended += 1;
+            }
+        }
+        extended
+    }
+
+    /// Set the TTL policy for a group of storage entries.
+    /// Only the admin can call this.
+    pub fn set_ttl_policy(env: Env, key_type: TtlKeyType, policy: TtlPolicy) -> Result<(), Error> {
+        Self::require_admin(&env);
+        if !ttl::set_policy(&env, key_type, &policy) {
+            return Self::emit_and_err(&env, Error::InvalidTtlPolicy);
+        }
+        events::publish(
+            &env,
+            events::TtlPolicySet {
+                key_type,
+                threshold: policy.threshold,
+                extend_to: policy.extend_to,
+            },
         );
+        Ok(())
+    }
+
+    /// Get the TTL policy for a group of storage entries.
+    pub fn get_ttl_policy(env: Env, key_type: TtlKeyType) -> TtlPolicy {
+        ttl::policy(&env, key_type)
+    }
+
+    /// Report how long `user`'s balance entry is known to stay live.
+    pub fn get_ttl_status(env: Env, user: Address) -> TtlStatus {
+        ttl::status(&env, &user)
+    }
+
+    // ── Vault Metadata ────────────────────────
+    /// Publish a new revision of the vault metadata.
+    ///
+    /// Previous revisions are kept and can be read with `get_vault_metadata_history`.
+    /// Only the admin can call this.
+    /// @return The new revision number.
+    pub fn set_vault_metadata(env: Env, metadata: VaultMetadata) -> Result<u32, Error> {
+        Self::require_admin(&env);
+        match metadata::set_vault_metadata(&env, metadata) {
+            Ok(version) => Ok(version),
+            Err(e) => Self::emit_and_err(&env, e),
+        }
+    }
+
+    /// Get the latest vault metadata, if any has been published.
+    pub fn get_vault_metadata(env: Env) -> Option<VaultMetadata> {
+        metadata::get_vault_metadata(&env)
+    }
+
+    /// Get up to `limit` metadata revisions starting at `from_version`, oldest first.
+    pub fn get_vault_metadata_history(env: Env, from_version: u32, limit: u32) -> Vec<MetadataRecord> {
+        metadata::get_history(&env, from_version, limit)
     }
 
     pub fn version(env: &Env) -> u32 {
@@ -3573,14 +4687,17 @@ impl VolatilityShield {
             .unwrap_or(0)
     }
 
-    pub fn check_version(env: &Env, expected_version: u32) {
+    /// Check that storage is in the layout this build writes and that no
+    /// migration is half-way through.
+    pub fn check_version(env: &Env) -> Result<(), Error> {
         let current = Self::version(env);
-        if current != expected_version {
-            panic!(
-                "VersionMismatch: Expected contract version {} but found {}",
-                expected_version, current
-            );
+        if current != migration::CONTRACT_VERSION {
+            return Self::emit_and_err(env, Error::VersionMismatch);
+        }
+        if migration::pending(env).is_some() {
+            return Self::emit_and_err(env, Error::MigrationInProgress);
         }
+        Ok(())
     }
 
     pub fn is_paused(env: Env) -> bool {
@@ -3594,15 +4711,16 @@ impl VolatilityShield {
         Self::emergency_shutdown_active(&env)
     }
 
-    fn assert_not_paused(env: &Env) {
+    fn assert_not_paused(env: &Env) -> Result<(), Error> {
         if env
             .storage()
             .instance()
             .get(&DataKey::Paused)
             .unwrap_or(false)
         {
-            panic!("ContractPaused");
+            return Self::emit_and_err(env, Error::ContractPaused);
         }
+        Ok(())
     }
 
     fn emergency_shutdown_active(env: &Env) -> bool {
@@ -3612,49 +4730,46 @@ impl VolatilityShield {
             .unwrap_or(false)
     }
 
-    fn assert_not_emergency_shutdown(env: &Env) {
+    fn assert_not_emergency_shutdown(env: &Env) -> Result<(), Error> {
         if Self::emergency_shutdown_active(env) {
-            panic!("EmergencyShutdownActive");
+            return Self::emit_and_err(env, Error::EmergencyShutdownActive);
         }
+        Ok(())
     }
 
     // ─────────────────────────────────────────
     // Private helpers
     // ─────────────────────────────────────────
 
-    /// Require that either `admin` or `oracle` has authorised this call.
+    /// Check that `caller` is either the admin or the oracle.
     ///
-    /// Require that either `admin` or `oracle` has authorised this call.
-    ///
-    /// Soroban OR-auth: the client must place an `InvokerContractAuthEntry`
-    /// for one of the two roles.  We use `require_auth()` on admin first; if
-    /// the tx was built with oracle auth instead, the oracle address should be
-    /// passed as the `admin` role by the off-chain builder, or — more commonly
-    /// — the oracle contract calls this vault as a sub-invocation.
-    ///
-    /// For simplicity: admin.require_auth() covers the admin case.
-    /// Oracle-initiated calls should be routed through a thin oracle contract
-    /// that calls rebalance() as a sub-invocation (so the vault sees the oracle
-    /// contract as the top-level caller).  In tests, use mock_all_auths().
-    fn require_admin_or_oracle(_env: &Env, caller: &Address, admin: &Address, oracle: &Address) {
+    /// Rebalance is only reachable through governance, where `caller` has
+    /// already authorised the proposal, approval or execution call. Calling
+    /// `require_auth` a second time in the same frame would fail.
+    fn assert_admin_or_oracle(
+        env: &Env,
+        caller: &Address,
+        admin: &Address,
+        oracle: &Address,
+    ) -> Result<(), Error> {
         if *caller == *admin || *caller == *oracle {
-            caller.require_auth();
+            Ok(())
         } else {
             // Neither admin nor oracle is the caller.
-            panic!("Unauthorized: expected admin or oracle");
+            Self::emit_and_err(env, Error::Unauthorized)
         }
     }
 
-    fn require_owner_or_delegate(env: &Env, owner: &Address, caller: &Address) {
+    fn require_owner_or_delegate(env: &Env, owner: &Address, caller: &Address) -> Result<(), Error> {
         caller.require_auth();
 
         if caller == owner {
-            return;
+            return Ok(());
         }
 
         match Self::read_delegate(env, owner) {
-            Some(delegate) if delegate == *caller => (),
-            _ => panic!("unauthorized: caller is neither owner nor delegate"),
+            Some(delegate) if delegate == *caller => Ok(()),
+            _ => Self::emit_and_err(env, Error::NotDelegate),
         }
     }
 
@@ -3689,21 +4804,15 @@ impl VolatilityShield {
 
     /// Returns a single-call snapshot of a specific user's position in the vault.
     ///
-    /// Includes balance, queued withdrawal (if any), and current voting power.
+    /// Includes balance, shares held in queued withdrawal tickets, and current voting power.
     /// Does not mutate any storage.
     pub fn get_user_summary(env: Env, user: Address) -> UserSummary {
         let balance = Self::read_user_balance(&env, &user);
 
-        let pending: Vec<QueuedWithdrawal> = env
-            .storage()
-            .instance()
-            .get(&DataKey::PendingWithdrawals)
-            .unwrap_or(Vec::new(&env));
-        let queued_shares: i128 = pending
+        let queued_shares: i128 = Self::get_user_withdrawal_tickets(env.clone(), user.clone())
             .iter()
-            .find(|w| w.user == user)
-            .map(|w| w.shares)
-            .unwrap_or(0);
+            .map(|ticket| ticket.shares)
+            .sum();
 
         let voting_power = Self::get_voting_power(env.clone(), user);
         UserSummary {
@@ -3728,19 +4837,10 @@ impl VolatilityShield {
             .instance()
             .get(&DataKey::Threshold)
             .unwrap_or(0);
-        let proposal_ids: Vec<u64> = env
-            .storage()
-            .instance()
-            .get(&DataKey::ProposalIds)
-            .unwrap_or(Vec::new(&env));
-        let proposals: Map<u64, Proposal> = env
-            .storage()
-            .instance()
-            .get(&DataKey::Proposals)
-            .unwrap_or(Map::new(&env));
+        let (head, tail) = collections::proposal_bounds(&env);
         let mut active_proposal_count = 0_u32;
-        for proposal_id in proposal_ids.iter() {
-            if let Some(proposal) = proposals.get(proposal_id) {
+        for proposal_id in head..tail {
+            if let Some(proposal) = collections::read_proposal(&env, proposal_id) {
                 if !proposal.executed {
                     active_proposal_count = active_proposal_count.saturating_add(1);
                 }
@@ -3779,6 +4879,31 @@ impl VolatilityShield {
     }
 }
 
+mod collections;
+pub mod events;
+mod liquidity;
+mod math;
+mod metadata;
+mod migration;
+mod rebalance;
+mod ttl;
+pub use collections::{CollectionKey, HistoryKind, ListKind};
+pub use events::{
+    Deposited, RebalancePartialFailure, RebalanceWithdrawTransferFailed, Rebalanced, Withdrawn,
+    EVENT_SCHEMA_VERSION,
+};
+pub use liquidity::IdleBufferConfig;
+pub use metadata::{FeeSchedule, MetadataKey, MetadataRecord, VaultMetadata};
+pub use migration::MigrationState;
+pub use rebalance::{
+    MoveResult, PlannedMove, RebalanceMode, RebalancePlan, RebalanceProgress, RebalanceReport,
+    RebalanceTrigger, StrategyOutcome,
+};
+use ttl::PersistentKey;
+pub use ttl::{StorageKey, TtlKey, TtlKeyType, TtlPolicy, TtlStatus};
+
 #[cfg(test)]
 mod invariants;
+mod lifecycle;
 mod test;
+mod vault_benchmarks;
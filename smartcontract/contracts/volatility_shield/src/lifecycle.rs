@@ -0,0 +1,566 @@
+//! Stateful model-based test of the full vault lifecycle.
+//!
+//! proptest generates sequences of user, keeper and admin actions. Each
+//! action runs against the contract and against [`Model`], a plain-Rust
+//! description of the vault's accounting, and the two are compared after
+//! every step together with these invariants:
+//!
+//! - conservation of value: every token is held by a user, the vault or a
+//!   strategy, shares in circulation equal free plus queued shares, and the
+//!   vault always holds the tokens locked for claims;
+//! - the share price never falls, except when a harvest follows a strategy
+//!   loss;
+//! - rounding never costs a user more than one unit per operation plus one
+//!   share's worth per deposit.
+#![cfg(test)]
+extern crate std;
+
+use super::*;
+use mock_strategy::{MockStrategy, MockStrategyClient};
+use proptest::prelude::*;
+use soroban_sdk::testutils::{Address as _, EnvTestConfig, Ledger as _};
+use soroban_sdk::token::{Client as TokenClient, StellarAssetClient};
+use std::collections::{BTreeMap, VecDeque};
+use std::vec;
+use std::vec::Vec as StdVec;
+
+const USERS: usize = 3;
+const TARGETS_BPS: [i128; 2] = [6_000, 4_000];
+const STRATEGIES: usize = TARGETS_BPS.len();
+const QUEUE_THRESHOLD: i128 = 150_000;
+const MAX_SLIPPAGE_BPS: u32 = 100;
+const PRICE_SCALE: i128 = 1_000_000_000;
+
+#[derive(Clone, Debug)]
+enum Action {
+    Deposit {
+        user: usize,
+        amount: i128,
+    },
+    /// Withdraw `bps` of the user's free shares; the vault may queue it.
+    Withdraw {
+        user: usize,
+        bps: i128,
+    },
+    Queue {
+        user: usize,
+        bps: i128,
+    },
+    /// Cancel the user's oldest pending ticket.
+    Cancel {
+        user: usize,
+    },
+    Fulfil {
+        limit: u32,
+    },
+    /// Claim the user's oldest fulfilled ticket.
+    Claim {
+        user: usize,
+    },
+    Rebalance,
+    Harvest,
+    /// Move a strategy's value by `bps`, minting or burning the tokens it
+    /// holds so its reported balance stays backed.
+    PriceChange {
+        strategy: usize,
+        bps: i128,
+    },
+    SetPaused(bool),
+    Shutdown,
+}
+
+fn action() -> impl proptest::strategy::Strategy<Value = Action> {
+    prop_oneof![
+        24 => (0..USERS, 1i128..1_000_000)
+            .prop_map(|(user, amount)| Action::Deposit { user, amount }),
+        18 => (0..USERS, 0i128..=10_000).prop_map(|(user, bps)| Action::Withdraw { user, bps }),
+        12 => (0..USERS, 0i128..=10_000).prop_map(|(user, bps)| Action::Queue { user, bps }),
+        6 => (0..USERS).prop_map(|user| Action::Cancel { user }),
+        15 => (1u32..5).prop_map(|limit| Action::Fulfil { limit }),
+        15 => (0..USERS).prop_map(|user| Action::Claim { user }),
+        9 => Just(Action::Rebalance),
+        6 => Just(Action::Harvest),
+        9 => (0..STRATEGIES, -3_000i128..3_000)
+            .prop_map(|(strategy, bps)| Action::PriceChange { strategy, bps }),
+        3 => any::<bool>().prop_map(Action::SetPaused),
+        1 => Just(Action::Shutdown),
+    ]
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Ticket {
+    id: u64,
+    user: usize,
+    shares: i128,
+}
+
+/// Expected vault accounting and token holdings.
+#[derive(Debug, Default)]
+struct Model {
+    total_assets: i128,
+    total_shares: i128,
+    shares: [i128; USERS],
+    queue: VecDeque<Ticket>,
+    /// Fulfilled tickets: owner and locked amount.
+    claimable: BTreeMap<u64, (usize, i128)>,
+    next_ticket: u64,
+    paused: bool,
+    shutdown: bool,
+
+    wallets: [i128; USERS],
+    vault_tokens: i128,
+    strategy_tokens: [i128; STRATEGIES],
+    /// Tokens minted into the system minus tokens burned from it.
+    supply: i128,
+
+    paid: [i128; USERS],
+    received: [i128; USERS],
+    /// Rounding each user may have lost so far.
+    slack: [i128; USERS],
+    /// Strategy gains net of losses that harvest has not booked yet.
+    unrealised: i128,
+    /// A strategy lost value since the last harvest.
+    loss_pending: bool,
+    /// A harvest has booked a strategy loss.
+    loss_realised: bool,
+}
+
+impl Model {
+    /// Total assets and shares including the vault's virtual position.
+    fn virtual_totals(&self) -> (i128, i128) {
+        (
+            self.total_assets + math::VIRTUAL_ASSETS,
+            self.total_shares + math::VIRTUAL_SHARES,
+        )
+    }
+
+    fn to_shares(&self, amount: i128) -> i128 {
+        let (assets, shares) = self.virtual_totals();
+        amount * shares / assets
+    }
+
+    fn to_assets(&self, shares: i128) -> i128 {
+        let (assets, total_shares) = self.virtual_totals();
+        shares * assets / total_shares
+    }
+
+    fn share_price(&self) -> i128 {
+        let (assets, shares) = self.virtual_totals();
+        assets * PRICE_SCALE / shares
+    }
+
+    /// Value of one share rounded up: the most a deposit can lose to share
+    /// rounding.
+    fn share_unit(&self) -> i128 {
+        let (assets, shares) = self.virtual_totals();
+        (assets + shares - 1) / shares
+    }
+
+    fn claimable_total(&self) -> i128 {
+        self.claimable.values().map(|(_, amount)| amount).sum()
+    }
+
+    fn idle(&self) -> i128 {
+        (self.vault_tokens - self.claimable_total()).max(0)
+    }
+
+    fn deployed(&self) -> i128 {
+        self.strategy_tokens.iter().sum()
+    }
+
+    fn queued_shares(&self, user: usize) -> i128 {
+        self.queue
+            .iter()
+            .filter(|ticket| ticket.user == user)
+            .map(|ticket| ticket.shares)
+            .sum()
+    }
+
+    fn push_ticket(&mut self, user: usize, shares: i128) {
+        self.shares[user] -= shares;
+        self.queue.push_back(Ticket {
+            id: self.next_ticket,
+            user,
+            shares,
+        });
+        self.next_ticket += 1;
+    }
+
+    /// Whether a withdrawal of `value` goes to the queue instead of paying
+    /// out at once.
+    fn must_queue(&self, value: i128) -> bool {
+        value > QUEUE_THRESHOLD || self.idle() < value
+    }
+
+    /// Fulfil up to `limit` tickets the way `fulfil_withdrawals` does:
+    /// strategies are unwound until the front ticket is covered, and
+    /// processing stops at the first ticket that is not.
+    fn fulfil(&mut self, limit: u32) -> u32 {
+        let mut deployed = self.deployed();
+        let mut processed = 0;
+        while processed < limit {
+            let Some(ticket) = self.queue.front().cloned() else {
+                break;
+            };
+            let amount = self.to_assets(ticket.shares);
+            let available = self.vault_tokens - self.claimable_total();
+            if available < amount {
+                let pulled = deployed.min(amount - available);
+                deployed -= pulled;
+                self.vault_tokens += pulled;
+                if self.vault_tokens - self.claimable_total() < amount {
+                    break;
+                }
+            }
+            self.queue.pop_front();
+            self.total_shares -= ticket.shares;
+            self.total_assets -= amount;
+            self.claimable.insert(ticket.id, (ticket.user, amount));
+            self.slack[ticket.user] += 1;
+            processed += 1;
+        }
+        processed
+    }
+}
+
+struct Harness<'a> {
+    admin: Address,
+    keeper: Address,
+    token: Address,
+    token_admin: StellarAssetClient<'a>,
+    token_client: TokenClient<'a>,
+    vault: VolatilityShieldClient<'a>,
+    strategies: StdVec<MockStrategyClient<'a>>,
+    users: StdVec<Address>,
+}
+
+impl<'a> Harness<'a> {
+    fn new(env: &'a Env) -> Self {
+        env.mock_all_auths_allowing_non_root_auth();
+        env.cost_estimate().budget().reset_unlimited();
+        env.ledger().set_timestamp(1_000);
+
+        let admin = Address::generate(env);
+        let token = env
+            .register_stellar_asset_contract_v2(admin.clone())
+            .address();
+        let vault_id = env.register(VolatilityShield, ());
+        let vault = VolatilityShieldClient::new(env, &vault_id);
+        vault.init(
+            &admin,
+            &token,
+            &Address::generate(env),
+            &Address::generate(env),
+            &0u32,
+            &soroban_sdk::vec![env, admin.clone()],
+            &1u32,
+        );
+        vault.set_withdraw_queue_threshold(&QUEUE_THRESHOLD);
+
+        let mut allocations = Map::new(env);
+        let strategies = TARGETS_BPS
+            .iter()
+            .map(|bps| {
+                let id = env.register(MockStrategy, ());
+                let strategy = MockStrategyClient::new(env, &id);
+                strategy.init(&vault_id, &token);
+                vault.propose_action(&admin, &ActionType::AddStrategy(id.clone()));
+                allocations.set(id, *bps);
+                strategy
+            })
+            .collect();
+        vault.set_oracle_data(&allocations, &env.ledger().timestamp());
+
+        Harness {
+            keeper: Address::generate(env),
+            token_admin: StellarAssetClient::new(env, &token),
+            token_client: TokenClient::new(env, &token),
+            users: (0..USERS).map(|_| Address::generate(env)).collect(),
+            admin,
+            token,
+            vault,
+            strategies,
+        }
+    }
+
+    /// Run `action` on the contract and the model and check that both agree
+    /// on whether it succeeded.
+    fn apply(&self, model: &mut Model, action: &Action) {
+        match *action {
+            Action::Deposit { user, amount } => {
+                let address = &self.users[user];
+                self.token_admin.mint(address, &amount);
+                model.wallets[user] += amount;
+                model.supply += amount;
+
+                let ok = self
+                    .vault
+                    .try_deposit(address, &self.token, &amount, &None)
+                    .is_ok();
+                let shares = model.to_shares(amount);
+                let seeded = model.total_shares > 0 || amount >= DEFAULT_MIN_INITIAL_DEPOSIT;
+                assert_eq!(
+                    ok,
+                    !model.paused && !model.shutdown && shares > 0 && seeded,
+                    "{action:?}"
+                );
+                if ok {
+                    model.slack[user] += model.share_unit() + 1;
+                    model.total_assets += amount;
+                    model.total_shares += shares;
+                    model.shares[user] += shares;
+                    model.wallets[user] -= amount;
+                    model.vault_tokens += amount;
+                    model.paid[user] += amount;
+                }
+            }
+            Action::Withdraw { user, bps } => {
+                let address = &self.users[user];
+                let shares = model.shares[user] * bps / 10_000;
+                let ok = self
+                    .vault
+                    .try_withdraw(address, address, &self.token, &shares)
+                    .is_ok();
+                assert_eq!(ok, !model.paused && shares > 0, "{action:?}");
+                if !ok {
+                    return;
+                }
+                let value = model.to_assets(shares);
+                if model.must_queue(value) {
+                    model.push_ticket(user, shares);
+                } else {
+                    model.total_assets -= value;
+                    model.total_shares -= shares;
+                    model.shares[user] -= shares;
+                    model.vault_tokens -= value;
+                    model.wallets[user] += value;
+                    model.received[user] += value;
+                    model.slack[user] += 1;
+                }
+            }
+            Action::Queue { user, bps } => {
+                let address = &self.users[user];
+                let shares = model.shares[user] * bps / 10_000;
+                let expected =
+                    !model.paused && shares > 0 && model.must_queue(model.to_assets(shares));
+                let ok = self
+                    .vault
+                    .try_queue_withdraw(address, address, &self.token, &shares)
+                    .is_ok();
+                assert_eq!(ok, expected, "{action:?}");
+                if ok {
+                    model.push_ticket(user, shares);
+                }
+            }
+            Action::Cancel { user } => {
+                let Some(index) = model.queue.iter().position(|t| t.user == user) else {
+                    return;
+                };
+                let ticket = model.queue.remove(index).unwrap();
+                self.vault.cancel_queued_withdrawal(&ticket.id);
+                model.shares[user] += ticket.shares;
+            }
+            Action::Fulfil { limit } => {
+                let expected = model.fulfil(limit);
+                let processed = self.vault.fulfil_withdrawals(&self.keeper, &limit);
+                assert_eq!(processed, expected, "{action:?}");
+                // Which strategies were unwound is up to the unwind order.
+                self.sync_strategy_tokens(model);
+            }
+            Action::Claim { user } => {
+                let Some((&id, &(_, amount))) = model
+                    .claimable
+                    .iter()
+                    .find(|(_, (owner, _))| *owner == user)
+                else {
+                    return;
+                };
+                assert_eq!(self.vault.claim_withdrawal(&id), amount);
+                model.claimable.remove(&id);
+                model.vault_tokens -= amount;
+                model.wallets[user] += amount;
+                model.received[user] += amount;
+            }
+            Action::Rebalance => {
+                let ok = self
+                    .vault
+                    .try_propose_action(&self.admin, &ActionType::Rebalance(MAX_SLIPPAGE_BPS))
+                    .is_ok();
+                if model.shutdown {
+                    assert!(!ok, "rebalance ran after shutdown");
+                }
+                // Rebalancing only moves tokens between the vault and its
+                // strategies; the totals are checked after the step.
+                model.vault_tokens = self.token_client.balance(&self.vault.address);
+                self.sync_strategy_tokens(model);
+            }
+            Action::Harvest => {
+                // Harvest books only what strategies gained or lost since
+                // the vault last booked their balances.
+                assert_eq!(self.vault.harvest(), model.unrealised);
+                model.total_assets += model.unrealised;
+                model.unrealised = 0;
+                if model.loss_pending {
+                    model.loss_pending = false;
+                    model.loss_realised = true;
+                }
+            }
+            Action::PriceChange { strategy, bps } => {
+                let balance = model.strategy_tokens[strategy];
+                if balance == 0 {
+                    return;
+                }
+                let client = &self.strategies[strategy];
+                let new_balance = balance * (10_000 + bps) / 10_000;
+                if new_balance > balance {
+                    self.token_admin
+                        .mint(&client.address, &(new_balance - balance));
+                } else if new_balance < balance {
+                    self.token_client
+                        .burn(&client.address, &(balance - new_balance));
+                    model.loss_pending = true;
+                }
+                client.simulate_price_drift(&new_balance);
+                model.supply += new_balance - balance;
+                model.unrealised += new_balance - balance;
+                model.strategy_tokens[strategy] = new_balance;
+            }
+            Action::SetPaused(paused) => {
+                self.vault.set_paused(&paused);
+                model.paused = paused;
+            }
+            Action::Shutdown => {
+                self.vault.emergency_shutdown(&self.admin);
+                // A repeated shutdown is a no-op and leaves the pause flag alone.
+                if !model.shutdown {
+                    model.shutdown = true;
+                    model.paused = true;
+                }
+            }
+        }
+    }
+
+    fn sync_strategy_tokens(&self, model: &mut Model) {
+        for (index, strategy) in self.strategies.iter().enumerate() {
+            model.strategy_tokens[index] = self.token_client.balance(&strategy.address);
+        }
+    }
+
+    /// Compare the contract with the model and check the invariants.
+    fn check(&self, model: &Model, action: &Action, price_before: i128, price_may_fall: bool) {
+        let vault = &self.vault;
+        assert_eq!(vault.total_assets(), model.total_assets, "after {action:?}");
+        assert_eq!(vault.total_shares(), model.total_shares, "after {action:?}");
+
+        let pending: StdVec<Ticket> = vault
+            .get_pending_withdrawals()
+            .iter()
+            .map(|ticket| Ticket {
+                id: ticket.id,
+                user: self.users.iter().position(|u| *u == ticket.user).unwrap(),
+                shares: ticket.shares,
+            })
+            .collect();
+        assert_eq!(
+            pending,
+            StdVec::from(model.queue.clone()),
+            "after {action:?}"
+        );
+        for (id, (_, amount)) in &model.claimable {
+            let claim = vault.get_claimable_withdrawal(id).unwrap();
+            assert_eq!(claim.amount, *amount, "after {action:?}");
+        }
+        assert_eq!(vault.get_total_claimable(), model.claimable_total());
+
+        for (index, user) in self.users.iter().enumerate() {
+            assert_eq!(vault.balance(user), model.shares[index], "after {action:?}");
+            assert_eq!(self.token_client.balance(user), model.wallets[index]);
+        }
+        assert_eq!(
+            self.token_client.balance(&vault.address),
+            model.vault_tokens,
+            "after {action:?}"
+        );
+        for (index, strategy) in self.strategies.iter().enumerate() {
+            // Mock strategies report exactly the tokens they hold.
+            assert_eq!(strategy.balance(), model.strategy_tokens[index]);
+            assert_eq!(
+                self.token_client.balance(&strategy.address),
+                model.strategy_tokens[index]
+            );
+        }
+
+        // Conservation of value.
+        let held = model.wallets.iter().sum::<i128>() + model.vault_tokens + model.deployed();
+        assert_eq!(held, model.supply, "tokens created or lost by {action:?}");
+        let queued: i128 = model.queue.iter().map(|ticket| ticket.shares).sum();
+        assert_eq!(
+            model.total_shares,
+            model.shares.iter().sum::<i128>() + queued,
+            "shares created or lost by {action:?}"
+        );
+        assert!(
+            model.vault_tokens >= model.claimable_total(),
+            "claims not covered after {action:?}"
+        );
+        assert_eq!(
+            model.total_assets + model.unrealised,
+            model.vault_tokens - model.claimable_total() + model.deployed(),
+            "total_assets ≠ idle − claimable + deployed after {action:?}"
+        );
+
+        // Share price monotonicity.
+        if !price_may_fall {
+            assert!(
+                model.share_price() >= price_before,
+                "share price fell from {price_before} to {} after {action:?}",
+                model.share_price()
+            );
+        }
+
+        // No user loss from rounding.
+        if !model.loss_realised {
+            for user in 0..USERS {
+                let claimable: i128 = model
+                    .claimable
+                    .values()
+                    .filter(|(owner, _)| *owner == user)
+                    .map(|(_, amount)| amount)
+                    .sum();
+                let value = model.to_assets(model.shares[user] + model.queued_shares(user))
+                    + claimable
+                    + model.received[user];
+                assert!(
+                    value + model.slack[user] + 1 >= model.paid[user],
+                    "user {user} lost {} to rounding after {action:?}",
+                    model.paid[user] - value
+                );
+            }
+        }
+    }
+}
+
+fn run(actions: &[Action]) {
+    let env = Env::new_with_config(EnvTestConfig {
+        capture_snapshot_at_drop: false,
+    });
+    let harness = Harness::new(&env);
+    let mut model = Model::default();
+    for action in actions {
+        let price_before = model.share_price();
+        let unrealised = model.unrealised;
+        harness.apply(&mut model, action);
+        // Only a harvest that books a net loss may lower the share price.
+        let price_may_fall = matches!(action, Action::Harvest) && unrealised < 0;
+        harness.check(&model, action, price_before, price_may_fall);
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn vault_lifecycle_matches_model(actions in prop::collection::vec(action(), 1..50)) {
+        run(&actions);
+    }
+}
@@ -0,0 +1,215 @@
+//! Sourcing liquidity for queued withdrawals.
+//!
+//! When idle tokens do not cover the next withdrawal ticket, the vault unwinds
+//! strategy positions in the admin-configured order, or lowest recent APY
+//! first when no order is configured. Keepers that fulfil tickets earn a
+//! bounty taken from the settled amount.
+//!
+//! Rebalancing leaves an idle buffer in the vault so small withdrawals can
+//! settle instantly instead of being queued.
+//!
+//! Every transfer between the vault and a strategy is booked, so harvest
+//! credits only what a strategy gained or lost since.
+
+use soroban_sdk::{contracttype, token, Address, Env, Vec};
+
+use crate::{collections, math, Error, StrategyClient, VolatilityShield};
+
+/// Upper bound for the keeper bounty (1%).
+pub const MAX_KEEPER_BOUNTY_BPS: u32 = 100;
+/// Harvest periods used to rank strategies by APY.
+const APY_PERIODS: u32 = 4;
+
+/// Tokens rebalance keeps in the vault: `target_bps` of total assets,
+/// clamped to `[min_amount, max_amount]`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IdleBufferConfig {
+    pub target_bps: u32,
+    pub min_amount: i128,
+    pub max_amount: i128,
+}
+
+#[contracttype]
+#[derive(Clone)]
+pub enum LiquidityKey {
+    /// Share of each fulfilled ticket paid to the keeper, in bps.
+    KeeperBountyBps,
+    /// Admin-configured strategy unwind order.
+    UnwindOrder,
+    /// Idle buffer kept in the vault during rebalance.
+    IdleBuffer,
+    /// Part of a strategy's balance already counted in total assets.
+    Booked(Address),
+}
+
+pub fn keeper_bounty_bps(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get(&LiquidityKey::KeeperBountyBps)
+        .unwrap_or(0)
+}
+
+pub fn set_keeper_bounty_bps(env: &Env, bps: u32) {
+    env.storage()
+        .instance()
+        .set(&LiquidityKey::KeeperBountyBps, &bps);
+}
+
+/// Bounty owed to the keeper for settling `amount`.
+pub fn keeper_bounty(env: &Env, amount: i128) -> Result<i128, Error> {
+    math::mul_div(amount, keeper_bounty_bps(env) as i128, 10_000)
+}
+
+/// Part of `strategy`'s balance already counted in total assets. `None` for
+/// strategies registered before the vault booked transfers.
+pub fn booked(env: &Env, strategy: &Address) -> Option<i128> {
+    env.storage()
+        .instance()
+        .get(&LiquidityKey::Booked(strategy.clone()))
+}
+
+pub fn set_booked(env: &Env, strategy: &Address, amount: i128) {
+    env.storage()
+        .instance()
+        .set(&LiquidityKey::Booked(strategy.clone()), &amount);
+}
+
+/// Book `delta` tokens moved into (positive) or out of (negative) `strategy`.
+pub fn book_transfer(env: &Env, strategy: &Address, delta: i128) {
+    if let Some(amount) = booked(env, strategy) {
+        set_booked(env, strategy, amount.saturating_add(delta));
+    }
+}
+
+pub fn clear_booked(env: &Env, strategy: &Address) {
+    env.storage()
+        .instance()
+        .remove(&LiquidityKey::Booked(strategy.clone()));
+}
+
+pub fn idle_buffer(env: &Env) -> IdleBufferConfig {
+    env.storage()
+        .instance()
+        .get(&LiquidityKey::IdleBuffer)
+        .unwrap_or(IdleBufferConfig {
+            target_bps: 0,
+            min_amount: 0,
+            max_amount: i128::MAX,
+        })
+}
+
+/// Store `config`. Returns false if it is out of range.
+pub fn set_idle_buffer(env: &Env, config: &IdleBufferConfig) -> bool {
+    if config.target_bps > 10_000 || config.min_amount < 0 || config.max_amount < config.min_amount
+    {
+        return false;
+    }
+    env.storage()
+        .instance()
+        .set(&LiquidityKey::IdleBuffer, config);
+    true
+}
+
+/// Tokens rebalance should leave idle when the vault holds `total_assets`.
+pub fn idle_buffer_target(env: &Env, total_assets: i128) -> Result<i128, Error> {
+    let config = idle_buffer(env);
+    let target = math::mul_div(total_assets, config.target_bps as i128, 10_000)?;
+    Ok(target
+        .clamp(config.min_amount, config.max_amount)
+        .min(total_assets.max(0)))
+}
+
+/// Base-token balance of the vault that is not locked for claims.
+pub fn idle_liquidity(env: &Env, token_client: &token::Client) -> i128 {
+    let balance = token_client.balance(&env.current_contract_address());
+    balance
+        .saturating_sub(collections::claimable_total(env))
+        .max(0)
+}
+
+pub fn set_unwind_order(env: &Env, order: &Vec<Address>) {
+    if order.is_empty() {
+        env.storage().instance().remove(&LiquidityKey::UnwindOrder);
+    } else {
+        env.storage()
+            .instance()
+            .set(&LiquidityKey::UnwindOrder, order);
+    }
+}
+
+/// Registered strategies in the order they are unwound.
+///
+/// Strategies named in the configured order come first; any others follow
+/// in registration order. Without a configured order, strategies are sorted
+/// by ascending APY so the best performers are unwound last.
+pub fn unwind_order(env: &Env) -> Vec<Address> {
+    let strategies = VolatilityShield::get_strategies(env);
+    let configured: Option<Vec<Address>> = env.storage().instance().get(&LiquidityKey::UnwindOrder);
+
+    let mut order = Vec::new(env);
+    match configured {
+        Some(configured) => {
+            for strategy in configured.iter() {
+                if strategies.contains(&strategy) {
+                    order.push_back(strategy);
+                }
+            }
+            for strategy in strategies.iter() {
+                if !order.contains(&strategy) {
+                    order.push_back(strategy);
+                }
+            }
+        }
+        None => {
+            // Insertion sort keeps registration order between equal APYs.
+            let mut apys: Vec<i128> = Vec::new(env);
+            for strategy in strategies.iter() {
+                let apy =
+                    VolatilityShield::get_strategy_apy(env.clone(), strategy.clone(), APY_PERIODS);
+                let mut index = apys.len();
+                while index > 0 && apys.get(index - 1).unwrap() > apy {
+                    index -= 1;
+                }
+                apys.insert(index, apy);
+                order.insert(index, strategy);
+            }
+        }
+    }
+    order
+}
+
+/// Withdraw up to `needed` tokens from strategies back into the vault, in
+/// unwind order. Strategies that fail or return less than asked are skipped.
+///
+/// Only the tokens that arrive are booked, so a withdrawal fee shows up as a
+/// loss at the next harvest.
+/// @return The number of tokens that actually arrived.
+pub fn pull_from_strategies(
+    env: &Env,
+    token_client: &token::Client,
+    needed: i128,
+) -> Result<i128, Error> {
+    let vault = env.current_contract_address();
+    let mut received = 0;
+    for strategy_addr in unwind_order(env).iter() {
+        if received >= needed {
+            break;
+        }
+        let strategy = StrategyClient::new(env, strategy_addr.clone());
+        let take = match strategy.try_balance() {
+            Ok(balance) => balance.min(math::sub(needed, received)?),
+            Err(_) => continue,
+        };
+        if take <= 0 {
+            continue;
+        }
+        let before = token_client.balance(&vault);
+        if strategy.try_withdraw(take).is_ok() {
+            let arrived = math::sub(token_client.balance(&vault), before)?.max(0);
+            book_transfer(env, &strategy_addr, -arrived);
+            received = math::add(received, arrived)?;
+        }
+    }
+    Ok(received)
+}
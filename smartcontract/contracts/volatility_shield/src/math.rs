@@ -0,0 +1,40 @@
+//! Checked arithmetic for pricing and share accounting.
+//!
+//! Values are USD scaled by `PRICE_SCALE`, so an `i128` product of an amount
+//! and a price overflows well before either operand does. Overflow surfaces
+//! as `Error::ArithmeticOverflow` rather than a host panic.
+
+use crate::Error;
+
+/// 1.0 in the vault's 9-decimal fixed point.
+pub const PRICE_SCALE: i128 = 1_000_000_000;
+
+/// Extra decimals shares carry over the value they represent: an empty
+/// vault mints `10^SHARE_DECIMALS_OFFSET` shares per unit of value.
+pub const SHARE_DECIMALS_OFFSET: u32 = 3;
+
+/// Shares the vault prices as if they were always outstanding, backed by
+/// `VIRTUAL_ASSETS`.
+///
+/// A donation to a nearly empty vault is mostly captured by this virtual
+/// position, so inflating the share price costs the attacker far more than
+/// the rounding it can take from the next depositor.
+pub const VIRTUAL_SHARES: i128 = 10_i128.pow(SHARE_DECIMALS_OFFSET);
+/// Value (9 decimals) backing `VIRTUAL_SHARES`.
+pub const VIRTUAL_ASSETS: i128 = 1;
+
+/// `value * mul / div`, rounded toward zero.
+pub fn mul_div(value: i128, mul: i128, div: i128) -> Result<i128, Error> {
+    value
+        .checked_mul(mul)
+        .and_then(|product| product.checked_div(div))
+        .ok_or(Error::ArithmeticOverflow)
+}
+
+pub fn add(a: i128, b: i128) -> Result<i128, Error> {
+    a.checked_add(b).ok_or(Error::ArithmeticOverflow)
+}
+
+pub fn sub(a: i128, b: i128) -> Result<i128, Error> {
+    a.checked_sub(b).ok_or(Error::ArithmeticOverflow)
+}
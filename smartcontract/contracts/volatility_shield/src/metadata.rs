@@ -1,59 +1,160 @@
-use soroban_sdk::{contracttype, Env, String};
+use soroban_sdk::{contracttype, Env, String, Vec};
 
-use crate::{DataKey, Error};
+use crate::events;
+use crate::ttl::{PersistentKey, TtlKeyType};
+use crate::{Error, VolatilityShield};
 
+pub const MAX_NAME_LEN: u32 = 64;
+pub const MAX_DESCRIPTION_LEN: u32 = 256;
+pub const MAX_STRATEGY_DESCRIPTION_LEN: u32 = 1024;
 pub const MAX_DOCS_URL_LEN: u32 = 512;
+pub const MAX_AUDIT_LINKS: u32 = 8;
+const MAX_FEE_BPS: u32 = 10_000;
 
+/// Fees charged by the vault, in basis points.
 #[contracttype]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FeeSchedule {
+    pub management_fee_bps: u32,
+    pub performance_fee_bps: u32,
+    pub withdrawal_fee_bps: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct VaultMetadata {
     /// Human-readable vault name. Maximum length: 64 characters.
     pub name: String,
     /// Human-readable vault description. Maximum length: 256 characters.
     pub description: String,
     /// Vault risk rating from 1 through 5.
-    pub risk_rating: u8,
+    pub risk_rating: u32,
     /// Optional documentation URL. Empty is allowed; non-empty values are capped at 512 characters.
     pub docs_url: String,
+    /// How the vault allocates capital. Maximum length: 1024 characters.
+    pub strategy_description: String,
+    /// Fees disclosed to depositors; each component is capped at 10_000 bps.
+    pub fee_schedule: FeeSchedule,
+    /// Links to published audit reports. At most 8, each capped at 512 characters.
+    pub audit_links: Vec<String>,
 }
 
-pub fn set_vault_metadata(env: &Env, metadata: VaultMetadata) -> Result<(), Error> {
-    // Validate name <= 64 chars
-    if metadata.name.len() > 64 {
-        return Err(Error::MetadataNameTooLong);
+/// A published revision of the vault metadata.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MetadataRecord {
+    /// Revision number, starting at 1.
+    pub version: u32,
+    pub updated_at: u64,
+    pub updated_ledger: u32,
+    pub metadata: VaultMetadata,
+}
+
+/// Metadata storage keys.
+#[contracttype]
+#[derive(Clone)]
+pub enum MetadataKey {
+    /// Latest published revision number.
+    LatestVersion,
+    /// A published revision, by version.
+    Record(u32),
+}
+
+impl PersistentKey for MetadataKey {
+    fn ttl_type(&self) -> TtlKeyType {
+        TtlKeyType::Metadata
     }
+}
 
-    // Validate description <= 256 chars
-    if metadata.description.len() > 256 {
+fn validate(metadata: &VaultMetadata) -> Result<(), Error> {
+    if metadata.name.len() > MAX_NAME_LEN {
+        return Err(Error::MetadataNameTooLong);
+    }
+    if metadata.description.len() > MAX_DESCRIPTION_LEN
+        || metadata.strategy_description.len() > MAX_STRATEGY_DESCRIPTION_LEN
+    {
         return Err(Error::MetadataDescriptionTooLong);
     }
-
-    // Validate risk_rating in 1–5
     if metadata.risk_rating < 1 || metadata.risk_rating > 5 {
         return Err(Error::InvalidRiskRating);
     }
 
-    // Validate docs_url <= 512 chars; empty docs_url is allowed.
+    // Empty docs_url is allowed.
     if metadata.docs_url.len() > MAX_DOCS_URL_LEN {
         return Err(Error::InvalidConfig);
     }
+    let fees = &metadata.fee_schedule;
+    if fees.management_fee_bps > MAX_FEE_BPS
+        || fees.performance_fee_bps > MAX_FEE_BPS
+        || fees.withdrawal_fee_bps > MAX_FEE_BPS
+    {
+        return Err(Error::InvalidConfig);
+    }
+    if metadata.audit_links.len() > MAX_AUDIT_LINKS
+        || metadata
+            .audit_links
+            .iter()
+            .any(|link| link.is_empty() || link.len() > MAX_DOCS_URL_LEN)
+    {
+        return Err(Error::InvalidConfig);
+    }
+    Ok(())
+}
 
-    env.storage()
-        .persistent()
-        .set(&DataKey::VaultMetadata, &metadata);
+pub fn latest_version(env: &Env) -> u32 {
+    VolatilityShield::get_persistent(env, &MetadataKey::LatestVersion).unwrap_or(0)
+}
 
-    env.events().publish(
-        (symbol_short!("meta"), symbol_short!("updated")),
-        (metadata.name.clone(), metadata.risk_rating),
+/// Validate and publish `metadata` as a new revision.
+/// Returns the new revision number.
+pub fn set_vault_metadata(env: &Env, metadata: VaultMetadata) -> Result<u32, Error> {
+    validate(&metadata)?;
+
+    let version = latest_version(env) + 1;
+    let record = MetadataRecord {
+        version,
+        updated_at: env.ledger().timestamp(),
+        updated_ledger: env.ledger().sequence(),
+        metadata,
+    };
+    VolatilityShield::set_persistent(env, &MetadataKey::Record(version), &record);
+    VolatilityShield::set_persistent(env, &MetadataKey::LatestVersion, &version);
+
+    events::publish(
+        env,
+        events::MetadataUpdated {
+            name: record.metadata.name.clone(),
+            risk_rating: record.metadata.risk_rating,
+            version,
+        },
     );
 
-    Ok(())
+    Ok(version)
 }
 
 pub fn get_vault_metadata(env: &Env) -> Option<VaultMetadata> {
-    env.storage()
-        .persistent()
-        .get(&DataKey::VaultMetadata)
+    get_record(env, latest_version(env)).map(|record| record.metadata)
+}
+
+pub fn get_record(env: &Env, version: u32) -> Option<MetadataRecord> {
+    if version == 0 {
+        return None;
+    }
+    VolatilityShield::get_persistent(env, &MetadataKey::Record(version))
+}
+
+/// Up to `limit` revisions starting at `from_version`, oldest first.
+pub fn get_history(env: &Env, from_version: u32, limit: u32) -> Vec<MetadataRecord> {
+    let mut history = Vec::new(env);
+    let latest = latest_version(env);
+    let mut version = from_version.max(1);
+    while version <= latest && history.len() < limit {
+        if let Some(record) = get_record(env, version) {
+            history.push_back(record);
+        }
+        version += 1;
+    }
+    history
 }
 
 #[cfg(test)]
@@ -69,9 +170,21 @@ mod tests {
             description: SorobanString::from_str(env, "Vault metadata"),
             risk_rating: 3,
             docs_url,
+            strategy_description: SorobanString::from_str(env, "Delta-neutral hedging"),
+            fee_schedule: FeeSchedule {
+                management_fee_bps: 200,
+                performance_fee_bps: 2_000,
+                withdrawal_fee_bps: 0,
+            },
+            audit_links: Vec::new(env),
         }
     }
 
+    fn set_in_contract(env: &Env, metadata: VaultMetadata) -> Result<u32, Error> {
+        let contract_id = env.register(VolatilityShield, ());
+        env.as_contract(&contract_id, || set_vault_metadata(env, metadata))
+    }
+
     #[test]
     fn accepts_valid_docs_url() {
         let env = Env::default();
@@ -80,7 +193,7 @@ mod tests {
             SorobanString::from_str(&env, "https://docs.xhedge.example/vault"),
         );
 
-        assert_eq!(set_vault_metadata(&env, metadata), Ok(()));
+        assert_eq!(set_in_contract(&env, metadata), Ok(1));
     }
 
     #[test]
@@ -88,7 +201,7 @@ mod tests {
         let env = Env::default();
         let metadata = metadata_with_docs_url(&env, SorobanString::from_str(&env, ""));
 
-        assert_eq!(set_vault_metadata(&env, metadata), Ok(()));
+        assert_eq!(set_in_contract(&env, metadata), Ok(1));
     }
 
     #[test]
@@ -97,7 +210,7 @@ mod tests {
         let url = std::string::String::from("a").repeat(MAX_DOCS_URL_LEN as usize);
         let metadata = metadata_with_docs_url(&env, SorobanString::from_str(&env, &url));
 
-        assert_eq!(set_vault_metadata(&env, metadata), Ok(()));
+        assert_eq!(set_in_contract(&env, metadata), Ok(1));
     }
 
     #[test]
@@ -106,6 +219,30 @@ mod tests {
         let url = std::string::String::from("a").repeat(MAX_DOCS_URL_LEN as usize + 1);
         let metadata = metadata_with_docs_url(&env, SorobanString::from_str(&env, &url));
 
-        assert_eq!(set_vault_metadata(&env, metadata), Err(Error::InvalidConfig));
+        assert_eq!(set_in_contract(&env, metadata), Err(Error::InvalidConfig));
+    }
+
+    #[test]
+    fn rejects_fee_above_100_percent() {
+        let env = Env::default();
+        let mut metadata = metadata_with_docs_url(&env, SorobanString::from_str(&env, ""));
+        metadata.fee_schedule.performance_fee_bps = MAX_FEE_BPS + 1;
+
+        assert_eq!(validate(&metadata), Err(Error::InvalidConfig));
+    }
+
+    #[test]
+    fn rejects_too_many_or_empty_audit_links() {
+        let env = Env::default();
+        let mut metadata = metadata_with_docs_url(&env, SorobanString::from_str(&env, ""));
+        for _ in 0..=MAX_AUDIT_LINKS {
+            metadata
+                .audit_links
+                .push_back(SorobanString::from_str(&env, "https://audits.example/report.pdf"));
+        }
+        assert_eq!(validate(&metadata), Err(Error::InvalidConfig));
+
+        metadata.audit_links = soroban_sdk::vec![&env, SorobanString::from_str(&env, "")];
+        assert_eq!(validate(&metadata), Err(Error::InvalidConfig));
     }
 }
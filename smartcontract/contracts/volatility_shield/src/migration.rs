@@ -0,0 +1,343 @@
+use soroban_sdk::{contracttype, Address, Env, IntoVal, Map, TryFromVal, Val, Vec};
+
+use crate::collections::{self, HistoryKind, ListKind};
+use crate::events;
+use crate::{
+    DataKey, Error, Proposal, QueuedWithdrawal, VoteTally, VolatilityShield, WithdrawalTicket,
+    YieldHistory, PAUSE_HISTORY_CAP, SHARE_PRICE_HISTORY_CAP, TVL_HISTORY_KEY,
+};
+
+/// Storage layout version written by this build.
+///
+/// State-changing entry points only accept storage at this version, so every
+/// bump needs a registered step and locks the vault until `migrate` has run.
+pub const CONTRACT_VERSION: u32 = 3;
+/// Items migrated per call when the caller does not choose a batch size.
+pub const DEFAULT_MIGRATION_BATCH: u32 = 100;
+/// Legacy instance collections the v2 → v3 step copies, in order.
+const V3_PHASES: u32 = 7;
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum MigrationKey {
+    State,
+    /// v2 → v3 progress: the legacy collection being copied and the cursor
+    /// at which it started.
+    V3Phase,
+}
+
+/// A migration that has been started but not yet completed.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MigrationState {
+    /// Version storage will be at once every remaining step has run.
+    pub target_version: u32,
+    /// Position inside the step currently running (`from = ContractVersion`).
+    pub cursor: u32,
+}
+
+struct StepProgress {
+    cursor: u32,
+    processed: u32,
+    done: bool,
+}
+
+/// Migrates a bounded slice of storage, starting at `cursor` and touching at
+/// most `limit` items.
+type MigrationStep = fn(&Env, u32, u32) -> StepProgress;
+
+/// Registered steps, keyed by the version they migrate *from*.
+const STEPS: [(u32, MigrationStep); 2] = [(1, migrate_v1_to_v2), (2, migrate_v2_to_v3)];
+
+fn step_from(version: u32) -> Option<MigrationStep> {
+    STEPS
+        .iter()
+        .find(|(from, _)| *from == version)
+        .map(|(_, step)| *step)
+}
+
+pub fn pending(env: &Env) -> Option<MigrationState> {
+    env.storage().instance().get(&MigrationKey::State)
+}
+
+/// Validate `target` and record it as the pending migration.
+///
+/// Re-issuing the target of an unfinished migration resumes it; asking for a
+/// different target while one is pending is rejected.
+pub fn begin(env: &Env, target: u32) -> Result<(), Error> {
+    if let Some(state) = pending(env) {
+        return if state.target_version == target {
+            Ok(())
+        } else {
+            Err(Error::MigrationInProgress)
+        };
+    }
+
+    let current = VolatilityShield::version(env);
+    if target <= current || target > CONTRACT_VERSION {
+        return Err(Error::UnsupportedVersion);
+    }
+    // Every intermediate step must be registered before anything is touched.
+    if (current..target).any(|from| step_from(from).is_none()) {
+        return Err(Error::UnsupportedVersion);
+    }
+
+    env.storage().instance().set(
+        &MigrationKey::State,
+        &MigrationState {
+            target_version: target,
+            cursor: 0,
+        },
+    );
+    events::publish(
+        env,
+        events::MigrationStarted {
+            from_version: current,
+            to_version: target,
+        },
+    );
+    Ok(())
+}
+
+/// Run the pending migration for at most `max_items` items.
+/// @return `true` once the target version has been reached.
+pub fn run(env: &Env, max_items: u32) -> Result<bool, Error> {
+    let mut state = match pending(env) {
+        Some(state) => state,
+        None => return Ok(true),
+    };
+
+    let mut budget = max_items.max(1);
+    let mut version = VolatilityShield::version(env);
+    while version < state.target_version && budget > 0 {
+        let step = step_from(version).ok_or(Error::UnsupportedVersion)?;
+        let progress = step(env, state.cursor, budget);
+        budget = budget.saturating_sub(progress.processed.max(1));
+
+        if !progress.done {
+            state.cursor = progress.cursor;
+            break;
+        }
+        version += 1;
+        state.cursor = 0;
+        env.storage()
+            .instance()
+            .set(&DataKey::ContractVersion, &version);
+        events::publish(
+            env,
+            events::MigrationStepCompleted {
+                from_version: version - 1,
+                to_version: version,
+            },
+        );
+    }
+
+    if version >= state.target_version {
+        env.storage().instance().remove(&MigrationKey::State);
+        events::publish(env, events::Migrated { version });
+        return Ok(true);
+    }
+
+    env.storage().instance().set(&MigrationKey::State, &state);
+    Ok(false)
+}
+
+/// v1 → v2: TVL history moves out of instance storage (which is loaded on
+/// every call) into its own persistent entry.
+fn migrate_v1_to_v2(env: &Env, cursor: u32, limit: u32) -> StepProgress {
+    let legacy: Vec<(u64, i128)> = env
+        .storage()
+        .instance()
+        .get(&TVL_HISTORY_KEY)
+        .unwrap_or(Vec::new(env));
+    let mut migrated: Vec<(u64, i128)> =
+        VolatilityShield::get_persistent(env, &TVL_HISTORY_KEY).unwrap_or(Vec::new(env));
+
+    let end = cursor.saturating_add(limit).min(legacy.len());
+    for i in cursor..end {
+        migrated.push_back(legacy.get(i).unwrap());
+    }
+    if end > cursor {
+        VolatilityShield::set_persistent(env, &TVL_HISTORY_KEY, &migrated);
+    }
+
+    let done = end >= legacy.len();
+    if done {
+        env.storage().instance().remove(&TVL_HISTORY_KEY);
+    }
+    StepProgress {
+        cursor: end,
+        processed: end - cursor,
+        done,
+    }
+}
+
+/// v2 → v3: proposals, the withdrawal queue, block/allow lists, share price
+/// and pause histories, vote tallies and strategy yield histories move from
+/// instance storage to keyed persistent entries (see `collections`).
+/// Queued withdrawals become withdrawal tickets; the request-time share price
+/// was never recorded, so they carry the price at migration time.
+///
+/// The legacy collections are copied one after another and only the one being
+/// copied is loaded; the cursor counts items copied so far. Each legacy key is
+/// removed once its items have been copied. Per-voter `VoteRecord`s cannot be
+/// enumerated and are left in place; `cast_vote` still honours them.
+fn migrate_v2_to_v3(env: &Env, cursor: u32, limit: u32) -> StepProgress {
+    let instance = env.storage().instance();
+    let (mut phase, mut phase_start): (u32, u32) =
+        instance.get(&MigrationKey::V3Phase).unwrap_or((0, 0));
+
+    let mut copied = 0;
+    while phase < V3_PHASES {
+        let offset = cursor + copied - phase_start;
+        let (len, count) = copy_v3_phase(env, phase, offset, limit - copied);
+        copied += count;
+        if offset + count < len {
+            break;
+        }
+        finish_v3_phase(env, phase);
+        phase += 1;
+        phase_start = cursor + copied;
+    }
+
+    let done = phase >= V3_PHASES;
+    if done {
+        instance.remove(&MigrationKey::V3Phase);
+    } else {
+        instance.set(&MigrationKey::V3Phase, &(phase, phase_start));
+    }
+    StepProgress {
+        cursor: cursor + copied,
+        processed: copied,
+        done,
+    }
+}
+
+/// Copy at most `limit` items of legacy collection `phase`, starting at
+/// `offset`.
+/// @return The collection length and the number of items copied.
+fn copy_v3_phase(env: &Env, phase: u32, offset: u32, limit: u32) -> (u32, u32) {
+    let instance = env.storage().instance();
+    match phase {
+        0 => {
+            let proposals: Map<u64, Proposal> =
+                instance.get(&DataKey::Proposals).unwrap_or(Map::new(env));
+            copy_legacy(env, &DataKey::ProposalIds, offset, limit, |id: u64| {
+                if let Some(proposal) = proposals.get(id) {
+                    collections::write_proposal(env, &proposal);
+                }
+                let tally_key = DataKey::VoteTally(id);
+                if let Some(tally) = instance.get::<_, VoteTally>(&tally_key) {
+                    VolatilityShield::set_persistent(env, &tally_key, &tally);
+                    instance.remove(&tally_key);
+                }
+            })
+        }
+        1 => {
+            let share_price = VolatilityShield::get_share_price(env);
+            copy_legacy(
+                env,
+                &DataKey::PendingWithdrawals,
+                offset,
+                limit,
+                |withdrawal: QueuedWithdrawal| {
+                    collections::queue_push(
+                        env,
+                        WithdrawalTicket {
+                            id: 0,
+                            user: withdrawal.user,
+                            asset: withdrawal.asset,
+                            shares: withdrawal.shares,
+                            share_price,
+                            timestamp: withdrawal.timestamp,
+                        },
+                    );
+                },
+            )
+        }
+        2 => copy_legacy(env, &DataKey::Blocklist, offset, limit, |user: Address| {
+            collections::list_add(env, ListKind::Blocklist, &user);
+        }),
+        3 => copy_legacy(env, &DataKey::Allowlist, offset, limit, |user: Address| {
+            collections::list_add(env, ListKind::Allowlist, &user);
+        }),
+        4 => copy_legacy(
+            env,
+            &DataKey::SharePriceHistory,
+            offset,
+            limit,
+            |entry: (u64, i128)| {
+                collections::history_push(
+                    env,
+                    HistoryKind::SharePrice,
+                    SHARE_PRICE_HISTORY_CAP,
+                    entry,
+                )
+            },
+        ),
+        5 => copy_legacy(
+            env,
+            &DataKey::PauseHistory,
+            offset,
+            limit,
+            |entry: (u64, Address, bool)| {
+                collections::history_push(env, HistoryKind::Pause, PAUSE_HISTORY_CAP, entry)
+            },
+        ),
+        _ => copy_legacy(
+            env,
+            &DataKey::Strategies,
+            offset,
+            limit,
+            |strategy: Address| {
+                let key = DataKey::StrategyYieldSnapshot(strategy);
+                if let Some(history) = instance.get::<_, YieldHistory>(&key) {
+                    VolatilityShield::set_persistent(env, &key, &history);
+                    instance.remove(&key);
+                }
+            },
+        ),
+    }
+}
+
+/// Pass items `offset..offset + limit` of the legacy vector at `key` to `copy`.
+/// @return The vector length and the number of items copied.
+fn copy_legacy<T>(
+    env: &Env,
+    key: &DataKey,
+    offset: u32,
+    limit: u32,
+    mut copy: impl FnMut(T),
+) -> (u32, u32)
+where
+    T: IntoVal<Env, Val> + TryFromVal<Env, Val>,
+{
+    let items: Vec<T> = env.storage().instance().get(key).unwrap_or(Vec::new(env));
+    let end = offset.saturating_add(limit).min(items.len()).max(offset);
+    for i in offset..end {
+        copy(items.get_unchecked(i));
+    }
+    (items.len(), end - offset)
+}
+
+/// Remove the legacy key of a fully copied collection.
+fn finish_v3_phase(env: &Env, phase: u32) {
+    let instance = env.storage().instance();
+    match phase {
+        0 => {
+            let proposal_ids: Vec<u64> =
+                instance.get(&DataKey::ProposalIds).unwrap_or(Vec::new(env));
+            let (_, tail) = collections::proposal_bounds(env);
+            collections::set_proposal_head(env, proposal_ids.first().unwrap_or(tail));
+            instance.remove(&DataKey::ProposalIds);
+            instance.remove(&DataKey::Proposals);
+        }
+        1 => instance.remove(&DataKey::PendingWithdrawals),
+        2 => instance.remove(&DataKey::Blocklist),
+        3 => instance.remove(&DataKey::Allowlist),
+        4 => instance.remove(&DataKey::SharePriceHistory),
+        5 => instance.remove(&DataKey::PauseHistory),
+        // Strategies stay registered.
+        _ => {}
+    }
+}
@@ -0,0 +1,388 @@
+//! Rebalance modes and planning.
+//!
+//! In `BestEffort` mode rebalance moves what it can and reports strategies
+//! that fail. In `Atomic` mode it first plans every move against current
+//! balances, vault liquidity and the slippage limit, and either executes all
+//! of them or none. The same plan backs the `simulate_rebalance` view.
+//!
+//! Large rebalances can also run incrementally: `start_rebalance` snapshots
+//! the target allocations and keepers advance a cursor over the strategies,
+//! moving at most `max_move_per_step` tokens per step. Targets are kept in
+//! bps and resolved against live total assets at every step, so deposits and
+//! withdrawals made while a rebalance is in flight are taken into account.
+//!
+//! Keepers can also trigger a rebalance with `poke_rebalance` once the
+//! allocation drift or the time since the last rebalance crosses the
+//! admin-configured `RebalanceTrigger`.
+
+use soroban_sdk::{contracttype, token, Address, Env, Map, Vec};
+
+use crate::{liquidity, math, Error, StrategyClient, VolatilityShield};
+
+/// Seconds a keeper must wait after any rebalance before poking again,
+/// whichever trigger fires.
+pub const MIN_POKE_SPACING: u64 = 60;
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RebalanceMode {
+    /// Skip failing strategies and keep going.
+    BestEffort,
+    /// Commit every planned move or none of them.
+    Atomic,
+}
+
+/// One strategy's move in a rebalance plan.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PlannedMove {
+    pub strategy: Address,
+    pub current_balance: i128,
+    pub target_balance: i128,
+    /// Tokens moved into the strategy; negative values are withdrawals.
+    pub delta: i128,
+    /// Expected distance from the target after the move, in bps of the target.
+    pub slippage_bps: i128,
+    /// False if the strategy balance cannot be read.
+    pub reachable: bool,
+    /// False if the strategy is unreachable or the slippage limit would be
+    /// exceeded.
+    pub executable: bool,
+}
+
+/// Result of planning a rebalance, returned by `simulate_rebalance`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RebalancePlan {
+    pub total_assets: i128,
+    /// Tokens kept idle in the vault (see `set_idle_buffer`).
+    pub idle_target: i128,
+    /// Moves in execution order.
+    pub moves: Vec<PlannedMove>,
+    /// Whether an atomic rebalance would commit this plan.
+    pub feasible: bool,
+}
+
+/// State of an incremental rebalance, returned by `get_rebalance_progress`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RebalanceProgress {
+    /// Target allocations (bps) snapshotted by `start_rebalance`.
+    pub allocations: Map<Address, i128>,
+    /// Strategies in processing order.
+    pub strategies: Vec<Address>,
+    /// Index into `strategies` of the next strategy to move.
+    pub cursor: u32,
+    /// Largest amount moved in or out of a strategy in one step.
+    pub max_move_per_step: i128,
+    /// Tokens moved so far, in either direction.
+    pub moved: i128,
+    pub steps: u32,
+    pub started_at: u64,
+}
+
+/// When `poke_rebalance` may run a rebalance, and what it pays the keeper.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RebalanceTrigger {
+    /// Rebalance once any strategy's share of deployed assets is this many
+    /// bps away from its target. 0 disables the drift trigger.
+    pub drift_bps: u32,
+    /// Rebalance once this many seconds have passed since the last one.
+    /// 0 disables the interval trigger.
+    pub min_interval: u64,
+    /// Slippage limit for keeper-triggered rebalances.
+    pub max_slippage_bps: u32,
+    /// Base tokens paid from idle liquidity to the keeper, only when the
+    /// rebalance moved funds or reduced the drift.
+    pub keeper_reward: i128,
+}
+
+/// What happened to one strategy during a rebalance.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MoveResult {
+    /// Already at target, or no idle tokens were left to fund a deposit.
+    Unchanged,
+    Withdrawn,
+    Deposited,
+    /// The strategy rejected the move; it has been flagged.
+    Failed,
+    /// The strategy balance could not be read; it has been flagged.
+    Unreachable,
+}
+
+impl MoveResult {
+    pub fn from_delta(delta: i128) -> Self {
+        if delta > 0 {
+            MoveResult::Deposited
+        } else if delta < 0 {
+            MoveResult::Withdrawn
+        } else {
+            MoveResult::Unchanged
+        }
+    }
+
+    pub fn succeeded(&self) -> bool {
+        !matches!(self, MoveResult::Failed | MoveResult::Unreachable)
+    }
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StrategyOutcome {
+    pub strategy: Address,
+    pub balance_before: i128,
+    pub target_balance: i128,
+    /// Tokens moved into the strategy; negative values are withdrawals.
+    pub moved: i128,
+    pub result: MoveResult,
+}
+
+/// Summary of a completed rebalance. Returned by `poke_rebalance`, emitted
+/// as `RebalanceReport` and kept for `get_last_rebalance_report`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RebalanceReport {
+    pub total_assets_before: i128,
+    pub total_assets_after: i128,
+    /// Tokens pulled back from strategies.
+    pub withdrawn: i128,
+    /// Tokens sent to strategies.
+    pub deposited: i128,
+    pub successful_strategies: u32,
+    /// Outcomes in execution order (withdrawals first).
+    pub outcomes: Vec<StrategyOutcome>,
+}
+
+#[contracttype]
+#[derive(Clone)]
+pub enum RebalanceKey {
+    Mode,
+    /// Report of the last completed rebalance.
+    LastReport,
+    /// Timestamp of the last completed rebalance.
+    LastRebalanceAt,
+    Trigger,
+    /// Incremental rebalance in flight, if any.
+    InFlight,
+}
+
+pub fn mode(env: &Env) -> RebalanceMode {
+    env.storage()
+        .instance()
+        .get(&RebalanceKey::Mode)
+        .unwrap_or(RebalanceMode::BestEffort)
+}
+
+pub fn set_mode(env: &Env, mode: RebalanceMode) {
+    env.storage().instance().set(&RebalanceKey::Mode, &mode);
+}
+
+pub fn last_report(env: &Env) -> Option<RebalanceReport> {
+    env.storage().instance().get(&RebalanceKey::LastReport)
+}
+
+pub fn set_last_report(env: &Env, report: &RebalanceReport) {
+    env.storage()
+        .instance()
+        .set(&RebalanceKey::LastReport, report);
+}
+
+pub fn last_rebalance_at(env: &Env) -> Option<u64> {
+    env.storage().instance().get(&RebalanceKey::LastRebalanceAt)
+}
+
+pub fn record_rebalance(env: &Env) {
+    env.storage()
+        .instance()
+        .set(&RebalanceKey::LastRebalanceAt, &env.ledger().timestamp());
+}
+
+pub fn trigger(env: &Env) -> Option<RebalanceTrigger> {
+    env.storage().instance().get(&RebalanceKey::Trigger)
+}
+
+pub fn set_trigger(env: &Env, trigger: &RebalanceTrigger) {
+    env.storage()
+        .instance()
+        .set(&RebalanceKey::Trigger, trigger);
+}
+
+/// Largest distance, in bps of deployed assets, between a strategy's actual
+/// balance and its target. Strategies whose balance cannot be read are ignored.
+pub fn max_drift_bps(env: &Env, allocations: &Map<Address, i128>) -> Result<i128, Error> {
+    let total_assets = VolatilityShield::total_assets(env);
+    let deployable = math::sub(
+        total_assets,
+        liquidity::idle_buffer_target(env, total_assets)?,
+    )?;
+    if deployable <= 0 {
+        return Ok(0);
+    }
+    let mut max_drift = 0;
+    for (strategy, bps) in allocations.iter() {
+        if let Ok(balance) = StrategyClient::new(env, strategy).try_balance() {
+            let actual_bps = math::mul_div(balance, 10_000, deployable)?;
+            max_drift = max_drift.max(math::sub(actual_bps, bps)?.abs());
+        }
+    }
+    Ok(max_drift)
+}
+
+/// Whether `trigger` allows a keeper rebalance now. No trigger fires within
+/// `MIN_POKE_SPACING` of the last rebalance.
+pub fn needed(
+    env: &Env,
+    trigger: &RebalanceTrigger,
+    allocations: &Map<Address, i128>,
+) -> Result<bool, Error> {
+    let now = env.ledger().timestamp();
+    let last = last_rebalance_at(env);
+    if last.is_some_and(|last| now < last.saturating_add(MIN_POKE_SPACING)) {
+        return Ok(false);
+    }
+    if trigger.min_interval > 0 && now >= last.unwrap_or(0).saturating_add(trigger.min_interval) {
+        return Ok(true);
+    }
+    Ok(trigger.drift_bps > 0 && max_drift_bps(env, allocations)? > trigger.drift_bps as i128)
+}
+
+pub fn in_flight(env: &Env) -> Option<RebalanceProgress> {
+    env.storage().instance().get(&RebalanceKey::InFlight)
+}
+
+pub fn set_in_flight(env: &Env, progress: &RebalanceProgress) {
+    env.storage()
+        .instance()
+        .set(&RebalanceKey::InFlight, progress);
+}
+
+pub fn clear_in_flight(env: &Env) {
+    env.storage().instance().remove(&RebalanceKey::InFlight);
+}
+
+/// Next move for the strategy at the cursor of `progress`.
+///
+/// The delta is capped by `max_move_per_step`, and deposits are limited to
+/// idle tokens above the idle buffer. `slippage_bps` is the distance from the
+/// target left after the move. `None` if the strategy balance cannot be read.
+pub fn next_step(env: &Env, progress: &RebalanceProgress) -> Result<Option<PlannedMove>, Error> {
+    let Some(strategy) = progress.strategies.get(progress.cursor) else {
+        return Ok(None);
+    };
+    let bps = progress.allocations.get(strategy.clone()).unwrap_or(0);
+    let total_assets = VolatilityShield::total_assets(env);
+    let idle_target = liquidity::idle_buffer_target(env, total_assets)?;
+    let target_balance = math::mul_div(math::sub(total_assets, idle_target)?, bps, 10_000)?;
+    let Ok(current_balance) = StrategyClient::new(env, strategy.clone()).try_balance() else {
+        return Ok(None);
+    };
+
+    let mut delta = math::sub(target_balance, current_balance)?
+        .clamp(-progress.max_move_per_step, progress.max_move_per_step);
+    if delta > 0 {
+        let token_client = token::Client::new(env, &VolatilityShield::get_asset(env));
+        let spare = math::sub(liquidity::idle_liquidity(env, &token_client), idle_target)?;
+        delta = delta.min(spare.max(0));
+    }
+
+    Ok(Some(PlannedMove {
+        strategy,
+        current_balance,
+        target_balance,
+        delta,
+        slippage_bps: slippage_bps(math::add(current_balance, delta)?, target_balance)?,
+        reachable: true,
+        executable: true,
+    }))
+}
+
+/// Distance of `balance` from `target`, in bps of the target.
+fn slippage_bps(balance: i128, target: i128) -> Result<i128, Error> {
+    if target <= 0 {
+        return Ok(0);
+    }
+    math::mul_div(
+        VolatilityShield::balance_deviation_amount(balance, target),
+        10_000,
+        target,
+    )
+}
+
+/// Plan the moves needed to reach `allocations` (bps per strategy).
+///
+/// Withdrawals are ordered before deposits so deposits are funded from
+/// their proceeds first and only the net difference draws on idle vault
+/// tokens. A deposit the vault cannot fund is reduced, which shows up as
+/// slippage on that strategy.
+pub fn plan(
+    env: &Env,
+    allocations: &Map<Address, i128>,
+    max_slippage_bps: u32,
+) -> Result<RebalancePlan, Error> {
+    let total_assets = VolatilityShield::total_assets(env);
+    let idle_target = liquidity::idle_buffer_target(env, total_assets)?;
+    let deployable = math::sub(total_assets, idle_target)?;
+    // Tokens available for deposits: withdrawal proceeds plus idle tokens.
+    let mut cash = 0_i128;
+
+    let mut withdrawals = Vec::new(env);
+    let mut deposits = Vec::new(env);
+    for (strategy, bps) in allocations.iter() {
+        let target_balance = math::mul_div(deployable, bps, 10_000)?;
+        let (current_balance, reachable) =
+            match StrategyClient::new(env, strategy.clone()).try_balance() {
+                Ok(balance) => (balance, true),
+                Err(_) => (0, false),
+            };
+        let delta = if reachable {
+            math::sub(target_balance, current_balance)?
+        } else {
+            0
+        };
+        let planned = PlannedMove {
+            strategy,
+            current_balance,
+            target_balance,
+            delta,
+            slippage_bps: 0,
+            reachable,
+            executable: reachable,
+        };
+        if delta < 0 {
+            cash = math::sub(cash, delta)?;
+            withdrawals.push_back(planned);
+        } else {
+            deposits.push_back(planned);
+        }
+    }
+
+    if deposits.iter().any(|planned| planned.delta > 0) {
+        let token_client = token::Client::new(env, &VolatilityShield::get_asset(env));
+        cash = math::add(cash, liquidity::idle_liquidity(env, &token_client))?;
+    }
+
+    let mut moves = Vec::new(env);
+    let mut feasible = true;
+    for mut planned in withdrawals.iter().chain(deposits.iter()) {
+        if planned.delta > 0 {
+            planned.delta = planned.delta.min(cash.max(0));
+            cash = math::sub(cash, planned.delta)?;
+        }
+        planned.slippage_bps = slippage_bps(
+            math::add(planned.current_balance, planned.delta)?,
+            planned.target_balance,
+        )?;
+        planned.executable = planned.reachable && planned.slippage_bps <= max_slippage_bps as i128;
+        feasible &= planned.executable;
+        moves.push_back(planned);
+    }
+
+    Ok(RebalancePlan {
+        total_assets,
+        idle_target,
+        moves,
+        feasible,
+    })
+}
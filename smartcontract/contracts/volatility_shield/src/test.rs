@@ -4,7 +4,7 @@ use soroban_sdk::token::Client as TokenClient;
 use soroban_sdk::token::StellarAssetClient;
 use soroban_sdk::{
     testutils::storage::Persistent as _, testutils::Address as _, testutils::Ledger as _, Address,
-    Env, Map, Symbol,
+    BytesN, Env, Map, Symbol,
 };
 
 extern crate std;
@@ -19,6 +19,93 @@ fn create_token_contract<'a>(
     (contract_id.address(), stellar_asset_client, token_client)
 }
 
+/// A vault built by `VaultBuilder`, with the token it was initialised with.
+struct TestVault<'a> {
+    id: Address,
+    client: VolatilityShieldClient<'a>,
+    admin: Address,
+    token: Address,
+    token_admin: StellarAssetClient<'a>,
+    token_client: TokenClient<'a>,
+    strategies: Vec<Address>,
+}
+
+/// Initialises a vault with a single-signer admin, mocking every auth, and
+/// registers real-token mock strategies through `AddStrategy` proposals.
+struct VaultBuilder<'a> {
+    env: &'a Env,
+    oracle: Option<Address>,
+    strategies: u32,
+}
+
+impl<'a> VaultBuilder<'a> {
+    fn new(env: &'a Env) -> Self {
+        Self {
+            env,
+            oracle: None,
+            strategies: 0,
+        }
+    }
+
+    fn oracle(mut self, oracle: &Address) -> Self {
+        self.oracle = Some(oracle.clone());
+        self
+    }
+
+    fn strategies(mut self, count: u32) -> Self {
+        self.strategies = count;
+        self
+    }
+
+    fn build(self) -> TestVault<'a> {
+        let env = self.env;
+        env.mock_all_auths_allowing_non_root_auth();
+        let (token, token_admin, token_client) =
+            create_token_contract(env, &Address::generate(env));
+        let id = env.register(VolatilityShield, ());
+        let client = VolatilityShieldClient::new(env, &id);
+        let admin = Address::generate(env);
+        client.init(
+            &admin,
+            &token,
+            &self.oracle.unwrap_or_else(|| Address::generate(env)),
+            &Address::generate(env),
+            &0u32,
+            &soroban_sdk::vec![env, admin.clone()],
+            &1u32,
+        );
+
+        let mut strategies = Vec::new(env);
+        for _ in 0..self.strategies {
+            let strategy = env.register(mock_strategy::MockStrategy, ());
+            mock_strategy::MockStrategyClient::new(env, &strategy).init(&id, &token);
+            client.propose_action(&admin, &ActionType::AddStrategy(strategy.clone()));
+            strategies.push_back(strategy);
+        }
+        TestVault {
+            id,
+            client,
+            admin,
+            token,
+            token_admin,
+            token_client,
+            strategies,
+        }
+    }
+}
+
+/// Whether an event named `name` was published.
+fn published(env: &Env, name: &str) -> bool {
+    use soroban_sdk::testutils::Events as _;
+    use soroban_sdk::TryFromVal;
+
+    env.events().all().iter().any(|(_, topics, _)| {
+        topics.get(0).is_some_and(|topic| {
+            Symbol::try_from_val(env, &topic).is_ok_and(|s| s == Symbol::new(env, name))
+        })
+    })
+}
+
 #[test]
 fn test_init_stores_roles() {
     let env = Env::default();
@@ -112,6 +199,32 @@ fn test_error_to_symbol_snapshot() {
         (Error::UserBlocked, "user_blocked"),
         (Error::CircuitBreakerActive, "circuit_breaker_active"),
         (Error::EmergencyShutdownActive, "emergency_shutdown_active"),
+        (Error::InvalidThreshold, "invalid_threshold"),
+        (Error::ActionNotDefensive, "action_not_defensive"),
+        (Error::UpgradeAlreadyPending, "upgrade_already_pending"),
+        (Error::UnsupportedVersion, "unsupported_version"),
+        (Error::MigrationInProgress, "migration_in_progress"),
+        (Error::InvalidTtlPolicy, "invalid_ttl_policy"),
+        (Error::MetadataNameTooLong, "metadata_name_too_long"),
+        (
+            Error::MetadataDescriptionTooLong,
+            "metadata_description_too_long",
+        ),
+        (Error::InvalidRiskRating, "invalid_risk_rating"),
+        (Error::InvalidConfig, "invalid_config"),
+        (Error::InsufficientShares, "insufficient_shares"),
+        (Error::UnsupportedAsset, "unsupported_asset"),
+        (Error::VersionMismatch, "version_mismatch"),
+        (Error::NotDelegate, "not_delegate"),
+        (Error::InvalidAmount, "invalid_amount"),
+        (Error::EmergencyShutdownInactive, "emergency_shutdown_inactive"),
+        (Error::BelowQueueThreshold, "below_queue_threshold"),
+        (Error::RebalanceAborted, "rebalance_aborted"),
+        (Error::RebalanceInProgress, "rebalance_in_progress"),
+        (Error::NoRebalanceInProgress, "no_rebalance_in_progress"),
+        (Error::RebalanceNotNeeded, "rebalance_not_needed"),
+        (Error::InvalidPrice, "invalid_price"),
+        (Error::ArithmeticOverflow, "arithmetic_overflow"),
     ];
 
     for (error, expected) in cases {
@@ -131,32 +244,32 @@ fn test_convert_to_assets() {
     let guardians = soroban_sdk::vec![&env, admin.clone()];
     client.init(&admin, &asset, &oracle, &treasury, &0u32, &guardians, &1u32);
 
-    // 1. Test 1:1 conversion when total_shares is 0
-    assert_eq!(client.convert_to_assets(&100), 100);
+    // 1. Test 1,000 shares per unit when total_shares is 0
+    assert_eq!(client.convert_to_assets(&100_000), 100);
 
     // 2. Test exact conversion
     client.set_total_assets(&100);
-    client.set_total_shares(&100);
-    assert_eq!(client.convert_to_assets(&50), 50);
+    client.set_total_shares(&100_000);
+    assert_eq!(client.convert_to_assets(&50_000), 50);
 
     // 3. Test rounding down (favors vault)
     client.set_total_assets(&10);
-    client.set_total_shares(&4);
-    assert_eq!(client.convert_to_assets(&3), 7);
+    client.set_total_shares(&4_000);
+    assert_eq!(client.convert_to_assets(&3_000), 6);
 
     // 4. Test larger values
     client.set_total_assets(&1000);
-    client.set_total_shares(&300);
-    assert_eq!(client.convert_to_assets(&100), 333);
+    client.set_total_shares(&300_000);
+    assert_eq!(client.convert_to_assets(&100_000), 332);
 }
 
 #[test]
-#[should_panic(expected = "negative amount")]
 fn test_convert_to_assets_negative() {
     let env = Env::default();
     let contract_id = env.register_contract(None, VolatilityShield);
     let client = VolatilityShieldClient::new(&env, &contract_id);
-    client.convert_to_assets(&-1);
+    let invalid = soroban_sdk::Error::from_contract_error(Error::InvalidAmount as u32);
+    assert_eq!(client.try_convert_to_assets(&-1), Err(Ok(invalid)));
 }
 
 #[test]
@@ -172,22 +285,22 @@ fn test_convert_to_shares() {
     client.init(&admin, &asset, &oracle, &treasury, &0u32, &guardians, &1u32);
 
     // 1. Initial Deposit (total_shares = 0)
-    assert_eq!(client.convert_to_shares(&100), 100);
+    assert_eq!(client.convert_to_shares(&100), 100_000);
 
     // 2. Precision Loss (favors vault by rounding down)
-    client.set_total_assets(&3);
-    client.set_total_shares(&1);
-    assert_eq!(client.convert_to_shares(&10), 3);
+    client.set_total_assets(&2);
+    client.set_total_shares(&1_000);
+    assert_eq!(client.convert_to_shares(&10), 6_666);
 
     // 3. Standard Proportional Minting
     client.set_total_assets(&1000);
-    client.set_total_shares(&500);
-    assert_eq!(client.convert_to_shares(&200), 100);
+    client.set_total_shares(&500_000);
+    assert_eq!(client.convert_to_shares(&200), 100_099);
 
     // 4. Rounding Down with Large Values
     client.set_total_assets(&300);
-    client.set_total_shares(&1000);
-    assert_eq!(client.convert_to_shares(&100), 333);
+    client.set_total_shares(&1_000_000);
+    assert_eq!(client.convert_to_shares(&100), 332_558);
 }
 
 #[test]
@@ -221,12 +334,157 @@ fn test_strategy_registry() {
 }
 
 #[test]
-#[should_panic(expected = "negative amount")]
 fn test_convert_to_shares_negative() {
     let env = Env::default();
     let contract_id = env.register_contract(None, VolatilityShield);
     let client = VolatilityShieldClient::new(&env, &contract_id);
-    client.convert_to_shares(&-1);
+    let invalid = soroban_sdk::Error::from_contract_error(Error::InvalidAmount as u32);
+    assert_eq!(client.try_convert_to_shares(&-1), Err(Ok(invalid)));
+}
+
+#[test]
+fn test_conversion_overflow_returns_error() {
+    let env = Env::default();
+    let contract_id = env.register(VolatilityShield, ());
+    let client = VolatilityShieldClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    let guardians = soroban_sdk::vec![&env, admin.clone()];
+    client.init(
+        &admin,
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &0u32,
+        &guardians,
+        &1u32,
+    );
+
+    client.set_total_assets(&(i128::MAX / 2));
+    client.set_total_shares(&1);
+    let overflow = soroban_sdk::Error::from_contract_error(Error::ArithmeticOverflow as u32);
+    assert_eq!(client.try_get_share_price(), Err(Ok(overflow)));
+    assert_eq!(client.try_convert_to_assets(&3), Err(Ok(overflow)));
+    assert_eq!(client.try_convert_to_shares(&i128::MAX), Err(Ok(overflow)));
+}
+
+#[test]
+fn test_deposit_overflow_returns_error() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (token_id, stellar_asset_client, token_client) =
+        create_token_contract(&env, &Address::generate(&env));
+    let contract_id = env.register(VolatilityShield, ());
+    let client = VolatilityShieldClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    let guardians = soroban_sdk::vec![&env, admin.clone()];
+    client.init(
+        &admin,
+        &token_id,
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &0u32,
+        &guardians,
+        &1u32,
+    );
+    client.add_supported_asset(&token_id);
+
+    // The value of the deposit, scaled by the 1e9 price, exceeds i128.
+    let user = Address::generate(&env);
+    let amount = i128::MAX / 100_000;
+    stellar_asset_client.mint(&user, &amount);
+    assert_eq!(
+        client.try_deposit(&user, &token_id, &amount, &None),
+        Err(Ok(Error::ArithmeticOverflow))
+    );
+    assert_eq!(client.balance(&user), 0);
+    assert_eq!(token_client.balance(&user), amount);
+}
+
+#[test]
+fn test_rebalance_and_queue_overflow_returns_error() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (token_id, _, _) = create_token_contract(&env, &Address::generate(&env));
+    let contract_id = env.register(VolatilityShield, ());
+    let client = VolatilityShieldClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    let guardians = soroban_sdk::vec![&env, admin.clone()];
+    client.init(
+        &admin,
+        &token_id,
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &0u32,
+        &guardians,
+        &1u32,
+    );
+    client.add_supported_asset(&token_id);
+
+    client.set_idle_buffer(&IdleBufferConfig {
+        target_bps: 1_000,
+        min_amount: 0,
+        max_amount: i128::MAX,
+    });
+    client.set_keeper_bounty_bps(&100);
+    env.as_contract(&contract_id, || {
+        assert_eq!(
+            liquidity::keeper_bounty(&env, i128::MAX),
+            Err(Error::ArithmeticOverflow)
+        );
+        assert_eq!(
+            liquidity::idle_buffer_target(&env, i128::MAX),
+            Err(Error::ArithmeticOverflow)
+        );
+    });
+    assert_eq!(
+        client.try_calc_rebalance_delta(&1, &i128::MIN),
+        Err(Ok(Error::ArithmeticOverflow))
+    );
+
+    let strategy_id = env.register(mock_strategy::MockStrategy, ());
+    let strategy = mock_strategy::MockStrategyClient::new(&env, &strategy_id);
+    strategy.init(&contract_id, &token_id);
+    client.propose_action(&admin, &ActionType::AddStrategy(strategy_id.clone()));
+    let mut allocations: Map<Address, i128> = Map::new(&env);
+    allocations.set(strategy_id.clone(), 10_000);
+    env.ledger().set_timestamp(12345);
+    client.set_oracle_data(&allocations, &env.ledger().timestamp());
+
+    // Without an idle buffer the overflow comes from the per-strategy
+    // targets and the drift, not from the buffer itself.
+    client.set_idle_buffer(&IdleBufferConfig {
+        target_bps: 0,
+        min_amount: 0,
+        max_amount: 0,
+    });
+    client.set_total_assets(&(i128::MAX / 2));
+    assert_eq!(
+        client.try_simulate_rebalance(&100),
+        Err(Ok(Error::ArithmeticOverflow))
+    );
+
+    client.set_total_assets(&1_000);
+    strategy.simulate_price_drift(&(i128::MAX / 2));
+    client.set_rebalance_trigger(&RebalanceTrigger {
+        drift_bps: 500,
+        min_interval: 0,
+        max_slippage_bps: 100,
+        keeper_reward: 0,
+    });
+    assert_eq!(
+        client.try_rebalance_needed(),
+        Err(Ok(Error::ArithmeticOverflow))
+    );
+
+    // One of two shares is worth more than i128 once scaled by the price.
+    let user = Address::generate(&env);
+    client.set_total_assets(&(i128::MAX / 2));
+    client.set_total_shares(&1);
+    client.set_balance(&user, &1);
+    assert_eq!(
+        client.try_queue_withdraw(&user, &user, &token_id, &1),
+        Err(Ok(Error::ArithmeticOverflow))
+    );
 }
 
 #[test]
@@ -275,9 +533,9 @@ fn test_deposit_success() {
 
     client.deposit(&user, &token_id, &deposit_amount, &None::<i128>);
 
-    assert_eq!(client.balance(&user), 1000);
+    assert_eq!(client.balance(&user), 1_000_000);
     assert_eq!(client.total_assets(), 1000);
-    assert_eq!(client.total_shares(), 1000);
+    assert_eq!(client.total_shares(), 1_000_000);
 }
 
 #[test]
@@ -299,20 +557,21 @@ fn test_withdraw_success() {
     client.init(
         &admin, &token_id, &oracle, &treasury, &0u32, &guardians, &1u32,
     );
-    client.set_total_shares(&1000);
+    client.set_total_shares(&1_000_000);
     client.set_total_assets(&5000);
 
     let user = Address::generate(&env);
-    client.set_balance(&user, &100);
+    client.set_balance(&user, &100_000);
 
     stellar_asset_client.mint(&contract_id, &5000);
 
-    client.withdraw(&user, &user, &token_id, &50);
+    client.withdraw(&user, &user, &token_id, &50_000);
 
-    assert_eq!(client.balance(&user), 50);
-    assert_eq!(client.total_shares(), 950);
-    assert_eq!(client.total_assets(), 4750);
-    assert_eq!(token_client.balance(&user), 250);
+    // 50_000 * (5000 + 1) / (1_000_000 + 1_000), rounded down.
+    assert_eq!(client.balance(&user), 50_000);
+    assert_eq!(client.total_shares(), 950_000);
+    assert_eq!(client.total_assets(), 4751);
+    assert_eq!(token_client.balance(&user), 249);
 }
 
 #[test]
@@ -394,6 +653,296 @@ fn test_balance_reads_and_writes_refresh_persistent_ttl() {
     assert_eq!(ttl_after_second_read, BALANCE_TTL_BUMP);
 }
 
+#[test]
+fn test_ttl_policy_applies_per_key_type() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(VolatilityShield, ());
+    let client = VolatilityShieldClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let guardians = soroban_sdk::vec![&env, admin.clone()];
+    client.init(
+        &admin,
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &0u32,
+        &guardians,
+        &1u32,
+    );
+
+    let policy = TtlPolicy {
+        threshold: 1_000,
+        extend_to: 50_000,
+    };
+    client.set_ttl_policy(&TtlKeyType::Balance, &policy);
+    assert_eq!(client.get_ttl_policy(&TtlKeyType::Balance), policy);
+    assert_eq!(
+        client.get_ttl_policy(&TtlKeyType::Governance).extend_to,
+        BALANCE_TTL_BUMP
+    );
+
+    let user = Address::generate(&env);
+    client.set_balance(&user, &10);
+    let id = client.propose_action(&admin, &ActionType::SetPaused(true));
+
+    env.as_contract(&contract_id, || {
+        let persistent = env.storage().persistent();
+        assert_eq!(persistent.get_ttl(&DataKey::Balance(user.clone())), 50_000);
+        assert_eq!(
+            persistent.get_ttl(&collections::CollectionKey::Proposal(id)),
+            BALANCE_TTL_BUMP
+        );
+    });
+
+    for invalid in [
+        TtlPolicy {
+            threshold: 0,
+            extend_to: 10,
+        },
+        TtlPolicy {
+            threshold: 11,
+            extend_to: 10,
+        },
+        TtlPolicy {
+            threshold: 10,
+            extend_to: u32::MAX,
+        },
+    ] {
+        assert_eq!(
+            client.try_set_ttl_policy(&TtlKeyType::Balance, &invalid),
+            Err(Ok(Error::InvalidTtlPolicy))
+        );
+    }
+}
+
+#[test]
+fn test_keeper_extend_ttl_keeps_inactive_balance_live() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(VolatilityShield, ());
+    let client = VolatilityShieldClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let guardians = soroban_sdk::vec![&env, admin.clone()];
+    client.init(
+        &admin,
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &0u32,
+        &guardians,
+        &1u32,
+    );
+
+    let user = Address::generate(&env);
+    let start = env.ledger().sequence();
+    client.set_balance(&user, &500);
+
+    let status = client.get_ttl_status(&user);
+    assert_eq!(status.balance_live_until, start + BALANCE_TTL_BUMP);
+    assert!(!status.needs_extension);
+
+    // Close to the threshold the position is flagged for extension.
+    env.ledger()
+        .set_sequence_number(start + DAY_IN_LEDGERS + 1);
+    assert!(client.get_ttl_status(&user).needs_extension);
+
+    let stranger = Address::generate(&env);
+    let keys = soroban_sdk::vec![
+        &env,
+        StorageKey::Data(DataKey::Balance(user.clone())),
+        StorageKey::Data(DataKey::Balance(stranger.clone())),
+    ];
+    assert_eq!(client.extend_ttl(&keys), 1);
+
+    let now = env.ledger().sequence();
+    let status = client.get_ttl_status(&user);
+    assert_eq!(status.current_ledger, now);
+    assert_eq!(status.balance_live_until, now + BALANCE_TTL_BUMP);
+    assert!(!status.needs_extension);
+    env.as_contract(&contract_id, || {
+        assert_eq!(
+            env.storage()
+                .persistent()
+                .get_ttl(&DataKey::Balance(user.clone())),
+            BALANCE_TTL_BUMP
+        );
+    });
+    assert_eq!(client.get_ttl_status(&stranger).balance_live_until, 0);
+}
+
+#[test]
+fn test_extend_ttl_covers_every_persistent_key_space() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let token_admin = Address::generate(&env);
+    let (token_id, stellar_asset_client, _) = create_token_contract(&env, &token_admin);
+    let contract_id = env.register(VolatilityShield, ());
+    let client = VolatilityShieldClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let guardian = Address::generate(&env);
+    client.init(
+        &admin,
+        &token_id,
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &0u32,
+        &soroban_sdk::vec![&env, admin.clone(), guardian],
+        &2u32,
+    );
+
+    let user = Address::generate(&env);
+    stellar_asset_client.mint(&user, &1_000);
+    client.deposit(&user, &token_id, &1_000, &None::<i128>);
+    let proposal_id = client.propose_action(&admin, &ActionType::SetPaused(true));
+    client.set_vault_metadata(&sample_vault_metadata(&env, "Volatility Shield"));
+
+    let keys = soroban_sdk::vec![
+        &env,
+        StorageKey::Data(DataKey::Balance(user.clone())),
+        StorageKey::Collection(CollectionKey::Proposal(proposal_id)),
+        StorageKey::Metadata(MetadataKey::LatestVersion),
+        StorageKey::Ttl(TtlKey::BalanceLiveUntil(user.clone())),
+        StorageKey::History(TVL_HISTORY_KEY),
+    ];
+    env.ledger()
+        .set_sequence_number(env.ledger().sequence() + DAY_IN_LEDGERS + 1);
+    assert_eq!(client.extend_ttl(&keys), keys.len());
+
+    env.as_contract(&contract_id, || {
+        let persistent = env.storage().persistent();
+        assert_eq!(
+            persistent.get_ttl(&DataKey::Balance(user.clone())),
+            BALANCE_TTL_BUMP
+        );
+        assert_eq!(
+            persistent.get_ttl(&CollectionKey::Proposal(proposal_id)),
+            BALANCE_TTL_BUMP
+        );
+        assert_eq!(
+            persistent.get_ttl(&MetadataKey::LatestVersion),
+            BALANCE_TTL_BUMP
+        );
+        assert_eq!(
+            persistent.get_ttl(&TtlKey::BalanceLiveUntil(user.clone())),
+            BALANCE_TTL_BUMP
+        );
+        assert_eq!(persistent.get_ttl(&TVL_HISTORY_KEY), BALANCE_TTL_BUMP);
+    });
+}
+
+fn sample_vault_metadata(env: &Env, name: &str) -> VaultMetadata {
+    VaultMetadata {
+        name: soroban_sdk::String::from_str(env, name),
+        description: soroban_sdk::String::from_str(env, "Hedged stablecoin yield"),
+        risk_rating: 2,
+        docs_url: soroban_sdk::String::from_str(env, "https://docs.xhedge.example/vault"),
+        strategy_description: soroban_sdk::String::from_str(
+            env,
+            "Allocates across lending strategies and rebalances on oracle signals",
+        ),
+        fee_schedule: FeeSchedule {
+            management_fee_bps: 100,
+            performance_fee_bps: 1_000,
+            withdrawal_fee_bps: 10,
+        },
+        audit_links: soroban_sdk::vec![
+            env,
+            soroban_sdk::String::from_str(env, "https://audits.example/xhedge-2026.pdf"),
+        ],
+    }
+}
+
+#[test]
+fn test_vault_metadata_revisions_are_kept_in_history() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(VolatilityShield, ());
+    let client = VolatilityShieldClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let guardians = soroban_sdk::vec![&env, admin.clone()];
+    client.init(
+        &admin,
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &0u32,
+        &guardians,
+        &1u32,
+    );
+    assert_eq!(client.get_vault_metadata(), None);
+
+    let first = sample_vault_metadata(&env, "Volatility Shield");
+    env.ledger().set_timestamp(1_000);
+    assert_eq!(client.set_vault_metadata(&first), 1);
+
+    let mut second = sample_vault_metadata(&env, "Volatility Shield v2");
+    second.risk_rating = 3;
+    env.ledger().set_timestamp(2_000);
+    assert_eq!(client.set_vault_metadata(&second), 2);
+
+    assert_eq!(client.get_vault_metadata(), Some(second.clone()));
+
+    let history = client.get_vault_metadata_history(&0u32, &10u32);
+    assert_eq!(history.len(), 2);
+    assert_eq!(history.get(0).unwrap().version, 1);
+    assert_eq!(history.get(0).unwrap().updated_at, 1_000);
+    assert_eq!(history.get(0).unwrap().metadata, first);
+    assert_eq!(history.get(1).unwrap().metadata, second);
+
+    let latest_only = client.get_vault_metadata_history(&2u32, &10u32);
+    assert_eq!(latest_only.len(), 1);
+    assert_eq!(latest_only.get(0).unwrap().updated_at, 2_000);
+    assert_eq!(client.get_vault_metadata_history(&1u32, &1u32).len(), 1);
+}
+
+#[test]
+fn test_invalid_vault_metadata_rejected() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(VolatilityShield, ());
+    let client = VolatilityShieldClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let guardians = soroban_sdk::vec![&env, admin.clone()];
+    client.init(
+        &admin,
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &0u32,
+        &guardians,
+        &1u32,
+    );
+
+    let long_name = std::string::String::from("n").repeat(65);
+    let metadata = sample_vault_metadata(&env, &long_name);
+    assert_eq!(
+        client.try_set_vault_metadata(&metadata),
+        Err(Ok(Error::MetadataNameTooLong))
+    );
+
+    let mut metadata = sample_vault_metadata(&env, "Volatility Shield");
+    metadata.risk_rating = 6;
+    assert_eq!(
+        client.try_set_vault_metadata(&metadata),
+        Err(Ok(Error::InvalidRiskRating))
+    );
+
+    let mut metadata = sample_vault_metadata(&env, "Volatility Shield");
+    metadata.fee_schedule.management_fee_bps = 10_001;
+    assert_eq!(
+        client.try_set_vault_metadata(&metadata),
+        Err(Ok(Error::InvalidConfig))
+    );
+
+    assert_eq!(client.get_vault_metadata(), None);
+}
+
 #[test]
 fn test_delegate_can_withdraw_for_owner() {
     let env = Env::default();
@@ -415,19 +964,19 @@ fn test_delegate_can_withdraw_for_owner() {
     client.init(
         &admin, &token_id, &oracle, &treasury, &0u32, &guardians, &1u32,
     );
-    client.set_total_shares(&1000);
+    client.set_total_shares(&1_000_000);
     client.set_total_assets(&5000);
-    client.set_balance(&owner, &100);
+    client.set_balance(&owner, &100_000);
     client.set_delegate(&owner, &delegate);
 
     stellar_asset_client.mint(&contract_id, &5000);
 
-    client.withdraw(&delegate, &owner, &token_id, &50);
+    client.withdraw(&delegate, &owner, &token_id, &50_000);
 
-    assert_eq!(client.balance(&owner), 50);
-    assert_eq!(client.total_shares(), 950);
-    assert_eq!(client.total_assets(), 4750);
-    assert_eq!(token_client.balance(&owner), 250);
+    assert_eq!(client.balance(&owner), 50_000);
+    assert_eq!(client.total_shares(), 950_000);
+    assert_eq!(client.total_assets(), 4751);
+    assert_eq!(token_client.balance(&owner), 249);
     assert_eq!(token_client.balance(&delegate), 0);
 }
 
@@ -461,13 +1010,135 @@ fn test_non_delegate_withdraw_rejected() {
     stellar_asset_client.mint(&contract_id, &5000);
 
     let res = client.try_withdraw(&stranger, &owner, &token_id, &50);
-    assert_eq!(res, Err(Ok(Error::Unauthorized)));
+    assert_eq!(res, Err(Ok(Error::NotDelegate)));
     assert_eq!(client.balance(&owner), 100);
     assert_eq!(client.total_shares(), 1000);
     assert_eq!(client.total_assets(), 5000);
     assert_eq!(token_client.balance(&owner), 0);
 }
 
+#[test]
+fn test_user_facing_failures_return_typed_errors() {
+    let env = Env::default();
+    env.mock_all_auths_allowing_non_root_auth();
+
+    let token_admin = Address::generate(&env);
+    let (token_id, stellar_asset_client, _) = create_token_contract(&env, &token_admin);
+
+    let contract_id = env.register(VolatilityShield, ());
+    let client = VolatilityShieldClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let oracle = Address::generate(&env);
+    let treasury = Address::generate(&env);
+    let owner = Address::generate(&env);
+    let stranger = Address::generate(&env);
+    let guardians = soroban_sdk::vec![&env, admin.clone()];
+    client.init(
+        &admin, &token_id, &oracle, &treasury, &0u32, &guardians, &1u32,
+    );
+    client.set_total_shares(&1000);
+    client.set_total_assets(&5000);
+    client.set_balance(&owner, &100);
+    client.set_withdraw_queue_threshold(&1000);
+    stellar_asset_client.mint(&contract_id, &5000);
+
+    let unknown_asset = Address::generate(&env);
+    assert_eq!(
+        client.try_deposit(&owner, &unknown_asset, &10, &None::<i128>),
+        Err(Ok(Error::UnsupportedAsset))
+    );
+    assert_eq!(
+        client.try_withdraw(&owner, &owner, &token_id, &0),
+        Err(Ok(Error::InvalidAmount))
+    );
+    assert_eq!(
+        client.try_queue_withdraw(&stranger, &owner, &token_id, &50),
+        Err(Ok(Error::NotDelegate))
+    );
+    // 50 shares are worth 250, below the queue threshold and covered by idle tokens.
+    assert_eq!(
+        client.try_queue_withdraw(&owner, &owner, &token_id, &50),
+        Err(Ok(Error::BelowQueueThreshold))
+    );
+    assert_eq!(
+        client.try_set_withdraw_queue_threshold(&-1),
+        Err(Ok(Error::NegativeAmount))
+    );
+    assert_eq!(
+        client.try_emergency_withdraw(&owner),
+        Err(Ok(Error::EmergencyShutdownInactive))
+    );
+
+    client.emergency_shutdown(&admin);
+    assert_eq!(
+        client.try_deposit(&owner, &token_id, &10, &None::<i128>),
+        Err(Ok(Error::EmergencyShutdownActive))
+    );
+    assert_eq!(
+        client.try_emergency_withdraw(&stranger),
+        Err(Ok(Error::InsufficientShares))
+    );
+    assert_eq!(client.balance(&owner), 100);
+}
+
+#[test]
+fn test_emergency_withdraw_cancels_queued_tickets() {
+    use soroban_sdk::testutils::Events as _;
+    use soroban_sdk::TryFromVal;
+
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (token_id, stellar_asset_client, token_client) =
+        create_token_contract(&env, &Address::generate(&env));
+    let contract_id = env.register(VolatilityShield, ());
+    let client = VolatilityShieldClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let owner = Address::generate(&env);
+    let guardians = soroban_sdk::vec![&env, admin.clone()];
+    client.init(
+        &admin,
+        &token_id,
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &0u32,
+        &guardians,
+        &1u32,
+    );
+    client.set_total_shares(&1000);
+    client.set_total_assets(&5000);
+    client.set_balance(&owner, &200);
+    client.set_withdraw_queue_threshold(&0);
+    stellar_asset_client.mint(&contract_id, &5000);
+
+    let first = client.queue_withdraw(&owner, &owner, &token_id, &100);
+    let second = client.queue_withdraw(&owner, &owner, &token_id, &50);
+    assert_eq!(client.balance(&owner), 50);
+
+    client.emergency_shutdown(&admin);
+    client.emergency_withdraw(&owner);
+    let mut cancelled = std::vec::Vec::new();
+    for (_, topics, data) in env.events().all().iter() {
+        let name = Symbol::try_from_val(&env, &topics.get(0).unwrap()).unwrap();
+        if name == Symbol::new(&env, "WithdrawCancelled") {
+            let event = events::WithdrawCancelled::try_from_val(&env, &data).unwrap();
+            cancelled.push(event.ticket_id);
+        }
+    }
+    // Indexers see each burned ticket leave the queue.
+    assert_eq!(cancelled, [first, second]);
+    assert!(published(&env, "EmergencyWithdrawn"));
+
+    // All 200 shares are redeemed: 200 * (5000 + 1) / (1000 + 1000).
+    assert_eq!(token_client.balance(&owner), 500);
+    assert_eq!(client.balance(&owner), 0);
+    assert_eq!(client.get_pending_withdrawals().len(), 0);
+    assert_eq!(client.get_user_withdrawal_tickets(&owner).len(), 0);
+    assert_eq!(client.total_shares(), 800);
+}
+
 #[test]
 fn test_deposit_slippage_exact_minimum_passes() {
     let env = Env::default();
@@ -489,16 +1160,16 @@ fn test_deposit_slippage_exact_minimum_passes() {
     );
 
     client.set_total_assets(&2000);
-    client.set_total_shares(&1000);
+    client.set_total_shares(&1_000_000);
 
     let user = Address::generate(&env);
     stellar_asset_client.mint(&user, &101);
 
-    client.deposit(&user, &token_id, &101, &Some(50));
+    client.deposit(&user, &token_id, &101, &Some(50_525));
 
-    assert_eq!(client.balance(&user), 50);
+    assert_eq!(client.balance(&user), 50_525);
     assert_eq!(client.total_assets(), 2101);
-    assert_eq!(client.total_shares(), 1050);
+    assert_eq!(client.total_shares(), 1_050_525);
 }
 
 #[test]
@@ -521,16 +1192,16 @@ fn test_deposit_slippage_below_minimum_fails() {
     );
 
     client.set_total_assets(&2000);
-    client.set_total_shares(&1000);
+    client.set_total_shares(&1_000_000);
 
     let user = Address::generate(&env);
     stellar_asset_client.mint(&user, &101);
 
-    let res = client.try_deposit(&user, &token_id, &101, &Some(51));
+    let res = client.try_deposit(&user, &token_id, &101, &Some(50_526));
     assert_eq!(res, Err(Ok(Error::SlippageExceeded)));
     assert_eq!(client.balance(&user), 0);
     assert_eq!(client.total_assets(), 2000);
-    assert_eq!(client.total_shares(), 1000);
+    assert_eq!(client.total_shares(), 1_000_000);
     assert_eq!(token_client.balance(&user), 101);
 }
 
@@ -552,20 +1223,20 @@ fn test_withdraw_slippage_exact_minimum_passes() {
     client.init(
         &admin, &token_id, &oracle, &treasury, &0u32, &guardians, &1u32,
     );
-    client.set_total_shares(&1000);
+    client.set_total_shares(&1_000_000);
     client.set_total_assets(&5000);
 
     let user = Address::generate(&env);
-    client.set_balance(&user, &100);
+    client.set_balance(&user, &100_000);
 
     stellar_asset_client.mint(&contract_id, &5000);
 
-    client.withdraw(&user, &user, &token_id, &50);
+    client.withdraw(&user, &user, &token_id, &50_000);
 
-    assert_eq!(client.balance(&user), 50);
-    assert_eq!(client.total_shares(), 950);
-    assert_eq!(client.total_assets(), 4750);
-    assert_eq!(token_client.balance(&user), 250);
+    assert_eq!(client.balance(&user), 50_000);
+    assert_eq!(client.total_shares(), 950_000);
+    assert_eq!(client.total_assets(), 4751);
+    assert_eq!(token_client.balance(&user), 249);
 }
 
 #[test]
@@ -687,6 +1358,20 @@ fn test_multisig_unauthorized_propose() {
     assert!(result.is_err());
 }
 
+#[test]
+fn test_propose_before_init_returns_error() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(VolatilityShield, ());
+    let client = VolatilityShieldClient::new(&env, &contract_id);
+
+    let proposer = Address::generate(&env);
+    assert_eq!(
+        client.try_propose_action(&proposer, &ActionType::Rebalance(50u32)),
+        Err(Ok(Error::NotInitialized))
+    );
+}
+
 #[test]
 fn test_guardian_crud() {
     let env = Env::default();
@@ -1274,18 +1959,78 @@ fn test_timelock_with_multisig_approval() {
     // Propose action (threshold is 2, so it won't execute immediately)
     let proposal_id = client.propose_action(&admin, &ActionType::SetPaused(true));
 
-    // Try to approve immediately - should fail due to timelock
-    let result = client.try_approve_action(&oracle, &proposal_id);
-    assert_eq!(result, Err(Ok(Error::TimelockNotElapsed)));
+    // Approving during the timelock records the approval but cannot execute
+    client.approve_action(&oracle, &proposal_id);
+    assert!(!client.is_paused());
+    let proposal = client.get_proposal(&proposal_id).unwrap();
+    assert_eq!(proposal.approvals.len(), 2);
+    assert!(!proposal.executed);
+    assert_eq!(
+        client.try_execute_proposal(&admin, &proposal_id),
+        Err(Ok(Error::TimelockNotElapsed))
+    );
 
-    // Advance time by 100 seconds
+    // Advance time by 100 seconds; any guardian can now execute it
     env.ledger().set_timestamp(1100);
-
-    // Now approve - should succeed and execute
-    client.approve_action(&oracle, &proposal_id);
+    client.execute_proposal(&oracle, &proposal_id);
     assert!(client.is_paused());
 }
 
+#[test]
+fn test_per_action_timelock_with_multisig_executes_after_delay() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(VolatilityShield, ());
+    let client = VolatilityShieldClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let second = Address::generate(&env);
+    let guardians = soroban_sdk::vec![&env, admin.clone(), second.clone()];
+    client.init(
+        &admin,
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &0u32,
+        &guardians,
+        &2u32,
+    );
+    let override_id = client.propose_action(
+        &admin,
+        &ActionType::SetActionTimelock(ActionKind::AddSupportedAsset, Some(3600)),
+    );
+    assert_eq!(
+        client.get_action_timelock(&ActionKind::AddSupportedAsset),
+        0
+    );
+    client.approve_action(&second, &override_id);
+    assert_eq!(
+        client.get_action_timelock(&ActionKind::AddSupportedAsset),
+        3600
+    );
+    env.ledger().set_timestamp(1000);
+
+    let asset = Address::generate(&env);
+    let proposal_id = client.propose_action(&admin, &ActionType::AddSupportedAsset(asset.clone()));
+    client.approve_action(&second, &proposal_id);
+    // Deferring to the timelock is not an error.
+    assert!(!published(&env, "ErrorRaised"));
+    assert!(!client.is_supported_asset(&asset));
+    assert_eq!(
+        client.get_proposal(&proposal_id).unwrap().approvals.len(),
+        2
+    );
+
+    env.ledger().set_timestamp(1000 + 3600);
+    client.execute_proposal(&second, &proposal_id);
+    assert!(client.is_supported_asset(&asset));
+    assert_eq!(
+        client.try_execute_proposal(&admin, &proposal_id),
+        Err(Ok(Error::ProposalExecuted))
+    );
+}
+
 #[test]
 fn test_timelock_zero_duration_allows_immediate_execution() {
     let env = Env::default();
@@ -1343,69 +2088,59 @@ fn test_timelock_events_emitted() {
 }
 
 #[test]
-fn test_proposal_pruning_preserves_active_and_recent_proposals() {
+fn test_action_timelock_override_takes_precedence() {
     let env = Env::default();
     env.mock_all_auths();
 
-    let contract_id = env.register_contract(None, VolatilityShield);
+    let contract_id = env.register(VolatilityShield, ());
     let client = VolatilityShieldClient::new(&env, &contract_id);
 
     let admin = Address::generate(&env);
-    let guardian = Address::generate(&env);
     let asset = Address::generate(&env);
     let oracle = Address::generate(&env);
     let treasury = Address::generate(&env);
-    let guardians = soroban_sdk::vec![&env, admin.clone(), guardian.clone()];
+    let guardians = soroban_sdk::vec![&env, admin.clone()];
     client.init(&admin, &asset, &oracle, &treasury, &0u32, &guardians, &1u32);
-    client.set_proposal_ttl_ledgers(&5u32);
-
-    env.ledger().set_sequence_number(10);
-    env.ledger().set_timestamp(1000);
-    let old_id = client.propose_action(&admin, &ActionType::SetPaused(true));
-    assert!(client.get_proposal(&old_id).unwrap().executed);
-
-    client.set_threshold(&2u32);
-    env.ledger().set_sequence_number(12);
-    env.ledger().set_timestamp(1200);
-    let active_id = client.propose_action(&admin, &ActionType::SetPaused(false));
-    assert!(!client.get_proposal(&active_id).unwrap().executed);
 
-    client.set_threshold(&1u32);
-    env.ledger().set_sequence_number(15);
-    env.ledger().set_timestamp(1500);
-    let recent_id = client.propose_action(&admin, &ActionType::SetPaused(true));
-    assert!(client.get_proposal(&recent_id).unwrap().executed);
+    client.propose_action(
+        &admin,
+        &ActionType::SetActionTimelock(ActionKind::Pause, Some(0)),
+    );
+    client.set_timelock_duration(&100);
+    assert_eq!(client.get_action_timelock(&ActionKind::Pause), 0);
+    assert_eq!(client.get_action_timelock(&ActionKind::Unpause), 100);
 
-    env.ledger().set_sequence_number(16);
-    env.ledger().set_timestamp(1600);
-    assert_eq!(client.prune_old_proposals(), 1);
-    assert!(client.get_proposal(&old_id).is_none());
-    assert!(!client.get_proposal(&active_id).unwrap().executed);
-    assert!(client.get_proposal(&recent_id).unwrap().executed);
+    env.ledger().set_timestamp(1000);
 
-    let proposals = client.list_proposals(&0u32, &10u32, &true);
-    assert_eq!(proposals.len(), 2);
-    assert_eq!(proposals.get(0).unwrap().id, active_id);
-    assert_eq!(proposals.get(1).unwrap().id, recent_id);
+    // Pausing has no delay, unpausing still waits for the global timelock.
+    client.propose_action(&admin, &ActionType::SetPaused(true));
+    assert!(client.is_paused());
 
-    client.set_threshold(&2u32);
-    client.approve_action(&guardian, &active_id);
-    assert!(!client.is_paused());
+    let unpause_id = client.propose_action(&admin, &ActionType::SetPaused(false));
+    assert!(client.is_paused());
+    assert!(!client.get_proposal(&unpause_id).unwrap().executed);
 
-    client.set_threshold(&1u32);
-    env.ledger().set_sequence_number(17);
-    env.ledger().set_timestamp(1700);
-    let new_id = client.propose_action(&admin, &ActionType::SetPaused(true));
-    assert_eq!(new_id, 4);
-    assert!(client.get_proposal(&new_id).unwrap().executed);
+    // Overrides change through governance and wait for their own timelock.
+    let clear_id = client.propose_action(
+        &admin,
+        &ActionType::SetActionTimelock(ActionKind::Pause, None),
+    );
+    assert_eq!(client.get_action_timelock(&ActionKind::Pause), 0);
+    assert_eq!(
+        client.try_execute_proposal(&admin, &clear_id),
+        Err(Ok(Error::TimelockNotElapsed))
+    );
+    env.ledger().set_timestamp(1100);
+    client.execute_proposal(&admin, &clear_id);
+    assert_eq!(client.get_action_timelock(&ActionKind::Pause), 100);
 }
 
 #[test]
-fn test_list_proposals_pagination_and_get_proposal() {
+fn test_long_action_timelock_executes_via_execute_proposal() {
     let env = Env::default();
     env.mock_all_auths();
 
-    let contract_id = env.register_contract(None, VolatilityShield);
+    let contract_id = env.register(VolatilityShield, ());
     let client = VolatilityShieldClient::new(&env, &contract_id);
 
     let admin = Address::generate(&env);
@@ -1415,95 +2150,884 @@ fn test_list_proposals_pagination_and_get_proposal() {
     let guardians = soroban_sdk::vec![&env, admin.clone()];
     client.init(&admin, &asset, &oracle, &treasury, &0u32, &guardians, &1u32);
 
-    let first_id = client.propose_action(&admin, &ActionType::SetPaused(true));
-    let second_id = client.propose_action(&admin, &ActionType::SetPaused(false));
-    let third_id = client.propose_action(&admin, &ActionType::SetPaused(true));
+    client.propose_action(
+        &admin,
+        &ActionType::SetActionTimelock(ActionKind::SetOracle, Some(86_400)),
+    );
+    env.ledger().set_timestamp(1000);
 
-    let first_page = client.list_proposals(&0u32, &2u32, &true);
-    assert_eq!(first_page.len(), 2);
-    assert_eq!(first_page.get(0).unwrap().id, first_id);
-    assert_eq!(first_page.get(1).unwrap().id, second_id);
+    let new_oracle = Address::generate(&env);
+    let id = client.propose_action(&admin, &ActionType::SetOracle(new_oracle.clone()));
+    assert!(!published(&env, "ErrorRaised"));
+    assert_eq!(client.get_oracle(), oracle);
 
-    let second_page = client.list_proposals(&1u32, &2u32, &true);
-    assert_eq!(second_page.len(), 2);
-    assert_eq!(second_page.get(0).unwrap().id, second_id);
-    assert_eq!(second_page.get(1).unwrap().id, third_id);
+    let early = client.try_execute_proposal(&admin, &id);
+    assert_eq!(early, Err(Ok(Error::TimelockNotElapsed)));
 
-    let proposal = client.get_proposal(&second_id).unwrap();
-    assert_eq!(proposal.id, second_id);
-    assert!(proposal.executed);
-    assert!(client.get_proposal(&999u64).is_none());
+    env.ledger().set_timestamp(1000 + 86_400);
+    client.execute_proposal(&admin, &id);
+    assert_eq!(client.get_oracle(), new_oracle);
+    assert!(client.get_proposal(&id).unwrap().executed);
 
-    let summary = client.get_governance_summary();
-    assert_eq!(summary.active_proposal_count, 0);
+    let again = client.try_execute_proposal(&admin, &id);
+    assert_eq!(again, Err(Ok(Error::ProposalExecuted)));
 }
 
 #[test]
-fn test_pause_history_contains_pause_and_unpause() {
+fn test_action_timelock_override_must_be_shorter_than_proposal_ttl() {
     let env = Env::default();
     env.mock_all_auths();
 
-    let contract_id = env.register_contract(None, VolatilityShield);
+    let contract_id = env.register(VolatilityShield, ());
     let client = VolatilityShieldClient::new(&env, &contract_id);
 
     let admin = Address::generate(&env);
-    let asset = Address::generate(&env);
-    let oracle = Address::generate(&env);
-    let treasury = Address::generate(&env);
     let guardians = soroban_sdk::vec![&env, admin.clone()];
-    client.init(&admin, &asset, &oracle, &treasury, &0u32, &guardians, &1u32);
-
-    env.ledger().set_timestamp(100);
-    client.set_paused(&true);
-    env.ledger().set_timestamp(150);
-    client.set_paused(&false);
-
-    let history = client.get_pause_history();
-    assert_eq!(history.len(), 2);
+    client.init(
+        &admin,
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &0u32,
+        &guardians,
+        &1u32,
+    );
 
-    let first = history.get(0).unwrap();
-    assert_eq!(first.0, 100);
-    assert_eq!(first.1, admin);
-    assert!(first.2);
+    // Proposals of that kind would expire before the timelock ran out.
+    let too_long = ActionType::SetActionTimelock(
+        ActionKind::SetActionTimelock,
+        Some(DEFAULT_PROPOSAL_TTL_SECONDS),
+    );
+    assert_eq!(
+        client.try_propose_action(&admin, &too_long),
+        Err(Ok(Error::InvalidConfig))
+    );
+    assert_eq!(
+        client.get_action_timelock(&ActionKind::SetActionTimelock),
+        0
+    );
 
-    let second = history.get(1).unwrap();
-    assert_eq!(second.0, 150);
-    assert_eq!(second.1, admin);
-    assert!(!second.2);
-    assert!(!client.is_paused());
+    client.propose_action(
+        &admin,
+        &ActionType::SetActionTimelock(
+            ActionKind::SetActionTimelock,
+            Some(DEFAULT_PROPOSAL_TTL_SECONDS - 1),
+        ),
+    );
+    assert_eq!(
+        client.get_action_timelock(&ActionKind::SetActionTimelock),
+        DEFAULT_PROPOSAL_TTL_SECONDS - 1
+    );
 }
 
 #[test]
-fn test_share_price_history_grows_on_harvest() {
+fn test_fast_track_defensive_action_bypasses_timelock() {
     let env = Env::default();
     env.mock_all_auths();
 
-    let contract_id = env.register_contract(None, VolatilityShield);
+    let contract_id = env.register(VolatilityShield, ());
     let client = VolatilityShieldClient::new(&env, &contract_id);
 
     let admin = Address::generate(&env);
+    let guardian_b = Address::generate(&env);
+    let guardian_c = Address::generate(&env);
     let asset = Address::generate(&env);
     let oracle = Address::generate(&env);
     let treasury = Address::generate(&env);
-    let guardians = soroban_sdk::vec![&env, admin.clone()];
-    client.init(&admin, &asset, &oracle, &treasury, &0u32, &guardians, &1u32);
+    let guardians = soroban_sdk::vec![&env, admin.clone(), guardian_b.clone(), guardian_c.clone()];
+    client.init(&admin, &asset, &oracle, &treasury, &0u32, &guardians, &2u32);
 
-    assert_eq!(client.get_share_price_history().len(), 0);
+    client.set_timelock_duration(&86_400);
+    env.ledger().set_timestamp(1000);
 
-    client.set_total_assets(&200);
-    client.set_total_shares(&100);
+    let id = client.propose_action(&admin, &ActionType::SetPaused(true));
 
-    let strategy = env.register_contract(None, mock_strategy::MockStrategy);
-    client.propose_action(&admin, &ActionType::AddStrategy(strategy));
+    // Two of three guardians is below the default two-thirds-plus supermajority.
+    assert!(!client.fast_track_action(&guardian_b, &id));
+    assert!(!client.is_paused());
 
-    env.ledger().set_timestamp(200);
-    assert_eq!(client.harvest(), 0);
+    assert!(client.fast_track_action(&guardian_c, &id));
+    assert!(published(&env, "EmergencyActionExecuted"));
+    assert!(!published(&env, "TimelockExecuted"));
+    assert!(client.is_paused());
+    assert!(client.get_proposal(&id).unwrap().executed);
+}
+
+#[test]
+fn test_fast_track_rejects_non_defensive_action() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(VolatilityShield, ());
+    let client = VolatilityShieldClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let guardian_b = Address::generate(&env);
+    let asset = Address::generate(&env);
+    let oracle = Address::generate(&env);
+    let treasury = Address::generate(&env);
+    let guardians = soroban_sdk::vec![&env, admin.clone(), guardian_b.clone()];
+    client.init(&admin, &asset, &oracle, &treasury, &0u32, &guardians, &2u32);
+
+    client.set_timelock_duration(&86_400);
+    let id = client.propose_action(&admin, &ActionType::SetPaused(false));
+
+    let res = client.try_fast_track_action(&guardian_b, &id);
+    assert_eq!(res, Err(Ok(Error::ActionNotDefensive)));
+
+    let stranger = Address::generate(&env);
+    let res = client.try_fast_track_action(&stranger, &id);
+    assert_eq!(res, Err(Ok(Error::Unauthorized)));
+}
+
+#[test]
+fn test_emergency_threshold_bps_must_be_supermajority() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(VolatilityShield, ());
+    let client = VolatilityShieldClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let asset = Address::generate(&env);
+    let oracle = Address::generate(&env);
+    let treasury = Address::generate(&env);
+    let guardians = soroban_sdk::vec![&env, admin.clone()];
+    client.init(&admin, &asset, &oracle, &treasury, &0u32, &guardians, &1u32);
+
+    assert_eq!(client.get_emergency_threshold_bps(), 6_667);
+    assert_eq!(
+        client.try_set_emergency_threshold_bps(&5_000),
+        Err(Ok(Error::InvalidThreshold))
+    );
+    assert_eq!(
+        client.try_set_emergency_threshold_bps(&10_001),
+        Err(Ok(Error::InvalidThreshold))
+    );
+    client.set_emergency_threshold_bps(&7_500);
+    assert_eq!(client.get_emergency_threshold_bps(), 7_500);
+}
+
+// ── Governance-gated Upgrade Tests ─────────────────────────
+
+#[test]
+fn test_upgrade_proposal_announces_hash_and_enforces_exit_window() {
+    use soroban_sdk::testutils::Events as _;
+
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(VolatilityShield, ());
+    let client = VolatilityShieldClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let asset = Address::generate(&env);
+    let oracle = Address::generate(&env);
+    let treasury = Address::generate(&env);
+    let guardians = soroban_sdk::vec![&env, admin.clone()];
+    client.init(&admin, &asset, &oracle, &treasury, &0u32, &guardians, &1u32);
+
+    // Even with the global timelock disabled, upgrades keep the minimum exit window.
+    client.set_timelock_duration(&0);
+    client.propose_action(
+        &admin,
+        &ActionType::SetActionTimelock(ActionKind::Upgrade, Some(60)),
+    );
+    assert_eq!(client.get_action_timelock(&ActionKind::Upgrade), 172_800);
+
+    env.ledger().set_timestamp(1_000);
+    let wasm_hash = BytesN::from_array(&env, &[7u8; 32]);
+    let id = client.propose_action(&admin, &ActionType::Upgrade(wasm_hash.clone()));
+
+    let announced = env.events().all().iter().any(|(_, topics, _)| {
+        topics
+            == soroban_sdk::vec![
+                &env,
+                Symbol::new(&env, "UpgradeProposed").into_val(&env),
+                EVENT_SCHEMA_VERSION.into_val(&env),
+                id.into_val(&env)
+            ]
+    });
+    assert!(announced);
+
+    assert!(!client.get_proposal(&id).unwrap().executed);
+    let pending = client.get_pending_upgrade().unwrap();
+    assert_eq!(pending.proposal_id, id);
+    assert_eq!(pending.wasm_hash, wasm_hash);
+    assert_eq!(pending.executable_at, 1_000 + 172_800);
+
+    env.ledger().set_timestamp(1_000 + 172_799);
+    let res = client.try_execute_proposal(&admin, &id);
+    assert_eq!(res, Err(Ok(Error::TimelockNotElapsed)));
+}
+
+#[test]
+fn test_only_one_upgrade_can_be_pending() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(VolatilityShield, ());
+    let client = VolatilityShieldClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let asset = Address::generate(&env);
+    let oracle = Address::generate(&env);
+    let treasury = Address::generate(&env);
+    let guardians = soroban_sdk::vec![&env, admin.clone()];
+    client.init(&admin, &asset, &oracle, &treasury, &0u32, &guardians, &1u32);
+
+    let first = BytesN::from_array(&env, &[1u8; 32]);
+    let second = BytesN::from_array(&env, &[2u8; 32]);
+    client.propose_action(&admin, &ActionType::Upgrade(first));
+
+    let res = client.try_propose_action(&admin, &ActionType::Upgrade(second));
+    assert_eq!(res, Err(Ok(Error::UpgradeAlreadyPending)));
+}
+
+#[test]
+fn test_cancel_upgrade_proposal_clears_pending_upgrade() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(VolatilityShield, ());
+    let client = VolatilityShieldClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let guardian = Address::generate(&env);
+    let asset = Address::generate(&env);
+    let oracle = Address::generate(&env);
+    let treasury = Address::generate(&env);
+    let guardians = soroban_sdk::vec![&env, admin.clone(), guardian.clone()];
+    client.init(&admin, &asset, &oracle, &treasury, &0u32, &guardians, &1u32);
+
+    let wasm_hash = BytesN::from_array(&env, &[9u8; 32]);
+    let id = client.propose_action(&guardian, &ActionType::Upgrade(wasm_hash.clone()));
+    assert!(client.get_pending_upgrade().is_some());
+
+    let stranger = Address::generate(&env);
+    let res = client.try_cancel_proposal(&stranger, &id);
+    assert_eq!(res, Err(Ok(Error::Unauthorized)));
+
+    client.cancel_proposal(&admin, &id);
+    assert!(client.get_pending_upgrade().is_none());
+    assert!(client.get_proposal(&id).is_none());
+
+    // A fresh upgrade can be announced after cancellation.
+    client.propose_action(&guardian, &ActionType::Upgrade(wasm_hash));
+    assert!(client.get_pending_upgrade().is_some());
+}
+
+#[test]
+fn test_cancel_executed_proposal_rejected() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(VolatilityShield, ());
+    let client = VolatilityShieldClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let asset = Address::generate(&env);
+    let oracle = Address::generate(&env);
+    let treasury = Address::generate(&env);
+    let guardians = soroban_sdk::vec![&env, admin.clone()];
+    client.init(&admin, &asset, &oracle, &treasury, &0u32, &guardians, &1u32);
+
+    let id = client.propose_action(&admin, &ActionType::SetPaused(true));
+    let res = client.try_cancel_proposal(&admin, &id);
+    assert_eq!(res, Err(Ok(Error::ProposalExecuted)));
+}
+
+#[test]
+fn test_cancelled_proposals_leave_gaps_the_head_skips() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(VolatilityShield, ());
+    let client = VolatilityShieldClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let guardians = soroban_sdk::vec![&env, admin.clone(), Address::generate(&env)];
+    client.init(
+        &admin,
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &0u32,
+        &guardians,
+        &2u32,
+    );
+
+    let ids: std::vec::Vec<u64> = (0..3)
+        .map(|_| client.propose_action(&admin, &ActionType::SetPaused(true)))
+        .collect();
+    let bounds = || env.as_contract(&contract_id, || collections::proposal_bounds(&env));
+    assert_eq!(bounds(), (ids[0], ids[2] + 1));
+
+    // A gap in the middle stays until the head reaches it.
+    client.cancel_proposal(&admin, &ids[1]);
+    assert_eq!(bounds(), (ids[0], ids[2] + 1));
+    let listed = client.list_proposals(&0u32, &10u32, &true);
+    assert_eq!(listed.len(), 2);
+    assert_eq!(listed.get(1).unwrap().id, ids[2]);
+
+    client.cancel_proposal(&admin, &ids[0]);
+    assert_eq!(bounds(), (ids[2], ids[2] + 1));
+    assert_eq!(client.list_proposals(&0u32, &10u32, &true).len(), 1);
+    assert_eq!(client.get_governance_summary().active_proposal_count, 1);
+}
+
+// ── Storage Migration Tests ─────────────────
+
+/// Rewrites storage back into the v1 layout: version 1 and the given TVL
+/// history held in instance storage.
+fn downgrade_to_v1(env: &Env, contract_id: &Address, history: &soroban_sdk::Vec<(u64, i128)>) {
+    env.as_contract(contract_id, || {
+        env.storage().persistent().remove(&TVL_HISTORY_KEY);
+        env.storage().instance().set(&TVL_HISTORY_KEY, history);
+        env.storage().instance().set(&DataKey::ContractVersion, &1u32);
+    });
+}
+
+#[test]
+fn test_fresh_deployment_starts_at_latest_version() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(VolatilityShield, ());
+    let client = VolatilityShieldClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let guardians = soroban_sdk::vec![&env, admin.clone()];
+    client.init(
+        &admin,
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &0u32,
+        &guardians,
+        &1u32,
+    );
+
+    assert_eq!(client.version(), migration::CONTRACT_VERSION);
+    assert_eq!(client.get_migration_status(), None);
+    assert_eq!(
+        client.try_migrate(&migration::CONTRACT_VERSION),
+        Err(Ok(Error::UnsupportedVersion))
+    );
+    assert_eq!(
+        client.try_migrate(&(migration::CONTRACT_VERSION + 1)),
+        Err(Ok(Error::UnsupportedVersion))
+    );
+}
+
+#[test]
+fn test_batched_migration_preserves_balances_and_tvl_history() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let token_admin = Address::generate(&env);
+    let (token_id, stellar_asset_client, _) = create_token_contract(&env, &token_admin);
+    let contract_id = env.register(VolatilityShield, ());
+    let client = VolatilityShieldClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let guardians = soroban_sdk::vec![&env, admin.clone()];
+    client.init(
+        &admin,
+        &token_id,
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &0u32,
+        &guardians,
+        &1u32,
+    );
+
+    let mut users = std::vec::Vec::new();
+    for i in 0..5u32 {
+        let user = Address::generate(&env);
+        stellar_asset_client.mint(&user, &10_000);
+        env.ledger().set_sequence_number(100 + i);
+        client.deposit(&user, &token_id, &(1_000 * (i as i128 + 1)), &None::<i128>);
+        users.push(user);
+    }
+
+    let balances: std::vec::Vec<i128> = users.iter().map(|u| client.balance(u)).collect();
+    let total_shares = client.total_shares();
+    let total_assets = client.total_assets();
+    assert_eq!(client.get_tvl_history(&0u64, &100u32).len(), 5);
+
+    // Pad the legacy history with older snapshots so it spans several batches.
+    let mut history = soroban_sdk::Vec::new(&env);
+    for i in 0..245u64 {
+        history.push_back((i, i as i128));
+    }
+    history.append(&client.get_tvl_history(&0u64, &100u32));
+    downgrade_to_v1(&env, &contract_id, &history);
+    assert_eq!(client.version(), 1);
+    assert_eq!(client.get_tvl_history(&0u64, &1_000u32), history);
+
+    // The first call migrates one default-sized batch and leaves the rest pending.
+    assert!(!client.migrate(&migration::CONTRACT_VERSION));
+    assert_eq!(
+        client.get_migration_status(),
+        Some(MigrationState {
+            target_version: migration::CONTRACT_VERSION,
+            cursor: 100,
+        })
+    );
+    assert_eq!(client.version(), 1);
+
+    // State-changing calls are rejected while the layout is half-migrated.
+    let blocked = client.try_deposit(&users[0], &token_id, &10, &None::<i128>);
+    assert_eq!(blocked, Err(Ok(Error::VersionMismatch)));
+    let blocked = client.try_queue_withdraw(&users[0], &users[0], &token_id, &balances[0]);
+    assert_eq!(blocked, Err(Ok(Error::VersionMismatch)));
+
+    // A different target cannot hijack the pending migration.
+    assert_eq!(client.try_migrate(&2u32), Err(Ok(Error::MigrationInProgress)));
+
+    assert!(!client.continue_migration(&60u32));
+    // Re-issuing the same target resumes rather than restarting.
+    assert!(client.migrate(&migration::CONTRACT_VERSION));
+    assert_eq!(client.version(), migration::CONTRACT_VERSION);
+    assert_eq!(client.get_migration_status(), None);
+
+    for (user, balance) in users.iter().zip(balances.iter()) {
+        assert_eq!(client.balance(user), *balance);
+    }
+    assert_eq!(client.total_shares(), total_shares);
+    assert_eq!(client.total_assets(), total_assets);
+    assert_eq!(client.get_tvl_history(&0u64, &1_000u32), history);
+    env.as_contract(&contract_id, || {
+        assert!(!env.storage().instance().has(&TVL_HISTORY_KEY));
+    });
+
+    // The vault keeps working on the new layout.
+    env.ledger().set_sequence_number(200);
+    client.deposit(&users[0], &token_id, &10, &None::<i128>);
+    assert_eq!(client.get_tvl_history(&0u64, &1_000u32).len(), 251);
+}
+
+#[test]
+fn test_migrate_runs_in_a_single_call_when_batch_fits() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let token_admin = Address::generate(&env);
+    let (token_id, stellar_asset_client, _) = create_token_contract(&env, &token_admin);
+    let contract_id = env.register(VolatilityShield, ());
+    let client = VolatilityShieldClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let guardians = soroban_sdk::vec![&env, admin.clone()];
+    client.init(
+        &admin,
+        &token_id,
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &0u32,
+        &guardians,
+        &1u32,
+    );
+
+    let user = Address::generate(&env);
+    stellar_asset_client.mint(&user, &1_000);
+    client.deposit(&user, &token_id, &1_000, &None::<i128>);
+    let history = client.get_tvl_history(&0u64, &10u32);
+
+    downgrade_to_v1(&env, &contract_id, &history);
+    assert!(client.migrate(&migration::CONTRACT_VERSION));
+    assert_eq!(client.version(), migration::CONTRACT_VERSION);
+    assert_eq!(client.get_tvl_history(&0u64, &10u32), history);
+    assert_eq!(client.balance(&user), 1_000_000);
+}
+
+#[test]
+fn test_v2_collections_migrate_to_persistent_storage() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(VolatilityShield, ());
+    let client = VolatilityShieldClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let asset = Address::generate(&env);
+    let guardians = soroban_sdk::vec![&env, admin.clone()];
+    client.init(
+        &admin,
+        &asset,
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &0u32,
+        &guardians,
+        &1u32,
+    );
+
+    let proposal = Proposal {
+        id: 7,
+        proposer: admin.clone(),
+        action: ActionType::SetPaused(true),
+        approvals: soroban_sdk::vec![&env, admin.clone()],
+        executed: false,
+        executed_ledger: 0,
+        proposed_at: env.ledger().timestamp(),
+    };
+    let withdrawals = soroban_sdk::vec![
+        &env,
+        QueuedWithdrawal {
+            user: Address::generate(&env),
+            asset: asset.clone(),
+            shares: 10,
+            timestamp: 1,
+        },
+        QueuedWithdrawal {
+            user: Address::generate(&env),
+            asset: asset.clone(),
+            shares: 20,
+            timestamp: 2,
+        },
+    ];
+    let blocklist = soroban_sdk::vec![&env, Address::generate(&env), Address::generate(&env)];
+    let allowlist = soroban_sdk::vec![&env, Address::generate(&env)];
+    let share_prices = soroban_sdk::vec![&env, (1u64, 1_000_000_000i128), (2, 1_100_000_000)];
+    let pauses = soroban_sdk::vec![&env, (5u64, admin.clone(), true)];
+    let tally = VoteTally {
+        yes_votes: 40,
+        no_votes: 2,
+    };
+
+    // Rebuild the v2 layout: everything in instance storage.
+    env.as_contract(&contract_id, || {
+        let mut proposals = Map::new(&env);
+        proposals.set(7u64, proposal.clone());
+        let instance = env.storage().instance();
+        instance.set(&DataKey::Proposals, &proposals);
+        instance.set(&DataKey::ProposalIds, &soroban_sdk::vec![&env, 7u64]);
+        instance.set(&DataKey::NextProposalId, &8u64);
+        instance.set(&DataKey::VoteTally(7), &tally);
+        instance.set(&DataKey::PendingWithdrawals, &withdrawals);
+        instance.set(&DataKey::Blocklist, &blocklist);
+        instance.set(&DataKey::Allowlist, &allowlist);
+        instance.set(&DataKey::SharePriceHistory, &share_prices);
+        instance.set(&DataKey::PauseHistory, &pauses);
+        // Legacy vaults minted shares 1:1 with value.
+        let legacy_total = 1_000_000_000i128;
+        instance.set(&DataKey::TotalAssets, &legacy_total);
+        instance.set(&DataKey::AssetTotalAssets(asset.clone()), &legacy_total);
+        instance.set(&DataKey::TotalShares, &legacy_total);
+        instance.set(&DataKey::ContractVersion, &2u32);
+    });
+
+    // Nine legacy items, three per call, so batches straddle collections.
+    env.as_contract(&contract_id, || {
+        migration::begin(&env, migration::CONTRACT_VERSION).unwrap()
+    });
+    assert!(!client.continue_migration(&3u32));
+    assert!(!client.continue_migration(&3u32));
+    assert_eq!(client.get_migration_status().unwrap().cursor, 6);
+    // Collections are copied one at a time; finished ones are already gone.
+    env.as_contract(&contract_id, || {
+        let instance = env.storage().instance();
+        assert!(!instance.has(&DataKey::Allowlist));
+        assert!(instance.has(&DataKey::SharePriceHistory));
+    });
+    assert!(client.continue_migration(&3u32));
+    assert_eq!(client.version(), migration::CONTRACT_VERSION);
+
+    assert_eq!(client.get_proposal(&7u64), Some(proposal));
+    assert_eq!(client.list_proposals(&0u32, &10u32, &true).len(), 1);
+    assert_eq!(client.get_vote_tally(&7u64), tally);
+    let tickets = client.get_pending_withdrawals();
+    assert_eq!(tickets.len(), withdrawals.len());
+    for (id, (ticket, withdrawal)) in tickets.iter().zip(withdrawals.iter()).enumerate() {
+        assert_eq!(ticket.id, id as u64);
+        assert_eq!(ticket.user, withdrawal.user);
+        assert_eq!(ticket.asset, withdrawal.asset);
+        assert_eq!(ticket.shares, withdrawal.shares);
+        assert_eq!(ticket.timestamp, withdrawal.timestamp);
+        // Still quoted per share, so the price stays on the legacy scale.
+        assert_eq!(ticket.share_price, 999_999_001);
+        assert_eq!(
+            client.get_user_withdrawal_tickets(&withdrawal.user),
+            soroban_sdk::vec![&env, ticket]
+        );
+    }
+    assert_eq!(client.get_blocklist(), blocklist);
+    assert_eq!(client.get_allowlist(), allowlist);
+    assert_eq!(client.get_share_price_history(), share_prices);
+    assert_eq!(client.get_pause_history(), pauses);
+
+    env.as_contract(&contract_id, || {
+        let instance = env.storage().instance();
+        assert!(!instance.has(&DataKey::Proposals));
+        assert!(!instance.has(&DataKey::ProposalIds));
+        assert_eq!(collections::proposal_bounds(&env).0, 7);
+        assert!(!instance.has(&DataKey::VoteTally(7)));
+        assert!(!instance.has(&DataKey::PendingWithdrawals));
+        assert!(!instance.has(&DataKey::Blocklist));
+        assert!(!instance.has(&DataKey::Allowlist));
+        assert!(!instance.has(&DataKey::SharePriceHistory));
+        assert!(!instance.has(&DataKey::PauseHistory));
+    });
+}
+
+#[test]
+fn test_blocklist_spans_pages_and_compacts_on_removal() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register(VolatilityShield, ());
+    let client = VolatilityShieldClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let guardians = soroban_sdk::vec![&env, admin.clone()];
+    client.init(
+        &admin,
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &0u32,
+        &guardians,
+        &1u32,
+    );
+
+    let mut users = std::vec::Vec::new();
+    for _ in 0..(collections::PAGE_SIZE * 2 + 10) {
+        let user = Address::generate(&env);
+        client.add_to_blocklist(&user);
+        users.push(user);
+    }
+    // Adding twice is a no-op.
+    client.add_to_blocklist(&users[0]);
+    assert_eq!(client.get_blocklist().len(), collections::PAGE_SIZE * 2 + 10);
+
+    client.remove_from_blocklist(&users[0]);
+    client.remove_from_blocklist(&users[70]);
+    let last = users.last().unwrap().clone();
+    client.remove_from_blocklist(&last);
+
+    let blocklist = client.get_blocklist();
+    assert_eq!(blocklist.len(), collections::PAGE_SIZE * 2 + 7);
+    for (i, user) in users.iter().enumerate() {
+        let removed = i == 0 || i == 70 || *user == last;
+        assert_eq!(blocklist.contains(user.clone()), !removed);
+    }
+}
+
+#[test]
+fn test_cancelled_withdrawal_is_skipped_in_fifo_processing() {
+    let env = Env::default();
+    env.mock_all_auths_allowing_non_root_auth();
+
+    let token_admin = Address::generate(&env);
+    let (token_id, stellar_asset_client, token_client) = create_token_contract(&env, &token_admin);
+    let contract_id = env.register(VolatilityShield, ());
+    let client = VolatilityShieldClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let guardians = soroban_sdk::vec![&env, admin.clone()];
+    client.init(
+        &admin,
+        &token_id,
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &0u32,
+        &guardians,
+        &1u32,
+    );
+    client.set_withdraw_queue_threshold(&1000);
+    client.set_total_shares(&900_000);
+    client.set_total_assets(&4500);
+    stellar_asset_client.mint(&contract_id, &4500);
+
+    let users = [
+        Address::generate(&env),
+        Address::generate(&env),
+        Address::generate(&env),
+    ];
+    let mut tickets = soroban_sdk::Vec::new(&env);
+    for user in users.iter() {
+        client.set_balance(user, &300_000);
+        tickets.push_back(client.queue_withdraw(user, user, &token_id, &300_000));
+    }
+
+    client.cancel_queued_withdrawal(&tickets.get(1).unwrap());
+    assert_eq!(client.get_pending_withdrawals().len(), 2);
+
+    assert_eq!(client.fulfil_withdrawals(&admin, &1), 1);
+    assert_eq!(client.claim_withdrawal(&tickets.get(0).unwrap()), 1498);
+    assert_eq!(token_client.balance(&users[0]), 1498);
+    let pending = client.get_pending_withdrawals();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending.get(0).unwrap().user, users[2]);
+
+    assert_eq!(client.fulfil_withdrawals(&admin, &5), 1);
+    client.claim_withdrawal(&tickets.get(2).unwrap());
+    assert_eq!(token_client.balance(&users[2]), 1499);
+    assert_eq!(client.balance(&users[1]), 300_000);
+    assert_eq!(client.fulfil_withdrawals(&admin, &5), 0);
+}
+
+#[test]
+fn test_proposal_pruning_preserves_active_and_recent_proposals() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, VolatilityShield);
+    let client = VolatilityShieldClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let guardian = Address::generate(&env);
+    let asset = Address::generate(&env);
+    let oracle = Address::generate(&env);
+    let treasury = Address::generate(&env);
+    let guardians = soroban_sdk::vec![&env, admin.clone(), guardian.clone()];
+    client.init(&admin, &asset, &oracle, &treasury, &0u32, &guardians, &1u32);
+    client.set_proposal_ttl_ledgers(&5u32);
+
+    env.ledger().set_sequence_number(10);
+    env.ledger().set_timestamp(1000);
+    let old_id = client.propose_action(&admin, &ActionType::SetPaused(true));
+    assert!(client.get_proposal(&old_id).unwrap().executed);
+
+    client.set_threshold(&2u32);
+    env.ledger().set_sequence_number(12);
+    env.ledger().set_timestamp(1200);
+    let active_id = client.propose_action(&admin, &ActionType::SetPaused(false));
+    assert!(!client.get_proposal(&active_id).unwrap().executed);
+
+    client.set_threshold(&1u32);
+    env.ledger().set_sequence_number(15);
+    env.ledger().set_timestamp(1500);
+    let recent_id = client.propose_action(&admin, &ActionType::SetPaused(true));
+    assert!(client.get_proposal(&recent_id).unwrap().executed);
+
+    env.ledger().set_sequence_number(16);
+    env.ledger().set_timestamp(1600);
+    assert_eq!(client.prune_old_proposals(), 1);
+    assert!(client.get_proposal(&old_id).is_none());
+    assert!(!client.get_proposal(&active_id).unwrap().executed);
+    assert!(client.get_proposal(&recent_id).unwrap().executed);
+
+    let proposals = client.list_proposals(&0u32, &10u32, &true);
+    assert_eq!(proposals.len(), 2);
+    assert_eq!(proposals.get(0).unwrap().id, active_id);
+    assert_eq!(proposals.get(1).unwrap().id, recent_id);
+
+    client.set_threshold(&2u32);
+    client.approve_action(&guardian, &active_id);
+    assert!(!client.is_paused());
+
+    client.set_threshold(&1u32);
+    env.ledger().set_sequence_number(17);
+    env.ledger().set_timestamp(1700);
+    let new_id = client.propose_action(&admin, &ActionType::SetPaused(true));
+    assert_eq!(new_id, 4);
+    assert!(client.get_proposal(&new_id).unwrap().executed);
+}
+
+#[test]
+fn test_list_proposals_pagination_and_get_proposal() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, VolatilityShield);
+    let client = VolatilityShieldClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let asset = Address::generate(&env);
+    let oracle = Address::generate(&env);
+    let treasury = Address::generate(&env);
+    let guardians = soroban_sdk::vec![&env, admin.clone()];
+    client.init(&admin, &asset, &oracle, &treasury, &0u32, &guardians, &1u32);
+
+    let first_id = client.propose_action(&admin, &ActionType::SetPaused(true));
+    let second_id = client.propose_action(&admin, &ActionType::SetPaused(false));
+    let third_id = client.propose_action(&admin, &ActionType::SetPaused(true));
+
+    let first_page = client.list_proposals(&0u32, &2u32, &true);
+    assert_eq!(first_page.len(), 2);
+    assert_eq!(first_page.get(0).unwrap().id, first_id);
+    assert_eq!(first_page.get(1).unwrap().id, second_id);
+
+    let second_page = client.list_proposals(&1u32, &2u32, &true);
+    assert_eq!(second_page.len(), 2);
+    assert_eq!(second_page.get(0).unwrap().id, second_id);
+    assert_eq!(second_page.get(1).unwrap().id, third_id);
+
+    let proposal = client.get_proposal(&second_id).unwrap();
+    assert_eq!(proposal.id, second_id);
+    assert!(proposal.executed);
+    assert!(client.get_proposal(&999u64).is_none());
+
+    let summary = client.get_governance_summary();
+    assert_eq!(summary.active_proposal_count, 0);
+}
+
+#[test]
+fn test_pause_history_contains_pause_and_unpause() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, VolatilityShield);
+    let client = VolatilityShieldClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let asset = Address::generate(&env);
+    let oracle = Address::generate(&env);
+    let treasury = Address::generate(&env);
+    let guardians = soroban_sdk::vec![&env, admin.clone()];
+    client.init(&admin, &asset, &oracle, &treasury, &0u32, &guardians, &1u32);
+
+    env.ledger().set_timestamp(100);
+    client.set_paused(&true);
+    env.ledger().set_timestamp(150);
+    client.set_paused(&false);
+
+    let history = client.get_pause_history();
+    assert_eq!(history.len(), 2);
+
+    let first = history.get(0).unwrap();
+    assert_eq!(first.0, 100);
+    assert_eq!(first.1, admin);
+    assert!(first.2);
+
+    let second = history.get(1).unwrap();
+    assert_eq!(second.0, 150);
+    assert_eq!(second.1, admin);
+    assert!(!second.2);
+    assert!(!client.is_paused());
+}
+
+#[test]
+fn test_share_price_history_grows_on_harvest() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register_contract(None, VolatilityShield);
+    let client = VolatilityShieldClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let asset = Address::generate(&env);
+    let oracle = Address::generate(&env);
+    let treasury = Address::generate(&env);
+    let guardians = soroban_sdk::vec![&env, admin.clone()];
+    client.init(&admin, &asset, &oracle, &treasury, &0u32, &guardians, &1u32);
+
+    assert_eq!(client.get_share_price_history().len(), 0);
+
+    client.set_total_assets(&200);
+    client.set_total_shares(&100_000);
+
+    let strategy = env.register_contract(None, mock_strategy::MockStrategy);
+    client.propose_action(&admin, &ActionType::AddStrategy(strategy));
+
+    env.ledger().set_timestamp(200);
+    assert_eq!(client.harvest(), 0);
 
     let history = client.get_share_price_history();
     assert_eq!(history.len(), 1);
     let entry = history.get(0).unwrap();
     assert_eq!(entry.0, 200);
-    assert_eq!(entry.1, 2_000_000_000);
+    // (200 + 1) * 1e9 / (100_000 + 1_000) shares
+    assert_eq!(entry.1, 1_990_099);
 }
 
 #[test]
@@ -1580,17 +3104,17 @@ fn test_withdraw_below_threshold_processes_immediately() {
 
     // Setup user with balance
     let user = Address::generate(&env);
-    client.set_total_shares(&1000);
+    client.set_total_shares(&1_000_000);
     client.set_total_assets(&5000);
-    client.set_balance(&user, &200);
+    client.set_balance(&user, &200_000);
     stellar_asset_client.mint(&contract_id, &5000);
 
-    // Withdraw 50 shares (converts to 250 assets, below threshold)
-    client.withdraw(&user, &user, &token_id, &50);
+    // Withdraw 50_000 shares (converts to 249 assets, below threshold)
+    client.withdraw(&user, &user, &token_id, &50_000);
 
     // Should process immediately
-    assert_eq!(client.balance(&user), 150);
-    assert_eq!(token_client.balance(&user), 250);
+    assert_eq!(client.balance(&user), 150_000);
+    assert_eq!(token_client.balance(&user), 249);
     assert_eq!(client.get_pending_withdrawals().len(), 0);
 }
 
@@ -1618,20 +3142,20 @@ fn test_withdraw_above_threshold_queues() {
 
     // Setup user with balance
     let user = Address::generate(&env);
-    client.set_total_shares(&1000);
+    client.set_total_shares(&1_000_000);
     client.set_total_assets(&5000);
-    client.set_balance(&user, &500);
+    client.set_balance(&user, &500_000);
     stellar_asset_client.mint(&contract_id, &5000);
 
-    // Queue 300 shares via queue_withdraw (converts to 1500 assets, above threshold)
-    client.queue_withdraw(&user, &user, &token_id, &300);
+    // Queue 300_000 shares via queue_withdraw (converts to 1498 assets, above threshold)
+    client.queue_withdraw(&user, &user, &token_id, &300_000);
 
     // Should be queued; balance is reduced immediately
-    assert_eq!(client.balance(&user), 200);
+    assert_eq!(client.balance(&user), 200_000);
     let pending = client.get_pending_withdrawals();
     assert_eq!(pending.len(), 1);
     assert_eq!(pending.get(0).unwrap().user, user);
-    assert_eq!(pending.get(0).unwrap().shares, 300);
+    assert_eq!(pending.get(0).unwrap().shares, 300_000);
 }
 
 #[test]
@@ -1656,19 +3180,19 @@ fn test_delegate_can_queue_withdraw_for_owner() {
     );
 
     client.set_withdraw_queue_threshold(&1000);
-    client.set_total_shares(&1000);
+    client.set_total_shares(&1_000_000);
     client.set_total_assets(&5000);
-    client.set_balance(&owner, &500);
+    client.set_balance(&owner, &500_000);
     client.set_delegate(&owner, &delegate);
     stellar_asset_client.mint(&contract_id, &5000);
 
-    client.queue_withdraw(&delegate, &owner, &token_id, &300);
+    client.queue_withdraw(&delegate, &owner, &token_id, &300_000);
 
-    assert_eq!(client.balance(&owner), 200);
+    assert_eq!(client.balance(&owner), 200_000);
     let pending = client.get_pending_withdrawals();
     assert_eq!(pending.len(), 1);
     assert_eq!(pending.get(0).unwrap().user, owner);
-    assert_eq!(pending.get(0).unwrap().shares, 300);
+    assert_eq!(pending.get(0).unwrap().shares, 300_000);
 }
 
 #[test]
@@ -1695,23 +3219,24 @@ fn test_process_withdraw_queue() {
 
     // Setup user with balance
     let user = Address::generate(&env);
-    client.set_total_shares(&1000);
+    client.set_total_shares(&1_000_000);
     client.set_total_assets(&5000);
-    client.set_balance(&user, &500);
+    client.set_balance(&user, &500_000);
     stellar_asset_client.mint(&contract_id, &5000);
 
-    // Queue a withdrawal directly (300 shares = 1500 assets > threshold of 1000)
-    client.queue_withdraw(&user, &user, &token_id, &300);
+    // Queue a withdrawal directly (300_000 shares = 1498 assets > threshold of 1000)
+    let ticket_id = client.queue_withdraw(&user, &user, &token_id, &300_000);
     assert_eq!(client.get_pending_withdrawals().len(), 1);
 
-    // Process the queue
-    client.process_queued_withdrawals(&1);
-
-    // Withdrawal should be processed
+    // Fulfil the queue, then claim
+    client.fulfil_withdrawals(&admin, &1);
     assert_eq!(client.get_pending_withdrawals().len(), 0);
-    assert_eq!(token_client.balance(&user), 1500); // 300 shares * 5 = 1500 assets
-    assert_eq!(client.total_shares(), 700);
-    assert_eq!(client.total_assets(), 3500);
+    client.claim_withdrawal(&ticket_id);
+
+    // Withdrawal should be settled
+    assert_eq!(token_client.balance(&user), 1498); // 300_000 * 5001 / 1_001_000
+    assert_eq!(client.total_shares(), 700_000);
+    assert_eq!(client.total_assets(), 3502);
 }
 
 #[test]
@@ -1738,28 +3263,27 @@ fn test_cancel_withdraw() {
 
     // Setup user with balance
     let user = Address::generate(&env);
-    client.set_total_shares(&1000);
+    client.set_total_shares(&1_000_000);
     client.set_total_assets(&5000);
-    client.set_balance(&user, &500);
+    client.set_balance(&user, &500_000);
     stellar_asset_client.mint(&contract_id, &5000);
 
-    // Queue a withdrawal directly (300 shares = 1500 assets > threshold of 1000)
-    client.queue_withdraw(&user, &user, &token_id, &300);
+    // Queue a withdrawal directly (300_000 shares = 1498 assets > threshold of 1000)
+    let ticket_id = client.queue_withdraw(&user, &user, &token_id, &300_000);
     // Balance is subtracted immediately
-    assert_eq!(client.balance(&user), 200);
+    assert_eq!(client.balance(&user), 200_000);
     assert_eq!(client.get_pending_withdrawals().len(), 1);
 
     // Cancel the withdrawal
-    client.cancel_queued_withdrawal(&user);
+    client.cancel_queued_withdrawal(&ticket_id);
 
     // cancel_queued_withdrawal returns shares to balance
-    assert_eq!(client.balance(&user), 500);
+    assert_eq!(client.balance(&user), 500_000);
     assert_eq!(client.get_pending_withdrawals().len(), 0);
 }
 
 #[test]
-#[should_panic(expected = "user already has a pending withdrawal")]
-fn test_cannot_queue_multiple_withdrawals() {
+fn test_user_can_hold_multiple_withdrawal_tickets() {
     let env = Env::default();
     env.mock_all_auths_allowing_non_root_auth();
 
@@ -1780,19 +3304,42 @@ fn test_cannot_queue_multiple_withdrawals() {
     client.set_withdraw_queue_threshold(&1000);
 
     let user = Address::generate(&env);
-    client.set_total_shares(&1000);
+    client.set_total_shares(&1_000_000);
     client.set_total_assets(&5000);
     // Give user enough balance for both withdrawals
-    client.set_balance(&user, &600);
+    client.set_balance(&user, &600_000);
     stellar_asset_client.mint(&contract_id, &5000);
 
-    // Queue first withdrawal via queue_withdraw (300 shares = 1500 assets, above threshold of 1000)
-    client.queue_withdraw(&user, &user, &token_id, &300);
-    // User now has 300 shares remaining
-
-    // Try to queue another - should panic because user already has pending withdrawal
-    // This will try to withdraw 250 shares = 1250 assets, which is above threshold
-    client.queue_withdraw(&user, &user, &token_id, &250);
+    // Queue first withdrawal via queue_withdraw (300_000 shares = 1498 assets, above threshold of 1000)
+    let first = client.queue_withdraw(&user, &user, &token_id, &300_000);
+    // User now has 300_000 shares remaining
+
+    // A second request (250_000 shares = 1249 assets) gets its own ticket
+    let second = client.queue_withdraw(&user, &user, &token_id, &250_000);
+    assert_ne!(first, second);
+    assert_eq!(client.balance(&user), 50_000);
+
+    let ticket = client.get_withdrawal_ticket(&second).unwrap();
+    assert_eq!(ticket.id, second);
+    assert_eq!(ticket.user, user);
+    assert_eq!(ticket.shares, 250_000);
+    // (5000 + 1) assets per (1_000_000 + 1_000) shares at request time
+    assert_eq!(ticket.share_price, 4_996_003);
+
+    let tickets = client.get_user_withdrawal_tickets(&user);
+    assert_eq!(tickets.len(), 2);
+    assert_eq!(tickets.get(0).unwrap().id, first);
+    assert_eq!(client.get_user_summary(&user).queued_shares, 550_000);
+
+    // Cancelling one ticket leaves the other queued
+    client.cancel_queued_withdrawal(&first);
+    assert_eq!(client.get_withdrawal_ticket(&first), None);
+    assert_eq!(client.balance(&user), 350_000);
+    assert_eq!(client.get_pending_withdrawals(), soroban_sdk::vec![&env, ticket]);
+    assert_eq!(
+        client.try_cancel_queued_withdrawal(&first),
+        Err(Ok(Error::WithdrawalNotFound))
+    );
 }
 
 #[test]
@@ -1811,131 +3358,369 @@ fn test_process_withdraw_queue_empty() {
     client.init(&admin, &asset, &oracle, &treasury, &0u32, &guardians, &1u32);
 
     // Process empty queue - should return 0 (no-op, not an error)
-    let processed = client.process_queued_withdrawals(&1);
+    let processed = client.fulfil_withdrawals(&admin, &1);
     assert_eq!(processed, 0);
 }
 
 #[test]
-fn test_cancel_withdraw_not_found() {
+fn test_cancel_withdraw_not_found() {
+    let env = Env::default();
+    env.mock_all_auths_allowing_non_root_auth();
+
+    let contract_id = env.register_contract(None, VolatilityShield);
+    let client = VolatilityShieldClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let asset = Address::generate(&env);
+    let oracle = Address::generate(&env);
+    let treasury = Address::generate(&env);
+    let guardians = soroban_sdk::vec![&env, admin.clone()];
+    client.init(&admin, &asset, &oracle, &treasury, &0u32, &guardians, &1u32);
+
+    // Try to cancel non-existent withdrawal
+    let result = client.try_cancel_queued_withdrawal(&0u64);
+    assert_eq!(result, Err(Ok(Error::WithdrawalNotFound)));
+}
+
+#[test]
+fn test_withdrawal_queue_fifo_order() {
+    let env = Env::default();
+    env.mock_all_auths_allowing_non_root_auth();
+
+    let token_admin = Address::generate(&env);
+    let (token_id, stellar_asset_client, token_client) = create_token_contract(&env, &token_admin);
+
+    let contract_id = env.register_contract(None, VolatilityShield);
+    let client = VolatilityShieldClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let oracle = Address::generate(&env);
+    let treasury = Address::generate(&env);
+    let guardians = soroban_sdk::vec![&env, admin.clone()];
+    client.init(
+        &admin, &token_id, &oracle, &treasury, &0u32, &guardians, &1u32,
+    );
+
+    client.set_withdraw_queue_threshold(&1000);
+
+    // Setup two users
+    let user1 = Address::generate(&env);
+    let user2 = Address::generate(&env);
+    client.set_total_shares(&1_000_000);
+    client.set_total_assets(&5000);
+    client.set_balance(&user1, &300_000);
+    client.set_balance(&user2, &300_000);
+    stellar_asset_client.mint(&contract_id, &5000);
+
+    // Queue withdrawals in order using queue_withdraw
+    let ticket1 = client.queue_withdraw(&user1, &user1, &token_id, &300_000);
+    let ticket2 = client.queue_withdraw(&user2, &user2, &token_id, &300_000);
+
+    let pending = client.get_pending_withdrawals();
+    assert_eq!(pending.len(), 2);
+    assert_eq!(pending.get(0).unwrap().user, user1);
+    assert_eq!(pending.get(1).unwrap().user, user2);
+
+    // Fulfil first withdrawal
+    client.fulfil_withdrawals(&admin, &1);
+    assert!(client.get_claimable_withdrawal(&ticket1).is_some());
+    assert!(client.get_claimable_withdrawal(&ticket2).is_none());
+    client.claim_withdrawal(&ticket1);
+    assert_eq!(token_client.balance(&user1), 1498);
+    assert_eq!(token_client.balance(&user2), 0);
+
+    // Fulfil second withdrawal
+    client.fulfil_withdrawals(&admin, &1);
+    client.claim_withdrawal(&ticket2);
+    assert_eq!(token_client.balance(&user2), 1499);
+}
+
+#[test]
+fn test_withdrawal_queue_full_lifecycle() {
+    let env = Env::default();
+    env.mock_all_auths_allowing_non_root_auth();
+
+    let token_admin = Address::generate(&env);
+    let (token_id, stellar_asset_client, token_client) = create_token_contract(&env, &token_admin);
+
+    let contract_id = env.register_contract(None, VolatilityShield);
+    let client = VolatilityShieldClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let oracle = Address::generate(&env);
+    let treasury = Address::generate(&env);
+    let guardians = soroban_sdk::vec![&env, admin.clone()];
+    client.init(
+        &admin, &token_id, &oracle, &treasury, &0u32, &guardians, &1u32,
+    );
+
+    client.set_withdraw_queue_threshold(&1000);
+
+    let user = Address::generate(&env);
+    client.set_total_shares(&1_000_000);
+    client.set_total_assets(&5000);
+    client.set_balance(&user, &500_000);
+    stellar_asset_client.mint(&contract_id, &5000);
+
+    // 1. Queue withdrawal via queue_withdraw
+    let ticket_id = client.queue_withdraw(&user, &user, &token_id, &300_000);
+    // Balance is subtracted immediately (500_000 - 300_000 = 200_000)
+    assert_eq!(client.balance(&user), 200_000);
+    assert_eq!(client.get_pending_withdrawals().len(), 1);
+
+    // 2. Cancel withdrawal - cancel_queued_withdrawal returns shares, so balance = 200_000 + 300_000 = 500_000
+    client.cancel_queued_withdrawal(&ticket_id);
+    assert_eq!(client.balance(&user), 500_000);
+    assert_eq!(client.get_pending_withdrawals().len(), 0);
+
+    // 3. Queue again (user has 500_000 shares now)
+    let ticket_id = client.queue_withdraw(&user, &user, &token_id, &300_000);
+    assert_eq!(client.balance(&user), 200_000); // reduced immediately to 200_000
+    assert_eq!(client.get_pending_withdrawals().len(), 1);
+
+    // 4. Fulfil, then claim — claim_withdrawal transfers tokens.
+    // User balance stays at 200_000 (since it was already deducted).
+    client.fulfil_withdrawals(&admin, &1);
+    client.claim_withdrawal(&ticket_id);
+    assert_eq!(client.balance(&user), 200_000);
+    assert_eq!(token_client.balance(&user), 1498);
+    assert_eq!(client.get_pending_withdrawals().len(), 0);
+}
+
+#[test]
+fn test_fulfil_locks_assets_and_claim_pays_out() {
     let env = Env::default();
     env.mock_all_auths_allowing_non_root_auth();
 
-    let contract_id = env.register_contract(None, VolatilityShield);
+    let token_admin = Address::generate(&env);
+    let (token_id, stellar_asset_client, token_client) = create_token_contract(&env, &token_admin);
+    let contract_id = env.register(VolatilityShield, ());
     let client = VolatilityShieldClient::new(&env, &contract_id);
 
     let admin = Address::generate(&env);
-    let asset = Address::generate(&env);
-    let oracle = Address::generate(&env);
-    let treasury = Address::generate(&env);
     let guardians = soroban_sdk::vec![&env, admin.clone()];
-    client.init(&admin, &asset, &oracle, &treasury, &0u32, &guardians, &1u32);
+    client.init(
+        &admin,
+        &token_id,
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &0u32,
+        &guardians,
+        &1u32,
+    );
 
-    let user = Address::generate(&env);
+    // 1000 idle tokens in the vault, 2000 deployed to a strategy.
+    let strategy_id = env.register(mock_strategy::MockStrategy, ());
+    let strategy = mock_strategy::MockStrategyClient::new(&env, &strategy_id);
+    strategy.init(&contract_id, &token_id);
+    client.propose_action(&admin, &ActionType::AddStrategy(strategy_id.clone()));
+    stellar_asset_client.mint(&contract_id, &1000);
+    stellar_asset_client.mint(&strategy_id, &2000);
+    strategy.deposit(&2000);
 
-    // Try to cancel non-existent withdrawal
-    let result = client.try_cancel_queued_withdrawal(&user);
-    assert_eq!(result, Err(Ok(Error::WithdrawalNotFound)));
+    client.set_withdraw_queue_threshold(&1000);
+    client.set_total_shares(&1_000_000);
+    client.set_total_assets(&5000);
+    let user1 = Address::generate(&env);
+    let user2 = Address::generate(&env);
+    client.set_balance(&user1, &300_000);
+    client.set_balance(&user2, &500_000);
+    let ticket1 = client.queue_withdraw(&user1, &user1, &token_id, &300_000);
+    let ticket2 = client.queue_withdraw(&user2, &user2, &token_id, &500_000);
+
+    // Ticket 1 (1498) is covered by idle tokens plus 498 pulled from the
+    // strategy. Ticket 2 (2498) cannot be covered and stays queued.
+    assert_eq!(client.fulfil_withdrawals(&admin, &5), 1);
+    assert_eq!(token_client.balance(&user1), 0);
+    assert_eq!(client.get_total_claimable(), 1498);
+    assert_eq!(client.total_shares(), 700_000);
+    assert_eq!(client.total_assets(), 3502);
+    assert_eq!(client.get_pending_withdrawals().len(), 1);
+    assert_eq!(client.get_pending_withdrawals().get(0).unwrap().id, ticket2);
+
+    let claim = client.get_claimable_withdrawal(&ticket1).unwrap();
+    assert_eq!(claim.user, user1);
+    assert_eq!(claim.amount, 1498);
+    assert_eq!(claim.share_price, 4_996_003);
+
+    assert_eq!(client.claim_withdrawal(&ticket1), 1498);
+    assert_eq!(token_client.balance(&user1), 1498);
+    assert_eq!(client.get_total_claimable(), 0);
+    assert_eq!(
+        client.try_claim_withdrawal(&ticket1),
+        Err(Ok(Error::WithdrawalNotFound))
+    );
+    // Unfulfilled tickets cannot be claimed.
+    assert_eq!(
+        client.try_claim_withdrawal(&ticket2),
+        Err(Ok(Error::WithdrawalNotFound))
+    );
+
+    stellar_asset_client.mint(&contract_id, &1000);
+    assert_eq!(client.fulfil_withdrawals(&admin, &5), 1);
+    assert_eq!(client.claim_withdrawal(&ticket2), 2498);
+    assert_eq!(token_client.balance(&user2), 2498);
 }
 
 #[test]
-fn test_withdrawal_queue_fifo_order() {
+fn test_keeper_fulfils_with_bounty_in_unwind_order() {
     let env = Env::default();
     env.mock_all_auths_allowing_non_root_auth();
 
     let token_admin = Address::generate(&env);
     let (token_id, stellar_asset_client, token_client) = create_token_contract(&env, &token_admin);
-
-    let contract_id = env.register_contract(None, VolatilityShield);
+    let contract_id = env.register(VolatilityShield, ());
     let client = VolatilityShieldClient::new(&env, &contract_id);
 
     let admin = Address::generate(&env);
-    let oracle = Address::generate(&env);
-    let treasury = Address::generate(&env);
     let guardians = soroban_sdk::vec![&env, admin.clone()];
     client.init(
-        &admin, &token_id, &oracle, &treasury, &0u32, &guardians, &1u32,
+        &admin,
+        &token_id,
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &0u32,
+        &guardians,
+        &1u32,
     );
 
-    client.set_withdraw_queue_threshold(&1000);
-
-    // Setup two users
-    let user1 = Address::generate(&env);
-    let user2 = Address::generate(&env);
-    client.set_total_shares(&1000);
-    client.set_total_assets(&5000);
-    client.set_balance(&user1, &300);
-    client.set_balance(&user2, &300);
-    stellar_asset_client.mint(&contract_id, &5000);
-
-    // Queue withdrawals in order using queue_withdraw
-    client.queue_withdraw(&user1, &user1, &token_id, &300);
-    client.queue_withdraw(&user2, &user2, &token_id, &300);
+    // Everything is deployed: 2000 tokens in each of two strategies.
+    let mut strategies = soroban_sdk::Vec::new(&env);
+    for _ in 0..2 {
+        let strategy_id = env.register(mock_strategy::MockStrategy, ());
+        let strategy = mock_strategy::MockStrategyClient::new(&env, &strategy_id);
+        strategy.init(&contract_id, &token_id);
+        client.propose_action(&admin, &ActionType::AddStrategy(strategy_id.clone()));
+        stellar_asset_client.mint(&strategy_id, &2000);
+        strategy.deposit(&2000);
+        strategies.push_back(strategy_id);
+    }
+    let first = mock_strategy::MockStrategyClient::new(&env, &strategies.get(0).unwrap());
+    let second = mock_strategy::MockStrategyClient::new(&env, &strategies.get(1).unwrap());
 
-    let pending = client.get_pending_withdrawals();
-    assert_eq!(pending.len(), 2);
-    assert_eq!(pending.get(0).unwrap().user, user1);
-    assert_eq!(pending.get(1).unwrap().user, user2);
+    // Without a configured order, equal APYs keep registration order.
+    assert_eq!(client.get_unwind_order(), strategies);
+    assert_eq!(
+        client.try_set_unwind_order(&soroban_sdk::vec![&env, Address::generate(&env)]),
+        Err(Ok(Error::ZeroAddressStrategy))
+    );
+    client.set_unwind_order(&soroban_sdk::vec![&env, strategies.get(1).unwrap()]);
+    assert_eq!(
+        client.get_unwind_order(),
+        soroban_sdk::vec![&env, strategies.get(1).unwrap(), strategies.get(0).unwrap()]
+    );
 
-    // Process first withdrawal
-    client.process_queued_withdrawals(&1);
-    assert_eq!(token_client.balance(&user1), 1500);
-    assert_eq!(token_client.balance(&user2), 0);
+    assert_eq!(
+        client.try_set_keeper_bounty_bps(&101),
+        Err(Ok(Error::InvalidConfig))
+    );
+    client.set_keeper_bounty_bps(&50);
+    assert_eq!(client.get_keeper_bounty_bps(), 50);
 
-    // Process second withdrawal
-    client.process_queued_withdrawals(&1);
-    assert_eq!(token_client.balance(&user2), 1500);
+    client.set_withdraw_queue_threshold(&100);
+    client.set_total_shares(&1_000_000);
+    client.set_total_assets(&4000);
+    let user = Address::generate(&env);
+    client.set_balance(&user, &250_000);
+    let ticket = client.queue_withdraw(&user, &user, &token_id, &250_000);
+
+    // Any address can act as keeper; the 999 needed comes from the second
+    // strategy and 0.5% of it, rounded down, goes to the keeper.
+    let keeper = Address::generate(&env);
+    assert_eq!(client.fulfil_withdrawals(&keeper, &5), 1);
+    assert_eq!(second.balance(), 1001);
+    assert_eq!(first.balance(), 2000);
+    assert_eq!(token_client.balance(&keeper), 4);
+    assert_eq!(client.total_assets(), 3001);
+    assert_eq!(client.get_total_claimable(), 995);
+
+    assert_eq!(client.claim_withdrawal(&ticket), 995);
+    assert_eq!(token_client.balance(&user), 995);
+    assert_eq!(token_client.balance(&contract_id), 0);
 }
-
 #[test]
-fn test_withdrawal_queue_full_lifecycle() {
+fn test_idle_buffer_and_withdraw_fallback_to_queue() {
     let env = Env::default();
     env.mock_all_auths_allowing_non_root_auth();
 
     let token_admin = Address::generate(&env);
     let (token_id, stellar_asset_client, token_client) = create_token_contract(&env, &token_admin);
-
-    let contract_id = env.register_contract(None, VolatilityShield);
+    let contract_id = env.register(VolatilityShield, ());
     let client = VolatilityShieldClient::new(&env, &contract_id);
 
     let admin = Address::generate(&env);
-    let oracle = Address::generate(&env);
-    let treasury = Address::generate(&env);
     let guardians = soroban_sdk::vec![&env, admin.clone()];
     client.init(
-        &admin, &token_id, &oracle, &treasury, &0u32, &guardians, &1u32,
+        &admin,
+        &token_id,
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &0u32,
+        &guardians,
+        &1u32,
     );
 
-    client.set_withdraw_queue_threshold(&1000);
+    let bad_range = IdleBufferConfig {
+        target_bps: 1_000,
+        min_amount: 700,
+        max_amount: 600,
+    };
+    assert_eq!(
+        client.try_set_idle_buffer(&bad_range),
+        Err(Ok(Error::InvalidConfig))
+    );
+    let bad_bps = IdleBufferConfig {
+        target_bps: 10_001,
+        min_amount: 0,
+        max_amount: i128::MAX,
+    };
+    assert_eq!(
+        client.try_set_idle_buffer(&bad_bps),
+        Err(Ok(Error::InvalidConfig))
+    );
 
-    let user = Address::generate(&env);
-    client.set_total_shares(&1000);
+    let config = IdleBufferConfig {
+        target_bps: 1_000,
+        min_amount: 200,
+        max_amount: 600,
+    };
+    client.set_idle_buffer(&config);
+    assert_eq!(client.get_idle_buffer(), config);
+    env.as_contract(&contract_id, || {
+        assert_eq!(liquidity::idle_buffer_target(&env, 5_000), Ok(500));
+        assert_eq!(liquidity::idle_buffer_target(&env, 1_000), Ok(200));
+        assert_eq!(liquidity::idle_buffer_target(&env, 10_000), Ok(600));
+        // Never more than the vault holds.
+        assert_eq!(liquidity::idle_buffer_target(&env, 100), Ok(100));
+    });
+
+    client.set_total_shares(&1_000_000);
     client.set_total_assets(&5000);
-    client.set_balance(&user, &500);
-    stellar_asset_client.mint(&contract_id, &5000);
+    stellar_asset_client.mint(&contract_id, &1000);
+    assert_eq!(client.get_idle_liquidity(), 1000);
 
-    // 1. Queue withdrawal via queue_withdraw
-    client.queue_withdraw(&user, &user, &token_id, &300);
-    // Balance is subtracted immediately (500 - 300 = 200)
-    assert_eq!(client.balance(&user), 200);
-    assert_eq!(client.get_pending_withdrawals().len(), 1);
+    let user = Address::generate(&env);
+    client.set_balance(&user, &300_000);
 
-    // 2. Cancel withdrawal - cancel_queued_withdrawal returns shares, so balance = 200 + 300 = 500
-    client.cancel_queued_withdrawal(&user);
-    assert_eq!(client.balance(&user), 500);
-    assert_eq!(client.get_pending_withdrawals().len(), 0);
+    // 499 tokens are covered by idle liquidity and paid out instantly.
+    client.withdraw(&user, &user, &token_id, &100_000);
+    assert_eq!(token_client.balance(&user), 499);
+    assert_eq!(client.get_idle_liquidity(), 501);
 
-    // 3. Queue again (user has 500 shares now)
-    client.queue_withdraw(&user, &user, &token_id, &300);
-    assert_eq!(client.balance(&user), 200); // reduced immediately to 200
-    assert_eq!(client.get_pending_withdrawals().len(), 1);
+    // 749 tokens are not, so the request is queued with no queue threshold set.
+    client.withdraw(&user, &user, &token_id, &150_000);
+    assert_eq!(token_client.balance(&user), 499);
+    assert_eq!(client.balance(&user), 50_000);
+    let pending = client.get_pending_withdrawals();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending.get(0).unwrap().shares, 150_000);
 
-    // 4. Process withdrawal — process_queued_withdrawals transfers tokens.
-    // User balance stays at 200 (since it was already deducted).
-    client.process_queued_withdrawals(&1);
-    assert_eq!(client.balance(&user), 200);
-    assert_eq!(token_client.balance(&user), 1500);
-    assert_eq!(client.get_pending_withdrawals().len(), 0);
+    // Tokens locked for a claim no longer count as idle.
+    stellar_asset_client.mint(&contract_id, &250);
+    assert_eq!(client.fulfil_withdrawals(&admin, &1), 1);
+    assert_eq!(client.get_idle_liquidity(), 2);
 }
+
 // ── Oracle Allocation Validation Tests ─────────────────────────
 //
 // All tests that supply a non-empty allocation map first register the strategy
@@ -2235,15 +4020,15 @@ fn test_queue_withdraw_prevents_double_spending() {
     // Set threshold so 600 triggers queue
     client.set_withdraw_queue_threshold(&500);
 
-    // Queue 600
-    client.withdraw(&user, &user, &token_id, &600);
+    // Queue 600_000 shares, worth 600
+    client.withdraw(&user, &user, &token_id, &600_000);
 
-    // User balance should be 400 now (1000 - 600)
-    assert_eq!(client.balance(&user), 400);
+    // User balance should be 400_000 now (1_000_000 - 600_000)
+    assert_eq!(client.balance(&user), 400_000);
 
-    // Try to withdraw another 500 - should fail as user only has 400 left
-    let res = client.try_withdraw(&user, &user, &token_id, &500);
-    assert!(res.is_err());
+    // Try to withdraw another 500_000 - should fail as user only has 400_000 left
+    let res = client.try_withdraw(&user, &user, &token_id, &500_000);
+    assert_eq!(res, Err(Ok(Error::InsufficientShares)));
 }
 
 #[test]
@@ -2270,14 +4055,15 @@ fn test_cancel_queued_withdrawal_restores_balance() {
     client.deposit(&user, &token_id, &1000, &None::<i128>);
 
     client.set_withdraw_queue_threshold(&500);
-    client.withdraw(&user, &user, &token_id, &600);
-    assert_eq!(client.balance(&user), 400);
+    client.withdraw(&user, &user, &token_id, &600_000);
+    assert_eq!(client.balance(&user), 400_000);
 
     // Cancel
-    client.cancel_queued_withdrawal(&user);
+    let ticket = client.get_user_withdrawal_tickets(&user).get(0).unwrap();
+    client.cancel_queued_withdrawal(&ticket.id);
 
-    // Balance should be back to 1000
-    assert_eq!(client.balance(&user), 1000);
+    // Balance should be back to 1_000_000
+    assert_eq!(client.balance(&user), 1_000_000);
 }
 
 // ── Additional Coverage Tests ─────────────────────────
@@ -2302,7 +4088,6 @@ fn test_unauthorized_rebalance_rejected() {
 }
 
 #[test]
-#[should_panic]
 fn test_deposit_while_paused_fails() {
     let env = Env::default();
     env.mock_all_auths();
@@ -2319,11 +4104,11 @@ fn test_deposit_while_paused_fails() {
 
     client.set_paused(&true);
     let user = Address::generate(&env);
-    client.deposit(&user, &asset, &100, &None::<i128>);
+    let result = client.try_deposit(&user, &asset, &100, &None::<i128>);
+    assert_eq!(result, Err(Ok(Error::ContractPaused)));
 }
 
 #[test]
-#[should_panic]
 fn test_deposit_zero_fails() {
     let env = Env::default();
     env.mock_all_auths();
@@ -2336,11 +4121,11 @@ fn test_deposit_zero_fails() {
     let guardians = soroban_sdk::vec![&env, admin.clone()];
     client.init(&admin, &asset, &oracle, &treasury, &0u32, &guardians, &1u32);
 
-    client.deposit(&Address::generate(&env), &asset, &0, &None::<i128>);
+    let result = client.try_deposit(&Address::generate(&env), &asset, &0, &None::<i128>);
+    assert_eq!(result, Err(Ok(Error::InvalidAmount)));
 }
 
 #[test]
-#[should_panic]
 fn test_withdraw_cap_exceeded() {
     let env = Env::default();
     env.mock_all_auths();
@@ -2354,13 +4139,14 @@ fn test_withdraw_cap_exceeded() {
     client.init(&admin, &asset, &oracle, &treasury, &0u32, &guardians, &1u32);
 
     client.set_withdraw_cap(&100);
-    client.set_total_shares(&1000);
+    client.set_total_shares(&1_000_000);
     client.set_total_assets(&1000);
     let user = Address::generate(&env);
-    client.set_balance(&user, &200);
+    client.set_balance(&user, &200_000);
 
-    // Attempt withdrawal of 150 which exceeds cap of 100
-    client.withdraw(&user, &user, &asset, &150);
+    // Attempt withdrawal worth 150 which exceeds cap of 100
+    let result = client.try_withdraw(&user, &user, &asset, &150_000);
+    assert_eq!(result, Err(Ok(Error::WithdrawalCapExceeded)));
 }
 
 #[test]
@@ -2464,8 +4250,8 @@ fn test_batch_deposit_all_success() {
 
     let operations = soroban_sdk::vec![
         &env,
-        (user1.clone(), token_id.clone(), 500i128),
-        (user2.clone(), token_id.clone(), 500i128),
+        (user1.clone(), token_id.clone(), 1000i128),
+        (user2.clone(), token_id.clone(), 1000i128),
     ];
 
     let results = client.batch_deposit(&operations);
@@ -2473,9 +4259,9 @@ fn test_batch_deposit_all_success() {
     assert!(results.get(0).unwrap());
     assert!(results.get(1).unwrap());
 
-    assert_eq!(client.balance(&user1), 500);
-    assert_eq!(client.balance(&user2), 500);
-    assert_eq!(client.total_assets(), 1000);
+    assert_eq!(client.balance(&user1), 1_000_000);
+    assert_eq!(client.balance(&user2), 1_000_000);
+    assert_eq!(client.total_assets(), 2000);
 }
 
 #[test]
@@ -2506,7 +4292,7 @@ fn test_batch_deposit_partial_failure() {
 
     let operations = soroban_sdk::vec![
         &env,
-        (user1.clone(), token_id.clone(), 500i128), // success
+        (user1.clone(), token_id.clone(), 1000i128), // success
         (user2.clone(), token_id.clone(), -100i128), // fail: negative amount
         (user3.clone(), Address::generate(&env), 100i128), // fail: unsupported asset
     ];
@@ -2517,10 +4303,10 @@ fn test_batch_deposit_partial_failure() {
     assert!(!results.get(1).unwrap());
     assert!(!results.get(2).unwrap());
 
-    assert_eq!(client.balance(&user1), 500);
+    assert_eq!(client.balance(&user1), 1_000_000);
     assert_eq!(client.balance(&user2), 0);
     assert_eq!(client.balance(&user3), 0);
-    assert_eq!(client.total_assets(), 500);
+    assert_eq!(client.total_assets(), 1000);
 }
 
 #[test]
@@ -2544,19 +4330,19 @@ fn test_batch_withdraw_partial_failure() {
     );
 
     stellar_asset_client.mint(&contract_id, &5000);
-    client.set_total_shares(&1000);
+    client.set_total_shares(&1_000_000);
     client.set_total_assets(&5000);
 
     let user1 = Address::generate(&env);
     let user2 = Address::generate(&env);
-    client.set_balance(&user1, &500);
-    client.set_balance(&user2, &100);
+    client.set_balance(&user1, &500_000);
+    client.set_balance(&user2, &100_000);
 
     let operations = soroban_sdk::vec![
         &env,
-        (user1.clone(), token_id.clone(), 200i128), // success
-        (user2.clone(), token_id.clone(), 150i128), // fail: not enough balance
-        (user1.clone(), token_id.clone(), -50i128), // fail: negative amount
+        (user1.clone(), token_id.clone(), 200_000i128), // success
+        (user2.clone(), token_id.clone(), 150_000i128), // fail: not enough balance
+        (user1.clone(), token_id.clone(), -50i128),     // fail: negative amount
     ];
 
     let results = client.batch_withdraw(&operations);
@@ -2565,10 +4351,10 @@ fn test_batch_withdraw_partial_failure() {
     assert!(!results.get(1).unwrap());
     assert!(!results.get(2).unwrap());
 
-    assert_eq!(client.balance(&user1), 300);
-    assert_eq!(client.balance(&user2), 100);
-    assert_eq!(client.total_shares(), 800);
-    assert_eq!(client.total_assets(), 4000);
+    assert_eq!(client.balance(&user1), 300_000);
+    assert_eq!(client.balance(&user2), 100_000);
+    assert_eq!(client.total_shares(), 800_000);
+    assert_eq!(client.total_assets(), 4001);
 }
 
 #[test]
@@ -3132,7 +4918,6 @@ fn test_set_allowlist_mode() {
 }
 
 #[test]
-#[should_panic(expected = "Compliance check failed")]
 fn test_blocked_user_cannot_deposit() {
     let env = Env::default();
     env.mock_all_auths();
@@ -3159,12 +4944,11 @@ fn test_blocked_user_cannot_deposit() {
     // Mint tokens to blocked user
     stellar_asset_client.mint(&blocked_user, &1000);
 
-    // Attempt to deposit - should panic
-    client.deposit(&blocked_user, &token_id, &100, &None::<i128>);
+    let result = client.try_deposit(&blocked_user, &token_id, &100, &None::<i128>);
+    assert_eq!(result, Err(Ok(Error::UserBlocked)));
 }
 
 #[test]
-#[should_panic(expected = "Compliance check failed")]
 fn test_non_allowlisted_user_cannot_deposit() {
     let env = Env::default();
     env.mock_all_auths();
@@ -3193,8 +4977,8 @@ fn test_non_allowlisted_user_cannot_deposit() {
     // Mint tokens to non-allowlisted user
     stellar_asset_client.mint(&non_allowed_user, &1000);
 
-    // Attempt to deposit - should panic
-    client.deposit(&non_allowed_user, &token_id, &100, &None::<i128>);
+    let result = client.try_deposit(&non_allowed_user, &token_id, &100, &None::<i128>);
+    assert_eq!(result, Err(Ok(Error::UserBlocked)));
 }
 
 #[test]
@@ -3225,7 +5009,7 @@ fn test_allowlisted_user_can_deposit() {
     stellar_asset_client.mint(&allowed_user, &1000);
 
     // Deposit should succeed
-    client.deposit(&allowed_user, &token_id, &100, &None::<i128>);
+    client.deposit(&allowed_user, &token_id, &1000, &None::<i128>);
 
     // Verify deposit succeeded
     let balance = client.balance(&allowed_user);
@@ -3243,7 +5027,7 @@ fn test_get_vault_summary() {
     let summary = client.get_vault_summary();
     assert_eq!(summary.total_assets, 0);
     assert_eq!(summary.total_shares, 0);
-    assert_eq!(summary.share_price, 1_000_000_000); // 1.0 depending on implementation
+    assert_eq!(summary.share_price, 1_000_000); // 0.001: shares carry three extra decimals
     assert_eq!(summary.paused, false);
 
     // After init and deposit, total_assets and total_shares should reflect changes
@@ -3428,18 +5212,18 @@ fn test_deposit_state_consistent_before_token_transfer() {
     );
 
     // Mint tokens to user and capture pre-deposit vault state.
-    stellar_asset_client.mint(&user, &1000);
+    stellar_asset_client.mint(&user, &1500);
 
     let total_shares_before = client.total_shares();
     let total_assets_before = client.total_assets();
     let user_balance_before = client.balance(&user);
 
     // Perform a normal deposit — should succeed.
-    client.deposit(&user, &token_id, &500, &None);
+    client.deposit(&user, &token_id, &1000, &None);
 
     // State should have advanced.
-    assert_eq!(client.total_shares(), total_shares_before + 500);
-    assert_eq!(client.balance(&user), user_balance_before + 500);
+    assert_eq!(client.total_shares(), total_shares_before + 1_000_000);
+    assert_eq!(client.balance(&user), user_balance_before + 1_000_000);
 
     assert!(
         client.total_shares() > total_shares_before,
@@ -3456,7 +5240,7 @@ fn test_deposit_state_consistent_before_token_transfer() {
 
     // Vault should hold the deposited tokens.
     let vault_tokens = token_client.balance(&contract_id);
-    assert_eq!(vault_tokens, 500, "vault should hold deposited tokens");
+    assert_eq!(vault_tokens, 1000, "vault should hold deposited tokens");
 
     // Setup for failure: next deposit tries to transfer more than user has.
     let res = env.try_invoke_contract::<(), soroban_sdk::Error>(
@@ -3471,66 +5255,477 @@ fn test_deposit_state_consistent_before_token_transfer() {
         ],
     );
 
-    assert!(res.is_err());
+    assert!(res.is_err());
+
+    // Verify state was reverted correctly.
+    assert_eq!(client.total_shares(), total_shares_before + 1_000_000);
+    assert_eq!(client.balance(&user), user_balance_before + 1_000_000);
+}
+
+// ── SC-30: rebalance() deposit CEI ───────────────────────────────────────────
+/// Verify that a failed rebalance execution leaves vault token balances
+/// unchanged — no partial token transfers occur on rollback.
+#[test]
+fn test_rebalance_increase_no_tokens_moved_on_failed_execution() {
+    use mock_strategy::MockStrategyClient;
+
+    let env = Env::default();
+    env.mock_all_auths_allowing_non_root_auth();
+
+    let token_admin = Address::generate(&env);
+    let (token_id, stellar_asset_client, token_client) = create_token_contract(&env, &token_admin);
+
+    let vault_id = env.register_contract(None, VolatilityShield);
+    let client = VolatilityShieldClient::new(&env, &vault_id);
+
+    let admin = Address::generate(&env);
+    let oracle = Address::generate(&env);
+    let treasury = Address::generate(&env);
+
+    let guardians = soroban_sdk::vec![&env, admin.clone()];
+    client.init(
+        &admin, &token_id, &oracle, &treasury, &0u32, &guardians, &1u32,
+    );
+
+    stellar_asset_client.mint(&vault_id, &10_000);
+    client.set_total_assets(&10_000);
+
+    let strategy_id = env.register_contract(None, mock_strategy::MockStrategy);
+    let strategy_client = MockStrategyClient::new(&env, &strategy_id);
+    strategy_client.init(&vault_id, &token_id);
+    client.propose_action(&admin, &ActionType::AddStrategy(strategy_id.clone()));
+
+    let mut allocations: Map<Address, i128> = Map::new(&env);
+    allocations.set(strategy_id.clone(), 10000i128);
+    env.ledger().set_timestamp(12345);
+    client.set_oracle_data(&allocations, &env.ledger().timestamp());
+
+    let vault_balance_before = token_client.balance(&vault_id);
+    assert_eq!(vault_balance_before, 10_000);
+
+    let _ = client.try_propose_action(&admin, &ActionType::Rebalance(500u32));
+
+    // CEI invariant: regardless of whether rebalance succeeded or rolled back,
+    // total tokens across vault + strategy must equal the pre-rebalance balance —
+    // no tokens may be lost in transit.
+    let vault_balance_after = token_client.balance(&vault_id);
+    let strategy_balance_after = token_client.balance(&strategy_id);
+    assert_eq!(
+        vault_balance_after + strategy_balance_after,
+        10_000,
+        "no tokens may be lost during rebalance"
+    );
+}
+
+#[test]
+fn test_atomic_rebalance_commits_all_moves_or_none() {
+    let env = Env::default();
+    env.mock_all_auths_allowing_non_root_auth();
+
+    let token_admin = Address::generate(&env);
+    let (token_id, stellar_asset_client, token_client) = create_token_contract(&env, &token_admin);
+    let vault_id = env.register(VolatilityShield, ());
+    let client = VolatilityShieldClient::new(&env, &vault_id);
+
+    let admin = Address::generate(&env);
+    let guardians = soroban_sdk::vec![&env, admin.clone()];
+    client.init(
+        &admin,
+        &token_id,
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &0u32,
+        &guardians,
+        &1u32,
+    );
+    stellar_asset_client.mint(&vault_id, &10_000);
+    client.set_total_assets(&10_000);
+
+    let healthy_id = env.register(mock_strategy::MockStrategy, ());
+    let healthy = mock_strategy::MockStrategyClient::new(&env, &healthy_id);
+    healthy.init(&vault_id, &token_id);
+    client.propose_action(&admin, &ActionType::AddStrategy(healthy_id.clone()));
+    // Registered, but not a contract: every call to it fails.
+    let broken_id = Address::generate(&env);
+    client.propose_action(&admin, &ActionType::AddStrategy(broken_id.clone()));
+
+    let mut allocations: Map<Address, i128> = Map::new(&env);
+    allocations.set(healthy_id.clone(), 5_000);
+    allocations.set(broken_id.clone(), 5_000);
+    env.ledger().set_timestamp(12345);
+    client.set_oracle_data(&allocations, &env.ledger().timestamp());
+
+    assert_eq!(client.get_rebalance_mode(), RebalanceMode::BestEffort);
+    client.set_rebalance_mode(&RebalanceMode::Atomic);
+
+    let plan = client.simulate_rebalance(&500);
+    assert!(!plan.feasible);
+    assert_eq!(plan.moves.len(), 2);
+    for planned in plan.moves.iter() {
+        if planned.strategy == healthy_id {
+            assert_eq!(planned.delta, 5_000);
+            assert!(planned.executable);
+        } else {
+            assert_eq!(planned.delta, 0);
+            assert!(!planned.executable);
+        }
+    }
+
+    // The healthy strategy's move is not committed either.
+    assert_eq!(
+        client.try_propose_action(&admin, &ActionType::Rebalance(500)),
+        Err(Ok(Error::RebalanceAborted))
+    );
+    assert_eq!(healthy.balance(), 0);
+    assert_eq!(token_client.balance(&vault_id), 10_000);
+
+    let mut allocations: Map<Address, i128> = Map::new(&env);
+    allocations.set(healthy_id.clone(), 10_000);
+    env.ledger().set_timestamp(12346);
+    client.set_oracle_data(&allocations, &env.ledger().timestamp());
+    client.set_idle_buffer(&IdleBufferConfig {
+        target_bps: 1_000,
+        min_amount: 0,
+        max_amount: i128::MAX,
+    });
+
+    let plan = client.simulate_rebalance(&500);
+    assert!(plan.feasible);
+    assert_eq!(plan.idle_target, 1_000);
+    assert_eq!(plan.moves.get(0).unwrap().delta, 9_000);
+    assert_eq!(plan.moves.get(0).unwrap().slippage_bps, 0);
+
+    client.propose_action(&admin, &ActionType::Rebalance(500));
+    assert_eq!(healthy.balance(), 9_000);
+    assert_eq!(token_client.balance(&healthy_id), 9_000);
+    assert_eq!(client.get_idle_liquidity(), 1_000);
+
+    // Accounted assets grow, but only 1_000 idle tokens can fund the deposit:
+    // the strategy would end 740 bps short of its 10_800 target.
+    client.set_total_assets(&12_000);
+    let plan = client.simulate_rebalance(&500);
+    let planned = plan.moves.get(0).unwrap();
+    assert_eq!(planned.target_balance, 10_800);
+    assert_eq!(planned.delta, 1_000);
+    assert_eq!(planned.slippage_bps, 740);
+    assert!(!plan.feasible);
+    assert!(client.simulate_rebalance(&1_000).feasible);
+}
+
+#[test]
+fn test_incremental_rebalance_moves_in_capped_steps() {
+    let env = Env::default();
+    env.mock_all_auths_allowing_non_root_auth();
+
+    let token_admin = Address::generate(&env);
+    let (token_id, stellar_asset_client, token_client) = create_token_contract(&env, &token_admin);
+    let vault_id = env.register(VolatilityShield, ());
+    let client = VolatilityShieldClient::new(&env, &vault_id);
+
+    let admin = Address::generate(&env);
+    let guardians = soroban_sdk::vec![&env, admin.clone()];
+    client.init(
+        &admin,
+        &token_id,
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &0u32,
+        &guardians,
+        &1u32,
+    );
+    stellar_asset_client.mint(&vault_id, &10_000);
+    client.set_total_assets(&10_000);
+    client.set_total_shares(&10_000_000);
+
+    let mut allocations: Map<Address, i128> = Map::new(&env);
+    for _ in 0..2 {
+        let strategy_id = env.register(mock_strategy::MockStrategy, ());
+        mock_strategy::MockStrategyClient::new(&env, &strategy_id).init(&vault_id, &token_id);
+        client.propose_action(&admin, &ActionType::AddStrategy(strategy_id.clone()));
+        allocations.set(strategy_id, 5_000);
+    }
+    env.ledger().set_timestamp(12345);
+    client.set_oracle_data(&allocations, &env.ledger().timestamp());
+
+    let keeper = Address::generate(&env);
+    assert_eq!(
+        client.try_continue_rebalance(&keeper, &1),
+        Err(Ok(Error::NoRebalanceInProgress))
+    );
+    client.start_rebalance(&admin, &3_000);
+    assert_eq!(
+        client.try_start_rebalance(&admin, &3_000),
+        Err(Ok(Error::RebalanceInProgress))
+    );
+    assert_eq!(
+        client.try_propose_action(&admin, &ActionType::Rebalance(500)),
+        Err(Ok(Error::RebalanceInProgress))
+    );
+
+    let progress = client.get_rebalance_progress().unwrap();
+    assert_eq!(progress.cursor, 0);
+    assert_eq!(progress.strategies.len(), 2);
+    let first = mock_strategy::MockStrategyClient::new(&env, &progress.strategies.get(0).unwrap());
+    let second = mock_strategy::MockStrategyClient::new(&env, &progress.strategies.get(1).unwrap());
+
+    // The first strategy needs 5_000, moved as 3_000 then 2_000.
+    assert!(!client.continue_rebalance(&keeper, &1));
+    assert_eq!(first.balance(), 3_000);
+    assert_eq!(client.get_rebalance_progress().unwrap().cursor, 0);
+    assert!(!client.continue_rebalance(&keeper, &1));
+    assert_eq!(first.balance(), 5_000);
+    assert_eq!(client.get_rebalance_progress().unwrap().cursor, 1);
+
+    // A withdrawal in flight lowers total assets and idle tokens; the second
+    // strategy's target follows the live value (4_500) but can only be funded
+    // with the 4_000 tokens left idle.
+    let user = Address::generate(&env);
+    client.set_balance(&user, &1_000_000);
+    client.withdraw(&user, &user, &token_id, &1_000_000);
+    assert_eq!(token_client.balance(&user), 1_000);
+
+    assert!(client.continue_rebalance(&keeper, &5));
+    assert_eq!(second.balance(), 4_000);
+    assert_eq!(token_client.balance(&vault_id), 0);
+    assert_eq!(client.get_rebalance_progress(), None);
+
+    client.start_rebalance(&admin, &1_000);
+    client.cancel_rebalance();
+    assert_eq!(client.get_rebalance_progress(), None);
+    assert_eq!(
+        client.try_cancel_rebalance(),
+        Err(Ok(Error::NoRebalanceInProgress))
+    );
+}
+
+#[test]
+fn test_rebalance_withdraws_before_depositing_and_reports_outcomes() {
+    let env = Env::default();
+    env.mock_all_auths_allowing_non_root_auth();
+
+    let token_admin = Address::generate(&env);
+    let (token_id, stellar_asset_client, token_client) = create_token_contract(&env, &token_admin);
+    let vault_id = env.register(VolatilityShield, ());
+    let client = VolatilityShieldClient::new(&env, &vault_id);
+
+    let admin = Address::generate(&env);
+    let guardians = soroban_sdk::vec![&env, admin.clone()];
+    client.init(
+        &admin,
+        &token_id,
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &0u32,
+        &guardians,
+        &1u32,
+    );
+    client.set_total_assets(&10_000);
+
+    // Everything sits in `full`; the vault holds no idle tokens, so `empty`
+    // can only be funded from what `full` returns.
+    let full_id = env.register(mock_strategy::MockStrategy, ());
+    let full = mock_strategy::MockStrategyClient::new(&env, &full_id);
+    let empty_id = env.register(mock_strategy::MockStrategy, ());
+    let empty = mock_strategy::MockStrategyClient::new(&env, &empty_id);
+    let broken_id = Address::generate(&env);
+    for strategy_id in [&full_id, &empty_id] {
+        mock_strategy::MockStrategyClient::new(&env, strategy_id).init(&vault_id, &token_id);
+    }
+    for strategy_id in [&full_id, &empty_id, &broken_id] {
+        client.propose_action(&admin, &ActionType::AddStrategy(strategy_id.clone()));
+    }
+    stellar_asset_client.mint(&full_id, &10_000);
+    full.deposit(&10_000);
 
-    // Verify state was reverted correctly.
-    assert_eq!(client.total_shares(), total_shares_before + 500);
-    assert_eq!(client.balance(&user), user_balance_before + 500);
+    let mut allocations: Map<Address, i128> = Map::new(&env);
+    allocations.set(full_id.clone(), 5_000);
+    allocations.set(empty_id.clone(), 5_000);
+    allocations.set(broken_id.clone(), 0);
+    env.ledger().set_timestamp(12345);
+    client.set_oracle_data(&allocations, &env.ledger().timestamp());
+
+    assert_eq!(client.get_last_rebalance_report(), None);
+    client.propose_action(&admin, &ActionType::Rebalance(100));
+    assert_eq!(full.balance(), 5_000);
+    assert_eq!(empty.balance(), 5_000);
+    assert_eq!(token_client.balance(&empty_id), 5_000);
+    assert_eq!(token_client.balance(&vault_id), 0);
+
+    let report = client.get_last_rebalance_report().unwrap();
+    assert_eq!(report.withdrawn, 5_000);
+    assert_eq!(report.deposited, 5_000);
+    assert_eq!(report.successful_strategies, 2);
+    assert_eq!(report.outcomes.len(), 3);
+    let first = report.outcomes.get(0).unwrap();
+    assert_eq!(first.strategy, full_id);
+    assert_eq!(first.result, MoveResult::Withdrawn);
+    assert_eq!(first.moved, -5_000);
+    for outcome in report.outcomes.iter() {
+        if outcome.strategy == empty_id {
+            assert_eq!(outcome.result, MoveResult::Deposited);
+            assert_eq!(outcome.moved, 5_000);
+        } else if outcome.strategy == broken_id {
+            assert_eq!(outcome.result, MoveResult::Unreachable);
+        }
+    }
 }
 
-// ── SC-30: rebalance() deposit CEI ───────────────────────────────────────────
-/// Verify that a failed rebalance execution leaves vault token balances
-/// unchanged — no partial token transfers occur on rollback.
 #[test]
-fn test_rebalance_increase_no_tokens_moved_on_failed_execution() {
-    use mock_strategy::MockStrategyClient;
-
+fn test_keeper_pokes_rebalance_on_drift_or_interval() {
     let env = Env::default();
     env.mock_all_auths_allowing_non_root_auth();
 
     let token_admin = Address::generate(&env);
     let (token_id, stellar_asset_client, token_client) = create_token_contract(&env, &token_admin);
-
-    let vault_id = env.register_contract(None, VolatilityShield);
+    let vault_id = env.register(VolatilityShield, ());
     let client = VolatilityShieldClient::new(&env, &vault_id);
 
     let admin = Address::generate(&env);
-    let oracle = Address::generate(&env);
-    let treasury = Address::generate(&env);
-
     let guardians = soroban_sdk::vec![&env, admin.clone()];
     client.init(
-        &admin, &token_id, &oracle, &treasury, &0u32, &guardians, &1u32,
+        &admin,
+        &token_id,
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &0u32,
+        &guardians,
+        &1u32,
     );
-
     stellar_asset_client.mint(&vault_id, &10_000);
     client.set_total_assets(&10_000);
+    client.set_idle_buffer(&IdleBufferConfig {
+        target_bps: 0,
+        min_amount: 100,
+        max_amount: i128::MAX,
+    });
 
-    let strategy_id = env.register_contract(None, mock_strategy::MockStrategy);
-    let strategy_client = MockStrategyClient::new(&env, &strategy_id);
-    strategy_client.init(&vault_id, &token_id);
+    let strategy_id = env.register(mock_strategy::MockStrategy, ());
+    let strategy = mock_strategy::MockStrategyClient::new(&env, &strategy_id);
+    strategy.init(&vault_id, &token_id);
     client.propose_action(&admin, &ActionType::AddStrategy(strategy_id.clone()));
-
     let mut allocations: Map<Address, i128> = Map::new(&env);
-    allocations.set(strategy_id.clone(), 10000i128);
+    allocations.set(strategy_id.clone(), 10_000);
     env.ledger().set_timestamp(12345);
     client.set_oracle_data(&allocations, &env.ledger().timestamp());
 
-    let vault_balance_before = token_client.balance(&vault_id);
-    assert_eq!(vault_balance_before, 10_000);
-
-    let _ = client.try_propose_action(&admin, &ActionType::Rebalance(500u32));
+    // No trigger configured: keepers cannot rebalance.
+    let keeper = Address::generate(&env);
+    assert!(!client.rebalance_needed());
+    assert_eq!(
+        client.try_poke_rebalance(&keeper),
+        Err(Ok(Error::RebalanceNotNeeded))
+    );
 
-    // CEI invariant: regardless of whether rebalance succeeded or rolled back,
-    // total tokens across vault + strategy must equal the pre-rebalance balance —
-    // no tokens may be lost in transit.
-    let vault_balance_after = token_client.balance(&vault_id);
-    let strategy_balance_after = token_client.balance(&strategy_id);
+    let trigger = RebalanceTrigger {
+        drift_bps: 500,
+        min_interval: 0,
+        max_slippage_bps: 100,
+        keeper_reward: 10,
+    };
+    client.set_rebalance_trigger(&trigger);
+    assert_eq!(client.get_rebalance_trigger(), Some(trigger.clone()));
+
+    // The strategy holds nothing against a 100% target.
+    assert!(client.rebalance_needed());
+    let report = client.poke_rebalance(&keeper);
+    assert_eq!(report.deposited, 9_900);
+    assert_eq!(strategy.balance(), 9_900);
+    assert_eq!(token_client.balance(&keeper), 10);
+    assert_eq!(client.total_assets(), 9_990);
+
+    // Paying the reward leaves the strategy 10 bps over target.
+    assert!(!client.rebalance_needed());
     assert_eq!(
-        vault_balance_after + strategy_balance_after,
-        10_000,
-        "no tokens may be lost during rebalance"
+        client.try_poke_rebalance(&keeper),
+        Err(Ok(Error::RebalanceNotNeeded))
+    );
+
+    client.set_rebalance_trigger(&RebalanceTrigger {
+        min_interval: 600,
+        ..trigger
+    });
+    assert!(!client.rebalance_needed());
+    env.ledger().set_timestamp(12345 + 600);
+    assert!(client.rebalance_needed());
+    let report = client.poke_rebalance(&keeper);
+    assert_eq!(report.withdrawn, 10);
+    assert_eq!(token_client.balance(&keeper), 20);
+}
+
+#[test]
+fn test_keeper_reward_requires_progress_and_spacing() {
+    let env = Env::default();
+    env.mock_all_auths_allowing_non_root_auth();
+
+    let token_admin = Address::generate(&env);
+    let (token_id, stellar_asset_client, token_client) = create_token_contract(&env, &token_admin);
+    let vault_id = env.register(VolatilityShield, ());
+    let client = VolatilityShieldClient::new(&env, &vault_id);
+
+    let admin = Address::generate(&env);
+    let guardians = soroban_sdk::vec![&env, admin.clone()];
+    client.init(
+        &admin,
+        &token_id,
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &0u32,
+        &guardians,
+        &1u32,
     );
+    stellar_asset_client.mint(&vault_id, &10_000);
+    client.set_total_assets(&10_000);
+    client.set_idle_buffer(&IdleBufferConfig {
+        target_bps: 0,
+        min_amount: 100,
+        max_amount: i128::MAX,
+    });
+
+    let strategy_id = env.register(mock_strategy::MockStrategy, ());
+    let strategy = mock_strategy::MockStrategyClient::new(&env, &strategy_id);
+    strategy.init(&vault_id, &token_id);
+    strategy.set_failure_modes(&mock_strategy::FailureModes {
+        fail_deposit: true,
+        ..Default::default()
+    });
+    client.propose_action(&admin, &ActionType::AddStrategy(strategy_id.clone()));
+    let mut allocations: Map<Address, i128> = Map::new(&env);
+    allocations.set(strategy_id.clone(), 10_000);
+    env.ledger().set_timestamp(12345);
+    client.set_oracle_data(&allocations, &env.ledger().timestamp());
+    client.set_rebalance_trigger(&RebalanceTrigger {
+        drift_bps: 100,
+        min_interval: 0,
+        max_slippage_bps: 10_000,
+        keeper_reward: 100,
+    });
+
+    // The strategy rejects the deposit: nothing moves, so nothing is paid,
+    // and further pokes in the same ledger are refused.
+    let keeper = Address::generate(&env);
+    let report = client.poke_rebalance(&keeper);
+    assert_eq!(report.deposited, 0);
+    for _ in 0..20 {
+        assert_eq!(
+            client.try_poke_rebalance(&keeper),
+            Err(Ok(Error::RebalanceNotNeeded))
+        );
+    }
+    assert_eq!(token_client.balance(&keeper), 0);
+    assert_eq!(client.total_assets(), 10_000);
+
+    // Once the spacing has passed and the strategy accepts funds, the
+    // keeper is paid for the first poke only.
+    strategy.set_failure_modes(&mock_strategy::FailureModes::default());
+    env.ledger()
+        .set_timestamp(12345 + rebalance::MIN_POKE_SPACING);
+    let report = client.poke_rebalance(&keeper);
+    assert_eq!(report.deposited, 9_900);
+    for _ in 0..20 {
+        assert!(client.try_poke_rebalance(&keeper).is_err());
+    }
+    assert_eq!(token_client.balance(&keeper), 100);
+    assert_eq!(client.total_assets(), 9_900);
 }
 
 // ── SC-31: rebalance() withdraw transfer failure ──────────────────────────────
@@ -3674,17 +5869,17 @@ fn test_tvl_history_appends_on_deposit_and_withdraw() {
     stellar_asset_client.mint(&user, &1_000);
 
     env.ledger().set_sequence_number(50);
-    client.deposit(&user, &token_id, &500, &None::<i128>);
+    client.deposit(&user, &token_id, &1_000, &None::<i128>);
 
     env.ledger().set_sequence_number(60);
-    client.withdraw(&user, &user, &token_id, &200);
+    client.withdraw(&user, &user, &token_id, &200_000);
 
     let history = client.get_tvl_history(&0u64, &100u32);
     assert_eq!(history.len(), 2);
     assert_eq!(history.get(0).unwrap().0, 50);
-    assert_eq!(history.get(0).unwrap().1, 500);
+    assert_eq!(history.get(0).unwrap().1, 1_000);
     assert_eq!(history.get(1).unwrap().0, 60);
-    assert_eq!(history.get(1).unwrap().1, 300);
+    assert_eq!(history.get(1).unwrap().1, 800);
 }
 
 #[test]
@@ -3759,3 +5954,759 @@ fn test_tvl_history_range_query_filters_and_limits() {
     assert_eq!(beyond.len(), 0);
 }
 
+
+/// Replays the vault's events into `state`: user shares, total assets and
+/// shares, and blocklist membership.
+fn apply_vault_events(
+    env: &Env,
+    vault: &Address,
+    state: &mut (Map<Address, i128>, i128, i128, Vec<Address>, bool),
+) {
+    use soroban_sdk::testutils::Events as _;
+    use soroban_sdk::TryFromVal;
+
+    let (balances, total_assets, total_shares, blocklist, blocklist_mode) = state;
+    for (contract, topics, data) in env.events().all().iter() {
+        if &contract != vault {
+            continue;
+        }
+        assert_eq!(
+            u32::try_from_val(env, &topics.get(1).unwrap()).unwrap(),
+            EVENT_SCHEMA_VERSION
+        );
+        let name = Symbol::try_from_val(env, &topics.get(0).unwrap()).unwrap();
+        if name == Symbol::new(env, "Deposited") {
+            let event = events::Deposited::try_from_val(env, &data).unwrap();
+            let shares = balances.get(event.depositor.clone()).unwrap_or(0);
+            balances.set(event.depositor, shares + event.shares_minted);
+            *total_assets = event.total_assets;
+            *total_shares = event.total_shares;
+        } else if name == Symbol::new(env, "Withdrawn") {
+            let event = events::Withdrawn::try_from_val(env, &data).unwrap();
+            let shares = balances.get(event.withdrawer.clone()).unwrap_or(0);
+            balances.set(event.withdrawer, shares - event.shares_burned);
+            *total_assets = event.total_assets;
+            *total_shares = event.total_shares;
+        } else if name == Symbol::new(env, "UserBlocked") {
+            let event = events::UserBlocked::try_from_val(env, &data).unwrap();
+            blocklist.push_back(event.user);
+        } else if name == Symbol::new(env, "UserUnblocked") {
+            let event = events::UserUnblocked::try_from_val(env, &data).unwrap();
+            let index = blocklist.first_index_of(event.user).unwrap();
+            blocklist.remove(index);
+        } else if name == Symbol::new(env, "BlocklistModeSet") {
+            *blocklist_mode = events::BlocklistModeSet::try_from_val(env, &data)
+                .unwrap()
+                .active;
+        }
+    }
+}
+
+#[test]
+fn test_event_stream_reconstructs_vault_state() {
+    let env = Env::default();
+    env.mock_all_auths_allowing_non_root_auth();
+
+    let token_admin = Address::generate(&env);
+    let (token_id, stellar_asset_client, _) = create_token_contract(&env, &token_admin);
+    let contract_id = env.register(VolatilityShield, ());
+    let client = VolatilityShieldClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let guardians = soroban_sdk::vec![&env, admin.clone()];
+    client.init(
+        &admin,
+        &token_id,
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &0u32,
+        &guardians,
+        &1u32,
+    );
+
+    let mut state = (Map::new(&env), 0_i128, 0_i128, Vec::new(&env), false);
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    stellar_asset_client.mint(&alice, &1_000);
+    stellar_asset_client.mint(&bob, &500);
+
+    client.deposit(&alice, &token_id, &1_000, &None::<i128>);
+    apply_vault_events(&env, &contract_id, &mut state);
+    client.deposit(&bob, &token_id, &500, &None::<i128>);
+    apply_vault_events(&env, &contract_id, &mut state);
+    client.withdraw(&alice, &alice, &token_id, &300_000);
+    apply_vault_events(&env, &contract_id, &mut state);
+
+    client.set_blocklist_mode(&true);
+    apply_vault_events(&env, &contract_id, &mut state);
+    client.add_to_blocklist(&alice);
+    apply_vault_events(&env, &contract_id, &mut state);
+    client.add_to_blocklist(&bob);
+    apply_vault_events(&env, &contract_id, &mut state);
+    client.remove_from_blocklist(&alice);
+    apply_vault_events(&env, &contract_id, &mut state);
+
+    let (balances, total_assets, total_shares, blocklist, blocklist_mode) = state;
+    assert_eq!(balances.get(alice.clone()).unwrap(), client.balance(&alice));
+    assert_eq!(balances.get(bob.clone()).unwrap(), client.balance(&bob));
+    assert_eq!(total_assets, client.total_assets());
+    assert_eq!(total_shares, client.total_shares());
+    assert_eq!(total_assets, 1_200);
+    assert_eq!(blocklist, client.get_blocklist());
+    assert_eq!(blocklist, soroban_sdk::vec![&env, bob]);
+    assert_eq!(blocklist_mode, client.is_blocklist_mode_active());
+}
+
+mod misbehaving_strategy_tests {
+    use super::*;
+    use mock_strategy::{FailureModes, MockStrategyClient, Reentry};
+
+    /// A vault holding 10_000 idle tokens, with the allocation split equally
+    /// between `count` real-token mock strategies.
+    fn setup(env: &Env, count: u32) -> TestVault<'_> {
+        let vault = VaultBuilder::new(env).strategies(count).build();
+        vault.token_admin.mint(&vault.id, &10_000);
+        vault.client.set_total_assets(&10_000);
+        vault.client.set_total_shares(&10_000);
+
+        let mut allocations: Map<Address, i128> = Map::new(env);
+        for strategy in vault.strategies.iter() {
+            allocations.set(strategy, 10_000 / count as i128);
+        }
+        env.ledger().set_timestamp(12345);
+        vault
+            .client
+            .set_oracle_data(&allocations, &env.ledger().timestamp());
+        vault
+    }
+
+    /// Move the whole allocation of a two-strategy vault to the second one.
+    fn shift_to_second(env: &Env, vault: &TestVault) {
+        let mut allocations: Map<Address, i128> = Map::new(env);
+        allocations.set(vault.strategies.get(0).unwrap(), 0);
+        allocations.set(vault.strategies.get(1).unwrap(), 10_000);
+        env.ledger().set_timestamp(env.ledger().timestamp() + 1);
+        vault
+            .client
+            .set_oracle_data(&allocations, &env.ledger().timestamp());
+    }
+
+    #[test]
+    fn test_best_effort_rebalance_skips_strategy_rejecting_deposits() {
+        let env = Env::default();
+        let vault = setup(&env, 2);
+        let broken = vault.strategies.get(0).unwrap();
+        let healthy = vault.strategies.get(1).unwrap();
+        MockStrategyClient::new(&env, &broken).set_failure_modes(&FailureModes {
+            fail_deposit: true,
+            ..Default::default()
+        });
+
+        vault
+            .client
+            .propose_action(&vault.admin, &ActionType::Rebalance(10_000));
+        assert!(published(&env, "RebalancePartialFailure"));
+
+        let report = vault.client.get_last_rebalance_report().unwrap();
+        assert_eq!(report.successful_strategies, 1);
+        assert_eq!(report.deposited, 5_000);
+        for outcome in report.outcomes.iter() {
+            if outcome.strategy == broken {
+                assert_eq!(outcome.result, MoveResult::Failed);
+            } else {
+                assert_eq!(outcome.result, MoveResult::Deposited);
+            }
+        }
+        let health = vault.client.get_strategy_health(&broken).unwrap();
+        assert!(!health.is_healthy);
+        assert_eq!(vault.token_client.balance(&broken), 0);
+        assert_eq!(vault.token_client.balance(&healthy), 5_000);
+        assert_eq!(vault.token_client.balance(&vault.id), 5_000);
+    }
+
+    #[test]
+    fn test_atomic_rebalance_aborts_when_strategy_rejects_withdrawal() {
+        let env = Env::default();
+        let vault = setup(&env, 2);
+        vault
+            .client
+            .propose_action(&vault.admin, &ActionType::Rebalance(100));
+        let broken = vault.strategies.get(0).unwrap();
+        let healthy = vault.strategies.get(1).unwrap();
+        MockStrategyClient::new(&env, &broken).set_failure_modes(&FailureModes {
+            fail_withdraw: true,
+            ..Default::default()
+        });
+        shift_to_second(&env, &vault);
+        vault.client.set_rebalance_mode(&RebalanceMode::Atomic);
+
+        assert_eq!(
+            vault
+                .client
+                .try_propose_action(&vault.admin, &ActionType::Rebalance(100)),
+            Err(Ok(Error::RebalanceAborted))
+        );
+        assert_eq!(MockStrategyClient::new(&env, &broken).balance(), 5_000);
+        assert_eq!(vault.token_client.balance(&broken), 5_000);
+        assert_eq!(vault.token_client.balance(&healthy), 5_000);
+    }
+
+    #[test]
+    fn test_rebalance_pulls_tokens_strategy_kept_back() {
+        let env = Env::default();
+        let vault = setup(&env, 2);
+        vault
+            .client
+            .propose_action(&vault.admin, &ActionType::Rebalance(100));
+        let short = vault.strategies.get(0).unwrap();
+        let healthy = vault.strategies.get(1).unwrap();
+        MockStrategyClient::new(&env, &short).set_failure_modes(&FailureModes {
+            withdraw_shortfall_bps: 5_000,
+            ..Default::default()
+        });
+        shift_to_second(&env, &vault);
+
+        vault
+            .client
+            .propose_action(&vault.admin, &ActionType::Rebalance(100));
+        let report = vault.client.get_last_rebalance_report().unwrap();
+        let outcome = report.outcomes.get(0).unwrap();
+        assert_eq!(outcome.result, MoveResult::Withdrawn);
+        assert_eq!(report.withdrawn, 5_000);
+        assert_eq!(vault.token_client.balance(&short), 0);
+        // The strategy still reports the tokens it kept back.
+        assert_eq!(MockStrategyClient::new(&env, &short).balance(), 2_500);
+        assert_eq!(vault.token_client.balance(&healthy), 10_000);
+    }
+
+    #[test]
+    fn test_rebalance_reports_withdrawal_fee_as_failed_transfer() {
+        let env = Env::default();
+        let vault = setup(&env, 2);
+        vault
+            .client
+            .propose_action(&vault.admin, &ActionType::Rebalance(100));
+        let charging = vault.strategies.get(0).unwrap();
+        let strategy = MockStrategyClient::new(&env, &charging);
+        strategy.set_failure_modes(&FailureModes {
+            withdraw_fee_bps: 1_000,
+            ..Default::default()
+        });
+        shift_to_second(&env, &vault);
+
+        // The burned fee can never be pulled back, so the move is undone.
+        vault
+            .client
+            .propose_action(&vault.admin, &ActionType::Rebalance(10_000));
+        assert!(published(&env, "RebalanceWithdrawTransferFailed"));
+        let report = vault.client.get_last_rebalance_report().unwrap();
+        let outcome = report.outcomes.get(0).unwrap();
+        assert_eq!(outcome.strategy, charging);
+        assert_eq!(outcome.result, MoveResult::Failed);
+        assert_eq!(strategy.balance(), 5_000);
+    }
+
+    #[test]
+    fn test_fulfil_books_only_tokens_received_from_strategy() {
+        let env = Env::default();
+        let vault = setup(&env, 1);
+        vault
+            .client
+            .propose_action(&vault.admin, &ActionType::Rebalance(100));
+        let charging = vault.strategies.get(0).unwrap();
+        let strategy = MockStrategyClient::new(&env, &charging);
+        assert_eq!(strategy.balance(), 10_000);
+        strategy.set_failure_modes(&FailureModes {
+            withdraw_fee_bps: 1_000,
+            withdraw_shortfall_bps: 5_000,
+            ..Default::default()
+        });
+
+        let user = Address::generate(&env);
+        vault.client.set_balance(&user, &5_000);
+        let ticket = vault
+            .client
+            .queue_withdraw(&user, &user, &vault.token_client.address, &5_000);
+
+        // Each call unwinds what it can; the ticket settles once enough
+        // tokens have arrived.
+        let mut calls = 0;
+        while vault.client.fulfil_withdrawals(&vault.admin, &1) == 0 {
+            calls += 1;
+            assert!(calls < 20);
+        }
+        assert!(calls > 0);
+        let claim = vault.client.get_claimable_withdrawal(&ticket).unwrap();
+
+        // The strategy kept back part of each withdrawal and burned a fee.
+        // Only the fee is lost, and harvest reports it.
+        let held = vault.token_client.balance(&vault.id) + vault.token_client.balance(&charging);
+        assert_eq!(strategy.balance(), vault.token_client.balance(&charging));
+        let fees = 10_000 - held;
+        assert!(fees > 0);
+        assert_eq!(vault.client.harvest(), -fees);
+        assert_eq!(
+            vault.client.total_assets(),
+            vault.token_client.balance(&vault.id) - claim.amount + strategy.balance()
+        );
+    }
+
+    #[test]
+    fn test_unreadable_strategy_balance() {
+        let env = Env::default();
+        let vault = setup(&env, 2);
+        let broken = vault.strategies.get(0).unwrap();
+        MockStrategyClient::new(&env, &broken).set_failure_modes(&FailureModes {
+            fail_balance: true,
+            ..Default::default()
+        });
+
+        vault
+            .client
+            .propose_action(&vault.admin, &ActionType::Rebalance(100));
+        let report = vault.client.get_last_rebalance_report().unwrap();
+        for outcome in report.outcomes.iter() {
+            if outcome.strategy == broken {
+                assert_eq!(outcome.result, MoveResult::Unreachable);
+            } else {
+                assert_eq!(outcome.result, MoveResult::Deposited);
+            }
+        }
+        let health = vault.client.get_strategy_health(&broken).unwrap();
+        assert!(!health.is_healthy);
+
+        // Harvest and the health check skip the strategy and keep it flagged.
+        assert_eq!(vault.client.harvest(), 0);
+        let unhealthy = vault.client.check_strategy_health();
+        assert!(unhealthy.contains(&broken));
+        assert!(!vault.client.get_strategy_health(&broken).unwrap().is_healthy);
+    }
+
+    #[test]
+    fn test_rebalance_respects_strategy_capacity() {
+        let env = Env::default();
+        let vault = setup(&env, 1);
+        let capped = vault.strategies.get(0).unwrap();
+        MockStrategyClient::new(&env, &capped).set_failure_modes(&FailureModes {
+            capacity: Some(2_000),
+            ..Default::default()
+        });
+
+        vault
+            .client
+            .propose_action(&vault.admin, &ActionType::Rebalance(10_000));
+        let report = vault.client.get_last_rebalance_report().unwrap();
+        assert_eq!(report.outcomes.get(0).unwrap().result, MoveResult::Failed);
+        assert_eq!(vault.token_client.balance(&vault.id), 10_000);
+    }
+
+    #[test]
+    fn test_deposit_loss_trips_slippage_and_health_checks() {
+        let env = Env::default();
+        let vault = setup(&env, 1);
+        let lossy = vault.strategies.get(0).unwrap();
+        MockStrategyClient::new(&env, &lossy).set_failure_modes(&FailureModes {
+            deposit_loss_bps: 2_000,
+            ..Default::default()
+        });
+
+        assert_eq!(
+            vault
+                .client
+                .try_propose_action(&vault.admin, &ActionType::Rebalance(100)),
+            Err(Ok(Error::SlippageExceeded))
+        );
+
+        // Accepting the loss leaves the strategy 20% under its target.
+        vault
+            .client
+            .propose_action(&vault.admin, &ActionType::Rebalance(10_000));
+        assert_eq!(MockStrategyClient::new(&env, &lossy).balance(), 8_000);
+        vault.client.set_max_consecutive_failures(&1);
+        let unhealthy = vault.client.check_strategy_health();
+        assert_eq!(unhealthy, soroban_sdk::vec![&env, lossy]);
+    }
+
+    #[test]
+    fn test_health_check_leaves_idle_buffer_out_of_expected_balance() {
+        let env = Env::default();
+        let vault = setup(&env, 1);
+        let strategy = vault.strategies.get(0).unwrap();
+        vault.client.set_idle_buffer(&IdleBufferConfig {
+            target_bps: 2_000,
+            min_amount: 0,
+            max_amount: i128::MAX,
+        });
+
+        vault
+            .client
+            .propose_action(&vault.admin, &ActionType::Rebalance(100));
+        assert_eq!(MockStrategyClient::new(&env, &strategy).balance(), 8_000);
+
+        // 8_000 is on target once the 2_000 buffer is left out.
+        vault.client.set_max_consecutive_failures(&1);
+        assert!(vault.client.check_strategy_health().is_empty());
+        let health = vault.client.get_strategy_health(&strategy).unwrap();
+        assert!(health.is_healthy);
+        assert_eq!(health.consecutive_failures, 0);
+    }
+
+    #[test]
+    fn test_vault_rejects_reentry_from_strategy() {
+        let env = Env::default();
+        let vault = setup(&env, 2);
+        let reentrant = MockStrategyClient::new(&env, &vault.strategies.get(0).unwrap());
+        reentrant.set_failure_modes(&FailureModes {
+            reentry: Reentry::Deposit,
+            ..Default::default()
+        });
+        vault
+            .client
+            .propose_action(&vault.admin, &ActionType::Rebalance(100));
+        assert_eq!(reentrant.reentry_accepted(), Some(false));
+        assert_eq!(reentrant.balance(), 5_000);
+
+        reentrant.set_failure_modes(&FailureModes {
+            reentry: Reentry::Withdraw,
+            ..Default::default()
+        });
+        shift_to_second(&env, &vault);
+        vault
+            .client
+            .propose_action(&vault.admin, &ActionType::Rebalance(100));
+        assert_eq!(reentrant.reentry_accepted(), Some(false));
+        assert_eq!(reentrant.balance(), 0);
+        assert_eq!(vault.client.total_assets(), 10_000);
+    }
+}
+
+mod oracle_tests {
+    use super::*;
+    use mock_oracle::{FailureMode, MockOracleClient, PriceData};
+
+    struct Setup<'a> {
+        vault: VolatilityShieldClient<'a>,
+        oracle: MockOracleClient<'a>,
+        admin: Address,
+        user: Address,
+        alt: Address,
+        strategy: Address,
+    }
+
+    /// A vault priced by a `MockOracle`, accepting a second asset worth $2
+    /// that `user` holds 1_000 of, with one registered strategy.
+    fn setup(env: &Env) -> Setup<'_> {
+        env.ledger().set_timestamp(1_000);
+        let oracle_id = env.register(mock_oracle::MockOracle, ());
+        let vault = VaultBuilder::new(env)
+            .oracle(&oracle_id)
+            .strategies(1)
+            .build();
+        let oracle = MockOracleClient::new(env, &oracle_id);
+        oracle.init(&vault.admin);
+
+        let (alt, alt_admin, _) = create_token_contract(env, &vault.admin);
+        vault.client.add_supported_asset(&alt);
+        oracle.set_price(&vault.admin, &alt, &2_000_000_000);
+        let user = Address::generate(env);
+        alt_admin.mint(&user, &1_000);
+        Setup {
+            strategy: vault.strategies.get(0).unwrap(),
+            vault: vault.client,
+            oracle,
+            admin: vault.admin,
+            user,
+            alt,
+        }
+    }
+
+    fn full_allocation(env: &Env, strategy: &Address) -> Map<Address, i128> {
+        let mut allocations = Map::new(env);
+        allocations.set(strategy.clone(), 10_000);
+        allocations
+    }
+
+    #[test]
+    fn test_mock_oracle_serves_timestamped_history() {
+        let env = Env::default();
+        let s = setup(&env);
+        assert_eq!(
+            s.oracle.try_init(&s.admin),
+            Err(Ok(mock_oracle::Error::AlreadyInitialized))
+        );
+        assert_eq!(s.oracle.decimals(), 9);
+
+        env.ledger().set_timestamp(1_100);
+        s.oracle.set_price(&s.admin, &s.alt, &2_100_000_000);
+        env.ledger().set_timestamp(1_200);
+        s.oracle.set_price(&s.admin, &s.alt, &1_900_000_000);
+        let at = |price, timestamp| PriceData { price, timestamp };
+
+        assert_eq!(s.oracle.last_timestamp(), 1_200);
+        assert_eq!(s.oracle.lastprice(&s.alt), Some(at(1_900_000_000, 1_200)));
+        assert_eq!(s.oracle.price(&s.alt), 1_900_000_000);
+        assert_eq!(
+            s.oracle.price_at(&s.alt, &1_150),
+            Some(at(2_100_000_000, 1_100))
+        );
+        assert_eq!(s.oracle.price_at(&s.alt, &999), None);
+        assert_eq!(
+            s.oracle.prices(&s.alt, &2),
+            Some(soroban_sdk::vec![
+                &env,
+                at(1_900_000_000, 1_200),
+                at(2_100_000_000, 1_100)
+            ])
+        );
+
+        // Unknown assets are not priced at 1.0.
+        let unknown = Address::generate(&env);
+        assert_eq!(s.oracle.lastprice(&unknown), None);
+        assert_eq!(s.oracle.prices(&unknown, &5), None);
+        assert_eq!(
+            s.oracle.try_price(&unknown),
+            Err(Ok(soroban_sdk::Error::from_contract_error(
+                mock_oracle::Error::NoPrice as u32
+            )))
+        );
+    }
+
+    #[test]
+    fn test_oracle_pushes_allocations_into_vault() {
+        let env = Env::default();
+        let s = setup(&env);
+        let allocations = full_allocation(&env, &s.strategy);
+
+        s.oracle
+            .push_allocations(&s.admin, &s.vault.address, &allocations);
+        assert_eq!(s.vault.get_vault_summary().oracle_last_update, 1_000);
+
+        // The vault validates pushed data like any other oracle update.
+        let mut unknown = Map::new(&env);
+        unknown.set(Address::generate(&env), 10_000);
+        env.ledger().set_timestamp(1_100);
+        assert!(s
+            .oracle
+            .try_push_allocations(&s.admin, &s.vault.address, &unknown)
+            .is_err());
+        assert_eq!(s.vault.get_vault_summary().oracle_last_update, 1_000);
+    }
+
+    #[test]
+    fn test_stale_oracle_feed_trips_vault_staleness_check() {
+        let env = Env::default();
+        let s = setup(&env);
+        let allocations = full_allocation(&env, &s.strategy);
+        s.oracle
+            .push_allocations(&s.admin, &s.vault.address, &allocations);
+
+        s.oracle.set_failure_mode(&s.admin, &FailureMode::Stale);
+        env.ledger().set_timestamp(1_000 + 3_601);
+        s.oracle.set_price(&s.admin, &s.alt, &3_000_000_000);
+        assert_eq!(s.oracle.price(&s.alt), 2_000_000_000);
+        assert_eq!(s.oracle.last_timestamp(), 1_000);
+
+        // The frozen feed cannot refresh the vault's data.
+        assert!(s
+            .oracle
+            .try_push_allocations(&s.admin, &s.vault.address, &allocations)
+            .is_err());
+        assert_eq!(
+            s.vault
+                .try_propose_action(&s.admin, &ActionType::Rebalance(100)),
+            Err(Ok(Error::StaleOracleData))
+        );
+
+        s.oracle.set_failure_mode(&s.admin, &FailureMode::None);
+        assert_eq!(s.oracle.price(&s.alt), 3_000_000_000);
+        s.oracle
+            .push_allocations(&s.admin, &s.vault.address, &allocations);
+        s.vault
+            .propose_action(&s.admin, &ActionType::Rebalance(100));
+    }
+
+    #[test]
+    fn test_vault_rejects_invalid_oracle_prices() {
+        let env = Env::default();
+        let s = setup(&env);
+        s.vault.deposit(&s.user, &s.alt, &500, &None::<i128>);
+        assert_eq!(s.vault.balance(&s.user), 1_000_000);
+
+        for mode in [FailureMode::Zero, FailureMode::Negative] {
+            s.oracle.set_failure_mode(&s.admin, &mode);
+            assert_eq!(
+                s.vault.try_deposit(&s.user, &s.alt, &100, &None::<i128>),
+                Err(Ok(Error::InvalidPrice))
+            );
+            assert_eq!(
+                s.vault.try_withdraw(&s.user, &s.user, &s.alt, &100_000),
+                Err(Ok(Error::InvalidPrice))
+            );
+            assert_eq!(
+                s.vault.try_queue_withdraw(&s.user, &s.user, &s.alt, &100_000),
+                Err(Ok(Error::InvalidPrice))
+            );
+            assert_eq!(
+                s.vault.try_get_asset_price(&s.alt),
+                Err(Ok(Error::InvalidPrice))
+            );
+        }
+
+        s.oracle.set_failure_mode(&s.admin, &FailureMode::Revert);
+        assert_eq!(
+            s.oracle.try_lastprice(&s.alt),
+            Err(Ok(soroban_sdk::Error::from_contract_error(
+                mock_oracle::Error::PriceUnavailable as u32
+            )))
+        );
+        assert!(s
+            .vault
+            .try_deposit(&s.user, &s.alt, &100, &None::<i128>)
+            .is_err());
+        assert_eq!(s.vault.balance(&s.user), 1_000_000);
+
+        s.oracle.set_failure_mode(&s.admin, &FailureMode::None);
+        s.vault.deposit(&s.user, &s.alt, &100, &None::<i128>);
+        assert_eq!(s.vault.balance(&s.user), 1_200_000);
+    }
+}
+
+mod inflation_attack_tests {
+    use super::*;
+    use mock_strategy::MockStrategyClient;
+
+    struct Setup<'a> {
+        vault: VolatilityShieldClient<'a>,
+        strategy: MockStrategyClient<'a>,
+        token: Address,
+        token_client: TokenClient<'a>,
+        attacker: Address,
+        victim: Address,
+    }
+
+    /// An empty vault with one registered strategy and a permissionless
+    /// harvest, so anyone can raise `total_assets` without minting shares.
+    fn setup(env: &Env) -> Setup<'_> {
+        let vault = VaultBuilder::new(env).strategies(1).build();
+        vault.client.set_harvest_interval(&1);
+
+        let attacker = Address::generate(env);
+        let victim = Address::generate(env);
+        vault.token_admin.mint(&attacker, &1_000_000);
+        vault.token_admin.mint(&victim, &1_000_000);
+        Setup {
+            strategy: MockStrategyClient::new(env, &vault.strategies.get(0).unwrap()),
+            vault: vault.client,
+            token: vault.token,
+            token_client: vault.token_client,
+            attacker,
+            victim,
+        }
+    }
+
+    /// Donate `amount` to the strategy and harvest it into `total_assets`.
+    fn donate(env: &Env, s: &Setup, amount: i128) {
+        s.token_client
+            .transfer(&s.attacker, &s.strategy.address, &amount);
+        s.strategy.deposit(&amount);
+        env.ledger()
+            .set_sequence_number(env.ledger().sequence() + 1);
+        s.vault.harvest();
+    }
+
+    #[test]
+    fn test_donation_attack_on_first_depositor_is_unprofitable() {
+        let env = Env::default();
+        let s = setup(&env);
+
+        // Seed the vault with the minimum and inflate the share price.
+        s.vault
+            .deposit(&s.attacker, &s.token, &1_000, &None::<i128>);
+        assert_eq!(s.vault.balance(&s.attacker), 1_000_000);
+        donate(&env, &s, 10_000);
+        assert_eq!(s.vault.total_assets(), 11_000);
+
+        // The virtual shares soak up most of the donation, so the victim
+        // still gets a fair number of shares.
+        s.vault.deposit(&s.victim, &s.token, &10_000, &None::<i128>);
+        assert_eq!(s.vault.balance(&s.victim), 909_917);
+
+        // The attacker exits at a loss.
+        s.vault
+            .withdraw(&s.attacker, &s.attacker, &s.token, &1_000_000);
+        let spent = 11_000;
+        let redeemed = s.token_client.balance(&s.attacker) - (1_000_000 - spent);
+        assert_eq!(redeemed, 10_990);
+
+        // The victim redeems their full deposit. Most of it sits in the
+        // strategy, so the withdrawal is queued and fulfilled from there.
+        s.vault.withdraw(&s.victim, &s.victim, &s.token, &909_917);
+        let ticket = s
+            .vault
+            .get_user_withdrawal_tickets(&s.victim)
+            .get(0)
+            .unwrap();
+        assert_eq!(s.vault.fulfil_withdrawals(&s.victim, &1), 1);
+        assert_eq!(s.vault.claim_withdrawal(&ticket.id), 10_000);
+        assert_eq!(s.token_client.balance(&s.victim), 1_000_000);
+    }
+
+    #[test]
+    fn test_deposit_minting_no_shares_is_rejected() {
+        let env = Env::default();
+        let s = setup(&env);
+        s.vault.set_min_initial_deposit(&0);
+
+        // Even without a minimum, a one-unit seed and a large donation cannot
+        // round a later deposit down to nothing: it is rejected instead, and
+        // the attacker has handed half the donation to the virtual shares.
+        s.vault.deposit(&s.attacker, &s.token, &1, &None::<i128>);
+        donate(&env, &s, 999_999);
+        assert_eq!(s.vault.convert_to_assets(&1_000), 500_000);
+        assert_eq!(
+            s.vault
+                .try_deposit(&s.victim, &s.token, &400, &None::<i128>),
+            Err(Ok(Error::InvalidAmount))
+        );
+        assert_eq!(s.token_client.balance(&s.victim), 1_000_000);
+    }
+
+    #[test]
+    fn test_min_initial_deposit_applies_to_empty_vault_only() {
+        let env = Env::default();
+        let s = setup(&env);
+        assert_eq!(s.vault.get_min_initial_deposit(), 1_000);
+        assert_eq!(
+            s.vault.try_set_min_initial_deposit(&-1),
+            Err(Ok(Error::InvalidConfig))
+        );
+
+        assert_eq!(
+            s.vault
+                .try_deposit(&s.attacker, &s.token, &999, &None::<i128>),
+            Err(Ok(Error::InvalidAmount))
+        );
+        let mut operations = soroban_sdk::Vec::new(&env);
+        operations.push_back((s.attacker.clone(), s.token.clone(), 1));
+        assert_eq!(
+            s.vault.batch_deposit(&operations),
+            soroban_sdk::vec![&env, false]
+        );
+        assert_eq!(s.vault.total_shares(), 0);
+
+        s.vault.set_min_initial_deposit(&100_000);
+        assert_eq!(s.vault.get_min_initial_deposit(), 100_000);
+        assert_eq!(
+            s.vault
+                .try_deposit(&s.victim, &s.token, &99_999, &None::<i128>),
+            Err(Ok(Error::InvalidAmount))
+        );
+        s.vault
+            .deposit(&s.victim, &s.token, &100_000, &None::<i128>);
+        assert_eq!(s.vault.balance(&s.victim), 100_000_000);
+
+        // Once the vault holds shares, smaller deposits are accepted again.
+        s.vault.deposit(&s.attacker, &s.token, &1, &None::<i128>);
+        assert_eq!(s.vault.balance(&s.attacker), 1_000);
+    }
+}
@@ -0,0 +1,225 @@
+//! TTL policies for persistent and instance storage.
+//!
+//! Every persistent key belongs to a `TtlKeyType`, and the admin can tune the
+//! `(threshold, extend_to)` pair used for each type. Contracts cannot read the
+//! TTL of an entry, so for user balances the vault additionally records the
+//! ledger it last extended the entry to; `get_ttl_status` reports that value.
+
+use soroban_sdk::{contracttype, Address, Env, IntoVal, Symbol, Val};
+
+use crate::collections::CollectionKey;
+use crate::metadata::MetadataKey;
+use crate::{DataKey, BALANCE_TTL_BUMP, BALANCE_TTL_THRESHOLD};
+
+/// Groups of storage entries that share a TTL policy.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TtlKeyType {
+    /// The contract instance and everything stored in it.
+    Instance,
+    /// User positions: share balances, per-asset balances and delegates.
+    Balance,
+    /// Proposals, vote records and tallies.
+    Governance,
+    /// The withdrawal queue.
+    Withdrawal,
+    /// Blocklist and allowlist entries.
+    Compliance,
+    /// Share price, pause, TVL and strategy yield histories.
+    History,
+    /// Published vault metadata revisions.
+    Metadata,
+}
+
+/// Extend an entry to `extend_to` ledgers once it has fewer than `threshold` left.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TtlPolicy {
+    pub threshold: u32,
+    pub extend_to: u32,
+}
+
+/// TTL view of a user's position, returned by `get_ttl_status`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TtlStatus {
+    pub current_ledger: u32,
+    /// Ledger the balance entry is known to stay live until (0 if the vault has
+    /// never extended it). Extensions made outside the vault can only push the
+    /// real value further out.
+    pub balance_live_until: u32,
+    /// Whether the balance is within its policy threshold and will be extended
+    /// on the next touch or `extend_ttl` call.
+    pub needs_extension: bool,
+}
+
+#[contracttype]
+#[derive(Clone)]
+pub enum TtlKey {
+    Policy(TtlKeyType),
+    /// Last ledger the vault extended `DataKey::Balance(user)` to.
+    BalanceLiveUntil(Address),
+}
+
+/// Any persistent key the vault writes, grouped by key space. Passed to
+/// `extend_ttl`.
+#[contracttype]
+#[derive(Clone)]
+pub enum StorageKey {
+    Data(DataKey),
+    Collection(CollectionKey),
+    Metadata(MetadataKey),
+    Ttl(TtlKey),
+    /// Aggregate histories stored under a symbol, e.g. the TVL history.
+    History(Symbol),
+}
+
+/// A key that can be stored in persistent storage.
+pub trait PersistentKey: IntoVal<Env, Val> {
+    fn ttl_type(&self) -> TtlKeyType;
+
+    /// Owner whose live-until ledger is tracked for this key, if any.
+    fn tracked_owner(&self) -> Option<Address> {
+        None
+    }
+}
+
+impl PersistentKey for DataKey {
+    fn ttl_type(&self) -> TtlKeyType {
+        match self {
+            DataKey::VoteRecord(..) | DataKey::VoteTally(_) => TtlKeyType::Governance,
+            DataKey::StrategyYieldSnapshot(_) => TtlKeyType::History,
+            // Balances, asset balances and delegates. Instance-only keys never
+            // reach persistent storage.
+            _ => TtlKeyType::Balance,
+        }
+    }
+
+    fn tracked_owner(&self) -> Option<Address> {
+        match self {
+            DataKey::Balance(user) => Some(user.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl PersistentKey for CollectionKey {
+    fn ttl_type(&self) -> TtlKeyType {
+        match self {
+            CollectionKey::Proposal(_) | CollectionKey::ProposalHead => TtlKeyType::Governance,
+            CollectionKey::WithdrawQueueBounds
+            | CollectionKey::WithdrawalTicket(_)
+            | CollectionKey::UserTickets(_)
+            | CollectionKey::ClaimableWithdrawal(_)
+            | CollectionKey::ClaimableTotal => TtlKeyType::Withdrawal,
+            CollectionKey::ListLen(_)
+            | CollectionKey::ListPage(..)
+            | CollectionKey::ListMember(..) => TtlKeyType::Compliance,
+            CollectionKey::HistoryBounds(_) | CollectionKey::HistoryPage(..) => {
+                TtlKeyType::History
+            }
+        }
+    }
+}
+
+/// Only `BalanceLiveUntil` is persistent; it lives as long as the balance.
+impl PersistentKey for TtlKey {
+    fn ttl_type(&self) -> TtlKeyType {
+        TtlKeyType::Balance
+    }
+}
+
+/// Symbol keys hold aggregate histories (e.g. TVL).
+impl PersistentKey for Symbol {
+    fn ttl_type(&self) -> TtlKeyType {
+        TtlKeyType::History
+    }
+}
+
+pub fn policy(env: &Env, key_type: TtlKeyType) -> TtlPolicy {
+    env.storage()
+        .instance()
+        .get(&TtlKey::Policy(key_type))
+        .unwrap_or(TtlPolicy {
+            threshold: BALANCE_TTL_THRESHOLD,
+            extend_to: BALANCE_TTL_BUMP,
+        })
+}
+
+pub fn set_policy(env: &Env, key_type: TtlKeyType, policy: &TtlPolicy) -> bool {
+    if policy.threshold == 0
+        || policy.threshold > policy.extend_to
+        || policy.extend_to > env.storage().max_ttl()
+    {
+        return false;
+    }
+    env.storage()
+        .instance()
+        .set(&TtlKey::Policy(key_type), policy);
+    true
+}
+
+pub fn extend_instance(env: &Env) {
+    let policy = policy(env, TtlKeyType::Instance);
+    env.storage()
+        .instance()
+        .extend_ttl(policy.threshold, policy.extend_to);
+}
+
+/// Extend an existing persistent entry according to its type's policy.
+pub fn extend_persistent<K: PersistentKey>(env: &Env, key: &K) {
+    let policy = policy(env, key.ttl_type());
+    let persistent = env.storage().persistent();
+    let owner = match key.tracked_owner() {
+        Some(owner) => owner,
+        None => {
+            persistent.extend_ttl(key, policy.threshold, policy.extend_to);
+            return;
+        }
+    };
+
+    // Extending to exactly `extend_to` guarantees the entry lives at least
+    // until the recorded ledger, whatever its real TTL was.
+    let tracker = TtlKey::BalanceLiveUntil(owner);
+    let current = env.ledger().sequence();
+    let live_until: u32 = persistent.get(&tracker).unwrap_or(0);
+    if live_until < current.saturating_add(policy.threshold) {
+        persistent.extend_ttl(key, policy.extend_to, policy.extend_to);
+        persistent.set(&tracker, &current.saturating_add(policy.extend_to));
+        persistent.extend_ttl(&tracker, policy.extend_to, policy.extend_to);
+    }
+}
+
+/// Extend `key` if it exists in persistent storage.
+/// @return Whether the entry was found.
+pub fn extend_existing(env: &Env, key: &StorageKey) -> bool {
+    fn extend<K: PersistentKey>(env: &Env, key: &K) -> bool {
+        let found = env.storage().persistent().has(key);
+        if found {
+            extend_persistent(env, key);
+        }
+        found
+    }
+    match key {
+        StorageKey::Data(key) => extend(env, key),
+        StorageKey::Collection(key) => extend(env, key),
+        StorageKey::Metadata(key) => extend(env, key),
+        StorageKey::Ttl(key) => extend(env, key),
+        StorageKey::History(key) => extend(env, key),
+    }
+}
+
+pub fn status(env: &Env, user: &Address) -> TtlStatus {
+    let current_ledger = env.ledger().sequence();
+    let balance_live_until: u32 = env
+        .storage()
+        .persistent()
+        .get(&TtlKey::BalanceLiveUntil(user.clone()))
+        .unwrap_or(0);
+    let threshold = policy(env, TtlKeyType::Balance).threshold;
+    TtlStatus {
+        current_ledger,
+        balance_live_until,
+        needs_extension: balance_live_until < current_ledger.saturating_add(threshold),
+    }
+}
@@ -0,0 +1,443 @@
+//! Soroban budget benchmarks for the vault's hot paths.
+//!
+//! Every benchmark builds its own vault, runs one contract call and reads
+//! the host budget and ledger footprint of that call. Results are checked
+//! against the per-transaction network limits and against the baselines in
+//! `BENCHMARKS.md`; a result more than `REGRESSION_TOLERANCE_PCT` above its
+//! baseline fails the test. Regenerate the baselines with
+//! `UPDATE_BENCHMARKS=1 cargo test -p volatility_shield vault_benchmarks`.
+#![cfg(test)]
+extern crate std;
+
+use super::*;
+use mock_strategy::{MockStrategy, MockStrategyClient};
+use soroban_sdk::testutils::{Address as _, EnvTestConfig, Ledger as _};
+use soroban_sdk::token::StellarAssetClient;
+use std::format;
+use std::string::{String as StdString, ToString};
+use std::vec::Vec as StdVec;
+
+const BENCHMARKS_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/BENCHMARKS.md");
+const COMMITTED_BENCHMARKS: &str = include_str!("../BENCHMARKS.md");
+const TABLE_START: &str = "<!-- benchmarks:start -->";
+const TABLE_END: &str = "<!-- benchmarks:end -->";
+
+/// Growth over the committed baseline that still passes.
+const REGRESSION_TOLERANCE_PCT: u64 = 10;
+
+/// Per-transaction limits of the Stellar network.
+const CEILING: Measurement = Measurement {
+    cpu_instructions: 100_000_000,
+    memory_bytes: 41_943_040,
+    read_entries: 40,
+    write_entries: 25,
+};
+
+/// Sizes to benchmark. The largest of each is the most the call fits in one
+/// transaction before hitting the read or write entry limit.
+const STRATEGY_COUNTS: [u32; 3] = [1, 5, 9];
+const QUEUE_LENGTHS: [u32; 3] = [1, 5, 7];
+const PROPOSAL_COUNTS: [u32; 2] = [10, 37];
+/// Pending proposals in front of a new one. Proposing costs the same however
+/// many there are.
+const PROPOSAL_BACKLOGS: [u32; 2] = [10, 1_000];
+
+const DEPOSIT: i128 = 1_000_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Measurement {
+    cpu_instructions: u64,
+    memory_bytes: u64,
+    read_entries: u64,
+    write_entries: u64,
+}
+
+impl Measurement {
+    /// Cost of the last top-level contract call made through `env`.
+    fn last_call(env: &Env) -> Self {
+        let estimate = env.cost_estimate();
+        let budget = estimate.budget();
+        let resources = estimate.resources();
+        Measurement {
+            cpu_instructions: budget.cpu_instruction_cost(),
+            memory_bytes: budget.memory_bytes_cost(),
+            read_entries: resources.read_entries as u64,
+            write_entries: resources.write_entries as u64,
+        }
+    }
+
+    fn metrics(&self) -> [(&'static str, u64); 4] {
+        [
+            ("CPU instructions", self.cpu_instructions),
+            ("memory bytes", self.memory_bytes),
+            ("read entries", self.read_entries),
+            ("write entries", self.write_entries),
+        ]
+    }
+}
+
+struct Bench<'a> {
+    env: &'a Env,
+    admin: Address,
+    asset: Address,
+    token: StellarAssetClient<'a>,
+    vault: VolatilityShieldClient<'a>,
+}
+
+impl<'a> Bench<'a> {
+    fn new(env: &'a Env) -> Self {
+        env.mock_all_auths_allowing_non_root_auth();
+        env.ledger().with_mut(|ledger| {
+            ledger.timestamp = 1_000;
+            ledger.sequence_number = 100;
+        });
+        let admin = Address::generate(env);
+        let asset = env
+            .register_stellar_asset_contract_v2(admin.clone())
+            .address();
+        let vault_id = env.register(VolatilityShield, ());
+        let vault = VolatilityShieldClient::new(env, &vault_id);
+        vault.init(
+            &admin,
+            &asset,
+            &Address::generate(env),
+            &Address::generate(env),
+            &50u32,
+            &soroban_sdk::vec![env, admin.clone()],
+            &1u32,
+        );
+        Bench {
+            env,
+            token: StellarAssetClient::new(env, &asset),
+            admin,
+            asset,
+            vault,
+        }
+    }
+
+    fn depositor(&self, amount: i128) -> Address {
+        let user = Address::generate(self.env);
+        self.token.mint(&user, &amount);
+        self.vault.deposit(&user, &self.asset, &amount, &None);
+        user
+    }
+
+    /// Register `count` strategies and target an equal share for each.
+    fn add_strategies(&self, count: u32) -> StdVec<Address> {
+        let mut allocations = Map::new(self.env);
+        let strategies: StdVec<Address> = (0..count)
+            .map(|_| {
+                let id = self.env.register(MockStrategy, ());
+                MockStrategyClient::new(self.env, &id).init(&self.vault.address, &self.asset);
+                self.vault
+                    .propose_action(&self.admin, &ActionType::AddStrategy(id.clone()));
+                id
+            })
+            .collect();
+        let share = 10_000 / count as i128;
+        for (index, strategy) in strategies.iter().enumerate() {
+            let remainder = if index == 0 {
+                10_000 - share * count as i128
+            } else {
+                0
+            };
+            allocations.set(strategy.clone(), share + remainder);
+        }
+        self.vault
+            .set_oracle_data(&allocations, &self.env.ledger().timestamp());
+        strategies
+    }
+
+    /// Deploy the idle balance to the registered strategies.
+    fn rebalance(&self) {
+        self.vault
+            .propose_action(&self.admin, &ActionType::Rebalance(100u32));
+    }
+}
+
+/// Run `setup` on a fresh vault, then measure `call`. Setup runs on an
+/// unlimited budget; the measured call is metered against the default one,
+/// which resets before every top-level call.
+fn measure<T>(setup: impl FnOnce(&Bench) -> T, call: impl FnOnce(&Bench, T)) -> Measurement {
+    let env = Env::new_with_config(EnvTestConfig {
+        capture_snapshot_at_drop: false,
+    });
+    env.cost_estimate().budget().reset_unlimited();
+    let bench = Bench::new(&env);
+    let state = setup(&bench);
+    env.cost_estimate().budget().reset_default();
+    call(&bench, state);
+    Measurement::last_call(&env)
+}
+
+fn sized(operation: &str, count: u32, one: &str, many: &str) -> StdString {
+    format!(
+        "{operation} ({count} {})",
+        if count == 1 { one } else { many }
+    )
+}
+
+fn run_benchmarks() -> StdVec<(StdString, Measurement)> {
+    let mut results = StdVec::new();
+
+    results.push((
+        "deposit".to_string(),
+        measure(
+            |b| {
+                b.depositor(DEPOSIT);
+                let user = Address::generate(b.env);
+                b.token.mint(&user, &DEPOSIT);
+                user
+            },
+            |b, user| b.vault.deposit(&user, &b.asset, &DEPOSIT, &None),
+        ),
+    ));
+
+    results.push((
+        "withdraw".to_string(),
+        measure(
+            |b| {
+                b.depositor(DEPOSIT);
+                let user = b.depositor(DEPOSIT);
+                (user.clone(), b.vault.balance(&user) / 2)
+            },
+            |b, (user, shares)| b.vault.withdraw(&user, &user, &b.asset, &shares),
+        ),
+    ));
+
+    for count in STRATEGY_COUNTS {
+        results.push((
+            sized("internal_rebalance", count, "strategy", "strategies"),
+            measure(
+                |b| {
+                    b.depositor(DEPOSIT);
+                    b.add_strategies(count);
+                },
+                |b, ()| b.rebalance(),
+            ),
+        ));
+    }
+
+    for count in STRATEGY_COUNTS {
+        results.push((
+            sized("check_strategy_health", count, "strategy", "strategies"),
+            measure(
+                |b| {
+                    b.depositor(DEPOSIT);
+                    b.add_strategies(count);
+                    b.rebalance();
+                },
+                |b, ()| {
+                    b.vault.check_strategy_health();
+                },
+            ),
+        ));
+    }
+
+    for count in STRATEGY_COUNTS {
+        results.push((
+            sized("harvest", count, "strategy", "strategies"),
+            measure(
+                |b| {
+                    b.depositor(DEPOSIT);
+                    b.add_strategies(count);
+                    b.rebalance();
+                },
+                |b, ()| {
+                    b.vault.harvest();
+                },
+            ),
+        ));
+    }
+
+    for count in QUEUE_LENGTHS {
+        results.push((
+            sized("fulfil_withdrawals", count, "ticket", "tickets"),
+            measure(
+                |b| {
+                    b.vault.set_withdraw_queue_threshold(&0);
+                    for _ in 0..count {
+                        let user = b.depositor(DEPOSIT);
+                        let shares = b.vault.balance(&user);
+                        b.vault.queue_withdraw(&user, &user, &b.asset, &shares);
+                    }
+                    Address::generate(b.env)
+                },
+                |b, keeper| {
+                    b.vault.fulfil_withdrawals(&keeper, &count);
+                },
+            ),
+        ));
+    }
+
+    for count in PROPOSAL_COUNTS {
+        results.push((
+            sized("list_proposals", count, "proposal", "proposals"),
+            measure(
+                |b| {
+                    b.vault.add_guardian(&Address::generate(b.env));
+                    b.vault.set_threshold(&2);
+                    for _ in 0..count {
+                        b.vault
+                            .propose_action(&b.admin, &ActionType::SetPaused(true));
+                    }
+                },
+                |b, ()| {
+                    b.vault.list_proposals(&0, &count, &false);
+                },
+            ),
+        ));
+    }
+
+    for count in PROPOSAL_BACKLOGS {
+        results.push((
+            sized(
+                "propose_action",
+                count,
+                "pending proposal",
+                "pending proposals",
+            ),
+            measure(
+                |b| {
+                    b.vault.add_guardian(&Address::generate(b.env));
+                    b.vault.set_threshold(&2);
+                    for _ in 0..count {
+                        b.vault
+                            .propose_action(&b.admin, &ActionType::SetPaused(true));
+                    }
+                },
+                |b, ()| {
+                    b.vault
+                        .propose_action(&b.admin, &ActionType::SetPaused(true));
+                },
+            ),
+        ));
+    }
+
+    results
+}
+
+fn with_separators(value: u64) -> StdString {
+    let digits = value.to_string();
+    let mut out = StdString::new();
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(digit);
+    }
+    out
+}
+
+fn render_table(results: &[(StdString, Measurement)]) -> StdString {
+    let mut table = StdString::from(
+        "| Benchmark | CPU instructions | Memory bytes | Read entries | Write entries |\n\
+         |-----------|------------------|--------------|--------------|---------------|\n",
+    );
+    for (name, m) in results {
+        table.push_str(&format!(
+            "| `{}` | {} | {} | {} | {} |\n",
+            name,
+            with_separators(m.cpu_instructions),
+            with_separators(m.memory_bytes),
+            m.read_entries,
+            m.write_entries,
+        ));
+    }
+    table
+}
+
+fn committed_table() -> (usize, usize) {
+    let start = COMMITTED_BENCHMARKS
+        .find(TABLE_START)
+        .expect("BENCHMARKS.md has no benchmarks:start marker")
+        + TABLE_START.len()
+        + 1;
+    let end = COMMITTED_BENCHMARKS
+        .find(TABLE_END)
+        .expect("BENCHMARKS.md has no benchmarks:end marker");
+    (start, end)
+}
+
+fn parse_baselines() -> StdVec<(StdString, Measurement)> {
+    let (start, end) = committed_table();
+    COMMITTED_BENCHMARKS[start..end]
+        .lines()
+        .skip(2)
+        .map(|row| {
+            let cells: StdVec<&str> = row
+                .trim_matches('|')
+                .split('|')
+                .map(|cell| cell.trim())
+                .collect();
+            let number = |index: usize| -> u64 {
+                cells[index]
+                    .replace(',', "")
+                    .parse()
+                    .unwrap_or_else(|_| panic!("bad baseline row: {row}"))
+            };
+            (
+                cells[0].trim_matches('`').to_string(),
+                Measurement {
+                    cpu_instructions: number(1),
+                    memory_bytes: number(2),
+                    read_entries: number(3),
+                    write_entries: number(4),
+                },
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn benchmarks_stay_within_network_limits_and_baselines() {
+    let results = run_benchmarks();
+
+    if std::env::var("UPDATE_BENCHMARKS").is_ok() {
+        let (start, end) = committed_table();
+        let updated = format!(
+            "{}{}{}",
+            &COMMITTED_BENCHMARKS[..start],
+            render_table(&results),
+            &COMMITTED_BENCHMARKS[end..]
+        );
+        std::fs::write(BENCHMARKS_PATH, updated).unwrap();
+        return;
+    }
+
+    let baselines = parse_baselines();
+    let mut failures = StdVec::new();
+    for (name, measured) in &results {
+        for ((metric, value), (_, ceiling)) in measured.metrics().into_iter().zip(CEILING.metrics())
+        {
+            if value > ceiling {
+                failures.push(format!(
+                    "{name}: {metric} {value} exceeds the network limit {ceiling}"
+                ));
+            }
+        }
+        let Some((_, baseline)) = baselines.iter().find(|(base, _)| base == name) else {
+            failures.push(format!("{name}: no baseline in BENCHMARKS.md"));
+            continue;
+        };
+        for ((metric, value), (_, base)) in measured.metrics().into_iter().zip(baseline.metrics()) {
+            let allowed = base + base * REGRESSION_TOLERANCE_PCT / 100;
+            if value > allowed {
+                failures.push(format!(
+                    "{name}: {metric} {value} is more than {REGRESSION_TOLERANCE_PCT}% above the baseline {base}"
+                ));
+            }
+        }
+    }
+    for (name, _) in &baselines {
+        if !results.iter().any(|(measured, _)| measured == name) {
+            failures.push(format!("{name}: baseline has no benchmark"));
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "benchmark regressions; rerun with UPDATE_BENCHMARKS=1 if they are intended:\n{}",
+        failures.join("\n")
+    );
+}
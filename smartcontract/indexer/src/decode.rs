@@ -0,0 +1,366 @@
+//! Input parsing and conversion of raw contract events into [`Event`]s.
+//!
+//! Each input line holds one event in one of two shapes:
+//!
+//! - a JSON object in the shape returned by the RPC `getEvents` method, with
+//!   `topic` (or `topics`) and `value` (or `data`). Each `ScVal` is either a
+//!   base64 XDR string or a `stellar-xdr` JSON object. `ledger` and
+//!   `contractId` are optional, and objects whose `type` is not `contract`
+//!   are skipped.
+//! - a bare base64 XDR `ContractEvent`.
+//!
+//! Blank lines and lines starting with `#` are ignored.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde_json::Value as Json;
+use stellar_xdr::curr::{ContractEvent, ContractEventBody, Limits, ReadXdr, ScAddress, ScVal};
+
+/// A contract event as read from the input, before it is checked against the
+/// schema.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RawEvent {
+    /// Strkey (`C...`) of the emitting contract, when the input records it.
+    pub contract_id: Option<String>,
+    pub ledger: Option<u32>,
+    pub topics: Vec<ScVal>,
+    pub data: ScVal,
+}
+
+/// An event payload value, with addresses rendered as strkeys.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Value {
+    Void,
+    Bool(bool),
+    U32(u32),
+    U64(u64),
+    I128(i128),
+    Symbol(String),
+    String(String),
+    Address(String),
+    Bytes(Vec<u8>),
+    Vec(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+    /// A value type no vault event uses; holds the XDR type name.
+    Unsupported(&'static str),
+}
+
+impl From<&ScVal> for Value {
+    fn from(val: &ScVal) -> Self {
+        match val {
+            ScVal::Void => Value::Void,
+            ScVal::Bool(b) => Value::Bool(*b),
+            ScVal::U32(v) => Value::U32(*v),
+            ScVal::U64(v) => Value::U64(*v),
+            ScVal::I128(parts) => Value::I128(((parts.hi as i128) << 64) | parts.lo as i128),
+            ScVal::Symbol(s) => Value::Symbol(s.to_utf8_string_lossy()),
+            ScVal::String(s) => Value::String(s.to_utf8_string_lossy()),
+            ScVal::Address(addr) => Value::Address(addr.to_string()),
+            ScVal::Bytes(b) => Value::Bytes(b.to_vec()),
+            ScVal::Vec(items) => Value::Vec(
+                items
+                    .as_ref()
+                    .map(|items| items.iter().map(Value::from).collect())
+                    .unwrap_or_default(),
+            ),
+            ScVal::Map(entries) => Value::Map(
+                entries
+                    .as_ref()
+                    .map(|entries| {
+                        entries
+                            .iter()
+                            .map(|entry| (Value::from(&entry.key), Value::from(&entry.val)))
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            ),
+            other => Value::Unsupported(other.name()),
+        }
+    }
+}
+
+/// A vault event decoded from its topics and payload.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Event {
+    pub name: String,
+    /// Schema version from the second topic.
+    pub version: u32,
+    pub ledger: Option<u32>,
+    /// Topics after the name and version.
+    pub keys: Vec<Value>,
+    pub fields: BTreeMap<String, Value>,
+}
+
+impl Event {
+    /// Decode the name, version and payload fields of a raw event.
+    pub fn from_raw(raw: &RawEvent) -> Result<Self, DecodeError> {
+        let (name, version) = match raw.topics.as_slice() {
+            [ScVal::Symbol(name), ScVal::U32(version), ..] => {
+                (name.to_utf8_string_lossy(), *version)
+            }
+            _ => return Err(DecodeError::NotAVaultEvent),
+        };
+
+        let mut fields = BTreeMap::new();
+        let Value::Map(entries) = Value::from(&raw.data) else {
+            return Err(DecodeError::WrongType {
+                event: name,
+                field: "data".into(),
+                expected: "map",
+            });
+        };
+        for (key, value) in entries {
+            let Value::Symbol(key) = key else {
+                return Err(DecodeError::WrongType {
+                    event: name,
+                    field: "data".into(),
+                    expected: "map with symbol keys",
+                });
+            };
+            fields.insert(key, value);
+        }
+
+        Ok(Event {
+            name,
+            version,
+            ledger: raw.ledger,
+            keys: raw.topics[2..].iter().map(Value::from).collect(),
+            fields,
+        })
+    }
+
+    fn field(&self, field: &str) -> Result<&Value, DecodeError> {
+        self.fields
+            .get(field)
+            .ok_or_else(|| DecodeError::MissingField {
+                event: self.name.clone(),
+                field: field.into(),
+            })
+    }
+
+    fn wrong_type(&self, field: &str, expected: &'static str) -> DecodeError {
+        DecodeError::WrongType {
+            event: self.name.clone(),
+            field: field.into(),
+            expected,
+        }
+    }
+
+    pub fn i128(&self, field: &str) -> Result<i128, DecodeError> {
+        match self.field(field)? {
+            Value::I128(v) => Ok(*v),
+            _ => Err(self.wrong_type(field, "i128")),
+        }
+    }
+
+    pub fn u64(&self, field: &str) -> Result<u64, DecodeError> {
+        match self.field(field)? {
+            Value::U64(v) => Ok(*v),
+            _ => Err(self.wrong_type(field, "u64")),
+        }
+    }
+
+    pub fn u32(&self, field: &str) -> Result<u32, DecodeError> {
+        match self.field(field)? {
+            Value::U32(v) => Ok(*v),
+            _ => Err(self.wrong_type(field, "u32")),
+        }
+    }
+
+    pub fn bool(&self, field: &str) -> Result<bool, DecodeError> {
+        match self.field(field)? {
+            Value::Bool(v) => Ok(*v),
+            _ => Err(self.wrong_type(field, "bool")),
+        }
+    }
+
+    pub fn address(&self, field: &str) -> Result<String, DecodeError> {
+        match self.field(field)? {
+            Value::Address(v) => Ok(v.clone()),
+            _ => Err(self.wrong_type(field, "address")),
+        }
+    }
+
+    /// Variant name of a payload-free contract enum such as `ActionKind`.
+    pub fn variant(&self, field: &str) -> Result<String, DecodeError> {
+        match self.field(field)? {
+            Value::Vec(items) => match items.as_slice() {
+                [Value::Symbol(name)] => Ok(name.clone()),
+                _ => Err(self.wrong_type(field, "enum variant")),
+            },
+            _ => Err(self.wrong_type(field, "enum variant")),
+        }
+    }
+
+    /// Entries of a `map<address, i128>` field.
+    pub fn address_amounts(&self, field: &str) -> Result<Vec<(String, i128)>, DecodeError> {
+        let Value::Map(entries) = self.field(field)? else {
+            return Err(self.wrong_type(field, "map<address, i128>"));
+        };
+        entries
+            .iter()
+            .map(|entry| match entry {
+                (Value::Address(addr), Value::I128(amount)) => Ok((addr.clone(), *amount)),
+                _ => Err(self.wrong_type(field, "map<address, i128>")),
+            })
+            .collect()
+    }
+}
+
+/// Why an input line or event could not be used.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    Json(String),
+    Xdr(String),
+    /// The topics do not start with an event name and schema version.
+    NotAVaultEvent,
+    UnknownEvent(String),
+    UnsupportedVersion {
+        event: String,
+        version: u32,
+        expected: u32,
+    },
+    MissingField {
+        event: String,
+        field: String,
+    },
+    UnexpectedField {
+        event: String,
+        field: String,
+    },
+    WrongType {
+        event: String,
+        field: String,
+        expected: &'static str,
+    },
+    /// A topic key does not match the payload field of the same name.
+    KeyMismatch {
+        event: String,
+        key: String,
+    },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Json(err) => write!(f, "invalid JSON: {err}"),
+            DecodeError::Xdr(err) => write!(f, "invalid XDR: {err}"),
+            DecodeError::NotAVaultEvent => {
+                write!(f, "topics do not start with an event name and version")
+            }
+            DecodeError::UnknownEvent(name) => write!(f, "unknown event `{name}`"),
+            DecodeError::UnsupportedVersion {
+                event,
+                version,
+                expected,
+            } => write!(
+                f,
+                "`{event}` uses schema version {version}, expected {expected}"
+            ),
+            DecodeError::MissingField { event, field } => {
+                write!(f, "`{event}` is missing field `{field}`")
+            }
+            DecodeError::UnexpectedField { event, field } => {
+                write!(f, "`{event}` has undeclared field `{field}`")
+            }
+            DecodeError::WrongType {
+                event,
+                field,
+                expected,
+            } => write!(f, "`{event}.{field}` is not a {expected}"),
+            DecodeError::KeyMismatch { event, key } => {
+                write!(f, "`{event}` topic `{key}` does not match its payload")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Parse one input line. Returns `None` for lines that hold no contract event.
+pub fn parse_line(line: &str) -> Result<Option<RawEvent>, DecodeError> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        Ok(None)
+    } else if line.starts_with('{') {
+        let json: Json =
+            serde_json::from_str(line).map_err(|err| DecodeError::Json(err.to_string()))?;
+        parse_json(&json)
+    } else {
+        parse_contract_event(line).map(Some)
+    }
+}
+
+fn parse_json(json: &Json) -> Result<Option<RawEvent>, DecodeError> {
+    let object = json
+        .as_object()
+        .ok_or_else(|| DecodeError::Json("expected an object".into()))?;
+    if let Some(kind) = object.get("type").and_then(Json::as_str) {
+        if kind != "contract" {
+            return Ok(None);
+        }
+    }
+
+    let topics = object
+        .get("topic")
+        .or_else(|| object.get("topics"))
+        .and_then(Json::as_array)
+        .ok_or_else(|| DecodeError::Json("missing `topic` array".into()))?
+        .iter()
+        .map(parse_json_scval)
+        .collect::<Result<Vec<_>, _>>()?;
+    let data = object
+        .get("value")
+        .or_else(|| object.get("data"))
+        .ok_or_else(|| DecodeError::Json("missing `value`".into()))
+        .and_then(parse_json_scval)?;
+    let ledger = match object.get("ledger") {
+        Some(ledger) => Some(
+            ledger
+                .as_u64()
+                .and_then(|ledger| u32::try_from(ledger).ok())
+                .ok_or_else(|| DecodeError::Json("`ledger` is not a u32".into()))?,
+        ),
+        None => None,
+    };
+    let contract_id = object
+        .get("contractId")
+        .or_else(|| object.get("contract_id"))
+        .and_then(Json::as_str)
+        .map(str::to_owned);
+
+    Ok(Some(RawEvent {
+        contract_id,
+        ledger,
+        topics,
+        data,
+    }))
+}
+
+/// An `ScVal` given as base64 XDR, as `{"xdr": "<base64>"}`, or as a
+/// `stellar-xdr` JSON object.
+fn parse_json_scval(json: &Json) -> Result<ScVal, DecodeError> {
+    if let Some(b64) = json
+        .as_str()
+        .or_else(|| json.get("xdr").and_then(Json::as_str))
+    {
+        return ScVal::from_xdr_base64(b64, Limits::none())
+            .map_err(|err| DecodeError::Xdr(err.to_string()));
+    }
+    serde_json::from_value(json.clone()).map_err(|err| DecodeError::Json(err.to_string()))
+}
+
+fn parse_contract_event(b64: &str) -> Result<RawEvent, DecodeError> {
+    let event = ContractEvent::from_xdr_base64(b64, Limits::none())
+        .map_err(|err| DecodeError::Xdr(err.to_string()))?;
+    let ContractEventBody::V0(body) = event.body;
+    Ok(RawEvent {
+        contract_id: event
+            .contract_id
+            .map(|hash| ScAddress::Contract(hash).to_string()),
+        ledger: None,
+        topics: body.topics.to_vec(),
+        data: body.data,
+    })
+}
@@ -0,0 +1,107 @@
+//! Off-chain indexer for VolatilityShield.
+//!
+//! Rebuilds balances, the withdrawal queue, proposals, strategy health and
+//! the TVL series of a vault from its contract events alone. Events are
+//! checked against the schema the contract publishes in
+//! `docs/contract_events.schema.json`; see [`decode`] for the accepted input
+//! formats.
+
+use std::fmt;
+use std::io::BufRead;
+
+pub mod decode;
+pub mod schema;
+pub mod state;
+
+pub use decode::{parse_line, DecodeError, Event, RawEvent, Value};
+pub use schema::Schema;
+pub use state::VaultState;
+
+/// Feeds events into a [`VaultState`], optionally only those of one contract.
+pub struct Indexer {
+    schema: Schema,
+    contract_id: Option<String>,
+    state: VaultState,
+}
+
+impl Indexer {
+    pub fn new(schema: Schema) -> Self {
+        Indexer {
+            schema,
+            contract_id: None,
+            state: VaultState::default(),
+        }
+    }
+
+    /// Skip events whose input names a different emitting contract.
+    pub fn for_contract(mut self, contract_id: impl Into<String>) -> Self {
+        self.contract_id = Some(contract_id.into());
+        self
+    }
+
+    pub fn state(&self) -> &VaultState {
+        &self.state
+    }
+
+    pub fn into_state(self) -> VaultState {
+        self.state
+    }
+
+    /// Check and apply one event. Returns `false` if it came from another
+    /// contract and was skipped.
+    pub fn ingest(&mut self, raw: &RawEvent) -> Result<bool, DecodeError> {
+        if let (Some(expected), Some(actual)) = (&self.contract_id, &raw.contract_id) {
+            if expected != actual {
+                return Ok(false);
+            }
+        }
+        let event = Event::from_raw(raw)?;
+        self.schema.check(&event)?;
+        self.state.apply(&event)?;
+        Ok(true)
+    }
+
+    /// Parse and apply one input line.
+    pub fn ingest_line(&mut self, line: &str) -> Result<bool, DecodeError> {
+        match parse_line(line)? {
+            Some(raw) => self.ingest(&raw),
+            None => Ok(false),
+        }
+    }
+
+    /// Apply every line of `reader`, stopping at the first bad line.
+    pub fn ingest_reader(&mut self, reader: impl BufRead) -> Result<(), ReadError> {
+        for (index, line) in reader.lines().enumerate() {
+            let line = line.map_err(ReadError::Io)?;
+            self.ingest_line(&line).map_err(|error| ReadError::Line {
+                line: index + 1,
+                error,
+            })?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum ReadError {
+    Io(std::io::Error),
+    /// 1-based line number of the line that failed to decode.
+    Line {
+        line: usize,
+        error: DecodeError,
+    },
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadError::Io(err) => write!(f, "{err}"),
+            ReadError::Line { line, error } => write!(f, "line {line}: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for ReadError {}
+
+#[cfg(test)]
+mod test;
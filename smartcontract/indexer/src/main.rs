@@ -0,0 +1,69 @@
+//! Rebuild vault state from a file of VolatilityShield events and print it
+//! as JSON.
+//!
+//! Usage: `vault_indexer [--contract <C...>] [FILE]`. Reads stdin when no
+//! file is given.
+
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::process::ExitCode;
+
+use vault_indexer::{Indexer, Schema};
+
+const USAGE: &str = "usage: vault_indexer [--contract <C...>] [FILE]";
+
+fn main() -> ExitCode {
+    let mut contract_id = None;
+    let mut path = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--contract" => match args.next() {
+                Some(id) => contract_id = Some(id),
+                None => return usage(),
+            },
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            _ if path.is_none() && !arg.starts_with('-') => path = Some(arg),
+            _ => return usage(),
+        }
+    }
+
+    let mut indexer = Indexer::new(Schema::bundled());
+    if let Some(id) = contract_id {
+        indexer = indexer.for_contract(id);
+    }
+
+    let result = match &path {
+        Some(path) => match File::open(path) {
+            Ok(file) => indexer.ingest_reader(BufReader::new(file)),
+            Err(err) => {
+                eprintln!("{path}: {err}");
+                return ExitCode::FAILURE;
+            }
+        },
+        None => indexer.ingest_reader(io::stdin().lock()),
+    };
+    if let Err(err) = result {
+        eprintln!("{}: {err}", path.as_deref().unwrap_or("stdin"));
+        return ExitCode::FAILURE;
+    }
+
+    match serde_json::to_string_pretty(indexer.state()) {
+        Ok(json) => {
+            println!("{json}");
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn usage() -> ExitCode {
+    eprintln!("{USAGE}");
+    ExitCode::FAILURE
+}
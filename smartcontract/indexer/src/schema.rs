@@ -0,0 +1,120 @@
+//! The event schema published with the contract in
+//! `docs/contract_events.schema.json`.
+
+use serde::Deserialize;
+
+use crate::decode::{DecodeError, Event, Value};
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Schema {
+    pub version: u32,
+    pub events: Vec<EventSpec>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct EventSpec {
+    pub name: String,
+    /// `["name", "version", keys...]`; keys name payload fields.
+    pub topics: Vec<String>,
+    pub fields: Vec<FieldSpec>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct FieldSpec {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: String,
+}
+
+impl Schema {
+    /// The schema of the contract this crate was built against.
+    pub fn bundled() -> Self {
+        Self::from_json(include_str!("../../../docs/contract_events.schema.json"))
+            .expect("bundled event schema is valid")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, DecodeError> {
+        serde_json::from_str(json).map_err(|err| DecodeError::Json(err.to_string()))
+    }
+
+    pub fn event(&self, name: &str) -> Option<&EventSpec> {
+        self.events.iter().find(|spec| spec.name == name)
+    }
+
+    /// Check that an event is declared in this schema version, and that its
+    /// topic keys and payload fields match the declaration.
+    pub fn check(&self, event: &Event) -> Result<(), DecodeError> {
+        let spec = self
+            .event(&event.name)
+            .ok_or_else(|| DecodeError::UnknownEvent(event.name.clone()))?;
+        if event.version != self.version {
+            return Err(DecodeError::UnsupportedVersion {
+                event: event.name.clone(),
+                version: event.version,
+                expected: self.version,
+            });
+        }
+
+        for field in &spec.fields {
+            let value = event
+                .fields
+                .get(&field.name)
+                .ok_or_else(|| DecodeError::MissingField {
+                    event: event.name.clone(),
+                    field: field.name.clone(),
+                })?;
+            if !value_matches(&field.ty, value) {
+                return Err(DecodeError::WrongType {
+                    event: event.name.clone(),
+                    field: field.name.clone(),
+                    expected: "declared type",
+                });
+            }
+        }
+        if let Some(field) = event
+            .fields
+            .keys()
+            .find(|name| !spec.fields.iter().any(|field| &field.name == *name))
+        {
+            return Err(DecodeError::UnexpectedField {
+                event: event.name.clone(),
+                field: field.clone(),
+            });
+        }
+
+        let keys = &spec.topics[2..];
+        if keys.len() != event.keys.len() {
+            return Err(DecodeError::KeyMismatch {
+                event: event.name.clone(),
+                key: keys.join(", "),
+            });
+        }
+        for (key, value) in keys.iter().zip(&event.keys) {
+            if event.fields.get(key) != Some(value) {
+                return Err(DecodeError::KeyMismatch {
+                    event: event.name.clone(),
+                    key: key.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether a value has the shape of a schema type. Contract types (`Udt`
+/// names) are not inspected further.
+fn value_matches(ty: &str, value: &Value) -> bool {
+    match ty {
+        "bool" => matches!(value, Value::Bool(_)),
+        "u32" => matches!(value, Value::U32(_)),
+        "u64" => matches!(value, Value::U64(_)),
+        "i128" => matches!(value, Value::I128(_)),
+        "symbol" => matches!(value, Value::Symbol(_)),
+        "string" => matches!(value, Value::String(_)),
+        "address" => matches!(value, Value::Address(_)),
+        ty if ty.starts_with("bytes") => matches!(value, Value::Bytes(_)),
+        ty if ty.starts_with("vec<") => matches!(value, Value::Vec(_)),
+        ty if ty.starts_with("map<") => matches!(value, Value::Map(_)),
+        _ => !matches!(value, Value::Unsupported(_)),
+    }
+}
@@ -0,0 +1,228 @@
+//! Vault state rebuilt from the event stream.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::decode::{DecodeError, Event};
+
+/// A queued withdrawal that has not been fulfilled or cancelled.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct QueuedWithdrawal {
+    pub user: String,
+    pub asset: String,
+    pub shares: i128,
+}
+
+/// A fulfilled withdrawal waiting to be claimed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Claimable {
+    pub user: String,
+    pub shares: i128,
+    pub amount: i128,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ProposalState {
+    pub proposer: String,
+    /// `ActionKind` variant name.
+    pub kind: String,
+    /// Guardians that approved, in order; the proposer is the first.
+    pub approvals: Vec<String>,
+    pub executed: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct StrategyState {
+    /// Balance recorded by the last health check or vault snapshot.
+    pub balance: i128,
+    pub is_healthy: bool,
+    pub consecutive_failures: u32,
+}
+
+/// One point of the TVL series: the vault's `total_assets` after an event.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct TvlPoint {
+    pub ledger: Option<u32>,
+    pub total_assets: i128,
+}
+
+/// Everything the indexer tracks about one vault.
+///
+/// Cancelled proposals are dropped, as the contract deletes them. Pruned
+/// proposals are kept: `ProposalsPruned` only reports how many were removed.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct VaultState {
+    pub total_assets: i128,
+    pub total_shares: i128,
+    pub balances: BTreeMap<String, i128>,
+    pub queue: BTreeMap<u64, QueuedWithdrawal>,
+    pub claimable: BTreeMap<u64, Claimable>,
+    pub proposals: BTreeMap<u64, ProposalState>,
+    pub strategies: BTreeMap<String, StrategyState>,
+    pub tvl: Vec<TvlPoint>,
+    pub paused: bool,
+    pub shutdown: bool,
+    /// Number of events applied so far.
+    pub events: u64,
+}
+
+impl VaultState {
+    /// Share balance of `user`; users without events hold no shares.
+    pub fn balance(&self, user: &str) -> i128 {
+        self.balances.get(user).copied().unwrap_or(0)
+    }
+
+    /// Apply one schema-checked event. Events that do not affect the tracked
+    /// state are counted and otherwise ignored.
+    pub fn apply(&mut self, event: &Event) -> Result<(), DecodeError> {
+        match event.name.as_str() {
+            "Deposited" => {
+                self.add_shares(event.address("depositor")?, event.i128("shares_minted")?);
+                self.set_totals(event)?;
+            }
+            "Withdrawn" => {
+                self.add_shares(event.address("withdrawer")?, -event.i128("shares_burned")?);
+                self.set_totals(event)?;
+            }
+            "EmergencyWithdrawn" => {
+                // The user's tickets were cancelled just before, so `shares`
+                // is their whole restored balance.
+                self.add_shares(event.address("user")?, -event.i128("shares")?);
+                self.set_totals(event)?;
+            }
+            "BalanceSet" => {
+                self.balances
+                    .insert(event.address("user")?, event.i128("shares")?);
+            }
+            "TotalAssetsSet" => self.set_total_assets(event.i128("total_assets")?, event),
+            "TotalSharesSet" => self.total_shares = event.i128("total_shares")?,
+            "WithdrawalsProcessed" | "Harvested" => self.set_totals(event)?,
+            "Rebalanced" => self.set_total_assets(event.i128("total_assets_after")?, event),
+            "RebalancePoked" => self.set_total_assets(event.i128("total_assets")?, event),
+            "VaultSnapshot" => {
+                self.set_totals(event)?;
+                for (strategy, balance) in event.address_amounts("allocations")? {
+                    self.strategy(strategy).balance = balance;
+                }
+            }
+
+            "WithdrawQueued" => {
+                let user = event.address("user")?;
+                let shares = event.i128("shares")?;
+                self.add_shares(user.clone(), -shares);
+                self.queue.insert(
+                    event.u64("ticket_id")?,
+                    QueuedWithdrawal {
+                        user,
+                        asset: event.address("asset")?,
+                        shares,
+                    },
+                );
+                self.set_totals(event)?;
+            }
+            "WithdrawCancelled" => {
+                self.queue.remove(&event.u64("ticket_id")?);
+                self.add_shares(event.address("user")?, event.i128("shares")?);
+            }
+            "WithdrawFulfilled" => {
+                let ticket_id = event.u64("ticket_id")?;
+                self.queue.remove(&ticket_id);
+                self.claimable.insert(
+                    ticket_id,
+                    Claimable {
+                        user: event.address("user")?,
+                        shares: event.i128("shares")?,
+                        amount: event.i128("amount")?,
+                    },
+                );
+            }
+            "WithdrawClaimed" => {
+                self.claimable.remove(&event.u64("ticket_id")?);
+            }
+
+            "ProposalCreated" => {
+                let proposer = event.address("proposer")?;
+                self.proposals.insert(
+                    event.u64("proposal_id")?,
+                    ProposalState {
+                        approvals: vec![proposer.clone()],
+                        proposer,
+                        kind: event.variant("kind")?,
+                        executed: false,
+                    },
+                );
+            }
+            "ProposalApproved" => {
+                let guardian = event.address("guardian")?;
+                if let Some(proposal) = self.proposals.get_mut(&event.u64("proposal_id")?) {
+                    if !proposal.approvals.contains(&guardian) {
+                        proposal.approvals.push(guardian);
+                    }
+                }
+            }
+            "ProposalExecuted" | "EmergencyActionExecuted" => {
+                if let Some(proposal) = self.proposals.get_mut(&event.u64("proposal_id")?) {
+                    proposal.executed = true;
+                }
+            }
+            "ProposalCancelled" => {
+                self.proposals.remove(&event.u64("proposal_id")?);
+            }
+
+            "StrategyAdded" => {
+                self.strategy(event.address("strategy")?);
+            }
+            "StrategyHealthUpdated" => {
+                let strategy = self.strategy(event.address("strategy")?);
+                strategy.balance = event.i128("balance")?;
+                strategy.is_healthy = event.bool("is_healthy")?;
+                strategy.consecutive_failures = event.u32("consecutive_failures")?;
+            }
+            "StrategyFlagged" => {
+                let strategy = self.strategy(event.address("strategy")?);
+                strategy.is_healthy = false;
+                strategy.consecutive_failures = 0;
+            }
+            "StrategyRemoved" => {
+                self.strategies.remove(&event.address("strategy")?);
+            }
+
+            "VaultPaused" => self.paused = true,
+            "VaultUnpaused" => self.paused = false,
+            "EmergencyShutdownActivated" => self.shutdown = true,
+            _ => {}
+        }
+        self.events += 1;
+        Ok(())
+    }
+
+    fn add_shares(&mut self, user: String, delta: i128) {
+        *self.balances.entry(user).or_insert(0) += delta;
+    }
+
+    fn strategy(&mut self, strategy: String) -> &mut StrategyState {
+        self.strategies.entry(strategy).or_insert(StrategyState {
+            balance: 0,
+            is_healthy: true,
+            consecutive_failures: 0,
+        })
+    }
+
+    /// Apply the `total_assets` and `total_shares` fields of an event.
+    fn set_totals(&mut self, event: &Event) -> Result<(), DecodeError> {
+        self.total_shares = event.i128("total_shares")?;
+        self.set_total_assets(event.i128("total_assets")?, event);
+        Ok(())
+    }
+
+    fn set_total_assets(&mut self, total_assets: i128, event: &Event) {
+        self.total_assets = total_assets;
+        if self.tvl.last().map(|point| point.total_assets) != Some(total_assets) {
+            self.tvl.push(TvlPoint {
+                ledger: event.ledger,
+                total_assets,
+            });
+        }
+    }
+}
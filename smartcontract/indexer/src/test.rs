@@ -0,0 +1,412 @@
+use super::*;
+use soroban_sdk::testutils::{Address as _, Events as _};
+use soroban_sdk::token::StellarAssetClient;
+use soroban_sdk::{Address, Env, IntoVal, TryFromVal, Val};
+use stellar_xdr::curr::{
+    ContractEvent, ContractEventBody, ContractEventType, ContractEventV0, ExtensionPoint, Limits,
+    ScAddress, ScVal, WriteXdr,
+};
+use volatility_shield::{ActionType, VolatilityShield, VolatilityShieldClient};
+
+fn to_scval(env: &Env, val: &Val) -> ScVal {
+    ScVal::try_from_val(env, val).unwrap()
+}
+
+fn strkey(env: &Env, address: &Address) -> String {
+    match to_scval(env, &address.into_val(env)) {
+        ScVal::Address(address) => address.to_string(),
+        other => panic!("not an address: {other:?}"),
+    }
+}
+
+fn b64(val: &ScVal) -> String {
+    val.to_xdr_base64(Limits::none()).unwrap()
+}
+
+/// Events of the last invocation as RPC-style JSON lines with base64 XDR
+/// values, including events of other contracts.
+fn event_lines(env: &Env) -> Vec<String> {
+    env.events()
+        .all()
+        .iter()
+        .map(|(contract, topics, data)| {
+            let topics: Vec<String> = topics.iter().map(|t| b64(&to_scval(env, &t))).collect();
+            serde_json::json!({
+                "type": "contract",
+                "ledger": env.ledger().sequence(),
+                "contractId": strkey(env, &contract),
+                "topic": topics,
+                "value": b64(&to_scval(env, &data)),
+            })
+            .to_string()
+        })
+        .collect()
+}
+
+fn feed(env: &Env, indexer: &mut Indexer) {
+    for line in event_lines(env) {
+        indexer.ingest_line(&line).unwrap();
+    }
+}
+
+fn setup_vault(env: &Env) -> (Address, Address, StellarAssetClient<'_>) {
+    let token_admin = Address::generate(env);
+    let token_id = env
+        .register_stellar_asset_contract_v2(token_admin)
+        .address();
+    let vault_id = env.register(VolatilityShield, ());
+    (
+        vault_id,
+        token_id.clone(),
+        StellarAssetClient::new(env, &token_id),
+    )
+}
+
+/// Compare everything the indexer rebuilt with the contract's own views.
+fn assert_matches_contract(
+    env: &Env,
+    client: &VolatilityShieldClient,
+    state: &VaultState,
+    users: &[&Address],
+    proposal_ids: &[u64],
+) {
+    let summary = client.get_vault_summary();
+    assert_eq!(state.total_assets, summary.total_assets);
+    assert_eq!(state.total_shares, summary.total_shares);
+    assert_eq!(state.paused, summary.paused);
+
+    for user in users {
+        let key = strkey(env, user);
+        let user_summary = client.get_user_summary(user);
+        assert_eq!(state.balance(&key), user_summary.balance);
+        let queued: i128 = state
+            .queue
+            .values()
+            .filter(|ticket| ticket.user == key)
+            .map(|ticket| ticket.shares)
+            .sum();
+        assert_eq!(queued, user_summary.queued_shares);
+    }
+
+    let pending = client.get_pending_withdrawals();
+    assert_eq!(state.queue.len(), pending.len() as usize);
+    for ticket in pending.iter() {
+        let queued = &state.queue[&ticket.id];
+        assert_eq!(queued.user, strkey(env, &ticket.user));
+        assert_eq!(queued.asset, strkey(env, &ticket.asset));
+        assert_eq!(queued.shares, ticket.shares);
+    }
+    for (ticket_id, claimable) in &state.claimable {
+        let on_chain = client.get_claimable_withdrawal(ticket_id).unwrap();
+        assert_eq!(claimable.user, strkey(env, &on_chain.user));
+        assert_eq!(claimable.amount, on_chain.amount);
+        assert_eq!(claimable.shares, on_chain.shares);
+    }
+    let claimable: i128 = state.claimable.values().map(|c| c.amount).sum();
+    assert_eq!(claimable, client.get_total_claimable());
+
+    for id in proposal_ids {
+        match client.get_proposal(id) {
+            Some(proposal) => {
+                let indexed = &state.proposals[id];
+                assert_eq!(indexed.proposer, strkey(env, &proposal.proposer));
+                assert_eq!(indexed.kind, format!("{:?}", proposal.action.kind()));
+                let approvals: Vec<String> =
+                    proposal.approvals.iter().map(|a| strkey(env, &a)).collect();
+                assert_eq!(indexed.approvals, approvals);
+                assert_eq!(indexed.executed, proposal.executed);
+            }
+            None => assert!(!state.proposals.contains_key(id)),
+        }
+    }
+
+    let strategies = client.get_strategies();
+    assert_eq!(state.strategies.len(), strategies.len() as usize);
+    for strategy in strategies.iter() {
+        let indexed = &state.strategies[&strkey(env, &strategy)];
+        if let Some(health) = client.get_strategy_health(&strategy) {
+            assert_eq!(indexed.balance, health.last_known_balance);
+            assert_eq!(indexed.is_healthy, health.is_healthy);
+            assert_eq!(indexed.consecutive_failures, health.consecutive_failures);
+        }
+    }
+
+    // The contract samples TVL on deposits, withdrawals and harvests; each
+    // sample must appear, in order, in the indexed series.
+    assert_eq!(state.tvl.last().unwrap().total_assets, summary.total_assets);
+    let mut series = state.tvl.iter();
+    for (ledger, total_assets) in client.get_tvl_history(&0, &u32::MAX).iter() {
+        assert!(
+            series
+                .any(|point| point.total_assets == total_assets
+                    && point.ledger == Some(ledger as u32))
+        );
+    }
+}
+
+#[test]
+fn test_indexer_rebuilds_state_matching_contract_views() {
+    let env = Env::default();
+    env.mock_all_auths_allowing_non_root_auth();
+
+    let (vault_id, token_id, token) = setup_vault(&env);
+    let client = VolatilityShieldClient::new(&env, &vault_id);
+    let mut indexer = Indexer::new(Schema::bundled()).for_contract(strkey(&env, &vault_id));
+
+    let admin = Address::generate(&env);
+    let guardian = Address::generate(&env);
+    let keeper = Address::generate(&env);
+    client.init(
+        &admin,
+        &token_id,
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &0u32,
+        &soroban_sdk::vec![&env, admin.clone(), guardian.clone()],
+        &2u32,
+    );
+    feed(&env, &mut indexer);
+
+    // Governance: one executed, one cancelled and one pending proposal.
+    let strategy_id = env.register(mock_strategy::MockStrategy, ());
+    let strategy = mock_strategy::MockStrategyClient::new(&env, &strategy_id);
+    strategy.init(&vault_id, &token_id);
+    let added = client.propose_action(&admin, &ActionType::AddStrategy(strategy_id.clone()));
+    feed(&env, &mut indexer);
+    client.approve_action(&guardian, &added);
+    feed(&env, &mut indexer);
+    let cancelled = client.propose_action(&admin, &ActionType::SetPaused(true));
+    feed(&env, &mut indexer);
+    client.cancel_proposal(&admin, &cancelled);
+    feed(&env, &mut indexer);
+    let pending = client.propose_action(
+        &guardian,
+        &ActionType::AddSupportedAsset(Address::generate(&env)),
+    );
+    feed(&env, &mut indexer);
+
+    // Deposits and an instant withdrawal. Token mints are emitted by the
+    // token contract and must be skipped.
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    token.mint(&alice, &1_000);
+    feed(&env, &mut indexer);
+    token.mint(&bob, &500);
+    feed(&env, &mut indexer);
+    client.deposit(&alice, &token_id, &1_000, &None::<i128>);
+    feed(&env, &mut indexer);
+    client.deposit(&bob, &token_id, &500, &None::<i128>);
+    feed(&env, &mut indexer);
+    client.withdraw(&alice, &alice, &token_id, &200_000);
+    feed(&env, &mut indexer);
+
+    // Queue: one ticket cancelled, one fulfilled, one left queued.
+    client.set_withdraw_queue_threshold(&100);
+    feed(&env, &mut indexer);
+    let bob_ticket = client.queue_withdraw(&bob, &bob, &token_id, &300_000);
+    feed(&env, &mut indexer);
+    let alice_ticket = client.queue_withdraw(&alice, &alice, &token_id, &200_000);
+    feed(&env, &mut indexer);
+    client.cancel_queued_withdrawal(&alice_ticket);
+    feed(&env, &mut indexer);
+    assert_eq!(client.fulfil_withdrawals(&keeper, &1), 1);
+    feed(&env, &mut indexer);
+    client.queue_withdraw(&alice, &alice, &token_id, &150_000);
+    feed(&env, &mut indexer);
+
+    let users = [&alice, &bob];
+    let proposals = [added, cancelled, pending];
+    assert_matches_contract(&env, &client, indexer.state(), &users, &proposals);
+    assert_eq!(indexer.state().claimable[&bob_ticket].amount, 300);
+
+    client.claim_withdrawal(&bob_ticket);
+    feed(&env, &mut indexer);
+
+    // Strategy health: a check records the balance, then the admin flags it.
+    token.mint(&strategy_id, &400);
+    feed(&env, &mut indexer);
+    strategy.deposit(&400);
+    client.check_strategy_health();
+    feed(&env, &mut indexer);
+    assert_eq!(
+        indexer.state().strategies[&strkey(&env, &strategy_id)].balance,
+        400
+    );
+    client.flag_strategy(&strategy_id);
+    feed(&env, &mut indexer);
+    client.set_paused(&true);
+    feed(&env, &mut indexer);
+
+    assert_matches_contract(&env, &client, indexer.state(), &users, &proposals);
+    let state = indexer.state();
+    assert!(state.claimable.is_empty());
+    assert!(state.proposals[&added].executed);
+    assert!(!state.proposals[&pending].executed);
+    assert!(!state.strategies[&strkey(&env, &strategy_id)].is_healthy);
+    assert_eq!(
+        state.tvl.iter().map(|p| p.total_assets).collect::<Vec<_>>(),
+        [1_000, 1_500, 1_300, 1_000]
+    );
+
+    // Emergency exit: Alice's queued ticket is burned along with her balance.
+    let alice_key = strkey(&env, &alice);
+    assert!(state.queue.values().any(|ticket| ticket.user == alice_key));
+    client.emergency_shutdown(&admin);
+    feed(&env, &mut indexer);
+    client.emergency_withdraw(&alice);
+    feed(&env, &mut indexer);
+
+    assert_matches_contract(&env, &client, indexer.state(), &users, &proposals);
+    let state = indexer.state();
+    assert!(state.shutdown);
+    assert_eq!(state.balance(&alice_key), 0);
+    assert!(state.queue.is_empty());
+}
+
+#[test]
+fn test_indexer_reads_contract_event_xdr_and_json_scvals() {
+    let env = Env::default();
+    env.mock_all_auths_allowing_non_root_auth();
+
+    let (vault_id, token_id, token) = setup_vault(&env);
+    let client = VolatilityShieldClient::new(&env, &vault_id);
+    let admin = Address::generate(&env);
+    client.init(
+        &admin,
+        &token_id,
+        &Address::generate(&env),
+        &Address::generate(&env),
+        &0u32,
+        &soroban_sdk::vec![&env, admin.clone()],
+        &1u32,
+    );
+    let alice = Address::generate(&env);
+    token.mint(&alice, &7_000);
+    client.deposit(&alice, &token_id, &7_000, &None::<i128>);
+
+    let (contract, topics, data) = env.events().all().last().unwrap();
+    let topics: Vec<ScVal> = topics.iter().map(|t| to_scval(&env, &t)).collect();
+    let data = to_scval(&env, &data);
+    let ScVal::Address(ScAddress::Contract(hash)) = to_scval(&env, &contract.into_val(&env)) else {
+        panic!("event not emitted by a contract");
+    };
+    let event = ContractEvent {
+        ext: ExtensionPoint::V0,
+        contract_id: Some(hash),
+        type_: ContractEventType::Contract,
+        body: ContractEventBody::V0(ContractEventV0 {
+            topics: topics.clone().try_into().unwrap(),
+            data: data.clone(),
+        }),
+    };
+
+    let xdr_line = event.to_xdr_base64(Limits::none()).unwrap();
+    let raw = parse_line(&xdr_line).unwrap().unwrap();
+    assert_eq!(raw.contract_id, Some(strkey(&env, &vault_id)));
+    assert_eq!(Event::from_raw(&raw).unwrap().name, "Deposited");
+
+    let json_line = serde_json::json!({ "topics": topics, "data": data }).to_string();
+    let input = format!("# deposits\n\n{xdr_line}\n{json_line}\n");
+    let mut indexer = Indexer::new(Schema::bundled());
+    indexer.ingest_reader(input.as_bytes()).unwrap();
+    assert_eq!(indexer.state().events, 2);
+    assert_eq!(indexer.state().balance(&strkey(&env, &alice)), 14_000_000);
+
+    let mut other = Indexer::new(Schema::bundled()).for_contract(strkey(&env, &token_id));
+    assert!(!other.ingest_line(&xdr_line).unwrap());
+    assert_eq!(
+        other.ingest_line(r#"{"type":"system","topic":[],"value":"AAAAAQ=="}"#),
+        Ok(false)
+    );
+}
+
+#[test]
+fn test_indexer_rejects_events_outside_the_schema() {
+    let env = Env::default();
+    let schema = Schema::bundled();
+    assert_eq!(schema.version, volatility_shield::EVENT_SCHEMA_VERSION);
+    let mut indexer = Indexer::new(schema.clone());
+
+    let symbol = |s: &str| ScVal::Symbol(s.try_into().unwrap());
+    let fields = |entries: Vec<(&str, ScVal)>| {
+        ScVal::Map(Some(
+            entries
+                .into_iter()
+                .map(|(key, val)| stellar_xdr::curr::ScMapEntry {
+                    key: symbol(key),
+                    val,
+                })
+                .collect::<Vec<_>>()
+                .try_into()
+                .unwrap(),
+        ))
+    };
+    let raw = |topics: Vec<ScVal>, data: ScVal| RawEvent {
+        contract_id: None,
+        ledger: None,
+        topics,
+        data,
+    };
+    let version = ScVal::U32(schema.version);
+
+    assert_eq!(
+        indexer.ingest(&raw(vec![symbol("Deposited")], fields(vec![]))),
+        Err(DecodeError::NotAVaultEvent)
+    );
+    assert_eq!(
+        indexer.ingest(&raw(
+            vec![symbol("Minted"), version.clone()],
+            fields(vec![])
+        )),
+        Err(DecodeError::UnknownEvent("Minted".into()))
+    );
+    assert_eq!(
+        indexer.ingest(&raw(
+            vec![symbol("ProposalsPruned"), ScVal::U32(schema.version - 1)],
+            fields(vec![("count", ScVal::U32(1))]),
+        )),
+        Err(DecodeError::UnsupportedVersion {
+            event: "ProposalsPruned".into(),
+            version: schema.version - 1,
+            expected: schema.version,
+        })
+    );
+    assert_eq!(
+        indexer.ingest(&raw(
+            vec![symbol("ProposalsPruned"), version.clone()],
+            fields(vec![("count", ScVal::U64(1))]),
+        )),
+        Err(DecodeError::WrongType {
+            event: "ProposalsPruned".into(),
+            field: "count".into(),
+            expected: "declared type",
+        })
+    );
+    assert_eq!(
+        indexer.ingest(&raw(
+            vec![symbol("ProposalsPruned"), version.clone()],
+            fields(vec![("count", ScVal::U32(1)), ("extra", ScVal::Bool(true))]),
+        )),
+        Err(DecodeError::UnexpectedField {
+            event: "ProposalsPruned".into(),
+            field: "extra".into(),
+        })
+    );
+    assert_eq!(
+        indexer.ingest(&raw(
+            vec![symbol("ProposalCancelled"), version.clone(), ScVal::U64(2)],
+            fields(vec![
+                (
+                    "caller",
+                    to_scval(&env, &Address::generate(&env).into_val(&env))
+                ),
+                ("proposal_id", ScVal::U64(1)),
+            ]),
+        )),
+        Err(DecodeError::KeyMismatch {
+            event: "ProposalCancelled".into(),
+            key: "proposal_id".into(),
+        })
+    );
+    assert_eq!(indexer.state().events, 0);
+}
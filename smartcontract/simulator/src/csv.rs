@@ -0,0 +1,49 @@
+//! CSV output of a simulation [`Report`](crate::Report).
+
+use std::io::{self, Write};
+
+use crate::sim::{Tick, UserRow};
+
+pub const SERIES_HEADER: &str = "ledger,timestamp,share_price,tvl,total_shares,idle,deployed,\
+queue_depth,queued_shares,alt_price,rebalances,harvests,failed_calls";
+
+pub const USERS_HEADER: &str = "ledger,user,shares,value,paid,received,pnl";
+
+/// Vault time series: share price, TVL, queue depth and the other
+/// vault-wide figures of every tick.
+pub fn write_series(mut out: impl Write, ticks: &[Tick]) -> io::Result<()> {
+    writeln!(out, "{SERIES_HEADER}")?;
+    for t in ticks {
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            t.ledger,
+            t.timestamp,
+            t.share_price,
+            t.tvl,
+            t.total_shares,
+            t.idle,
+            t.deployed,
+            t.queue_depth,
+            t.queued_shares,
+            t.alt_price,
+            t.rebalances,
+            t.harvests,
+            t.failed_calls,
+        )?;
+    }
+    Ok(())
+}
+
+/// Per-user PnL of every tick.
+pub fn write_users(mut out: impl Write, rows: &[UserRow]) -> io::Result<()> {
+    writeln!(out, "{USERS_HEADER}")?;
+    for r in rows {
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{}",
+            r.ledger, r.user, r.shares, r.value, r.paid, r.received, r.pnl,
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,20 @@
+//! Deterministic scenario simulator for VolatilityShield.
+//!
+//! Runs the vault, `mock_strategy` and `mock_oracle` contracts in a Soroban
+//! test environment for thousands of ledgers. Users deposit and withdraw at
+//! random, strategies drift with `simulate_price_drift`, the oracle follows a
+//! random price path, and keepers rebalance, harvest and fulfil the
+//! withdrawal queue. All randomness comes from [`Scenario::seed`], so a
+//! scenario always produces the same [`Report`].
+
+mod rng;
+mod sim;
+
+pub mod csv;
+pub mod scenario;
+
+pub use scenario::{Scenario, StrategyConfig};
+pub use sim::{run, Report, Tick, UserRow};
+
+#[cfg(test)]
+mod test;
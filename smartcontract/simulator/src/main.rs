@@ -0,0 +1,59 @@
+//! Run the default scenario and write `series.csv` and `users.csv`.
+//!
+//! Usage: `vault_simulator [--seed N] [--ledgers N] [--users N] [--out DIR]`.
+
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use vault_simulator::{csv, run, Scenario};
+
+const USAGE: &str = "usage: vault_simulator [--seed N] [--ledgers N] [--users N] [--out DIR]";
+
+fn main() -> ExitCode {
+    let mut scenario = Scenario::default();
+    let mut out = PathBuf::from("simulation");
+    let mut args = std::env::args().skip(1);
+    while let Some(flag) = args.next() {
+        let Some(value) = args.next() else {
+            return usage();
+        };
+        let parsed = match flag.as_str() {
+            "--seed" => value.parse().map(|v| scenario.seed = v).is_ok(),
+            "--ledgers" => value.parse().map(|v| scenario.ledgers = v).is_ok(),
+            "--users" => value.parse().map(|v| scenario.users = v).is_ok(),
+            "--out" => {
+                out = PathBuf::from(value);
+                true
+            }
+            _ => false,
+        };
+        if !parsed {
+            return usage();
+        }
+    }
+
+    let report = run(&scenario);
+    let written = fs::create_dir_all(&out)
+        .and_then(|_| File::create(out.join("series.csv")))
+        .and_then(|file| csv::write_series(BufWriter::new(file), &report.ticks))
+        .and_then(|_| File::create(out.join("users.csv")))
+        .and_then(|file| csv::write_users(BufWriter::new(file), &report.users));
+    if let Err(err) = written {
+        eprintln!("{}: {err}", out.display());
+        return ExitCode::FAILURE;
+    }
+    eprintln!(
+        "wrote {} ticks for {} users to {}",
+        report.ticks.len(),
+        scenario.users,
+        out.display()
+    );
+    ExitCode::SUCCESS
+}
+
+fn usage() -> ExitCode {
+    eprintln!("{USAGE}");
+    ExitCode::FAILURE
+}
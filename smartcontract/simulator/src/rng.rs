@@ -0,0 +1,36 @@
+//! Seeded SplitMix64 generator, so a scenario replays identically for the
+//! same seed.
+
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0..bound`; `bound` must be positive.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+
+    /// True with probability `bps / 10_000`.
+    pub fn chance(&mut self, bps: u32) -> bool {
+        self.below(10_000) < bps as u64
+    }
+
+    /// Uniform in `-spread..=spread`.
+    pub fn spread(&mut self, spread: i64) -> i64 {
+        if spread <= 0 {
+            return 0;
+        }
+        self.below(2 * spread as u64 + 1) as i64 - spread
+    }
+}
@@ -0,0 +1,100 @@
+//! Scenario parameters. Amounts are in base-asset units and prices use 9
+//! decimals, as in the vault.
+
+use volatility_shield::RebalanceTrigger;
+
+/// Yield model of one `mock_strategy` instance.
+#[derive(Clone, Debug)]
+pub struct StrategyConfig {
+    /// Target share of deployed assets in bps. Targets of all strategies
+    /// sum to 10 000.
+    pub target_bps: i128,
+    /// Mean change of the strategy balance per tick, in bps. May be negative.
+    pub drift_bps: i64,
+    /// Largest random deviation from `drift_bps` per tick, in bps.
+    pub volatility_bps: i64,
+}
+
+#[derive(Clone, Debug)]
+pub struct Scenario {
+    pub seed: u64,
+    /// Number of ledgers to simulate.
+    pub ledgers: u32,
+    /// Ledgers between two simulation steps; one CSV row is written per step.
+    pub ledgers_per_tick: u32,
+    pub seconds_per_ledger: u64,
+    pub users: u32,
+    /// How many of the users deposit the second asset. Its value counts
+    /// towards `total_assets` but cannot be deployed to strategies.
+    pub alt_asset_users: u32,
+    /// Vault fee in bps.
+    pub fee_bps: u32,
+    pub strategies: Vec<StrategyConfig>,
+    /// Starting oracle price of the second deposit asset.
+    pub alt_asset_price: i128,
+    /// Largest relative move of the second asset's price per tick, in bps.
+    pub alt_price_volatility_bps: i64,
+    /// Chance per user and tick of a deposit, in bps.
+    pub deposit_chance_bps: u32,
+    /// Chance per user and tick of a withdrawal, in bps.
+    pub withdraw_chance_bps: u32,
+    /// Deposits are uniform in `1..=max_deposit` token units.
+    pub max_deposit: i128,
+    /// Withdrawals above this many base units go through the queue.
+    pub withdraw_queue_threshold: i128,
+    /// When keepers rebalance and what they are paid.
+    pub rebalance_trigger: RebalanceTrigger,
+    /// Ticks between two oracle allocation updates.
+    pub oracle_update_every: u32,
+    /// Ticks between two harvests; 0 disables harvesting.
+    pub harvest_every: u32,
+    /// Tickets a keeper fulfils per tick.
+    pub fulfil_batch: u32,
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        Scenario {
+            seed: 1,
+            ledgers: 10_000,
+            ledgers_per_tick: 50,
+            seconds_per_ledger: 5,
+            users: 8,
+            alt_asset_users: 0,
+            fee_bps: 50,
+            strategies: vec![
+                StrategyConfig {
+                    target_bps: 6_000,
+                    drift_bps: 2,
+                    volatility_bps: 5,
+                },
+                StrategyConfig {
+                    target_bps: 4_000,
+                    drift_bps: 4,
+                    volatility_bps: 40,
+                },
+            ],
+            alt_asset_price: 1_000_000_000,
+            alt_price_volatility_bps: 100,
+            deposit_chance_bps: 1_500,
+            withdraw_chance_bps: 800,
+            max_deposit: 10_000,
+            withdraw_queue_threshold: 20_000,
+            rebalance_trigger: RebalanceTrigger {
+                drift_bps: 500,
+                min_interval: 3_600,
+                max_slippage_bps: 100,
+                keeper_reward: 0,
+            },
+            oracle_update_every: 10,
+            harvest_every: 40,
+            fulfil_batch: 10,
+        }
+    }
+}
+
+impl Scenario {
+    pub fn ticks(&self) -> u32 {
+        self.ledgers / self.ledgers_per_tick.max(1)
+    }
+}
@@ -0,0 +1,391 @@
+//! Runs a [`Scenario`] against the real contracts in a test environment.
+
+use soroban_sdk::testutils::{Address as _, EnvTestConfig, Ledger as _};
+use soroban_sdk::token::{StellarAssetClient, TokenClient};
+use soroban_sdk::{Address, Env, Map};
+
+use mock_oracle::MockOracleClient;
+use mock_strategy::{MockStrategy, MockStrategyClient};
+use volatility_shield::{ActionType, VolatilityShield, VolatilityShieldClient};
+
+use crate::rng::Rng;
+use crate::scenario::Scenario;
+
+/// Vault-wide figures after one simulation step.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tick {
+    pub ledger: u32,
+    pub timestamp: u64,
+    /// Share price with 9 decimals.
+    pub share_price: i128,
+    /// `total_assets` as accounted by the vault.
+    pub tvl: i128,
+    pub total_shares: i128,
+    /// Base tokens held by the vault and not locked for claims.
+    pub idle: i128,
+    /// Sum of the strategies' reported balances.
+    pub deployed: i128,
+    /// Number of pending withdrawal tickets.
+    pub queue_depth: u32,
+    pub queued_shares: i128,
+    pub alt_price: i128,
+    /// Running totals since the start of the run.
+    pub rebalances: u32,
+    pub harvests: u32,
+    pub failed_calls: u32,
+}
+
+/// One user's position after one simulation step, in base-asset units.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UserRow {
+    pub ledger: u32,
+    pub user: u32,
+    /// Shares held, including shares waiting in the withdrawal queue.
+    pub shares: i128,
+    /// Current value of `shares`.
+    pub value: i128,
+    /// Value of everything deposited so far, at the price of each deposit.
+    pub paid: i128,
+    /// Base tokens received from withdrawals and claims.
+    pub received: i128,
+    /// `value + received - paid`.
+    pub pnl: i128,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Report {
+    pub ticks: Vec<Tick>,
+    pub users: Vec<UserRow>,
+}
+
+struct User {
+    address: Address,
+    /// Deposits the second asset instead of the base asset.
+    uses_alt: bool,
+    paid: i128,
+    received: i128,
+    tickets: Vec<u64>,
+}
+
+struct Sim<'a> {
+    env: &'a Env,
+    rng: Rng,
+    scenario: &'a Scenario,
+    admin: Address,
+    keeper: Address,
+    vault: VolatilityShieldClient<'a>,
+    oracle: MockOracleClient<'a>,
+    base: Address,
+    base_admin: StellarAssetClient<'a>,
+    base_token: TokenClient<'a>,
+    alt: Address,
+    alt_admin: StellarAssetClient<'a>,
+    alt_price: i128,
+    strategies: Vec<MockStrategyClient<'a>>,
+    users: Vec<User>,
+    tick: u32,
+    rebalances: u32,
+    harvests: u32,
+    failed_calls: u32,
+}
+
+/// Run `scenario` from an empty vault and collect one row per tick.
+pub fn run(scenario: &Scenario) -> Report {
+    let env = Env::new_with_config(EnvTestConfig {
+        capture_snapshot_at_drop: false,
+    });
+    env.mock_all_auths_allowing_non_root_auth();
+    env.cost_estimate().budget().reset_unlimited();
+    // Keep every entry live for the whole run.
+    let ttl = scenario.ledgers.saturating_add(10_000);
+    env.ledger().with_mut(|ledger| {
+        ledger.min_persistent_entry_ttl = ttl;
+        ledger.min_temp_entry_ttl = ttl;
+        ledger.max_entry_ttl = ttl;
+    });
+
+    let mut sim = Sim::new(&env, scenario);
+    let mut report = Report::default();
+    for _ in 0..scenario.ticks() {
+        sim.step();
+        sim.record(&mut report);
+    }
+    report
+}
+
+impl<'a> Sim<'a> {
+    fn new(env: &'a Env, scenario: &'a Scenario) -> Self {
+        let admin = Address::generate(env);
+        let base = env
+            .register_stellar_asset_contract_v2(admin.clone())
+            .address();
+        let alt = env
+            .register_stellar_asset_contract_v2(admin.clone())
+            .address();
+
+        let oracle_id = env.register(mock_oracle::MockOracle, ());
+        let oracle = MockOracleClient::new(env, &oracle_id);
+        oracle.init(&admin);
+        oracle.set_price(&admin, &alt, &scenario.alt_asset_price);
+
+        let vault_id = env.register(VolatilityShield, ());
+        let vault = VolatilityShieldClient::new(env, &vault_id);
+        vault.init(
+            &admin,
+            &base,
+            &oracle_id,
+            &Address::generate(env),
+            &scenario.fee_bps,
+            &soroban_sdk::vec![env, admin.clone()],
+            &1u32,
+        );
+        vault.propose_action(&admin, &ActionType::AddSupportedAsset(alt.clone()));
+        vault.set_withdraw_queue_threshold(&scenario.withdraw_queue_threshold);
+        vault.set_rebalance_trigger(&scenario.rebalance_trigger);
+
+        let strategies = scenario
+            .strategies
+            .iter()
+            .map(|_| {
+                let id = env.register(MockStrategy, ());
+                let strategy = MockStrategyClient::new(env, &id);
+                strategy.init(&vault_id, &base);
+                vault.propose_action(&admin, &ActionType::AddStrategy(id));
+                strategy
+            })
+            .collect();
+
+        let users = (0..scenario.users)
+            .map(|index| User {
+                address: Address::generate(env),
+                uses_alt: index >= scenario.users.saturating_sub(scenario.alt_asset_users),
+                paid: 0,
+                received: 0,
+                tickets: Vec::new(),
+            })
+            .collect();
+
+        Sim {
+            env,
+            rng: Rng::new(scenario.seed),
+            scenario,
+            keeper: Address::generate(env),
+            admin,
+            vault,
+            oracle,
+            base_admin: StellarAssetClient::new(env, &base),
+            base_token: TokenClient::new(env, &base),
+            base,
+            alt_admin: StellarAssetClient::new(env, &alt),
+            alt,
+            alt_price: scenario.alt_asset_price,
+            strategies,
+            users,
+            tick: 0,
+            rebalances: 0,
+            harvests: 0,
+            failed_calls: 0,
+        }
+    }
+
+    fn step(&mut self) {
+        let scenario = self.scenario;
+        self.tick += 1;
+        let tick = self.tick;
+        self.env.ledger().with_mut(|ledger| {
+            ledger.sequence_number += scenario.ledgers_per_tick;
+            ledger.timestamp += scenario.ledgers_per_tick as u64 * scenario.seconds_per_ledger;
+        });
+
+        self.move_alt_price();
+        self.accrue_strategy_yield();
+        if scenario.oracle_update_every > 0
+            && (tick - 1).is_multiple_of(scenario.oracle_update_every)
+        {
+            self.push_allocations();
+        }
+        for index in 0..self.users.len() {
+            if self.rng.chance(scenario.deposit_chance_bps) {
+                self.deposit(index);
+            }
+            if self.rng.chance(scenario.withdraw_chance_bps) {
+                self.withdraw(index);
+            }
+        }
+        self.run_keeper();
+        if scenario.harvest_every > 0 && tick.is_multiple_of(scenario.harvest_every) {
+            self.count(self.vault.try_harvest().is_ok(), Counter::Harvest);
+        }
+    }
+
+    fn move_alt_price(&mut self) {
+        let change = self.rng.spread(self.scenario.alt_price_volatility_bps);
+        self.alt_price = (self.alt_price * (10_000 + change as i128) / 10_000).max(1);
+        self.oracle
+            .set_price(&self.admin, &self.alt, &self.alt_price);
+    }
+
+    /// Move each strategy's reported balance by its drift. Gains are minted
+    /// to the strategy so it can pay them out; losses leave tokens stranded.
+    fn accrue_strategy_yield(&mut self) {
+        for (strategy, config) in self.strategies.iter().zip(&self.scenario.strategies) {
+            let balance = strategy.balance();
+            if balance <= 0 {
+                continue;
+            }
+            let change = config.drift_bps + self.rng.spread(config.volatility_bps);
+            let new_balance = (balance * (10_000 + change as i128) / 10_000).max(0);
+            if new_balance > balance {
+                self.base_admin
+                    .mint(&strategy.address, &(new_balance - balance));
+            }
+            strategy.simulate_price_drift(&new_balance);
+        }
+    }
+
+    fn push_allocations(&mut self) {
+        let mut allocations = Map::new(self.env);
+        for (strategy, config) in self.strategies.iter().zip(&self.scenario.strategies) {
+            allocations.set(strategy.address.clone(), config.target_bps);
+        }
+        let ok = self
+            .vault
+            .try_set_oracle_data(&allocations, &self.env.ledger().timestamp())
+            .is_ok();
+        self.count(ok, Counter::None);
+    }
+
+    fn deposit(&mut self, index: usize) {
+        let amount = 1 + self.rng.below(self.scenario.max_deposit as u64) as i128;
+        let user = &self.users[index];
+        let (asset, token, value) = if user.uses_alt {
+            let value = amount * self.alt_price / 1_000_000_000;
+            (&self.alt, &self.alt_admin, value)
+        } else {
+            (&self.base, &self.base_admin, amount)
+        };
+        token.mint(&user.address, &amount);
+        let ok = self
+            .vault
+            .try_deposit(&user.address, asset, &amount, &None)
+            .is_ok();
+        if ok {
+            self.users[index].paid += value;
+        }
+        self.count(ok, Counter::None);
+    }
+
+    /// Withdraw a random part of the user's shares in the base asset. The
+    /// vault queues the withdrawal itself when it lacks liquidity; an
+    /// explicit queue request is the fallback when the withdrawal is refused.
+    fn withdraw(&mut self, index: usize) {
+        let address = self.users[index].address.clone();
+        let balance = self.vault.balance(&address);
+        if balance <= 0 {
+            return;
+        }
+        let shares = 1 + self.rng.below(balance as u64) as i128;
+        let before = self.base_token.balance(&address);
+        let ok = self
+            .vault
+            .try_withdraw(&address, &address, &self.base, &shares)
+            .is_ok()
+            || self
+                .vault
+                .try_queue_withdraw(&address, &address, &self.base, &shares)
+                .is_ok();
+        self.count(ok, Counter::None);
+
+        let user = &mut self.users[index];
+        user.received += self.base_token.balance(&address) - before;
+        for ticket in self.vault.get_user_withdrawal_tickets(&address).iter() {
+            if !user.tickets.contains(&ticket.id) {
+                user.tickets.push(ticket.id);
+            }
+        }
+    }
+
+    /// Rebalance when the trigger fires, fulfil queued withdrawals and let
+    /// users claim what was fulfilled.
+    fn run_keeper(&mut self) {
+        if self.vault.rebalance_needed() {
+            let ok = self.vault.try_poke_rebalance(&self.keeper).is_ok();
+            self.count(ok, Counter::Rebalance);
+        }
+        if !self.vault.get_pending_withdrawals().is_empty() {
+            let ok = self
+                .vault
+                .try_fulfil_withdrawals(&self.keeper, &self.scenario.fulfil_batch)
+                .is_ok();
+            self.count(ok, Counter::None);
+        }
+        for index in 0..self.users.len() {
+            let tickets = std::mem::take(&mut self.users[index].tickets);
+            for ticket in tickets {
+                if self.vault.get_claimable_withdrawal(&ticket).is_none() {
+                    self.users[index].tickets.push(ticket);
+                    continue;
+                }
+                match self.vault.try_claim_withdrawal(&ticket) {
+                    Ok(Ok(amount)) => self.users[index].received += amount,
+                    _ => {
+                        self.users[index].tickets.push(ticket);
+                        self.count(false, Counter::None);
+                    }
+                }
+            }
+        }
+    }
+
+    fn count(&mut self, ok: bool, counter: Counter) {
+        match (ok, counter) {
+            (false, _) => self.failed_calls += 1,
+            (true, Counter::Rebalance) => self.rebalances += 1,
+            (true, Counter::Harvest) => self.harvests += 1,
+            (true, Counter::None) => {}
+        }
+    }
+
+    fn record(&self, report: &mut Report) {
+        let ledger = self.env.ledger().sequence();
+        let pending = self.vault.get_pending_withdrawals();
+        report.ticks.push(Tick {
+            ledger,
+            timestamp: self.env.ledger().timestamp(),
+            share_price: self.vault.get_share_price(),
+            tvl: self.vault.total_assets(),
+            total_shares: self.vault.total_shares(),
+            idle: self.vault.get_idle_liquidity(),
+            deployed: self.strategies.iter().map(|s| s.balance()).sum(),
+            queue_depth: pending.len(),
+            queued_shares: pending.iter().map(|ticket| ticket.shares).sum(),
+            alt_price: self.alt_price,
+            rebalances: self.rebalances,
+            harvests: self.harvests,
+            failed_calls: self.failed_calls,
+        });
+
+        for (index, user) in self.users.iter().enumerate() {
+            let summary = self.vault.get_user_summary(&user.address);
+            let shares = summary.balance + summary.queued_shares;
+            let value = self.vault.convert_to_assets(&shares);
+            report.users.push(UserRow {
+                ledger,
+                user: index as u32,
+                shares,
+                value,
+                paid: user.paid,
+                received: user.received,
+                pnl: value + user.received - user.paid,
+            });
+        }
+    }
+}
+
+/// Which running total a successful call adds to.
+#[derive(Clone, Copy)]
+enum Counter {
+    None,
+    Rebalance,
+    Harvest,
+}